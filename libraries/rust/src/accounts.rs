@@ -16,5 +16,9 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 mod margin_pool;
+mod pool_history;
+mod registry;
 
 pub use margin_pool::*;
+pub use pool_history::*;
+pub use registry::*;
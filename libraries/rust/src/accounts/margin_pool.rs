@@ -24,6 +24,10 @@ pub struct MarginPoolAccounts {
     /// The address of the mint for tokens stored in the pool
     pub token_mint: Pubkey,
 
+    /// The program that owns the pool's token, which is the original token
+    /// program unless changed for a token-2022 mint
+    pub token_program: Pubkey,
+
     /// The address of the account holding the tokens in the pool
     pub vault: Pubkey,
 
@@ -34,6 +38,16 @@ pub struct MarginPoolAccounts {
     /// The address of the mint for loan notes, which represent user borrows
     /// from the pool
     pub loan_note_mint: Pubkey,
+
+    /// The address of the mint for term loan notes, which represent user
+    /// borrows at a fixed rate and term
+    pub term_loan_note_mint: Pubkey,
+
+    /// The address of the account caching the price of the pool's token
+    pub oracle: Pubkey,
+
+    /// The address of the account recording the history of the pool's rates
+    pub history: Pubkey,
 }
 
 impl MarginPoolAccounts {
@@ -56,12 +70,31 @@ impl MarginPoolAccounts {
             &jet_margin_pool::id(),
         );
 
+        let (term_loan_note_mint, _) = Pubkey::find_program_address(
+            &[address.as_ref(), b"term-loan-notes".as_ref()],
+            &jet_margin_pool::id(),
+        );
+
+        let (oracle, _) = Pubkey::find_program_address(
+            &[address.as_ref(), b"oracle".as_ref()],
+            &jet_margin_pool::id(),
+        );
+
+        let (history, _) = Pubkey::find_program_address(
+            &[address.as_ref(), b"history".as_ref()],
+            &jet_margin_pool::id(),
+        );
+
         Self {
             token_mint,
+            token_program: anchor_spl::token::ID,
             address,
             vault,
             deposit_note_mint,
             loan_note_mint,
+            term_loan_note_mint,
+            oracle,
+            history,
         }
     }
 }
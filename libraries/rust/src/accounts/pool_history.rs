@@ -0,0 +1,151 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::AccountDeserialize;
+use anyhow::{bail, Result};
+use solana_sdk::pubkey::Pubkey;
+
+use jet_margin_pool::{PoolHistory, PoolHistoryEntry};
+use jet_solana_rpc_api::SolanaRpcClient;
+
+use super::MarginPoolAccounts;
+
+const SECONDS_PER_YEAR: f64 = 31_536_000.0;
+
+/// The annual yields realized by a pool over some period of time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RealizedApy {
+    /// The time of the first history entry used
+    pub start: i64,
+
+    /// The time of the last history entry used
+    pub end: i64,
+
+    /// The yield earned by depositors
+    pub deposit: f64,
+
+    /// The yield paid by borrowers
+    pub borrow: f64,
+}
+
+/// Load the history of rates recorded for the pool of a token
+pub async fn get_pool_history(
+    rpc: &dyn SolanaRpcClient,
+    token_mint: &Pubkey,
+) -> Result<Box<PoolHistory>> {
+    let address = MarginPoolAccounts::derive_from_token(*token_mint).history;
+
+    match rpc.get_account(&address).await? {
+        None => bail!(
+            "no history {} found for pool of token {}",
+            address,
+            token_mint
+        ),
+        Some(account) => Ok(Box::new(PoolHistory::try_deserialize(
+            &mut &account.data[..],
+        )?)),
+    }
+}
+
+/// Calculate the yields realized by a pool between two points in time, based on the
+/// change in its note exchange rates.
+///
+/// The closest entries recorded within the window are used, so `None` is returned if
+/// the history doesn't have at least two entries in the window.
+pub fn realized_apy(history: &PoolHistory, start: i64, end: i64) -> Option<RealizedApy> {
+    let mut entries = history
+        .entries()
+        .filter(|entry| entry.timestamp >= start && entry.timestamp <= end);
+
+    let first = entries.next()?;
+    let last = entries.last()?;
+    let years = (last.timestamp - first.timestamp) as f64 / SECONDS_PER_YEAR;
+
+    if years <= 0.0 {
+        return None;
+    }
+
+    let annualize =
+        |rate: fn(&PoolHistoryEntry) -> f64| (rate(last) / rate(first)).powf(1.0 / years) - 1.0;
+
+    Some(RealizedApy {
+        start: first.timestamp,
+        end: last.timestamp,
+        deposit: annualize(|entry| entry.deposit_note_exchange_rate().as_u64(-12) as f64 / 1e12),
+        borrow: annualize(|entry| entry.loan_note_exchange_rate().as_u64(-12) as f64 / 1e12),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use jet_margin_pool::MarginPool;
+
+    use super::*;
+
+    const HALF_YEAR: i64 = 15_768_000;
+
+    fn history_with_deposit_values(values: &[(i64, u64)]) -> Box<PoolHistory> {
+        let mut history = Box::new(<PoolHistory as bytemuck::Zeroable>::zeroed());
+        let mut margin_pool = MarginPool {
+            deposit_notes: 1_000_000,
+            ..Default::default()
+        };
+
+        for (timestamp, deposit_tokens) in values {
+            margin_pool.deposit_tokens = *deposit_tokens;
+            history.record(margin_pool.history_entry(*timestamp), 0);
+        }
+
+        history
+    }
+
+    #[test]
+    fn realized_apy_is_annualized() {
+        let history = history_with_deposit_values(&[
+            (0, 1_000_000),
+            (HALF_YEAR, 1_050_000),
+            (2 * HALF_YEAR, 1_100_000),
+        ]);
+
+        // compounding 5% over half a year
+        let apy = realized_apy(&history, 0, HALF_YEAR).unwrap();
+        assert_eq!((apy.start, apy.end), (0, HALF_YEAR));
+        assert!((apy.deposit - 0.1025).abs() < 1e-9);
+
+        // nothing is borrowed, so borrowers pay nothing
+        assert_eq!(apy.borrow, 0.0);
+
+        let apy = realized_apy(&history, 0, 2 * HALF_YEAR).unwrap();
+        assert!((apy.deposit - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn realized_apy_uses_entries_within_the_window() {
+        let history = history_with_deposit_values(&[
+            (0, 1_000_000),
+            (HALF_YEAR, 1_050_000),
+            (2 * HALF_YEAR, 1_100_000),
+        ]);
+
+        let apy = realized_apy(&history, 1, 3 * HALF_YEAR).unwrap();
+        assert_eq!((apy.start, apy.end), (HALF_YEAR, 2 * HALF_YEAR));
+
+        // a single entry in the window isn't enough to measure a yield
+        assert!(realized_apy(&history, 1, HALF_YEAR).is_none());
+        assert!(realized_apy(&history, 3 * HALF_YEAR, 4 * HALF_YEAR).is_none());
+    }
+}
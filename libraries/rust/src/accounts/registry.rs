@@ -0,0 +1,87 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::AccountDeserialize;
+use anyhow::{bail, Result};
+
+use jet_control::{Registry, RegistryKind};
+use jet_metadata::{
+    LiquidatorAdapterMetadata, LiquidatorMetadata, MarginAdapterMetadata, TokenMetadata,
+};
+use jet_solana_rpc_api::SolanaRpcClient;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::instructions::control::get_registry_address;
+use crate::instructions::metadata::{
+    get_liquidation_adapter_metadata_address, get_metadata_address,
+};
+
+/// Load one of the registries listing the addresses known to the control program
+pub async fn get_registry(rpc: &dyn SolanaRpcClient, kind: RegistryKind) -> Result<Registry> {
+    let address = get_registry_address(kind);
+
+    match rpc.get_account(&address).await? {
+        None => bail!("no registry {} found for {:?}", address, kind),
+        Some(account) => Ok(Registry::try_deserialize(&mut &account.data[..])?),
+    }
+}
+
+/// Load the metadata for all the tokens registered with a margin pool
+pub async fn list_tokens(rpc: &dyn SolanaRpcClient) -> Result<Vec<TokenMetadata>> {
+    list_metadata(rpc, RegistryKind::Tokens, get_metadata_address).await
+}
+
+/// Load the metadata for all the programs registered as margin adapters
+pub async fn list_adapters(rpc: &dyn SolanaRpcClient) -> Result<Vec<MarginAdapterMetadata>> {
+    list_metadata(rpc, RegistryKind::Adapters, get_metadata_address).await
+}
+
+/// Load the metadata for all the registered liquidators
+pub async fn list_liquidators(rpc: &dyn SolanaRpcClient) -> Result<Vec<LiquidatorMetadata>> {
+    list_metadata(rpc, RegistryKind::Liquidators, get_metadata_address).await
+}
+
+pub async fn list_liquidation_adapters(
+    rpc: &dyn SolanaRpcClient,
+) -> Result<Vec<LiquidatorAdapterMetadata>> {
+    list_metadata(
+        rpc,
+        RegistryKind::LiquidationAdapters,
+        get_liquidation_adapter_metadata_address,
+    )
+    .await
+}
+
+async fn list_metadata<T: AccountDeserialize>(
+    rpc: &dyn SolanaRpcClient,
+    kind: RegistryKind,
+    metadata_address: fn(&Pubkey) -> Pubkey,
+) -> Result<Vec<T>> {
+    let registry = get_registry(rpc, kind).await?;
+    let mut metadata = Vec::with_capacity(registry.entries.len());
+
+    for key in registry.entries {
+        let address = metadata_address(&key);
+
+        match rpc.get_account(&address).await? {
+            None => bail!("no metadata {} found for {}", address, key),
+            Some(account) => metadata.push(T::try_deserialize(&mut &account.data[..])?),
+        }
+    }
+
+    Ok(metadata)
+}
@@ -17,3 +17,4 @@
 
 pub mod control;
 pub mod metadata;
+pub mod native;
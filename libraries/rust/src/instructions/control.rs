@@ -15,17 +15,22 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use jet_margin_pool::MarginPoolConfig;
-use solana_sdk::instruction::Instruction;
+use jet_margin_pool::{MarginPoolConfig, PoolFlags, RewardCampaignParams};
+use solana_sdk::instruction::{AccountMeta, Instruction};
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::system_program::ID as SYSTEM_PROGRAM_ID;
 
 use anchor_lang::{InstructionData, ToAccountMetas};
 
-use jet_control::{MarginPoolParams, TokenMetadataParams};
+use jet_control::{
+    LoanMetadataParams, MarginPoolParams, Proposal, ProposalAccountMeta, RegistryKind,
+    TokenMetadataParams,
+};
+use jet_metadata::{LiquidationAdapterPolicy, OracleSource};
 
-use super::metadata::get_metadata_address;
+use super::metadata::{get_liquidation_adapter_metadata_address, get_metadata_address};
 use crate::accounts::MarginPoolAccounts;
+use crate::ix_builder::MarginPoolIxBuilder;
 
 pub fn create_authority(payer: Pubkey) -> Instruction {
     let accounts = jet_control::accounts::CreateAuthority {
@@ -42,7 +47,96 @@ pub fn create_authority(payer: Pubkey) -> Instruction {
     }
 }
 
-pub fn register_token(pool: &MarginPoolAccounts, requester: &Pubkey) -> Instruction {
+pub fn migrate_authority(payer: Pubkey) -> Instruction {
+    let accounts = jet_control::accounts::MigrateAuthority {
+        payer,
+        authority: get_authority_address(),
+        system_program: SYSTEM_PROGRAM_ID,
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::MigrateAuthority {}.data(),
+    }
+}
+
+pub fn create_registry(authority: &Pubkey, payer: &Pubkey, kind: RegistryKind) -> Instruction {
+    let accounts = jet_control::accounts::CreateRegistry {
+        requester: *authority,
+        authority: get_authority_address(),
+        registry: get_registry_address(kind),
+        payer: *payer,
+        system_program: SYSTEM_PROGRAM_ID,
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::CreateRegistry { kind }.data(),
+    }
+}
+
+pub fn create_risk_tier(
+    authority: &Pubkey,
+    payer: &Pubkey,
+    name: &str,
+    pool_config: MarginPoolConfig,
+    token_params: TokenMetadataParams,
+) -> Instruction {
+    let accounts = jet_control::accounts::CreateRiskTier {
+        requester: *authority,
+        authority: get_authority_address(),
+        risk_tier: get_risk_tier_address(name),
+        payer: *payer,
+        system_program: SYSTEM_PROGRAM_ID,
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::CreateRiskTier {
+            name: name.to_owned(),
+            pool_config,
+            token_params,
+        }
+        .data(),
+    }
+}
+
+pub fn configure_risk_tier(
+    authority: &Pubkey,
+    name: &str,
+    pool_config: MarginPoolConfig,
+    token_params: TokenMetadataParams,
+) -> Instruction {
+    let accounts = jet_control::accounts::ConfigureRiskTier {
+        requester: *authority,
+        authority: get_authority_address(),
+        risk_tier: get_risk_tier_address(name),
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::ConfigureRiskTier {
+            pool_config,
+            token_params,
+        }
+        .data(),
+    }
+}
+
+pub fn register_token(
+    pool: &MarginPoolAccounts,
+    requester: &Pubkey,
+    payer: &Pubkey,
+    risk_tier: &str,
+) -> Instruction {
     let accounts = jet_control::accounts::RegisterToken {
         requester: *requester,
         authority: get_authority_address(),
@@ -51,14 +145,21 @@ pub fn register_token(pool: &MarginPoolAccounts, requester: &Pubkey) -> Instruct
         vault: pool.vault,
         deposit_note_mint: pool.deposit_note_mint,
         loan_note_mint: pool.loan_note_mint,
+        term_loan_note_mint: pool.term_loan_note_mint,
+        oracle: pool.oracle,
         token_mint: pool.token_mint,
         deposit_note_metadata: get_metadata_address(&pool.deposit_note_mint),
         loan_note_metadata: get_metadata_address(&pool.loan_note_mint),
+        term_loan_note_metadata: get_metadata_address(&pool.term_loan_note_mint),
         token_metadata: get_metadata_address(&pool.token_mint),
+        registry: get_registry_address(RegistryKind::Tokens),
+        risk_tier: get_risk_tier_address(risk_tier),
+        payer: *payer,
 
         margin_pool_program: jet_margin_pool::ID,
         metadata_program: jet_metadata::ID,
         token_program: anchor_spl::token::ID,
+        pool_token_program: pool.token_program,
         system_program: SYSTEM_PROGRAM_ID,
         rent: solana_sdk::sysvar::rent::ID,
     }
@@ -81,6 +182,8 @@ pub fn register_adapter(adapter: &Pubkey, authority: &Pubkey, payer: &Pubkey) ->
 
         payer: *payer,
 
+        registry: get_registry_address(RegistryKind::Adapters),
+
         metadata_program: jet_metadata::ID,
         system_program: SYSTEM_PROGRAM_ID,
     }
@@ -93,14 +196,256 @@ pub fn register_adapter(adapter: &Pubkey, authority: &Pubkey, payer: &Pubkey) ->
     }
 }
 
+pub fn deregister_adapter(adapter: &Pubkey, authority: &Pubkey, receiver: &Pubkey) -> Instruction {
+    let accounts = jet_control::accounts::DeregisterAdapter {
+        requester: *authority,
+        authority: get_authority_address(),
+
+        adapter: *adapter,
+        metadata_account: get_metadata_address(adapter),
+
+        receiver: *receiver,
+
+        registry: get_registry_address(RegistryKind::Adapters),
+
+        metadata_program: jet_metadata::ID,
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::DeregisterAdapter {}.data(),
+    }
+}
+
+pub fn resize_metadata(
+    metadata_account: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    space: u64,
+) -> Instruction {
+    let accounts = jet_control::accounts::ResizeMetadata {
+        requester: *authority,
+        authority: get_authority_address(),
+
+        metadata_account: *metadata_account,
+
+        payer: *payer,
+
+        metadata_program: jet_metadata::ID,
+        system_program: SYSTEM_PROGRAM_ID,
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::ResizeMetadata { space }.data(),
+    }
+}
+
+pub fn migrate_metadata(
+    metadata_account: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let accounts = jet_control::accounts::MigrateMetadata {
+        requester: *authority,
+        authority: get_authority_address(),
+
+        metadata_account: *metadata_account,
+
+        payer: *payer,
+
+        metadata_program: jet_metadata::ID,
+        system_program: SYSTEM_PROGRAM_ID,
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::MigrateMetadata {}.data(),
+    }
+}
+
+pub fn add_registry_entry(authority: &Pubkey, kind: RegistryKind, key: &Pubkey) -> Instruction {
+    let metadata_account = match kind {
+        RegistryKind::LiquidationAdapters => get_liquidation_adapter_metadata_address(key),
+        _ => get_metadata_address(key),
+    };
+
+    let accounts = jet_control::accounts::AddRegistryEntry {
+        requester: *authority,
+        authority: get_authority_address(),
+
+        key_account: *key,
+        metadata_account,
+
+        registry: get_registry_address(kind),
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::AddRegistryEntry {}.data(),
+    }
+}
+
+pub fn register_liquidation_adapter(
+    adapter: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let accounts = jet_control::accounts::RegisterLiquidationAdapter {
+        requester: *authority,
+        authority: get_authority_address(),
+
+        adapter: *adapter,
+        metadata_account: get_liquidation_adapter_metadata_address(adapter),
+
+        payer: *payer,
+
+        registry: get_registry_address(RegistryKind::LiquidationAdapters),
+
+        metadata_program: jet_metadata::ID,
+        system_program: SYSTEM_PROGRAM_ID,
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::RegisterLiquidationAdapter {}.data(),
+    }
+}
+
+pub fn deregister_liquidation_adapter(
+    adapter: &Pubkey,
+    authority: &Pubkey,
+    receiver: &Pubkey,
+) -> Instruction {
+    let accounts = jet_control::accounts::DeregisterLiquidationAdapter {
+        requester: *authority,
+        authority: get_authority_address(),
+
+        adapter: *adapter,
+        metadata_account: get_liquidation_adapter_metadata_address(adapter),
+
+        receiver: *receiver,
+
+        registry: get_registry_address(RegistryKind::LiquidationAdapters),
+
+        metadata_program: jet_metadata::ID,
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::DeregisterLiquidationAdapter {}.data(),
+    }
+}
+
+pub fn register_liquidator(
+    liquidator: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    adapter_policy: LiquidationAdapterPolicy,
+) -> Instruction {
+    let accounts = jet_control::accounts::RegisterLiquidator {
+        requester: *authority,
+        authority: get_authority_address(),
+
+        liquidator: *liquidator,
+        metadata_account: get_metadata_address(liquidator),
+
+        payer: *payer,
+
+        registry: get_registry_address(RegistryKind::Liquidators),
+
+        metadata_program: jet_metadata::ID,
+        system_program: SYSTEM_PROGRAM_ID,
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::RegisterLiquidator { adapter_policy }.data(),
+    }
+}
+
+pub fn revoke_liquidator(liquidator: &Pubkey, authority: &Pubkey) -> Instruction {
+    let accounts = jet_control::accounts::RevokeLiquidator {
+        requester: *authority,
+        authority: get_authority_address(),
+
+        liquidator: *liquidator,
+        metadata_account: get_metadata_address(liquidator),
+
+        registry: get_registry_address(RegistryKind::Liquidators),
+
+        metadata_program: jet_metadata::ID,
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::RevokeLiquidator {}.data(),
+    }
+}
+
+/// The oracle providing the price for a token
+#[derive(Clone, Copy, Debug)]
+pub enum TokenPriceOracle {
+    /// A pyth product and its price account
+    Pyth { product: Pubkey, price: Pubkey },
+
+    /// A switchboard aggregator
+    Switchboard { aggregator: Pubkey },
+
+    /// A price that never changes
+    Fixed { price: i64, exponent: i32 },
+}
+
+impl TokenPriceOracle {
+    fn source(&self) -> OracleSource {
+        match *self {
+            Self::Pyth { .. } => OracleSource::Pyth,
+            Self::Switchboard { .. } => OracleSource::Switchboard,
+            Self::Fixed { price, exponent } => OracleSource::Fixed { price, exponent },
+        }
+    }
+
+    fn product_address(&self) -> Pubkey {
+        match self {
+            Self::Pyth { product, .. } => *product,
+            _ => Pubkey::default(),
+        }
+    }
+
+    fn price_address(&self) -> Pubkey {
+        match self {
+            Self::Pyth { price, .. } => *price,
+            Self::Switchboard { aggregator } => *aggregator,
+            Self::Fixed { .. } => Pubkey::default(),
+        }
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct TokenConfiguration {
-    pub pyth_product: Option<Pubkey>,
-    pub pyth_price: Option<Pubkey>,
+    pub oracle: Option<TokenPriceOracle>,
 
     pub pool_config: Option<MarginPoolConfig>,
     pub pool_params: Option<MarginPoolParams>,
     pub metadata: Option<TokenMetadataParams>,
+    pub loan_metadata: Option<LoanMetadataParams>,
+    pub term_loan_metadata: Option<LoanMetadataParams>,
 }
 
 pub fn configure_token(
@@ -114,11 +459,17 @@ pub fn configure_token(
 
         token_mint: pool.token_mint,
         margin_pool: pool.address,
+        oracle: pool.oracle,
         token_metadata: get_metadata_address(&pool.token_mint),
         deposit_metadata: get_metadata_address(&pool.deposit_note_mint),
+        loan_metadata: get_metadata_address(&pool.loan_note_mint),
+        term_loan_metadata: get_metadata_address(&pool.term_loan_note_mint),
 
-        pyth_product: config.pyth_product.unwrap_or_default(),
-        pyth_price: config.pyth_price.unwrap_or_default(),
+        pyth_product: config
+            .oracle
+            .map(|o| o.product_address())
+            .unwrap_or_default(),
+        price_oracle: config.oracle.map(|o| o.price_address()).unwrap_or_default(),
 
         margin_pool_program: jet_margin_pool::ID,
         metadata_program: jet_metadata::ID,
@@ -130,13 +481,358 @@ pub fn configure_token(
         program_id: jet_control::ID,
         data: jet_control::instruction::ConfigureToken {
             metadata: config.metadata.clone(),
+            loan_metadata: config.loan_metadata.clone(),
+            term_loan_metadata: config.term_loan_metadata.clone(),
             pool_param: config.pool_params.clone(),
             pool_config: config.pool_config.clone(),
+            oracle_source: config.oracle.map(|o| o.source()),
+        }
+        .data(),
+    }
+}
+
+pub fn create_reward_campaign(
+    pool: &MarginPoolAccounts,
+    authority: &Pubkey,
+    note_mint: &Pubkey,
+    reward_mint: &Pubkey,
+    payer: &Pubkey,
+    params: RewardCampaignParams,
+) -> Instruction {
+    let pool_ix = MarginPoolIxBuilder::new(pool.token_mint);
+    let campaign = pool_ix.reward_campaign(note_mint);
+
+    let accounts = jet_control::accounts::CreateRewardCampaign {
+        requester: *authority,
+        authority: get_authority_address(),
+
+        margin_pool: pool.address,
+        campaign,
+        vault: pool_ix.reward_vault(&campaign),
+        stake_vault: pool_ix.reward_stake_vault(&campaign),
+        note_mint: *note_mint,
+        reward_mint: *reward_mint,
+
+        payer: *payer,
+
+        margin_pool_program: jet_margin_pool::ID,
+        token_program: anchor_spl::token::ID,
+        system_program: SYSTEM_PROGRAM_ID,
+        rent: solana_sdk::sysvar::rent::ID,
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::CreateRewardCampaign { params }.data(),
+    }
+}
+
+pub fn propose_root_authority(authority: &Pubkey, new_root: &Pubkey) -> Instruction {
+    let accounts = jet_control::accounts::ProposeRootAuthority {
+        requester: *authority,
+        authority: get_authority_address(),
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::ProposeRootAuthority {
+            new_root: *new_root,
+        }
+        .data(),
+    }
+}
+
+pub fn accept_root_authority(new_root: &Pubkey) -> Instruction {
+    let accounts = jet_control::accounts::AcceptRootAuthority {
+        new_root: *new_root,
+        authority: get_authority_address(),
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::AcceptRootAuthority {}.data(),
+    }
+}
+
+pub fn set_emergency_pauser(authority: &Pubkey, pauser: &Pubkey) -> Instruction {
+    let accounts = jet_control::accounts::SetEmergencyPauser {
+        requester: *authority,
+        authority: get_authority_address(),
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::SetEmergencyPauser { pauser: *pauser }.data(),
+    }
+}
+
+pub fn pause_pool(
+    pool: &MarginPoolAccounts,
+    requester: &Pubkey,
+    flags: PoolFlags,
+    paused: bool,
+) -> Instruction {
+    let accounts = jet_control::accounts::PausePool {
+        requester: *requester,
+        authority: get_authority_address(),
+
+        margin_pool: pool.address,
+
+        margin_pool_program: jet_margin_pool::ID,
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::PausePool {
+            flags: flags.bits(),
+            paused,
+        }
+        .data(),
+    }
+}
+
+pub fn begin_pool_retirement(pool: &MarginPoolAccounts, authority: &Pubkey) -> Instruction {
+    let accounts = jet_control::accounts::BeginPoolRetirement {
+        requester: *authority,
+        authority: get_authority_address(),
+
+        margin_pool: pool.address,
+
+        margin_pool_program: jet_margin_pool::ID,
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::BeginPoolRetirement {}.data(),
+    }
+}
+
+pub fn settle_pool_retirement(pool: &MarginPoolAccounts, authority: &Pubkey) -> Instruction {
+    let accounts = jet_control::accounts::SettlePoolRetirement {
+        requester: *authority,
+        authority: get_authority_address(),
+
+        margin_pool: pool.address,
+
+        margin_pool_program: jet_margin_pool::ID,
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::SettlePoolRetirement {}.data(),
+    }
+}
+
+pub fn write_off_pool_loans(pool: &MarginPoolAccounts, authority: &Pubkey) -> Instruction {
+    let accounts = jet_control::accounts::WriteOffPoolLoans {
+        requester: *authority,
+        authority: get_authority_address(),
+
+        margin_pool: pool.address,
+
+        margin_pool_program: jet_margin_pool::ID,
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::WriteOffPoolLoans {}.data(),
+    }
+}
+
+pub fn close_pool(
+    pool: &MarginPoolAccounts,
+    authority: &Pubkey,
+    remainder: &Pubkey,
+    receiver: &Pubkey,
+) -> Instruction {
+    let pool_ix = MarginPoolIxBuilder::new(pool.token_mint);
+    let accounts = jet_control::accounts::ClosePool {
+        requester: *authority,
+        authority: get_authority_address(),
+
+        margin_pool: pool.address,
+        oracle: pool.oracle,
+        vault: pool.vault,
+        token_mint: pool.token_mint,
+        history: pool.history,
+        withdrawal_queue: pool_ix.withdrawal_queue,
+        withdrawal_escrow: pool_ix.withdrawal_escrow,
+        remainder: *remainder,
+        receiver: *receiver,
+        registry: get_registry_address(RegistryKind::Tokens),
+
+        margin_pool_program: jet_margin_pool::ID,
+        token_program: anchor_spl::token::ID,
+        pool_token_program: pool.token_program,
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::ClosePool {}.data(),
+    }
+}
+
+pub fn create_governance(
+    authority: &Pubkey,
+    payer: &Pubkey,
+    council: Vec<Pubkey>,
+    threshold: u8,
+    timelock: i64,
+) -> Instruction {
+    let accounts = jet_control::accounts::CreateGovernance {
+        requester: *authority,
+        authority: get_authority_address(),
+        governance: get_governance_address(),
+        payer: *payer,
+        system_program: SYSTEM_PROGRAM_ID,
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::CreateGovernance {
+            council,
+            threshold,
+            timelock,
+        }
+        .data(),
+    }
+}
+
+/// The instruction to change the council, which has to be executed
+/// through a proposal
+pub fn configure_governance(council: Vec<Pubkey>, threshold: u8, timelock: i64) -> Instruction {
+    let accounts = jet_control::accounts::ConfigureGovernance {
+        governance: get_governance_address(),
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::ConfigureGovernance {
+            council,
+            threshold,
+            timelock,
+        }
+        .data(),
+    }
+}
+
+/// Propose an instruction for the control program, as the proposal with the
+/// given sequence number
+pub fn create_proposal(
+    proposer: &Pubkey,
+    payer: &Pubkey,
+    proposal_id: u64,
+    instruction: &Instruction,
+) -> Instruction {
+    assert_eq!(jet_control::ID, instruction.program_id);
+
+    let accounts = jet_control::accounts::CreateProposal {
+        proposer: *proposer,
+        governance: get_governance_address(),
+        proposal: get_proposal_address(proposal_id),
+        payer: *payer,
+        system_program: SYSTEM_PROGRAM_ID,
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::CreateProposal {
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|meta| ProposalAccountMeta {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: instruction.data.clone(),
         }
         .data(),
     }
 }
 
+pub fn approve_proposal(member: &Pubkey, proposal_id: u64) -> Instruction {
+    let accounts = jet_control::accounts::ApproveProposal {
+        member: *member,
+        governance: get_governance_address(),
+        proposal: get_proposal_address(proposal_id),
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::ApproveProposal {}.data(),
+    }
+}
+
+pub fn execute_proposal(proposal: &Proposal) -> Instruction {
+    let mut accounts = jet_control::accounts::ExecuteProposal {
+        governance: get_governance_address(),
+        proposal: get_proposal_address(proposal.id),
+        control_program: jet_control::ID,
+    }
+    .to_account_metas(None);
+
+    // the governance account signs through the control program
+    accounts.extend(proposal.accounts.iter().map(|meta| AccountMeta {
+        pubkey: meta.pubkey,
+        is_signer: meta.is_signer && meta.pubkey != get_governance_address(),
+        is_writable: meta.is_writable,
+    }));
+
+    Instruction {
+        accounts,
+        program_id: jet_control::ID,
+        data: jet_control::instruction::ExecuteProposal {}.data(),
+    }
+}
+
 pub fn get_authority_address() -> Pubkey {
     Pubkey::find_program_address(&[], &jet_control::ID).0
 }
+
+pub fn get_registry_address(kind: RegistryKind) -> Pubkey {
+    Pubkey::find_program_address(&[b"registry".as_ref(), kind.seed()], &jet_control::ID).0
+}
+
+pub fn get_risk_tier_address(name: &str) -> Pubkey {
+    Pubkey::find_program_address(&[b"risk-tier".as_ref(), name.as_bytes()], &jet_control::ID).0
+}
+
+pub fn get_governance_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"governance"], &jet_control::ID).0
+}
+
+pub fn get_proposal_address(proposal_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"proposal".as_ref(), &proposal_id.to_le_bytes()],
+        &jet_control::ID,
+    )
+    .0
+}
@@ -20,3 +20,14 @@ use solana_sdk::pubkey::Pubkey;
 pub fn get_metadata_address(address: &Pubkey) -> Pubkey {
     Pubkey::find_program_address(&[address.as_ref()], &jet_metadata::ID).0
 }
+
+pub fn get_liquidation_adapter_metadata_address(adapter: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            adapter.as_ref(),
+            jet_metadata::LiquidatorAdapterMetadata::SEED.as_bytes(),
+        ],
+        &jet_metadata::ID,
+    )
+    .0
+}
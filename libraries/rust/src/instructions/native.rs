@@ -0,0 +1,85 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use solana_sdk::instruction::Instruction;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_instruction;
+
+use spl_token::native_mint;
+
+/// The seed used to derive the temporary account holding wrapped SOL for a wallet
+const WRAPPED_SOL_SEED: &str = "jet-wrapped-sol";
+
+/// Check if a mint is for the native token, which needs wrapping
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+    *mint == native_mint::ID
+}
+
+/// The size of the temporary account holding wrapped SOL, for rent calculations
+pub fn wrapped_sol_account_size() -> usize {
+    spl_token::state::Account::LEN
+}
+
+/// Get the address of the temporary account holding wrapped SOL for a wallet
+pub fn get_wrapped_sol_address(owner: &Pubkey) -> Pubkey {
+    Pubkey::create_with_seed(owner, WRAPPED_SOL_SEED, &spl_token::ID).unwrap()
+}
+
+/// Instructions to wrap native SOL from a wallet, into a temporary token account
+/// owned by the wallet
+///
+/// # Params
+///
+/// `owner` - The wallet with the SOL to be wrapped
+/// `amount` - The amount of lamports to be wrapped
+/// `rent` - The lamports needed for the token account to be rent exempt
+pub fn wrap_native(owner: &Pubkey, amount: u64, rent: u64) -> Vec<Instruction> {
+    let address = get_wrapped_sol_address(owner);
+
+    vec![
+        system_instruction::create_account_with_seed(
+            owner,
+            &address,
+            owner,
+            WRAPPED_SOL_SEED,
+            rent + amount,
+            wrapped_sol_account_size() as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_account(
+            &spl_token::ID,
+            &address,
+            &native_mint::ID,
+            owner,
+        )
+        .unwrap(),
+    ]
+}
+
+/// Instruction to close the temporary wrapped SOL account for a wallet, returning
+/// all of its SOL to the wallet
+pub fn unwrap_native(owner: &Pubkey) -> Instruction {
+    spl_token::instruction::close_account(
+        &spl_token::ID,
+        &get_wrapped_sol_address(owner),
+        owner,
+        owner,
+        &[],
+    )
+    .unwrap()
+}
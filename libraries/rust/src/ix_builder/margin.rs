@@ -28,6 +28,10 @@ use jet_margin::instruction as ix_data;
 use jet_margin::program::JetMargin;
 use jet_margin::{accounts as ix_account, CompactAccountMeta};
 
+use crate::instructions::metadata::{
+    get_liquidation_adapter_metadata_address, get_metadata_address,
+};
+
 /// Utility for creating instructions to interact with the margin
 /// program for a specific account.
 pub struct MarginIxBuilder {
@@ -116,6 +120,27 @@ impl MarginIxBuilder {
         }
     }
 
+    /// Get instruction to refresh the configuration of a position from its metadata
+    ///
+    /// # Params
+    ///
+    /// `position_token_mint` - The mint for the relevant token for the position
+    pub fn refresh_position_metadata(&self, position_token_mint: Pubkey) -> Instruction {
+        let (metadata, _) =
+            Pubkey::find_program_address(&[position_token_mint.as_ref()], &jet_metadata::ID);
+
+        let accounts = ix_account::RefreshPositionMetadata {
+            margin_account: self.address,
+            metadata,
+        };
+
+        Instruction {
+            program_id: JetMargin::id(),
+            data: ix_data::RefreshPositionMetadata.data(),
+            accounts: accounts.to_account_metas(None),
+        }
+    }
+
     /// Get instruction to register new position
     ///
     /// # Params
@@ -234,19 +259,43 @@ impl MarginIxBuilder {
         }
     }
 
-    /// Invoke action as liquidator
-    #[allow(clippy::redundant_field_names)]
+    /// Invoke action as liquidator, through a program registered as a margin adapter
     pub fn liquidator_invoke(&self, adapter_ix: Instruction, liquidator: &Pubkey) -> Instruction {
+        let adapter_metadata = get_metadata_address(&adapter_ix.program_id);
+        self.liquidator_invoke_with_metadata(adapter_ix, liquidator, adapter_metadata)
+    }
+
+    /// Invoke action as liquidator, through a program registered as a liquidation adapter
+    pub fn liquidation_adapter_invoke(
+        &self,
+        adapter_ix: Instruction,
+        liquidator: &Pubkey,
+    ) -> Instruction {
+        let adapter_metadata = get_liquidation_adapter_metadata_address(&adapter_ix.program_id);
+        self.liquidator_invoke_with_metadata(adapter_ix, liquidator, adapter_metadata)
+    }
+
+    #[allow(clippy::redundant_field_names)]
+    fn liquidator_invoke_with_metadata(
+        &self,
+        adapter_ix: Instruction,
+        liquidator: &Pubkey,
+        adapter_metadata: Pubkey,
+    ) -> Instruction {
         let (liquidation, _) = Pubkey::find_program_address(
             &[b"liquidation", self.address.as_ref(), liquidator.as_ref()],
             &jet_margin::id(),
         );
+        let (liquidator_metadata, _) =
+            Pubkey::find_program_address(&[liquidator.as_ref()], &jet_metadata::id());
 
         invoke!(
             self.address,
             adapter_ix,
+            metadata = adapter_metadata,
             LiquidatorInvoke {
                 liquidator: *liquidator,
+                liquidator_metadata: liquidator_metadata,
                 liquidation: liquidation,
             }
         )
@@ -268,11 +317,14 @@ impl MarginIxBuilder {
             &[b"liquidation", self.address.as_ref(), original.as_ref()],
             &JetMargin::id(),
         );
+        let (liquidator_metadata, _) =
+            Pubkey::find_program_address(&[original.as_ref()], &jet_metadata::id());
 
         let accounts = ix_account::LiquidateEnd {
             margin_account: self.address,
             authority,
             liquidation,
+            liquidator_metadata,
         };
 
         Instruction {
@@ -319,17 +371,15 @@ macro_rules! invoke {
     (
         $margin_account:expr,
         $adapter_ix:ident,
+        metadata = $adapter_metadata:expr,
         $Instruction:ident $({
             $($additional_field:ident: $value:expr),* $(,)?
         })?
     ) => {{
-        let (adapter_metadata, _) =
-            Pubkey::find_program_address(&[$adapter_ix.program_id.as_ref()], &jet_metadata::ID);
-
         let mut accounts = ix_account::$Instruction {
             margin_account: $margin_account,
             adapter_program: $adapter_ix.program_id,
-            adapter_metadata,
+            adapter_metadata: $adapter_metadata,
             $(
                 $($additional_field: $value),*
             )?
@@ -357,5 +407,21 @@ macro_rules! invoke {
             accounts,
         }
     }};
+    (
+        $margin_account:expr,
+        $adapter_ix:ident,
+        $Instruction:ident $({
+            $($additional_field:ident: $value:expr),* $(,)?
+        })?
+    ) => {
+        invoke!(
+            $margin_account,
+            $adapter_ix,
+            metadata = get_metadata_address(&$adapter_ix.program_id),
+            $Instruction $({
+                $($additional_field: $value),*
+            })?
+        )
+    };
 }
 use invoke;
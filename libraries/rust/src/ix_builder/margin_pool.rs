@@ -18,7 +18,7 @@
 use anchor_lang::prelude::{Id, System, ToAccountMetas};
 use anchor_lang::InstructionData;
 use anchor_spl::token::Token;
-use solana_sdk::instruction::Instruction;
+use solana_sdk::instruction::{AccountMeta, Instruction};
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::sysvar::{rent::Rent, SysvarId};
 
@@ -33,6 +33,10 @@ pub struct MarginPoolIxBuilder {
     /// The address of the mint for tokens stored in the pool
     pub token_mint: Pubkey,
 
+    /// The program that owns the pool's token, which is the original token
+    /// program unless changed for a token-2022 mint
+    pub token_program: Pubkey,
+
     /// The address of the margin pool
     pub address: Pubkey,
 
@@ -46,6 +50,22 @@ pub struct MarginPoolIxBuilder {
     /// The address of the mint for loan notes, which represent user borrows
     /// from the pool
     pub loan_note_mint: Pubkey,
+
+    /// The address of the mint for term loan notes, which represent user
+    /// borrows at a fixed rate and term
+    pub term_loan_note_mint: Pubkey,
+
+    /// The address of the account caching the price of the pool's token
+    pub oracle: Pubkey,
+
+    /// The address of the account recording the history of the pool's rates
+    pub history: Pubkey,
+
+    /// The address of the queue of withdrawals waiting on liquidity
+    pub withdrawal_queue: Pubkey,
+
+    /// The address of the account holding deposit notes for queued withdrawals
+    pub withdrawal_escrow: Pubkey,
 }
 
 impl MarginPoolIxBuilder {
@@ -68,12 +88,43 @@ impl MarginPoolIxBuilder {
             &JetMarginPool::id(),
         );
 
+        let (term_loan_note_mint, _) = Pubkey::find_program_address(
+            &[address.as_ref(), b"term-loan-notes".as_ref()],
+            &JetMarginPool::id(),
+        );
+
+        let (oracle, _) = Pubkey::find_program_address(
+            &[address.as_ref(), b"oracle".as_ref()],
+            &JetMarginPool::id(),
+        );
+
+        let (history, _) = Pubkey::find_program_address(
+            &[address.as_ref(), b"history".as_ref()],
+            &JetMarginPool::id(),
+        );
+
+        let (withdrawal_queue, _) = Pubkey::find_program_address(
+            &[address.as_ref(), b"withdrawal-queue".as_ref()],
+            &JetMarginPool::id(),
+        );
+
+        let (withdrawal_escrow, _) = Pubkey::find_program_address(
+            &[address.as_ref(), b"withdrawal-escrow".as_ref()],
+            &JetMarginPool::id(),
+        );
+
         Self {
             token_mint,
+            token_program: Token::id(),
             address,
             vault,
             deposit_note_mint,
             loan_note_mint,
+            term_loan_note_mint,
+            oracle,
+            history,
+            withdrawal_queue,
+            withdrawal_escrow,
         }
     }
 
@@ -93,9 +144,12 @@ impl MarginPoolIxBuilder {
             margin_pool: self.address,
             deposit_note_mint: self.deposit_note_mint,
             loan_note_mint: self.loan_note_mint,
+            term_loan_note_mint: self.term_loan_note_mint,
+            oracle: self.oracle,
             vault: self.vault,
             payer,
             token_program: Token::id(),
+            pool_token_program: self.token_program,
             system_program: System::id(),
             rent: Rent::id(),
         }
@@ -108,6 +162,31 @@ impl MarginPoolIxBuilder {
         }
     }
 
+    /// Instruction to migrate a pool created before the pool had term loans
+    ///
+    /// # Params
+    ///
+    /// `payer` - The address paying for the rent
+    pub fn migrate(&self, payer: Pubkey) -> Instruction {
+        let accounts = ix_accounts::MigratePool {
+            margin_pool: self.address,
+            term_loan_note_mint: self.term_loan_note_mint,
+            oracle: self.oracle,
+            token_mint: self.token_mint,
+            payer,
+            token_program: Token::id(),
+            system_program: System::id(),
+            rent: Rent::id(),
+        }
+        .to_account_metas(None);
+
+        Instruction {
+            program_id: jet_margin_pool::ID,
+            data: ix_data::MigratePool {}.data(),
+            accounts,
+        }
+    }
+
     /// Instruction to deposit tokens into the pool in exchange for deposit notes
     ///
     /// # Params
@@ -125,12 +204,15 @@ impl MarginPoolIxBuilder {
     ) -> Instruction {
         let accounts = ix_accounts::Deposit {
             margin_pool: self.address,
+            history: self.history,
+            token_mint: self.token_mint,
             vault: self.vault,
             deposit_note_mint: self.deposit_note_mint,
             depositor,
             source,
             destination,
             token_program: Token::id(),
+            pool_token_program: self.token_program,
         }
         .to_account_metas(None);
 
@@ -141,6 +223,46 @@ impl MarginPoolIxBuilder {
         }
     }
 
+    /// Instruction to deposit tokens from a wallet into the pool, with the notes
+    /// received by a margin account
+    ///
+    /// # Params
+    ///
+    /// `margin_account` - The margin account to receive the deposit
+    /// `depositor` - The authority for the source tokens
+    /// `source` - The token account that has the tokens to be deposited
+    /// `destination` - The margin account position to send the deposit notes
+    /// `amount` - The amount of tokens to be deposited
+    pub fn margin_deposit(
+        &self,
+        margin_account: Pubkey,
+        depositor: Pubkey,
+        source: Pubkey,
+        destination: Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        let accounts = ix_accounts::MarginDeposit {
+            margin_account,
+            margin_pool: self.address,
+            history: self.history,
+            token_mint: self.token_mint,
+            vault: self.vault,
+            deposit_note_mint: self.deposit_note_mint,
+            depositor,
+            source,
+            destination,
+            token_program: Token::id(),
+            pool_token_program: self.token_program,
+        }
+        .to_account_metas(None);
+
+        Instruction {
+            program_id: jet_margin_pool::ID,
+            data: ix_data::MarginDeposit { amount }.data(),
+            accounts,
+        }
+    }
+
     /// Instruction to withdraw tokens from the pool in exchange for deposit notes
     ///
     /// # Params
@@ -156,14 +278,20 @@ impl MarginPoolIxBuilder {
         destination: Pubkey,
         amount: Amount,
     ) -> Instruction {
+        let reward_campaign = self.reward_campaign(&self.deposit_note_mint);
         let accounts = ix_accounts::Withdraw {
             margin_pool: self.address,
+            history: self.history,
+            reward_campaign,
+            reward_checkpoint: self.reward_checkpoint(&reward_campaign, &source),
+            token_mint: self.token_mint,
             vault: self.vault,
             deposit_note_mint: self.deposit_note_mint,
             depositor,
             source,
             destination,
             token_program: Token::id(),
+            pool_token_program: self.token_program,
         }
         .to_account_metas(None);
 
@@ -174,6 +302,130 @@ impl MarginPoolIxBuilder {
         }
     }
 
+    /// Instruction to create the queue for withdrawals waiting on liquidity
+    ///
+    /// # Params
+    ///
+    /// `payer` - The address paying for the rent
+    pub fn create_withdrawal_queue(&self, payer: Pubkey) -> Instruction {
+        let accounts = ix_accounts::CreateWithdrawalQueue {
+            margin_pool: self.address,
+            withdrawal_queue: self.withdrawal_queue,
+            escrow: self.withdrawal_escrow,
+            deposit_note_mint: self.deposit_note_mint,
+            payer,
+            token_program: Token::id(),
+            system_program: System::id(),
+            rent: Rent::id(),
+        }
+        .to_account_metas(None);
+
+        Instruction {
+            program_id: jet_margin_pool::ID,
+            data: ix_data::CreateWithdrawalQueue {}.data(),
+            accounts,
+        }
+    }
+
+    /// Instruction to queue a withdrawal, to be filled once the pool has liquidity
+    ///
+    /// # Params
+    ///
+    /// `depositor` - The authority for the deposit notes
+    /// `source` - The token account that has the deposit notes to be exchanged
+    /// `destination` - The token account to send the withdrawn tokens
+    /// `notes` - The amount of deposit notes to be withdrawn
+    pub fn queue_withdrawal(
+        &self,
+        depositor: Pubkey,
+        source: Pubkey,
+        destination: Pubkey,
+        notes: u64,
+    ) -> Instruction {
+        let reward_campaign = self.reward_campaign(&self.deposit_note_mint);
+        let accounts = ix_accounts::QueueWithdrawal {
+            margin_pool: self.address,
+            reward_campaign,
+            reward_checkpoint: self.reward_checkpoint(&reward_campaign, &source),
+            token_mint: self.token_mint,
+            withdrawal_queue: self.withdrawal_queue,
+            escrow: self.withdrawal_escrow,
+            depositor,
+            source,
+            destination,
+            token_program: Token::id(),
+        }
+        .to_account_metas(None);
+
+        Instruction {
+            program_id: jet_margin_pool::ID,
+            data: ix_data::QueueWithdrawal { notes }.data(),
+            accounts,
+        }
+    }
+
+    /// Instruction to fill queued withdrawals with the liquidity available in the pool
+    ///
+    /// # Params
+    ///
+    /// `destinations` - The destinations of the requests to fill, in queue order
+    pub fn fill_withdrawals(&self, destinations: &[Pubkey]) -> Instruction {
+        let mut accounts = ix_accounts::FillWithdrawals {
+            margin_pool: self.address,
+            history: self.history,
+            token_mint: self.token_mint,
+            withdrawal_queue: self.withdrawal_queue,
+            escrow: self.withdrawal_escrow,
+            vault: self.vault,
+            deposit_note_mint: self.deposit_note_mint,
+            token_program: Token::id(),
+            pool_token_program: self.token_program,
+        }
+        .to_account_metas(None);
+
+        accounts.extend(
+            destinations
+                .iter()
+                .map(|destination| AccountMeta::new(*destination, false)),
+        );
+
+        Instruction {
+            program_id: jet_margin_pool::ID,
+            data: ix_data::FillWithdrawals {}.data(),
+            accounts,
+        }
+    }
+
+    /// Instruction to cancel a queued withdrawal
+    ///
+    /// # Params
+    ///
+    /// `depositor` - The authority that queued the withdrawal
+    /// `destination` - The token account to return the deposit notes to
+    /// `sequence` - The sequence number of the request in the queue
+    pub fn cancel_withdrawal(
+        &self,
+        depositor: Pubkey,
+        destination: Pubkey,
+        sequence: u64,
+    ) -> Instruction {
+        let accounts = ix_accounts::CancelWithdrawal {
+            margin_pool: self.address,
+            withdrawal_queue: self.withdrawal_queue,
+            escrow: self.withdrawal_escrow,
+            depositor,
+            destination,
+            token_program: Token::id(),
+        }
+        .to_account_metas(None);
+
+        Instruction {
+            program_id: jet_margin_pool::ID,
+            data: ix_data::CancelWithdrawal { sequence }.data(),
+            accounts,
+        }
+    }
+
     /// Instruction to borrow tokens using a margin account
     ///
     /// # Params
@@ -181,17 +433,18 @@ impl MarginPoolIxBuilder {
     /// `margin_account` - The account being borrowed against
     /// `deposit_account` - The account to receive the notes for the borrowed tokens
     /// `loan_account` - The account to receive the notes representing the debt
-    /// `amount` - The amount of tokens to be borrowed
+    /// `amount` - The amount to be borrowed
     pub fn margin_borrow(
         &self,
         margin_account: Pubkey,
         deposit_account: Pubkey,
         loan_account: Pubkey,
-        amount: u64,
+        amount: Amount,
     ) -> Instruction {
         let accounts = ix_accounts::MarginBorrow {
             margin_account,
             margin_pool: self.address,
+            history: self.history,
             loan_note_mint: self.loan_note_mint,
             deposit_note_mint: self.deposit_note_mint,
             loan_account,
@@ -222,9 +475,17 @@ impl MarginPoolIxBuilder {
         loan_account: Pubkey,
         amount: Amount,
     ) -> Instruction {
+        let deposit_reward_campaign = self.reward_campaign(&self.deposit_note_mint);
+        let loan_reward_campaign = self.reward_campaign(&self.loan_note_mint);
         let accounts = ix_accounts::MarginRepay {
             margin_account,
             margin_pool: self.address,
+            history: self.history,
+            deposit_reward_campaign,
+            deposit_reward_checkpoint: self
+                .reward_checkpoint(&deposit_reward_campaign, &deposit_account),
+            loan_reward_campaign,
+            loan_reward_checkpoint: self.reward_checkpoint(&loan_reward_campaign, &loan_account),
             loan_note_mint: self.loan_note_mint,
             deposit_note_mint: self.deposit_note_mint,
             loan_account,
@@ -240,6 +501,192 @@ impl MarginPoolIxBuilder {
         }
     }
 
+    /// Instruction to repay tokens owed by a margin account, using tokens from
+    /// a wallet rather than the margin account's deposits
+    ///
+    /// # Params
+    ///
+    /// `margin_account` - The account with the loan to be repaid
+    /// `loan_account` - The account with the loan debt to be reduced
+    /// `repayment_account_authority` - The authority for the repayment tokens
+    /// `repayment_token_account` - The token account with the tokens to repay with
+    /// `amount` - The amount to be repaid
+    pub fn repay(
+        &self,
+        margin_account: Pubkey,
+        loan_account: Pubkey,
+        repayment_account_authority: Pubkey,
+        repayment_token_account: Pubkey,
+        amount: Amount,
+    ) -> Instruction {
+        let reward_campaign = self.reward_campaign(&self.loan_note_mint);
+        let accounts = ix_accounts::Repay {
+            margin_account,
+            margin_pool: self.address,
+            history: self.history,
+            reward_campaign,
+            reward_checkpoint: self.reward_checkpoint(&reward_campaign, &loan_account),
+            token_mint: self.token_mint,
+            vault: self.vault,
+            loan_note_mint: self.loan_note_mint,
+            loan_account,
+            repayment_account_authority,
+            repayment_token_account,
+            token_program: Token::id(),
+            pool_token_program: self.token_program,
+        }
+        .to_account_metas(None);
+
+        Instruction {
+            program_id: jet_margin_pool::ID,
+            data: ix_data::Repay { amount }.data(),
+            accounts,
+        }
+    }
+
+    /// Instruction to borrow tokens at a fixed rate for a fixed term, using a
+    /// margin account
+    ///
+    /// # Params
+    ///
+    /// `margin_account` - The account being borrowed against
+    /// `deposit_account` - The account to receive the notes for the borrowed tokens
+    /// `term_loan_account` - The account to receive the notes representing the debt
+    /// `payer` - The address paying rent for the term loan account
+    /// `amount` - The amount of tokens to be borrowed
+    /// `term` - The number of seconds until the loan must be repaid
+    pub fn margin_term_borrow(
+        &self,
+        margin_account: Pubkey,
+        deposit_account: Pubkey,
+        term_loan_account: Pubkey,
+        payer: Pubkey,
+        amount: u64,
+        term: i64,
+    ) -> Instruction {
+        let accounts = ix_accounts::MarginTermBorrow {
+            margin_account,
+            margin_pool: self.address,
+            history: self.history,
+            term_loan: self.term_loan(&margin_account),
+            term_loan_note_mint: self.term_loan_note_mint,
+            deposit_note_mint: self.deposit_note_mint,
+            term_loan_account,
+            deposit_account,
+            payer,
+            token_program: Token::id(),
+            system_program: System::id(),
+        }
+        .to_account_metas(None);
+
+        Instruction {
+            program_id: jet_margin_pool::ID,
+            data: ix_data::MarginTermBorrow { amount, term }.data(),
+            accounts,
+        }
+    }
+
+    /// Instruction to repay a term loan owed by a margin account
+    ///
+    /// # Params
+    ///
+    /// `margin_account` - The account with the term loan to be repaid
+    /// `deposit_account` - The account with notes to repay the loan
+    /// `term_loan_account` - The account with the term loan notes to be repaid
+    /// `owner` - The owner of the margin account, which receives the rent once repaid
+    /// `notes` - The amount of term loan notes to be repaid
+    pub fn margin_term_repay(
+        &self,
+        margin_account: Pubkey,
+        deposit_account: Pubkey,
+        term_loan_account: Pubkey,
+        owner: Pubkey,
+        notes: u64,
+    ) -> Instruction {
+        let accounts = ix_accounts::MarginTermRepay {
+            margin_account,
+            margin_pool: self.address,
+            history: self.history,
+            term_loan: self.term_loan(&margin_account),
+            term_loan_note_mint: self.term_loan_note_mint,
+            deposit_note_mint: self.deposit_note_mint,
+            term_loan_account,
+            deposit_account,
+            owner,
+            token_program: Token::id(),
+        }
+        .to_account_metas(None);
+
+        Instruction {
+            program_id: jet_margin_pool::ID,
+            data: ix_data::MarginTermRepay { notes }.data(),
+            accounts,
+        }
+    }
+
+    /// Instruction to burn all the loan notes held by a margin account, once
+    /// the pool's loans have been written off
+    ///
+    /// # Params
+    ///
+    /// `margin_account` - The account with the written off loan
+    /// `loan_account` - The account with the loan notes to be burned
+    pub fn margin_burn_loan_notes(
+        &self,
+        margin_account: Pubkey,
+        loan_account: Pubkey,
+    ) -> Instruction {
+        let loan_reward_campaign = self.reward_campaign(&self.loan_note_mint);
+        let accounts = ix_accounts::MarginBurnLoanNotes {
+            margin_account,
+            margin_pool: self.address,
+            loan_note_mint: self.loan_note_mint,
+            loan_account,
+            loan_reward_campaign,
+            loan_reward_checkpoint: self.reward_checkpoint(&loan_reward_campaign, &loan_account),
+            token_program: Token::id(),
+        }
+        .to_account_metas(None);
+
+        Instruction {
+            program_id: jet_margin_pool::ID,
+            data: ix_data::MarginBurnLoanNotes {}.data(),
+            accounts,
+        }
+    }
+
+    /// Instruction to burn the notes of a margin account's term loan and close
+    /// the loan, once the pool's loans have been written off
+    ///
+    /// # Params
+    ///
+    /// `margin_account` - The account with the written off term loan
+    /// `term_loan_account` - The account with the term loan notes to be burned
+    /// `owner` - The owner of the margin account, which receives the rent for the loan
+    pub fn margin_burn_term_loan_notes(
+        &self,
+        margin_account: Pubkey,
+        term_loan_account: Pubkey,
+        owner: Pubkey,
+    ) -> Instruction {
+        let accounts = ix_accounts::MarginBurnTermLoanNotes {
+            margin_account,
+            margin_pool: self.address,
+            term_loan: self.term_loan(&margin_account),
+            term_loan_note_mint: self.term_loan_note_mint,
+            term_loan_account,
+            owner,
+            token_program: Token::id(),
+        }
+        .to_account_metas(None);
+
+        Instruction {
+            program_id: jet_margin_pool::ID,
+            data: ix_data::MarginBurnTermLoanNotes {}.data(),
+            accounts,
+        }
+    }
+
     /// Instruction to withdraw tokens from the pool in exchange for deposit notes
     /// (owned by a margin account)
     ///
@@ -256,14 +703,20 @@ impl MarginPoolIxBuilder {
         destination: Pubkey,
         amount: Amount,
     ) -> Instruction {
+        let reward_campaign = self.reward_campaign(&self.deposit_note_mint);
         let accounts = ix_accounts::MarginWithdraw {
             margin_account,
             margin_pool: self.address,
+            history: self.history,
+            reward_campaign,
+            reward_checkpoint: self.reward_checkpoint(&reward_campaign, &source),
+            token_mint: self.token_mint,
             vault: self.vault,
             deposit_note_mint: self.deposit_note_mint,
             source,
             destination,
             token_program: Token::id(),
+            pool_token_program: self.token_program,
         }
         .to_account_metas(None);
 
@@ -279,12 +732,18 @@ impl MarginPoolIxBuilder {
     /// # Params
     ///
     /// `margin_account` - The margin account with the deposit to be withdrawn
-    /// `oracle` - The oracle account for this pool
-    pub fn margin_refresh_position(&self, margin_account: Pubkey, oracle: Pubkey) -> Instruction {
+    /// `token_price_oracle` - The pyth price account for this pool's token
+    pub fn margin_refresh_position(
+        &self,
+        margin_account: Pubkey,
+        token_price_oracle: Pubkey,
+    ) -> Instruction {
         let accounts = ix_accounts::MarginRefreshPosition {
             margin_account,
             margin_pool: self.address,
-            token_price_oracle: oracle,
+            oracle: self.oracle,
+            token_price_oracle,
+            term_loan: self.term_loan(&margin_account),
         }
         .to_account_metas(None);
 
@@ -302,6 +761,7 @@ impl MarginPoolIxBuilder {
             vault: self.vault,
             fee_destination,
             deposit_note_mint: self.deposit_note_mint,
+            history: self.history,
             token_program: Token::id(),
         }
         .to_account_metas(None);
@@ -312,4 +772,278 @@ impl MarginPoolIxBuilder {
             accounts,
         }
     }
+
+    /// Instruction to create the account recording the history of the pool's rates
+    ///
+    /// # Params
+    ///
+    /// `payer` - The address paying for the rent
+    pub fn create_history(&self, payer: Pubkey) -> Instruction {
+        let accounts = ix_accounts::CreatePoolHistory {
+            margin_pool: self.address,
+            history: self.history,
+            payer,
+            system_program: System::id(),
+        }
+        .to_account_metas(None);
+
+        Instruction {
+            program_id: jet_margin_pool::ID,
+            data: ix_data::CreatePoolHistory {}.data(),
+            accounts,
+        }
+    }
+
+    /// Instruction to create the checkpoint for notes an owner stakes in a campaign
+    ///
+    /// # Params
+    ///
+    /// `campaign` - The campaign to earn rewards from
+    /// `owner` - The owner of the notes to be staked
+    /// `payer` - The address paying for the rent
+    pub fn create_reward_checkpoint(
+        &self,
+        campaign: Pubkey,
+        owner: Pubkey,
+        payer: Pubkey,
+    ) -> Instruction {
+        let accounts = ix_accounts::CreateRewardCheckpoint {
+            margin_pool: self.address,
+            campaign,
+            owner,
+            checkpoint: self.reward_checkpoint(&campaign, &owner),
+            payer,
+            system_program: System::id(),
+        }
+        .to_account_metas(None);
+
+        Instruction {
+            program_id: jet_margin_pool::ID,
+            data: ix_data::CreateRewardCheckpoint {}.data(),
+            accounts,
+        }
+    }
+
+    /// Instruction to start earning rewards from a campaign for the notes in a
+    /// margin account position
+    ///
+    /// # Params
+    ///
+    /// `campaign` - The campaign to earn rewards from
+    /// `margin_account` - The margin account holding the notes
+    /// `token_account` - The position token account holding the notes
+    /// `payer` - The address paying for the rent
+    pub fn create_margin_reward_checkpoint(
+        &self,
+        campaign: Pubkey,
+        margin_account: Pubkey,
+        token_account: Pubkey,
+        payer: Pubkey,
+    ) -> Instruction {
+        let accounts = ix_accounts::CreateMarginRewardCheckpoint {
+            margin_pool: self.address,
+            campaign,
+            margin_account,
+            token_account,
+            checkpoint: self.reward_checkpoint(&campaign, &token_account),
+            payer,
+            system_program: System::id(),
+        }
+        .to_account_metas(None);
+
+        Instruction {
+            program_id: jet_margin_pool::ID,
+            data: ix_data::CreateMarginRewardCheckpoint {}.data(),
+            accounts,
+        }
+    }
+
+    /// Instruction to stake notes in a campaign
+    ///
+    /// # Params
+    ///
+    /// `campaign` - The campaign to earn rewards from
+    /// `owner` - The owner of the notes
+    /// `source` - The token account with the notes to be staked
+    /// `notes` - The amount of notes to stake
+    pub fn stake_rewards(
+        &self,
+        campaign: Pubkey,
+        owner: Pubkey,
+        source: Pubkey,
+        notes: u64,
+    ) -> Instruction {
+        let accounts = ix_accounts::StakeRewards {
+            margin_pool: self.address,
+            campaign,
+            checkpoint: self.reward_checkpoint(&campaign, &owner),
+            owner,
+            source,
+            stake_vault: self.reward_stake_vault(&campaign),
+            token_program: Token::id(),
+        }
+        .to_account_metas(None);
+
+        Instruction {
+            program_id: jet_margin_pool::ID,
+            data: ix_data::StakeRewards { notes }.data(),
+            accounts,
+        }
+    }
+
+    /// Instruction to withdraw notes staked in a campaign
+    ///
+    /// # Params
+    ///
+    /// `campaign` - The campaign the notes are staked in
+    /// `owner` - The owner of the staked notes
+    /// `destination` - The token account to receive the notes
+    /// `notes` - The amount of notes to unstake
+    pub fn unstake_rewards(
+        &self,
+        campaign: Pubkey,
+        owner: Pubkey,
+        destination: Pubkey,
+        notes: u64,
+    ) -> Instruction {
+        let accounts = ix_accounts::UnstakeRewards {
+            margin_pool: self.address,
+            campaign,
+            checkpoint: self.reward_checkpoint(&campaign, &owner),
+            owner,
+            stake_vault: self.reward_stake_vault(&campaign),
+            destination,
+            token_program: Token::id(),
+        }
+        .to_account_metas(None);
+
+        Instruction {
+            program_id: jet_margin_pool::ID,
+            data: ix_data::UnstakeRewards { notes }.data(),
+            accounts,
+        }
+    }
+
+    /// Instruction to claim the rewards earned by staked notes
+    ///
+    /// # Params
+    ///
+    /// `campaign` - The campaign to claim rewards from
+    /// `owner` - The owner of the staked notes
+    /// `destination` - The token account to receive the rewards
+    pub fn claim_rewards(
+        &self,
+        campaign: Pubkey,
+        owner: Pubkey,
+        destination: Pubkey,
+    ) -> Instruction {
+        let accounts = ix_accounts::ClaimRewards {
+            margin_pool: self.address,
+            campaign,
+            checkpoint: self.reward_checkpoint(&campaign, &owner),
+            owner,
+            vault: self.reward_vault(&campaign),
+            destination,
+            token_program: Token::id(),
+        }
+        .to_account_metas(None);
+
+        Instruction {
+            program_id: jet_margin_pool::ID,
+            data: ix_data::ClaimRewards {}.data(),
+            accounts,
+        }
+    }
+
+    /// Instruction to claim the rewards earned by notes held in a margin account
+    ///
+    /// # Params
+    ///
+    /// `margin_account` - The margin account holding the notes
+    /// `campaign` - The campaign to claim rewards from
+    /// `token_account` - The position token account holding the notes
+    /// `destination` - The token account to receive the rewards
+    pub fn margin_claim_rewards(
+        &self,
+        margin_account: Pubkey,
+        campaign: Pubkey,
+        token_account: Pubkey,
+        destination: Pubkey,
+    ) -> Instruction {
+        let accounts = ix_accounts::MarginClaimRewards {
+            margin_account,
+            margin_pool: self.address,
+            campaign,
+            checkpoint: self.reward_checkpoint(&campaign, &token_account),
+            token_account,
+            vault: self.reward_vault(&campaign),
+            destination,
+            token_program: Token::id(),
+        }
+        .to_account_metas(None);
+
+        Instruction {
+            program_id: jet_margin_pool::ID,
+            data: ix_data::MarginClaimRewards {}.data(),
+            accounts,
+        }
+    }
+
+    /// Get the address of the campaign rewarding holders of one of the pool's notes
+    pub fn reward_campaign(&self, note_mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                self.address.as_ref(),
+                note_mint.as_ref(),
+                b"reward-campaign".as_ref(),
+            ],
+            &JetMarginPool::id(),
+        )
+        .0
+    }
+
+    /// Get the address of the account funding the rewards for a campaign
+    pub fn reward_vault(&self, campaign: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[campaign.as_ref(), b"reward-vault".as_ref()],
+            &JetMarginPool::id(),
+        )
+        .0
+    }
+
+    /// Get the address of the account holding the notes staked in a campaign
+    pub fn reward_stake_vault(&self, campaign: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[campaign.as_ref(), b"reward-stake".as_ref()],
+            &JetMarginPool::id(),
+        )
+        .0
+    }
+
+    /// Get the address of the rewards checkpoint for a margin account position,
+    /// or for the notes staked by an owner
+    pub fn reward_checkpoint(&self, campaign: &Pubkey, token_account: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                campaign.as_ref(),
+                token_account.as_ref(),
+                b"reward-checkpoint".as_ref(),
+            ],
+            &JetMarginPool::id(),
+        )
+        .0
+    }
+
+    /// Get the address of the term loan account for a margin account
+    pub fn term_loan(&self, margin_account: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                self.address.as_ref(),
+                margin_account.as_ref(),
+                b"term-loan".as_ref(),
+            ],
+            &JetMarginPool::id(),
+        )
+        .0
+    }
 }
@@ -82,6 +82,7 @@ impl MarginSwapIxBuilder {
         amount_in: u64,
         minimum_amount_out: u64,
     ) -> Instruction {
+        let source_reward_campaign = source_pool.reward_campaign(&source_pool.deposit_note_mint);
         let accounts = ix_accounts::MarginSplSwap {
             margin_account,
             source_account: source_margin_position,
@@ -99,15 +100,22 @@ impl MarginSwapIxBuilder {
                 // but it's not clear if we need the program
                 swap_program,
             },
+            source_reward_campaign,
+            source_reward_checkpoint: source_pool
+                .reward_checkpoint(&source_reward_campaign, &source_margin_position),
             source_margin_pool: ix_accounts::MarginPoolInfo {
                 margin_pool: source_pool.address,
+                token_mint: source_pool.token_mint,
                 vault: source_pool.vault,
                 deposit_note_mint: source_pool.deposit_note_mint,
+                history: source_pool.history,
             },
             destination_margin_pool: ix_accounts::MarginPoolInfo {
                 margin_pool: destination_pool.address,
+                token_mint: destination_pool.token_mint,
                 vault: destination_pool.vault,
                 deposit_note_mint: destination_pool.deposit_note_mint,
+                history: destination_pool.history,
             },
             margin_pool_program: jet_margin_pool::id(),
             token_program: Token::id(),
@@ -19,7 +19,7 @@
 
 use std::sync::Arc;
 
-use jet_metadata::{PositionTokenMetadata, TokenMetadata};
+use jet_metadata::{PositionTokenMetadata, TokenKind, TokenMetadata};
 
 use anyhow::{bail, Result};
 use solana_sdk::instruction::Instruction;
@@ -30,10 +30,11 @@ use solana_sdk::transaction::Transaction;
 
 use anchor_lang::AccountDeserialize;
 
-use jet_margin::{MarginAccount, PositionKind};
-use jet_margin_pool::Amount;
+use jet_margin::{AccountPosition, MarginAccount, PositionKind};
+use jet_margin_pool::{Amount, AmountKind};
 use jet_solana_rpc_api::SolanaRpcClient;
 
+use crate::instructions::native;
 use crate::ix_builder::*;
 
 pub struct MarginTxBuilder {
@@ -149,6 +150,11 @@ impl MarginTxBuilder {
     /// `token_mint` - The address of the mint for the tokens being deposited
     /// `source` - The token account that the deposit will be transfered from
     /// `amount` - The amount of tokens to deposit
+    ///
+    /// # Notes
+    ///
+    /// - When depositing native SOL, `source` can be the owner's wallet, in which
+    ///   case the SOL is wrapped for the deposit.
     pub async fn deposit(
         &self,
         token_mint: &Pubkey,
@@ -157,14 +163,34 @@ impl MarginTxBuilder {
     ) -> Result<Transaction> {
         let mut instructions = vec![];
 
-        let pool = MarginPoolIxBuilder::new(*token_mint);
+        let mut pool = MarginPoolIxBuilder::new(*token_mint);
+        pool.token_program = self.get_token_program(token_mint).await?;
+
         let position = self
             .get_or_create_position(&mut instructions, &pool.deposit_note_mint)
             .await?;
 
-        instructions.push(pool.deposit(self.ix.owner, *source, position, amount));
+        let wrap_native = native::is_native_mint(token_mint) && *source == self.ix.owner;
+        let source = match wrap_native {
+            true => {
+                let rent = self.wrapped_sol_rent().await?;
+                instructions.extend(native::wrap_native(&self.ix.owner, amount, rent));
+                native::get_wrapped_sol_address(&self.ix.owner)
+            }
+            false => *source,
+        };
+
+        instructions.push(self.adapter_invoke_ix(pool.margin_deposit(
+            self.ix.address,
+            self.ix.owner,
+            source,
+            position,
+            amount,
+        )));
 
-        instructions.push(self.ix.update_position_balance(position));
+        if wrap_native {
+            instructions.push(native::unwrap_native(&self.ix.owner));
+        }
 
         self.create_transaction(&instructions).await
     }
@@ -174,8 +200,8 @@ impl MarginTxBuilder {
     /// # Params
     ///
     /// `token_mint` - The address of the mint for the tokens to borrow
-    /// `amount` - The amount of tokens to borrow
-    pub async fn borrow(&self, token_mint: &Pubkey, amount: u64) -> Result<Transaction> {
+    /// `amount` - The amount to borrow, as either tokens to receive or loan notes to issue
+    pub async fn borrow(&self, token_mint: &Pubkey, amount: Amount) -> Result<Transaction> {
         let mut instructions = vec![];
         let pool = MarginPoolIxBuilder::new(*token_mint);
         let token_metadata = self.get_token_metadata(token_mint).await?;
@@ -188,7 +214,7 @@ impl MarginTxBuilder {
             .await?;
 
         let inner_refresh_loan_ix =
-            pool.margin_refresh_position(self.ix.address, token_metadata.pyth_price);
+            pool.margin_refresh_position(self.ix.address, token_metadata.price_oracle);
         instructions.push(self.adapter_invoke_ix(inner_refresh_loan_ix));
 
         let inner_borrow_ix =
@@ -204,6 +230,12 @@ impl MarginTxBuilder {
     ///
     /// `token_mint` - The address of the mint for the tokens that were borrowed
     /// `amount` - The amount of tokens to repay
+    ///
+    /// # Notes
+    ///
+    /// - The loan is repaid with the deposits already in the margin account, so
+    ///   native SOL never has to be wrapped. Use [Self::repay_from_wallet] to repay
+    ///   with SOL held by a wallet.
     pub async fn repay(&self, token_mint: &Pubkey, amount: Amount) -> Result<Transaction> {
         let mut instructions = vec![];
         let pool = MarginPoolIxBuilder::new(*token_mint);
@@ -222,12 +254,200 @@ impl MarginTxBuilder {
         self.create_transaction(&instructions).await
     }
 
+    /// Transaction to repay a loan of tokens in a margin account, using tokens
+    /// from a wallet instead of the deposits in the margin account
+    ///
+    /// The tokens are paid by the signer for this builder, which does not need to
+    /// be the owner of the margin account.
+    ///
+    /// # Params
+    ///
+    /// `token_mint` - The address of the mint for the tokens that were borrowed
+    /// `source` - The token account with the tokens to repay with
+    /// `amount` - The amount of tokens to repay
+    pub async fn repay_from_wallet(
+        &self,
+        token_mint: &Pubkey,
+        source: &Pubkey,
+        amount: Amount,
+    ) -> Result<Transaction> {
+        let mut instructions = vec![];
+        let authority = self.signer();
+
+        let mut pool = MarginPoolIxBuilder::new(*token_mint);
+        pool.token_program = self.get_token_program(token_mint).await?;
+
+        let (loan_position, _) = self.ix.get_token_account_address(&pool.loan_note_mint);
+
+        let wrap_native = native::is_native_mint(token_mint) && *source == authority;
+        let source = match wrap_native {
+            true => {
+                if matches!(amount.kind, AmountKind::Notes) {
+                    bail!("repaying from a native SOL wallet requires an amount of tokens");
+                }
+
+                let rent = self.wrapped_sol_rent().await?;
+                instructions.extend(native::wrap_native(&authority, amount.value, rent));
+                native::get_wrapped_sol_address(&authority)
+            }
+            false => *source,
+        };
+
+        let inner_repay_ix = pool.repay(self.ix.address, loan_position, authority, source, amount);
+
+        // Anyone other than the owner can only repay through the permissionless
+        // accounting invocation, which accepts a claim being reduced
+        instructions.push(match self.is_liquidator || authority == self.ix.owner {
+            true => self.adapter_invoke_ix(inner_repay_ix),
+            false => self.ix.accounting_invoke(inner_repay_ix),
+        });
+
+        if wrap_native {
+            instructions.push(native::unwrap_native(&authority));
+        }
+
+        self.create_transaction(&instructions).await
+    }
+
+    /// Transaction to borrow tokens in a margin account, at a fixed rate for a fixed term
+    ///
+    /// # Params
+    ///
+    /// `token_mint` - The address of the mint for the tokens to borrow
+    /// `amount` - The amount of tokens to borrow
+    /// `term` - The number of seconds until the loan must be repaid
+    pub async fn term_borrow(
+        &self,
+        token_mint: &Pubkey,
+        amount: u64,
+        term: i64,
+    ) -> Result<Transaction> {
+        let mut instructions = vec![];
+        let pool = MarginPoolIxBuilder::new(*token_mint);
+        let token_metadata = self.get_token_metadata(token_mint).await?;
+
+        let deposit_position = self
+            .get_or_create_position(&mut instructions, &pool.deposit_note_mint)
+            .await?;
+        let term_loan_position = self
+            .get_or_create_position(&mut instructions, &pool.term_loan_note_mint)
+            .await?;
+
+        let inner_refresh_loan_ix =
+            pool.margin_refresh_position(self.ix.address, token_metadata.price_oracle);
+        instructions.push(self.adapter_invoke_ix(inner_refresh_loan_ix));
+
+        let inner_borrow_ix = pool.margin_term_borrow(
+            self.ix.address,
+            deposit_position,
+            term_loan_position,
+            self.ix.owner,
+            amount,
+            term,
+        );
+
+        instructions.push(self.adapter_invoke_ix(inner_borrow_ix));
+        self.create_transaction(&instructions).await
+    }
+
+    /// Transaction to repay a term loan of tokens in a margin account
+    ///
+    /// # Params
+    ///
+    /// `token_mint` - The address of the mint for the tokens that were borrowed
+    /// `notes` - The amount of term loan notes to repay
+    pub async fn term_repay(&self, token_mint: &Pubkey, notes: u64) -> Result<Transaction> {
+        let mut instructions = vec![];
+        let pool = MarginPoolIxBuilder::new(*token_mint);
+
+        let deposit_position = self
+            .get_or_create_position(&mut instructions, &pool.deposit_note_mint)
+            .await?;
+        let term_loan_position = self
+            .get_or_create_position(&mut instructions, &pool.term_loan_note_mint)
+            .await?;
+
+        let inner_repay_ix = pool.margin_term_repay(
+            self.ix.address,
+            deposit_position,
+            term_loan_position,
+            self.ix.owner,
+            notes,
+        );
+
+        instructions.push(self.adapter_invoke_ix(inner_repay_ix));
+        self.create_transaction(&instructions).await
+    }
+
+    /// Transaction to burn the loan notes held by the margin account, once the
+    /// pool's loans have been written off
+    ///
+    /// # Params
+    ///
+    /// `token_mint` - The address of the mint for the tokens that were borrowed
+    pub async fn burn_loan_notes(&self, token_mint: &Pubkey) -> Result<Transaction> {
+        let pool = MarginPoolIxBuilder::new(*token_mint);
+        let (loan_position, _) = self.ix.get_token_account_address(&pool.loan_note_mint);
+
+        let inner_burn_ix = pool.margin_burn_loan_notes(self.ix.address, loan_position);
+
+        self.create_transaction(&[self.adapter_invoke_ix(inner_burn_ix)])
+            .await
+    }
+
+    /// Transaction to burn the notes of the margin account's term loan, once
+    /// the pool's loans have been written off
+    ///
+    /// # Params
+    ///
+    /// `token_mint` - The address of the mint for the tokens that were borrowed
+    pub async fn burn_term_loan_notes(&self, token_mint: &Pubkey) -> Result<Transaction> {
+        let pool = MarginPoolIxBuilder::new(*token_mint);
+        let (term_loan_position, _) = self.ix.get_token_account_address(&pool.term_loan_note_mint);
+
+        let inner_burn_ix =
+            pool.margin_burn_term_loan_notes(self.ix.address, term_loan_position, self.ix.owner);
+
+        self.create_transaction(&[self.adapter_invoke_ix(inner_burn_ix)])
+            .await
+    }
+
+    /// Transaction to claim the rewards earned by one of the margin account's positions
+    ///
+    /// # Params
+    ///
+    /// `token_mint` - The address of the mint for the pool's tokens
+    /// `note_mint` - The address of the mint for the notes being rewarded
+    /// `destination` - The token account to receive the rewards
+    pub async fn claim_rewards(
+        &self,
+        token_mint: &Pubkey,
+        note_mint: &Pubkey,
+        destination: &Pubkey,
+    ) -> Result<Transaction> {
+        let pool = MarginPoolIxBuilder::new(*token_mint);
+        let campaign = pool.reward_campaign(note_mint);
+        let (position, _) = self.ix.get_token_account_address(note_mint);
+
+        let inner_claim_ix =
+            pool.margin_claim_rewards(self.ix.address, campaign, position, *destination);
+
+        self.create_transaction(&[self.adapter_invoke_ix(inner_claim_ix)])
+            .await
+    }
+
     /// Transaction to withdraw tokens deposited into a margin account
     ///
     /// # Params
     ///
     /// `token_mint` - The address of the mint for the tokens to be withdrawn
+    /// `destination` - The token account to receive the withdrawn tokens
     /// `amount` - The amount of tokens to withdraw
+    ///
+    /// # Notes
+    ///
+    /// - When withdrawing native SOL, `destination` can be the owner's wallet, in
+    ///   which case the SOL is unwrapped after the withdrawal.
     pub async fn withdraw(
         &self,
         token_mint: &Pubkey,
@@ -235,16 +455,32 @@ impl MarginTxBuilder {
         amount: Amount,
     ) -> Result<Transaction> {
         let mut instructions = vec![];
-        let pool = MarginPoolIxBuilder::new(*token_mint);
+        let mut pool = MarginPoolIxBuilder::new(*token_mint);
+        pool.token_program = self.get_token_program(token_mint).await?;
 
         let deposit_position = self
             .get_or_create_position(&mut instructions, &pool.deposit_note_mint)
             .await?;
 
+        let unwrap_native = native::is_native_mint(token_mint) && *destination == self.ix.owner;
+        let destination = match unwrap_native {
+            true => {
+                let rent = self.wrapped_sol_rent().await?;
+                instructions.extend(native::wrap_native(&self.ix.owner, 0, rent));
+                native::get_wrapped_sol_address(&self.ix.owner)
+            }
+            false => *destination,
+        };
+
         let inner_withdraw_ix =
-            pool.margin_withdraw(self.ix.address, deposit_position, *destination, amount);
+            pool.margin_withdraw(self.ix.address, deposit_position, destination, amount);
 
         instructions.push(self.adapter_invoke_ix(inner_withdraw_ix));
+
+        if unwrap_native {
+            instructions.push(native::unwrap_native(&self.ix.owner));
+        }
+
         self.create_transaction(&instructions).await
     }
 
@@ -254,6 +490,9 @@ impl MarginTxBuilder {
     ///
     /// - `transit_source_account` and `transit_destination_account` should be
     ///   created in a separate transaction to avoid packet size limits.
+    /// - The swap only moves tokens between the margin account's positions, so
+    ///   native SOL is swapped as wrapped SOL, with transit accounts for the
+    ///   native mint that are owned by the margin account.
     #[allow(clippy::too_many_arguments)]
     pub async fn swap(
         &self,
@@ -345,7 +584,7 @@ impl MarginTxBuilder {
         let metadata = self.get_token_metadata(token_mint).await?;
         let ix_builder = MarginPoolIxBuilder::new(*token_mint);
         let ix = self.ix.adapter_invoke(
-            ix_builder.margin_refresh_position(self.ix.address, metadata.pyth_price),
+            ix_builder.margin_refresh_position(self.ix.address, metadata.price_oracle),
         );
 
         self.create_transaction(&[ix]).await
@@ -361,9 +600,14 @@ impl MarginTxBuilder {
             let t_metadata = self
                 .get_token_metadata(&p_metadata.underlying_token_mint)
                 .await?;
+
+            if position_metadata_changed(position, &p_metadata) {
+                instructions.push(self.ix.refresh_position_metadata(position.token));
+            }
+
             let ix_builder = MarginPoolIxBuilder::new(p_metadata.underlying_token_mint);
             let ix = self.ix.adapter_invoke(
-                ix_builder.margin_refresh_position(self.ix.address, t_metadata.pyth_price),
+                ix_builder.margin_refresh_position(self.ix.address, t_metadata.price_oracle),
             );
 
             instructions.push(ix);
@@ -375,6 +619,19 @@ impl MarginTxBuilder {
             .collect()
     }
 
+    async fn wrapped_sol_rent(&self) -> Result<u64> {
+        self.rpc
+            .get_minimum_balance_for_rent_exemption(native::wrapped_sol_account_size())
+            .await
+    }
+
+    async fn get_token_program(&self, token_mint: &Pubkey) -> Result<Pubkey> {
+        match self.rpc.get_account(token_mint).await? {
+            None => bail!("no mint {} found", token_mint),
+            Some(account) => Ok(account.owner),
+        }
+    }
+
     async fn get_token_metadata(&self, token_mint: &Pubkey) -> Result<TokenMetadata> {
         let (md_address, _) =
             Pubkey::find_program_address(&[token_mint.as_ref()], &jet_metadata::ID);
@@ -446,3 +703,16 @@ impl MarginTxBuilder {
         }
     }
 }
+
+fn position_metadata_changed(position: &AccountPosition, metadata: &PositionTokenMetadata) -> bool {
+    let kind = match metadata.token_kind {
+        TokenKind::NonCollateral => PositionKind::NoValue,
+        TokenKind::Collateral => PositionKind::Deposit,
+        TokenKind::Claim => PositionKind::Claim,
+    };
+
+    position.kind().ok() != Some(kind)
+        || position.collateral_weight != metadata.collateral_weight
+        || position.collateral_max_staleness != metadata.collateral_max_staleness
+        || position.is_conservative() != metadata.conservative_valuation
+}
@@ -20,24 +20,34 @@
 use std::sync::Arc;
 
 use anchor_lang::{AccountDeserialize, AccountSerialize, InstructionData, ToAccountMetas};
-use anyhow::Error;
+use anyhow::{bail, Error};
 
+use jet_control::{Authority, Governance, Proposal, RegistryKind, TokenMetadataParams};
 use jet_margin::PositionKind;
-use jet_margin_sdk::accounts::MarginPoolAccounts;
+use jet_margin_sdk::accounts::{get_registry, MarginPoolAccounts};
 use jet_margin_sdk::instructions::control::{get_authority_address, TokenConfiguration};
 use solana_sdk::instruction::Instruction;
 use solana_sdk::signature::{Keypair, Signer};
 use solana_sdk::system_program;
 use solana_sdk::{pubkey::Pubkey, transaction::Transaction};
 
-use jet_margin_pool::{Amount, MarginPool, MarginPoolConfig};
+use jet_margin_pool::{
+    Amount, MarginPool, MarginPoolConfig, MarginPoolOracle, RewardCampaignParams,
+};
 use jet_margin_sdk::tx_builder::MarginTxBuilder;
-use jet_metadata::{LiquidatorMetadata, MarginAdapterMetadata, TokenKind, TokenMetadata};
+use jet_metadata::{LiquidationAdapterPolicy, MarginAdapterMetadata, TokenKind, TokenMetadata};
 use jet_solana_rpc_api::SolanaRpcClient;
 
 use crate::swap::SwapPool;
 use crate::{send_and_confirm, tokens::TokenOracle};
 
+/// The largest amount of metadata written in a single transaction
+const METADATA_CHUNK_SIZE: usize = 512;
+
+/// The risk tier created along with the authority, which leaves new pools
+/// unconfigured, as non-collateral with no interest
+pub const DEFAULT_RISK_TIER: &str = "default";
+
 /// Information needed to create a new margin pool
 pub struct MarginPoolSetupInfo {
     pub token: Pubkey,
@@ -88,23 +98,118 @@ impl MarginClient {
         })
     }
 
-    /// Find all the margin pools created
+    /// Act on another user's margin account, with a keypair that isn't the owner
+    /// of the account, so only permissionless actions can be taken
+    pub async fn third_party(
+        &self,
+        keypair: &Keypair,
+        owner: &Pubkey,
+    ) -> Result<MarginUser, Error> {
+        let tx = MarginTxBuilder::new(
+            self.rpc.clone(),
+            Some(Keypair::from_bytes(&keypair.to_bytes())?),
+            *owner,
+            0,
+            false,
+        );
+
+        Ok(MarginUser {
+            tx,
+            rpc: self.rpc.clone(),
+        })
+    }
+
+    /// Find all the margin pools for the registered tokens
     pub async fn find_pools(&self) -> Result<Vec<MarginPool>, Error> {
-        self.rpc
-            .get_program_accounts(
-                &jet_margin_pool::ID,
-                Some(std::mem::size_of::<MarginPool>()),
-            )
-            .await?
-            .into_iter()
-            .map(|(_, account)| {
-                MarginPool::try_deserialize(&mut &account.data[..]).map_err(Error::from)
-            })
-            .collect()
+        let registry = get_registry(self.rpc.as_ref(), RegistryKind::Tokens).await?;
+        let mut pools = Vec::with_capacity(registry.entries.len());
+
+        for token_mint in registry.entries {
+            let address = MarginPoolAccounts::derive_from_token(token_mint).address;
+
+            match self.rpc.get_account(&address).await? {
+                None => bail!("no pool {} found for token {}", address, token_mint),
+                Some(account) => pools.push(MarginPool::try_deserialize(&mut &account.data[..])?),
+            }
+        }
+
+        Ok(pools)
+    }
+
+    pub async fn get_pool(&self, token: &Pubkey) -> Result<MarginPool, Error> {
+        let address = MarginPoolAccounts::derive_from_token(*token).address;
+
+        match self.rpc.get_account(&address).await? {
+            None => bail!("no pool {} found for token {}", address, token),
+            Some(account) => Ok(MarginPool::try_deserialize(&mut &account.data[..])?),
+        }
+    }
+
+    /// Migrate a pool created before the pool had term loans
+    pub async fn migrate_pool(&self, token: &Pubkey) -> Result<(), Error> {
+        let ix = jet_margin_sdk::ix_builder::MarginPoolIxBuilder::new(*token)
+            .migrate(self.rpc.payer().pubkey());
+
+        send_and_confirm(&self.rpc, &[ix], &[]).await?;
+        Ok(())
+    }
+
+    pub async fn get_pool_oracle(&self, token: &Pubkey) -> Result<MarginPoolOracle, Error> {
+        let address = MarginPoolAccounts::derive_from_token(*token).oracle;
+
+        match self.rpc.get_account(&address).await? {
+            None => bail!("no oracle cache {} found for token {}", address, token),
+            Some(account) => Ok(MarginPoolOracle::try_deserialize(&mut &account.data[..])?),
+        }
     }
 
+    /// Create the control authority, along with its registries and the
+    /// default risk tier
     pub async fn create_authority(&self) -> Result<(), Error> {
-        let ix = jet_margin_sdk::instructions::control::create_authority(self.rpc.payer().pubkey());
+        let payer = self.rpc.payer().pubkey();
+        let mut instructions = vec![jet_margin_sdk::instructions::control::create_authority(
+            payer,
+        )];
+
+        for kind in [
+            RegistryKind::Tokens,
+            RegistryKind::Adapters,
+            RegistryKind::Liquidators,
+            RegistryKind::LiquidationAdapters,
+        ] {
+            instructions.push(jet_margin_sdk::instructions::control::create_registry(
+                &payer, &payer, kind,
+            ));
+        }
+
+        instructions.push(jet_margin_sdk::instructions::control::create_risk_tier(
+            &payer,
+            &payer,
+            DEFAULT_RISK_TIER,
+            MarginPoolConfig::default(),
+            TokenMetadataParams::default(),
+        ));
+
+        send_and_confirm(&self.rpc, &instructions, &[]).await?;
+        Ok(())
+    }
+
+    /// Migrate an authority account created before it stored the root authority
+    pub async fn migrate_authority(&self) -> Result<(), Error> {
+        let ix =
+            jet_margin_sdk::instructions::control::migrate_authority(self.rpc.payer().pubkey());
+
+        send_and_confirm(&self.rpc, &[ix], &[]).await?;
+        Ok(())
+    }
+
+    /// Rewrite a metadata entry with the current version of its layout
+    pub async fn migrate_metadata(&self, metadata_account: &Pubkey) -> Result<(), Error> {
+        let ix = jet_margin_sdk::instructions::control::migrate_metadata(
+            metadata_account,
+            &self.rpc.payer().pubkey(),
+            &self.rpc.payer().pubkey(),
+        );
 
         send_and_confirm(&self.rpc, &[ix], &[]).await?;
         Ok(())
@@ -121,6 +226,39 @@ impl MarginClient {
         Ok(())
     }
 
+    pub async fn deregister_adapter(&self, adapter: &Pubkey) -> Result<(), Error> {
+        let ix = jet_margin_sdk::instructions::control::deregister_adapter(
+            adapter,
+            &self.rpc.payer().pubkey(),
+            &self.rpc.payer().pubkey(),
+        );
+
+        send_and_confirm(&self.rpc, &[ix], &[]).await?;
+        Ok(())
+    }
+
+    pub async fn register_liquidation_adapter(&self, adapter: &Pubkey) -> Result<(), Error> {
+        let ix = jet_margin_sdk::instructions::control::register_liquidation_adapter(
+            adapter,
+            &self.rpc.payer().pubkey(),
+            &self.rpc.payer().pubkey(),
+        );
+
+        send_and_confirm(&self.rpc, &[ix], &[]).await?;
+        Ok(())
+    }
+
+    pub async fn deregister_liquidation_adapter(&self, adapter: &Pubkey) -> Result<(), Error> {
+        let ix = jet_margin_sdk::instructions::control::deregister_liquidation_adapter(
+            adapter,
+            &self.rpc.payer().pubkey(),
+            &self.rpc.payer().pubkey(),
+        );
+
+        send_and_confirm(&self.rpc, &[ix], &[]).await?;
+        Ok(())
+    }
+
     pub async fn configure_token(
         &self,
         token: &Pubkey,
@@ -140,13 +278,8 @@ impl MarginClient {
 
     /// Create a new margin pool for a token
     pub async fn create_pool(&self, setup_info: &MarginPoolSetupInfo) -> Result<(), Error> {
-        let pool = MarginPoolAccounts::derive_from_token(setup_info.token);
-        let ix = jet_margin_sdk::instructions::control::register_token(
-            &pool,
-            &self.rpc.payer().pubkey(),
-        );
-
-        send_and_confirm(&self.rpc, &[ix], &[]).await?;
+        self.register_token(&setup_info.token, DEFAULT_RISK_TIER)
+            .await?;
 
         //self.set_position_token_metadata(
         //    jet_margin_pool::ID,
@@ -169,14 +302,259 @@ impl MarginClient {
         Ok(())
     }
 
-    pub async fn set_liquidator_metadata(&self, liquidator: Pubkey) -> Result<(), Error> {
-        let metadata = LiquidatorMetadata { liquidator };
+    /// Register a token, creating its pool with the parameters of the risk tier
+    pub async fn register_token(&self, token: &Pubkey, risk_tier: &str) -> Result<(), Error> {
+        let mut pool = MarginPoolAccounts::derive_from_token(*token);
+
+        if let Some(mint) = self.rpc.get_account(token).await? {
+            pool.token_program = mint.owner;
+        }
+
+        let ix = jet_margin_sdk::instructions::control::register_token(
+            &pool,
+            &self.rpc.payer().pubkey(),
+            &self.rpc.payer().pubkey(),
+            risk_tier,
+        );
+
+        send_and_confirm(&self.rpc, &[ix], &[]).await?;
+        Ok(())
+    }
+
+    pub async fn create_risk_tier(
+        &self,
+        name: &str,
+        pool_config: MarginPoolConfig,
+        token_params: TokenMetadataParams,
+    ) -> Result<(), Error> {
+        let ix = jet_margin_sdk::instructions::control::create_risk_tier(
+            &self.rpc.payer().pubkey(),
+            &self.rpc.payer().pubkey(),
+            name,
+            pool_config,
+            token_params,
+        );
+
+        send_and_confirm(&self.rpc, &[ix], &[]).await?;
+        Ok(())
+    }
+
+    /// Begin retiring the margin pool for a token
+    pub async fn begin_pool_retirement(&self, token: &Pubkey) -> Result<(), Error> {
+        let pool = MarginPoolAccounts::derive_from_token(*token);
+        let ix = jet_margin_sdk::instructions::control::begin_pool_retirement(
+            &pool,
+            &self.rpc.payer().pubkey(),
+        );
 
-        self.set_metadata(liquidator, &metadata).await
+        send_and_confirm(&self.rpc, &[ix], &[]).await?;
+        Ok(())
+    }
+
+    /// Create a campaign distributing `reward_mint` to holders of one of a pool's notes
+    pub async fn create_reward_campaign(
+        &self,
+        token: &Pubkey,
+        note_mint: &Pubkey,
+        reward_mint: &Pubkey,
+        params: RewardCampaignParams,
+    ) -> Result<(), Error> {
+        let pool = MarginPoolAccounts::derive_from_token(*token);
+        let ix = jet_margin_sdk::instructions::control::create_reward_campaign(
+            &pool,
+            &self.rpc.payer().pubkey(),
+            note_mint,
+            reward_mint,
+            &self.rpc.payer().pubkey(),
+            params,
+        );
+
+        send_and_confirm(&self.rpc, &[ix], &[]).await?;
+        Ok(())
+    }
+
+    /// Settle the outstanding loans of a retiring margin pool
+    pub async fn settle_pool_retirement(&self, token: &Pubkey) -> Result<(), Error> {
+        let pool = MarginPoolAccounts::derive_from_token(*token);
+        let ix = jet_margin_sdk::instructions::control::settle_pool_retirement(
+            &pool,
+            &self.rpc.payer().pubkey(),
+        );
+
+        send_and_confirm(&self.rpc, &[ix], &[]).await?;
+        Ok(())
+    }
+
+    /// Write off the loans of a settled pool that were never repaid
+    pub async fn write_off_pool_loans(&self, token: &Pubkey) -> Result<(), Error> {
+        let pool = MarginPoolAccounts::derive_from_token(*token);
+        let ix = jet_margin_sdk::instructions::control::write_off_pool_loans(
+            &pool,
+            &self.rpc.payer().pubkey(),
+        );
+
+        send_and_confirm(&self.rpc, &[ix], &[]).await?;
+        Ok(())
+    }
+
+    /// Close a retired margin pool, sending any tokens left in the vault to
+    /// the `remainder` account
+    pub async fn close_pool(&self, token: &Pubkey, remainder: &Pubkey) -> Result<(), Error> {
+        let pool = MarginPoolAccounts::derive_from_token(*token);
+        let ix = jet_margin_sdk::instructions::control::close_pool(
+            &pool,
+            &self.rpc.payer().pubkey(),
+            remainder,
+            &self.rpc.payer().pubkey(),
+        );
+
+        send_and_confirm(&self.rpc, &[ix], &[]).await?;
+        Ok(())
+    }
+
+    pub async fn get_authority(&self) -> Result<Authority, Error> {
+        let account = match self.rpc.get_account(&get_authority_address()).await? {
+            None => bail!("authority account does not exist"),
+            Some(account) => account,
+        };
+
+        Ok(Authority::try_deserialize(&mut &account.data[..])?)
+    }
+
+    /// Propose a new root authority, which has to accept before it takes effect
+    pub async fn propose_root_authority(&self, new_root: &Pubkey) -> Result<(), Error> {
+        let ix = jet_margin_sdk::instructions::control::propose_root_authority(
+            &self.rpc.payer().pubkey(),
+            new_root,
+        );
+
+        send_and_confirm(&self.rpc, &[ix], &[]).await?;
+        Ok(())
+    }
+
+    pub async fn accept_root_authority(&self, new_root: &Keypair) -> Result<(), Error> {
+        let ix = jet_margin_sdk::instructions::control::accept_root_authority(&new_root.pubkey());
+
+        send_and_confirm(&self.rpc, &[ix], &[new_root]).await?;
+        Ok(())
+    }
+
+    /// Create the council that governs the control program
+    pub async fn create_governance(
+        &self,
+        council: &[Pubkey],
+        threshold: u8,
+        timelock: i64,
+    ) -> Result<(), Error> {
+        let ix = jet_margin_sdk::instructions::control::create_governance(
+            &self.rpc.payer().pubkey(),
+            &self.rpc.payer().pubkey(),
+            council.to_vec(),
+            threshold,
+            timelock,
+        );
+
+        send_and_confirm(&self.rpc, &[ix], &[]).await?;
+        Ok(())
+    }
+
+    pub async fn get_governance(&self) -> Result<Governance, Error> {
+        let address = jet_margin_sdk::instructions::control::get_governance_address();
+        let account = match self.rpc.get_account(&address).await? {
+            None => bail!("governance account does not exist"),
+            Some(account) => account,
+        };
+
+        Ok(Governance::try_deserialize(&mut &account.data[..])?)
+    }
+
+    pub async fn get_proposal(&self, proposal_id: u64) -> Result<Proposal, Error> {
+        let address = jet_margin_sdk::instructions::control::get_proposal_address(proposal_id);
+        let account = match self.rpc.get_account(&address).await? {
+            None => bail!("proposal {} does not exist", proposal_id),
+            Some(account) => account,
+        };
+
+        Ok(Proposal::try_deserialize(&mut &account.data[..])?)
+    }
+
+    /// Propose a control program instruction to the council, returning the
+    /// id of the new proposal
+    pub async fn create_proposal(
+        &self,
+        proposer: &Keypair,
+        instruction: &Instruction,
+    ) -> Result<u64, Error> {
+        let proposal_id = self.get_governance().await?.proposal_count;
+        let ix = jet_margin_sdk::instructions::control::create_proposal(
+            &proposer.pubkey(),
+            &self.rpc.payer().pubkey(),
+            proposal_id,
+            instruction,
+        );
+
+        send_and_confirm(&self.rpc, &[ix], &[proposer]).await?;
+        Ok(proposal_id)
+    }
+
+    pub async fn approve_proposal(&self, member: &Keypair, proposal_id: u64) -> Result<(), Error> {
+        let ix =
+            jet_margin_sdk::instructions::control::approve_proposal(&member.pubkey(), proposal_id);
+
+        send_and_confirm(&self.rpc, &[ix], &[member]).await?;
+        Ok(())
+    }
+
+    pub async fn execute_proposal(&self, proposal_id: u64) -> Result<(), Error> {
+        let proposal = self.get_proposal(proposal_id).await?;
+        let ix = jet_margin_sdk::instructions::control::execute_proposal(&proposal);
+
+        send_and_confirm(&self.rpc, &[ix], &[]).await?;
+        Ok(())
+    }
+
+    /// Add an address registered before the registries were created to the
+    /// registry of its kind
+    pub async fn add_registry_entry(&self, kind: RegistryKind, key: &Pubkey) -> Result<(), Error> {
+        let ix = jet_margin_sdk::instructions::control::add_registry_entry(
+            &self.rpc.payer().pubkey(),
+            kind,
+            key,
+        );
+
+        send_and_confirm(&self.rpc, &[ix], &[]).await?;
+        Ok(())
+    }
+
+    pub async fn register_liquidator(
+        &self,
+        liquidator: &Pubkey,
+        adapter_policy: LiquidationAdapterPolicy,
+    ) -> Result<(), Error> {
+        let ix = jet_margin_sdk::instructions::control::register_liquidator(
+            liquidator,
+            &self.rpc.payer().pubkey(),
+            &self.rpc.payer().pubkey(),
+            adapter_policy,
+        );
+
+        send_and_confirm(&self.rpc, &[ix], &[]).await?;
+        Ok(())
+    }
+
+    pub async fn revoke_liquidator(&self, liquidator: &Pubkey) -> Result<(), Error> {
+        let ix = jet_margin_sdk::instructions::control::revoke_liquidator(
+            liquidator,
+            &self.rpc.payer().pubkey(),
+        );
+
+        send_and_confirm(&self.rpc, &[ix], &[]).await?;
+        Ok(())
     }
 
     pub async fn set_adapter_metadata(&self, program: Pubkey) -> Result<(), Error> {
         let metadata = MarginAdapterMetadata {
+            version: MarginAdapterMetadata::VERSION,
             adapter_program: program,
         };
 
@@ -221,15 +599,14 @@ impl MarginClient {
         let mut data = vec![];
         metadata.try_serialize(&mut data)?;
 
-        // FIXME: support metadata >512 bytes
-        data.resize(std::cmp::min(8 + std::mem::size_of::<T>(), 512), 0);
+        data.resize(8 + std::mem::size_of::<T>(), 0);
 
         let (md_address, _) = Pubkey::find_program_address(&[key.as_ref()], &jet_metadata::ID);
         let ix_create = Instruction {
             program_id: jet_metadata::ID,
             data: jet_metadata::instruction::CreateEntry {
                 seed: String::new(),
-                space: 8 + std::mem::size_of::<T>() as u64,
+                space: data.len() as u64,
             }
             .data(),
             accounts: jet_metadata::accounts::CreateEntry {
@@ -241,17 +618,29 @@ impl MarginClient {
             }
             .to_account_metas(None),
         };
-        let ix_set = Instruction {
-            program_id: jet_metadata::ID,
-            data: jet_metadata::instruction::SetEntry { offset: 0, data }.data(),
-            accounts: jet_metadata::accounts::SetEntry {
-                authority: get_authority_address(),
-                metadata_account: md_address,
-            }
-            .to_account_metas(None),
-        };
 
-        send_and_confirm(&self.rpc, &[ix_create, ix_set], &[]).await?;
+        // the data has to fit within a transaction, so larger entries are
+        // written in chunks after the first
+        let mut instructions = vec![ix_create];
+
+        for (i, chunk) in data.chunks(METADATA_CHUNK_SIZE).enumerate() {
+            instructions.push(Instruction {
+                program_id: jet_metadata::ID,
+                data: jet_metadata::instruction::SetEntry {
+                    offset: (i * METADATA_CHUNK_SIZE) as u64,
+                    data: chunk.to_vec(),
+                }
+                .data(),
+                accounts: jet_metadata::accounts::SetEntry {
+                    authority: get_authority_address(),
+                    metadata_account: md_address,
+                }
+                .to_account_metas(None),
+            });
+
+            send_and_confirm(&self.rpc, &instructions, &[]).await?;
+            instructions.clear();
+        }
 
         Ok(())
     }
@@ -329,7 +718,7 @@ impl MarginUser {
             .await
     }
 
-    pub async fn borrow(&self, mint: &Pubkey, amount: u64) -> Result<(), Error> {
+    pub async fn borrow(&self, mint: &Pubkey, amount: Amount) -> Result<(), Error> {
         self.send_confirm_tx(self.tx.borrow(mint, amount).await?)
             .await
     }
@@ -339,6 +728,46 @@ impl MarginUser {
             .await
     }
 
+    pub async fn repay_from_wallet(
+        &self,
+        mint: &Pubkey,
+        source: &Pubkey,
+        amount: Amount,
+    ) -> Result<(), Error> {
+        self.send_confirm_tx(self.tx.repay_from_wallet(mint, source, amount).await?)
+            .await
+    }
+
+    pub async fn term_borrow(&self, mint: &Pubkey, amount: u64, term: i64) -> Result<(), Error> {
+        self.send_confirm_tx(self.tx.term_borrow(mint, amount, term).await?)
+            .await
+    }
+
+    pub async fn term_repay(&self, mint: &Pubkey, notes: u64) -> Result<(), Error> {
+        self.send_confirm_tx(self.tx.term_repay(mint, notes).await?)
+            .await
+    }
+
+    pub async fn burn_loan_notes(&self, mint: &Pubkey) -> Result<(), Error> {
+        self.send_confirm_tx(self.tx.burn_loan_notes(mint).await?)
+            .await
+    }
+
+    pub async fn burn_term_loan_notes(&self, mint: &Pubkey) -> Result<(), Error> {
+        self.send_confirm_tx(self.tx.burn_term_loan_notes(mint).await?)
+            .await
+    }
+
+    pub async fn claim_rewards(
+        &self,
+        mint: &Pubkey,
+        note_mint: &Pubkey,
+        destination: &Pubkey,
+    ) -> Result<(), Error> {
+        self.send_confirm_tx(self.tx.claim_rewards(mint, note_mint, destination).await?)
+            .await
+    }
+
     /// Swap between two tokens using a swap pool.
     ///
     /// The `source_mint` and `destination_mint` determine the direction of
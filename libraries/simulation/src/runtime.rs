@@ -32,6 +32,7 @@ use solana_sdk::msg;
 use solana_sdk::native_token::LAMPORTS_PER_SOL;
 use solana_sdk::packet::PACKET_DATA_SIZE;
 use solana_sdk::program_error::ProgramError;
+use solana_sdk::program_pack::Pack;
 use solana_sdk::program_stubs::{set_syscall_stubs, SyscallStubs};
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::{Keypair, Signature};
@@ -61,6 +62,10 @@ impl TestRuntime {
             .entry(spl_token::ID)
             .or_insert_with(|| Box::new(spl_token::processor::Processor::process));
 
+        programs
+            .entry(spl_token_2022::ID)
+            .or_insert_with(|| Box::new(spl_token_2022::processor::Processor::process));
+
         programs
             .entry(spl_token_swap::ID)
             .or_insert_with(|| Box::new(spl_token_swap::processor::Processor::process));
@@ -89,6 +94,21 @@ impl TestRuntime {
             0,
         );
 
+        // The native mint always exists, so that SOL can be wrapped into token accounts
+        let mut native_mint = vec![0; spl_token::state::Mint::LEN];
+        spl_token::state::Mint {
+            decimals: spl_token::native_mint::DECIMALS,
+            is_initialized: true,
+            ..Default::default()
+        }
+        .pack_into_slice(&mut native_mint);
+        ctx.create_account(
+            spl_token::native_mint::ID,
+            spl_token::ID,
+            native_mint,
+            solana_sdk::rent::Rent::default().minimum_balance(spl_token::state::Mint::LEN),
+        );
+
         let runtime = Box::new(RuntimeStubs(ctx.clone()));
 
         set_syscall_stubs(runtime);
@@ -160,6 +180,8 @@ impl TestRuntime {
             self.invoke(&instruction).map_err(|e| (i, e))?;
         }
 
+        self.0.purge_closed_accounts(&message.account_keys);
+
         Ok(self.0.create_signature(true))
     }
 
@@ -321,6 +343,23 @@ impl RuntimeContext {
         }
     }
 
+    /// Reset any accounts that were closed by having their lamports drained, so
+    /// their addresses can be reused like they would be on chain
+    fn purge_closed_accounts(&self, addresses: &[Pubkey]) {
+        let accounts = self.accounts.lock().unwrap();
+
+        for address in addresses {
+            if let Some(account) = accounts.get(address) {
+                let mut account = account.borrow_mut();
+
+                if account.lamports == 0 && !system_program::check_id(&account.owner) {
+                    account.data.clear();
+                    account.owner = SYSTEM_PROGRAM_ID;
+                }
+            }
+        }
+    }
+
     fn handle_system_instruction(
         &self,
         instr: &Instruction,
@@ -335,6 +374,21 @@ impl RuntimeContext {
                 lamports,
             } => self.system_create_account(account_infos, owner, space, lamports),
 
+            SystemInstruction::CreateAccountWithSeed {
+                base,
+                seed,
+                lamports,
+                space,
+                owner,
+            } => self.system_create_account_with_seed(
+                account_infos,
+                base,
+                &seed,
+                owner,
+                space,
+                lamports,
+            ),
+
             SystemInstruction::Transfer { lamports } => {
                 self.system_transfer(account_infos, lamports)
             }
@@ -360,14 +414,50 @@ impl RuntimeContext {
         Ok(())
     }
 
+    fn system_create_account_with_seed(
+        &self,
+        accounts: &[AccountInfo],
+        base: Pubkey,
+        seed: &str,
+        owner: Pubkey,
+        space: u64,
+        lamports: u64,
+    ) -> Result<(), ProgramError> {
+        if !accounts.iter().any(|a| *a.key == base && a.is_signer) {
+            msg!("create_account_with_seed: base {:?} must sign", base);
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let address = Pubkey::create_with_seed(&base, seed, &owner)
+            .map_err(|_| ProgramError::InvalidSeeds)?;
+
+        if address != *accounts[1].key {
+            msg!(
+                "create_account_with_seed: address {:?} does not match the seed",
+                accounts[1].key
+            );
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        self.allocate(&accounts[1], space)?;
+        self.system_assign(&accounts[1..], owner)?;
+        self.system_transfer(accounts, lamports)?;
+
+        Ok(())
+    }
+
     fn system_allocate(&self, accounts: &[AccountInfo], space: u64) -> Result<(), ProgramError> {
         if !accounts[0].is_signer {
             msg!("allocate: account {:?} must sign", accounts[0].key);
             return Err(ProgramError::MissingRequiredSignature);
         }
 
-        if !accounts[0].data_is_empty() || !system_program::check_id(accounts[0].owner) {
-            msg!("allocate: account {:?} in use", accounts[0].key);
+        self.allocate(&accounts[0], space)
+    }
+
+    fn allocate(&self, account: &AccountInfo, space: u64) -> Result<(), ProgramError> {
+        if !account.data_is_empty() || !system_program::check_id(account.owner) {
+            msg!("allocate: account {:?} in use", account.key);
             return Err(ProgramError::AccountAlreadyInitialized);
         }
 
@@ -378,9 +468,9 @@ impl RuntimeContext {
 
         let mut accounts_db = self.accounts.lock().unwrap();
 
-        if !accounts_db.contains_key(accounts[0].owner) {
+        if !accounts_db.contains_key(account.owner) {
             accounts_db.insert(
-                *accounts[0].key,
+                *account.key,
                 RefCell::new(StoredAccount {
                     data: vec![0u8; space as usize],
                     owner: SYSTEM_PROGRAM_ID,
@@ -395,15 +485,13 @@ impl RuntimeContext {
             // make sure the accounts map isn't reallocated
             assert!(accounts_db.len() <= ACCOUNT_TABLE_SIZE);
 
-            let stored_account = accounts_db.get(accounts[0].key).unwrap();
+            let stored_account = accounts_db.get(account.key).unwrap();
             let stored_account_ref = &mut *stored_account.as_ptr();
 
             stored_account_ref.data.resize(space as usize, 0u8);
 
-            accounts[0].data.replace(&mut stored_account_ref.data);
-            accounts[0]
-                .lamports
-                .replace(&mut stored_account_ref.lamports);
+            account.data.replace(&mut stored_account_ref.data);
+            account.lamports.replace(&mut stored_account_ref.lamports);
         }
 
         Ok(())
@@ -29,6 +29,9 @@ use solana_sdk::{system_instruction, system_program};
 
 use anchor_lang::{InstructionData, ToAccountMetas};
 
+use spl_token_2022::extension::{transfer_fee, ExtensionType, StateWithExtensions};
+use spl_token_2022::state::{Account as Account2022, Mint as Mint2022};
+
 use jet_solana_rpc_api::SolanaRpcClient;
 
 use crate::send_and_confirm;
@@ -86,11 +89,79 @@ impl TokenManager {
         Ok(keypair.pubkey())
     }
 
+    /// Create a new token-2022 mint with some extensions, where the payer is the
+    /// authority for the mint and any of its extensions.
+    ///
+    /// # Params
+    ///
+    /// `decimals` - the number of decimal places the mint should have
+    /// `extensions` - the extensions to initialize the mint with
+    pub async fn create_token_2022(
+        &self,
+        decimals: u8,
+        extensions: &MintExtensions,
+    ) -> Result<Pubkey, Error> {
+        let keypair = crate::generate_keypair();
+        let payer = self.rpc.payer();
+
+        let mut extension_types = vec![];
+        let mut ix_extensions = vec![];
+
+        if let Some((basis_points, maximum_fee)) = extensions.transfer_fee {
+            extension_types.push(ExtensionType::TransferFeeConfig);
+            ix_extensions.push(transfer_fee::instruction::initialize_transfer_fee_config(
+                &spl_token_2022::ID,
+                &keypair.pubkey(),
+                Some(&payer.pubkey()),
+                Some(&payer.pubkey()),
+                basis_points,
+                maximum_fee,
+            )?);
+        }
+
+        if extensions.close_authority {
+            extension_types.push(ExtensionType::MintCloseAuthority);
+            ix_extensions.push(
+                spl_token_2022::instruction::initialize_mint_close_authority(
+                    &spl_token_2022::ID,
+                    &keypair.pubkey(),
+                    Some(&payer.pubkey()),
+                )?,
+            );
+        }
+
+        let space = ExtensionType::get_account_len::<Mint2022>(&extension_types);
+        let rent_lamports = self
+            .rpc
+            .get_minimum_balance_for_rent_exemption(space)
+            .await?;
+
+        let mut instructions = vec![system_instruction::create_account(
+            &payer.pubkey(),
+            &keypair.pubkey(),
+            rent_lamports,
+            space as u64,
+            &spl_token_2022::ID,
+        )];
+        instructions.extend(ix_extensions);
+        instructions.push(spl_token_2022::instruction::initialize_mint(
+            &spl_token_2022::ID,
+            &keypair.pubkey(),
+            &payer.pubkey(),
+            None,
+            decimals,
+        )?);
+
+        send_and_confirm(&self.rpc, &instructions, &[&keypair]).await?;
+
+        Ok(keypair.pubkey())
+    }
+
     /// Create a new token account belonging to the owner, with the supplied mint
     pub async fn create_account(&self, mint: &Pubkey, owner: &Pubkey) -> Result<Pubkey, Error> {
         let keypair = crate::generate_keypair();
         let payer = self.rpc.payer();
-        let space = spl_token::state::Account::LEN;
+        let (token_program, space) = self.get_token_program(mint).await?;
         let rent_lamports = self
             .rpc
             .get_minimum_balance_for_rent_exemption(space)
@@ -101,11 +172,11 @@ impl TokenManager {
             &keypair.pubkey(),
             rent_lamports,
             space as u64,
-            &spl_token::ID,
+            &token_program,
         );
 
-        let ix_initialize = spl_token::instruction::initialize_account(
-            &spl_token::ID,
+        let ix_initialize = spl_token_2022::instruction::initialize_account(
+            &token_program,
             &keypair.pubkey(),
             mint,
             owner,
@@ -131,6 +202,15 @@ impl TokenManager {
 
     /// Create oracle accounts for a token
     pub async fn create_oracle(&self, mint: &Pubkey) -> Result<TokenOracle, Error> {
+        self.create_oracle_with_quote(mint, "USD").await
+    }
+
+    /// Create oracle accounts for a token, with prices quoted in another currency
+    pub async fn create_oracle_with_quote(
+        &self,
+        mint: &Pubkey,
+        quote_currency: &str,
+    ) -> Result<TokenOracle, Error> {
         let payer = self.rpc.payer();
         let (price_address, price_bump) = Pubkey::find_program_address(
             &[mint.as_ref(), b"oracle:price".as_ref()],
@@ -177,12 +257,12 @@ impl TokenManager {
 
         write_pyth_product_attributes(
             &mut product_account.attr,
-            &[("asset_type", "Crypto"), ("quote_currency", "USD")],
+            &[("asset_type", "Crypto"), ("quote_currency", quote_currency)],
         );
 
         self.set_pod_metadata(&product_address, &product_account)
             .await?;
-        self.set_pod_metadata(&price_address, &default_price())
+        self.set_pod_metadata(&price_address, &default_price(&product_address))
             .await?;
 
         Ok(TokenOracle {
@@ -191,6 +271,68 @@ impl TokenManager {
         })
     }
 
+    /// Create a switchboard aggregator with an initial price
+    pub async fn create_switchboard_oracle(&self, price: &TokenPrice) -> Result<Pubkey, Error> {
+        let keypair = crate::generate_keypair();
+        let payer = self.rpc.payer();
+        let space = 8 + std::mem::size_of::<switchboard_v2::AggregatorAccountData>();
+        let rent_lamports = self
+            .rpc
+            .get_minimum_balance_for_rent_exemption(space)
+            .await?;
+
+        let ix_create_account = system_instruction::create_account(
+            &payer.pubkey(),
+            &keypair.pubkey(),
+            rent_lamports,
+            space as u64,
+            &mock_switchboard::ID,
+        );
+
+        let ix_initialize = Instruction {
+            program_id: mock_switchboard::ID,
+            accounts: mock_switchboard::accounts::Initialize {
+                aggregator: keypair.pubkey(),
+            }
+            .to_account_metas(None),
+            data: mock_switchboard::instruction::Initialize {
+                price: price.price,
+                expo: price.exponent,
+                conf: price.confidence,
+            }
+            .data(),
+        };
+
+        send_and_confirm(&self.rpc, &[ix_create_account, ix_initialize], &[&keypair]).await?;
+
+        Ok(keypair.pubkey())
+    }
+
+    /// Publish a new price to a switchboard aggregator, using the exponent it
+    /// was created with
+    pub async fn set_switchboard_price(
+        &self,
+        aggregator: &Pubkey,
+        price: &TokenPrice,
+    ) -> Result<(), Error> {
+        let ix = Instruction {
+            program_id: mock_switchboard::ID,
+            accounts: mock_switchboard::accounts::SetPrice {
+                aggregator: *aggregator,
+            }
+            .to_account_metas(None),
+            data: mock_switchboard::instruction::SetPrice {
+                price: price.price,
+                conf: price.confidence,
+            }
+            .data(),
+        };
+
+        send_and_confirm(&self.rpc, &[ix], &[]).await?;
+
+        Ok(())
+    }
+
     /// Mint tokens to an account
     pub async fn mint(
         &self,
@@ -200,10 +342,27 @@ impl TokenManager {
     ) -> Result<(), Error> {
         let payer = self.rpc.payer();
 
+        // The native mint has no authority, so wrap SOL from the payer instead
+        if *mint == spl_token::native_mint::ID {
+            send_and_confirm(
+                &self.rpc,
+                &[
+                    system_instruction::transfer(&payer.pubkey(), destination, amount),
+                    spl_token::instruction::sync_native(&spl_token::ID, destination)?,
+                ],
+                &[],
+            )
+            .await?;
+
+            return Ok(());
+        }
+
+        let (token_program, _) = self.get_token_program(mint).await?;
+
         send_and_confirm(
             &self.rpc,
-            &[spl_token::instruction::mint_to(
-                &spl_token::ID,
+            &[spl_token_2022::instruction::mint_to(
+                &token_program,
                 mint,
                 destination,
                 &payer.pubkey(),
@@ -219,7 +378,11 @@ impl TokenManager {
 
     /// Set the oracle price of a token
     pub async fn set_price(&self, mint: &Pubkey, price: &TokenPrice) -> Result<(), Error> {
-        let mut price_data = default_price();
+        let (product_address, _) = Pubkey::find_program_address(
+            &[mint.as_ref(), b"oracle:product".as_ref()],
+            &jet_metadata::ID,
+        );
+        let mut price_data = default_price(&product_address);
 
         let price_value = jet_proto_math::Number128::from_decimal(price.price, price.exponent)
             .as_u64(price_data.expo) as i64;
@@ -246,9 +409,32 @@ impl TokenManager {
             bail!("account {} does not exist", account);
         }
 
-        let state = spl_token::state::Account::unpack(&account_data.unwrap().data)?;
+        let data = account_data.unwrap().data;
+        let state = StateWithExtensions::<Account2022>::unpack(&data)?;
 
-        Ok(state.amount)
+        Ok(state.base.amount)
+    }
+
+    /// Get the token program that owns a mint, and the size of the token
+    /// accounts for the mint
+    async fn get_token_program(&self, mint: &Pubkey) -> Result<(Pubkey, usize), Error> {
+        let account = match self.rpc.get_account(mint).await? {
+            Some(account) => account,
+            None => bail!("mint {} does not exist", mint),
+        };
+
+        if account.owner != spl_token_2022::ID {
+            return Ok((account.owner, spl_token::state::Account::LEN));
+        }
+
+        let state = StateWithExtensions::<Mint2022>::unpack(&account.data)?;
+        let extensions =
+            ExtensionType::get_required_init_account_extensions(&state.get_extension_types()?);
+
+        Ok((
+            spl_token_2022::ID,
+            ExtensionType::get_account_len::<Account2022>(&extensions),
+        ))
     }
 
     async fn set_pod_metadata<T: bytemuck::Pod>(
@@ -285,6 +471,16 @@ impl TokenManager {
     }
 }
 
+/// The extensions to initialize a token-2022 mint with
+#[derive(Default, Clone, Copy)]
+pub struct MintExtensions {
+    /// Charge a fee on transfers, as basis points of the amount up to a maximum fee
+    pub transfer_fee: Option<(u16, u64)>,
+
+    /// Allow the mint to be closed by the payer
+    pub close_authority: bool,
+}
+
 #[derive(Clone, Copy)]
 pub struct TokenOracle {
     pub price: Pubkey,
@@ -308,7 +504,7 @@ fn write_pyth_product_attributes(mut storage: &mut [u8], attributes: &[(&str, &s
     }
 }
 
-fn default_price() -> pyth_client::Price {
+fn default_price(product: &Pubkey) -> pyth_client::Price {
     pyth_client::Price {
         ver: pyth_client::VERSION,
         magic: pyth_client::MAGIC,
@@ -316,6 +512,9 @@ fn default_price() -> pyth_client::Price {
         size: std::mem::size_of::<pyth_client::Price>() as u32,
         expo: -8,
         next: pyth_client::AccKey { val: [0u8; 32] },
+        prod: pyth_client::AccKey {
+            val: product.to_bytes(),
+        },
         ptype: pyth_client::PriceType::Price,
         ..pyth_client::Price::zeroed()
     }
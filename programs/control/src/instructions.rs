@@ -15,12 +15,64 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+mod accept_root_authority;
+mod add_registry_entry;
+mod approve_proposal;
+mod begin_pool_retirement;
+mod close_pool;
+mod configure_governance;
+mod configure_risk_tier;
 mod configure_token;
 mod create_authority;
+mod create_governance;
+mod create_proposal;
+mod create_registry;
+mod create_reward_campaign;
+mod create_risk_tier;
+mod deregister_adapter;
+mod deregister_liquidation_adapter;
+mod execute_proposal;
+mod migrate_authority;
+mod migrate_metadata;
+mod pause_pool;
+mod propose_root_authority;
 mod register_adapter;
+mod register_liquidation_adapter;
+mod register_liquidator;
 mod register_token;
+mod resize_metadata;
+mod revoke_liquidator;
+mod set_emergency_pauser;
+mod settle_pool_retirement;
+mod write_off_pool_loans;
 
+pub use accept_root_authority::*;
+pub use add_registry_entry::*;
+pub use approve_proposal::*;
+pub use begin_pool_retirement::*;
+pub use close_pool::*;
+pub use configure_governance::*;
+pub use configure_risk_tier::*;
 pub use configure_token::*;
 pub use create_authority::*;
+pub use create_governance::*;
+pub use create_proposal::*;
+pub use create_registry::*;
+pub use create_reward_campaign::*;
+pub use create_risk_tier::*;
+pub use deregister_adapter::*;
+pub use deregister_liquidation_adapter::*;
+pub use execute_proposal::*;
+pub use migrate_authority::*;
+pub use migrate_metadata::*;
+pub use pause_pool::*;
+pub use propose_root_authority::*;
 pub use register_adapter::*;
+pub use register_liquidation_adapter::*;
+pub use register_liquidator::*;
 pub use register_token::*;
+pub use resize_metadata::*;
+pub use revoke_liquidator::*;
+pub use set_emergency_pauser::*;
+pub use settle_pool_retirement::*;
+pub use write_off_pool_loans::*;
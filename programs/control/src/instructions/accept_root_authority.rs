@@ -0,0 +1,49 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+
+use super::Authority;
+use crate::ErrorCode;
+
+#[derive(Accounts)]
+pub struct AcceptRootAuthority<'info> {
+    /// The proposed root, which must sign to show it can act as the root
+    pub new_root: Signer<'info>,
+
+    #[account(mut)]
+    pub authority: Account<'info, Authority>,
+}
+
+pub fn accept_root_authority_handler(ctx: Context<AcceptRootAuthority>) -> Result<()> {
+    let authority = &mut ctx.accounts.authority;
+
+    if authority.pending_root == Pubkey::default()
+        || authority.pending_root != ctx.accounts.new_root.key()
+    {
+        msg!(
+            "{} has not been proposed as the root",
+            ctx.accounts.new_root.key()
+        );
+        return err!(ErrorCode::Unauthorized);
+    }
+
+    authority.root = authority.pending_root;
+    authority.pending_root = Pubkey::default();
+
+    Ok(())
+}
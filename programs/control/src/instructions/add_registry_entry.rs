@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+
+use jet_metadata::{
+    LiquidatorAdapterMetadata, LiquidatorMetadata, MarginAdapterMetadata, TokenMetadata,
+};
+
+use super::{Authority, Registry, RegistryKind};
+use crate::ErrorCode;
+
+#[derive(Accounts)]
+pub struct AddRegistryEntry<'info> {
+    #[account(constraint = authority.is_root(requester.key) @ ErrorCode::Unauthorized)]
+    pub requester: Signer<'info>,
+    pub authority: Account<'info, Authority>,
+
+    /// CHECK:
+    pub key_account: AccountInfo<'info>,
+
+    /// CHECK:
+    #[account(owner = jet_metadata::ID)]
+    pub metadata_account: AccountInfo<'info>,
+
+    #[account(mut, seeds = [b"registry".as_ref(), registry.kind.seed()], bump)]
+    pub registry: Account<'info, Registry>,
+}
+
+/// Find the address registered by a metadata entry of the registry's kind
+fn registered_address(kind: RegistryKind, metadata: &AccountInfo) -> Result<Pubkey> {
+    Ok(match kind {
+        RegistryKind::Tokens => Account::<TokenMetadata>::try_from(metadata)?.token_mint,
+        RegistryKind::Adapters => {
+            Account::<MarginAdapterMetadata>::try_from(metadata)?.adapter_program
+        }
+        RegistryKind::Liquidators => Account::<LiquidatorMetadata>::try_from(metadata)?.liquidator,
+        RegistryKind::LiquidationAdapters => {
+            Account::<LiquidatorAdapterMetadata>::try_from(metadata)?.adapter_program
+        }
+    })
+}
+
+pub fn add_registry_entry_handler(ctx: Context<AddRegistryEntry>) -> Result<()> {
+    let kind = ctx.accounts.registry.kind;
+    let key = ctx.accounts.key_account.key();
+
+    let seed: &[u8] = match kind {
+        RegistryKind::LiquidationAdapters => LiquidatorAdapterMetadata::SEED.as_bytes(),
+        _ => &[],
+    };
+    let (address, _) = Pubkey::find_program_address(&[key.as_ref(), seed], &jet_metadata::ID);
+
+    // only addresses that are still registered in the metadata can be added,
+    // which excludes revoked liquidators
+    if ctx.accounts.metadata_account.key() != address
+        || registered_address(kind, &ctx.accounts.metadata_account)? != key
+    {
+        msg!("{} is not registered as one of the {:?}", key, kind);
+        return err!(ErrorCode::InvalidRegistryEntry);
+    }
+
+    ctx.accounts.registry.add(key)?;
+
+    Ok(())
+}
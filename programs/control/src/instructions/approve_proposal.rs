@@ -0,0 +1,42 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+
+use super::{Governance, Proposal};
+
+#[derive(Accounts)]
+pub struct ApproveProposal<'info> {
+    /// The council member approving the proposal
+    pub member: Signer<'info>,
+
+    #[account(seeds = [b"governance".as_ref()], bump = governance.seed[0])]
+    pub governance: Account<'info, Governance>,
+
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+}
+
+pub fn approve_proposal_handler(ctx: Context<ApproveProposal>) -> Result<()> {
+    let member = ctx.accounts.member.key();
+
+    ctx.accounts.proposal.approve(
+        &ctx.accounts.governance,
+        member,
+        Clock::get()?.unix_timestamp,
+    )
+}
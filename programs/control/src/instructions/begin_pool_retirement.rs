@@ -0,0 +1,61 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+
+use jet_margin_pool::cpi::accounts::BeginRetirement;
+use jet_margin_pool::program::JetMarginPool;
+
+use super::Authority;
+use crate::ErrorCode;
+
+#[derive(Accounts)]
+pub struct BeginPoolRetirement<'info> {
+    #[account(constraint = authority.is_root(requester.key) @ ErrorCode::Unauthorized)]
+    pub requester: Signer<'info>,
+    pub authority: Account<'info, Authority>,
+
+    /// CHECK:
+    #[account(mut)]
+    pub margin_pool: UncheckedAccount<'info>,
+
+    pub margin_pool_program: Program<'info, JetMarginPool>,
+}
+
+impl<'info> BeginPoolRetirement<'info> {
+    fn begin_retirement_context(&self) -> CpiContext<'_, '_, '_, 'info, BeginRetirement<'info>> {
+        CpiContext::new(
+            self.margin_pool_program.to_account_info(),
+            BeginRetirement {
+                margin_pool: self.margin_pool.to_account_info(),
+                authority: self.authority.to_account_info(),
+            },
+        )
+    }
+}
+
+pub fn begin_pool_retirement_handler(ctx: Context<BeginPoolRetirement>) -> Result<()> {
+    let authority = [&ctx.accounts.authority.seed[..]];
+
+    jet_margin_pool::cpi::begin_retirement(
+        ctx.accounts
+            .begin_retirement_context()
+            .with_signer(&[&authority]),
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::Token;
+
+use jet_margin_pool::cpi::accounts::ClosePool as CloseMarginPool;
+use jet_margin_pool::program::JetMarginPool;
+
+use super::{Authority, Registry};
+use crate::ErrorCode;
+
+#[derive(Accounts)]
+pub struct ClosePool<'info> {
+    #[account(constraint = authority.is_root(requester.key) @ ErrorCode::Unauthorized)]
+    pub requester: Signer<'info>,
+    pub authority: Account<'info, Authority>,
+
+    /// CHECK:
+    #[account(mut)]
+    pub margin_pool: UncheckedAccount<'info>,
+
+    /// CHECK:
+    #[account(mut)]
+    pub oracle: UncheckedAccount<'info>,
+
+    /// CHECK:
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+
+    /// CHECK:
+    #[account(mut)]
+    pub token_mint: UncheckedAccount<'info>,
+
+    /// CHECK:
+    #[account(mut)]
+    pub history: UncheckedAccount<'info>,
+
+    /// CHECK:
+    #[account(mut)]
+    pub withdrawal_queue: UncheckedAccount<'info>,
+
+    /// CHECK:
+    #[account(mut)]
+    pub withdrawal_escrow: UncheckedAccount<'info>,
+
+    /// CHECK:
+    #[account(mut)]
+    pub remainder: UncheckedAccount<'info>,
+
+    /// CHECK:
+    #[account(mut)]
+    pub receiver: UncheckedAccount<'info>,
+
+    #[account(mut, seeds = [b"registry".as_ref(), b"tokens".as_ref()], bump)]
+    pub registry: Account<'info, Registry>,
+
+    pub margin_pool_program: Program<'info, JetMarginPool>,
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK:
+    pub pool_token_program: UncheckedAccount<'info>,
+}
+
+impl<'info> ClosePool<'info> {
+    fn close_pool_context(&self) -> CpiContext<'_, '_, '_, 'info, CloseMarginPool<'info>> {
+        CpiContext::new(
+            self.margin_pool_program.to_account_info(),
+            CloseMarginPool {
+                margin_pool: self.margin_pool.to_account_info(),
+                oracle: self.oracle.to_account_info(),
+                vault: self.vault.to_account_info(),
+                token_mint: self.token_mint.to_account_info(),
+                history: self.history.to_account_info(),
+                withdrawal_queue: self.withdrawal_queue.to_account_info(),
+                withdrawal_escrow: self.withdrawal_escrow.to_account_info(),
+                remainder: self.remainder.to_account_info(),
+                receiver: self.receiver.to_account_info(),
+                authority: self.authority.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+                pool_token_program: self.pool_token_program.to_account_info(),
+            },
+        )
+    }
+}
+
+pub fn close_pool_handler(ctx: Context<ClosePool>) -> Result<()> {
+    let authority = [&ctx.accounts.authority.seed[..]];
+
+    jet_margin_pool::cpi::close_pool(ctx.accounts.close_pool_context().with_signer(&[&authority]))?;
+
+    ctx.accounts.registry.remove(&ctx.accounts.token_mint.key());
+
+    Ok(())
+}
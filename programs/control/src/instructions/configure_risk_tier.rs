@@ -0,0 +1,43 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+
+use jet_margin_pool::MarginPoolConfig;
+
+use super::{Authority, RiskTier, TokenMetadataParams};
+use crate::ErrorCode;
+
+#[derive(Accounts)]
+pub struct ConfigureRiskTier<'info> {
+    #[account(constraint = authority.is_root(requester.key) @ ErrorCode::Unauthorized)]
+    pub requester: Signer<'info>,
+    pub authority: Account<'info, Authority>,
+
+    #[account(mut)]
+    pub risk_tier: Account<'info, RiskTier>,
+}
+
+pub fn configure_risk_tier_handler(
+    ctx: Context<ConfigureRiskTier>,
+    pool_config: MarginPoolConfig,
+    token_params: TokenMetadataParams,
+) -> Result<()> {
+    ctx.accounts.risk_tier.configure(pool_config, token_params);
+
+    Ok(())
+}
@@ -22,11 +22,12 @@ use jet_margin_pool::MarginPoolConfig;
 use jet_margin_pool::{cpi::accounts::Configure, MarginPool};
 use jet_metadata::cpi::accounts::SetEntry;
 use jet_metadata::program::JetMetadata;
-use jet_metadata::{PositionTokenMetadata, TokenKind, TokenMetadata};
+use jet_metadata::{OracleSource, PositionTokenMetadata, TokenKind, TokenMetadata};
 
 use super::Authority;
+use crate::ErrorCode;
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default)]
 pub struct TokenMetadataParams {
     /// Description of this token
     pub token_kind: TokenKind,
@@ -36,6 +37,22 @@ pub struct TokenMetadataParams {
 
     /// The maximum staleness (seconds) that's acceptable for this token when used as collateral.
     pub collateral_max_staleness: u64,
+
+    /// Whether positions in this token should be valued conservatively
+    pub conservative_valuation: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default)]
+pub struct LoanMetadataParams {
+    /// Description of the loan notes, which must be a claim
+    pub token_kind: TokenKind,
+
+    /// The weight applied to the value of a loan when checking the health of
+    /// an account, which must be at least 100%
+    pub borrow_weight: u16,
+
+    /// The maximum staleness (seconds) that's acceptable for a loan's balance.
+    pub max_staleness: u64,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
@@ -45,7 +62,7 @@ pub struct MarginPoolParams {
 
 #[derive(Accounts)]
 pub struct ConfigureToken<'info> {
-    #[cfg_attr(not(feature = "devnet"), account(address = crate::ROOT_AUTHORITY))]
+    #[account(constraint = authority.is_root(requester.key) @ ErrorCode::Unauthorized)]
     pub requester: Signer<'info>,
     pub authority: Account<'info, Authority>,
 
@@ -55,17 +72,31 @@ pub struct ConfigureToken<'info> {
     #[account(mut, has_one = token_mint)]
     pub margin_pool: Account<'info, MarginPool>,
 
+    /// CHECK:
+    #[account(mut)]
+    pub oracle: UncheckedAccount<'info>,
+
     #[account(mut, has_one = token_mint)]
     pub token_metadata: Account<'info, TokenMetadata>,
 
-    #[account(mut, constraint = deposit_metadata.underlying_token_mint == token_mint.key())]
+    #[account(mut, constraint = deposit_metadata.position_token_mint == margin_pool.deposit_note_mint)]
     pub deposit_metadata: Account<'info, PositionTokenMetadata>,
 
+    /// The metadata for the loan notes, which is only read when it's being configured
+    /// CHECK: verified in the handler
+    #[account(mut)]
+    pub loan_metadata: UncheckedAccount<'info>,
+
+    /// The metadata for the term loan notes, which is only read when it's being configured
+    /// CHECK: verified in the handler
+    #[account(mut)]
+    pub term_loan_metadata: UncheckedAccount<'info>,
+
     /// CHECK:
     pub pyth_product: UncheckedAccount<'info>,
 
     /// CHECK:
-    pub pyth_price: UncheckedAccount<'info>,
+    pub price_oracle: UncheckedAccount<'info>,
 
     pub margin_pool_program: Program<'info, JetMarginPool>,
     pub metadata_program: Program<'info, JetMetadata>,
@@ -78,8 +109,10 @@ impl<'info> ConfigureToken<'info> {
             Configure {
                 margin_pool: self.margin_pool.to_account_info(),
                 authority: self.authority.to_account_info(),
+                oracle: self.oracle.to_account_info(),
+                token_mint: self.token_mint.to_account_info(),
                 pyth_product: self.pyth_product.to_account_info(),
-                pyth_price: self.pyth_price.to_account_info(),
+                price_oracle: self.price_oracle.to_account_info(),
             },
         )
     }
@@ -103,20 +136,87 @@ impl<'info> ConfigureToken<'info> {
             },
         )
     }
+
+    fn set_loan_metadata_context(
+        &self,
+        metadata_account: &UncheckedAccount<'info>,
+    ) -> CpiContext<'_, '_, '_, 'info, SetEntry<'info>> {
+        CpiContext::new(
+            self.metadata_program.to_account_info(),
+            SetEntry {
+                metadata_account: metadata_account.to_account_info(),
+                authority: self.authority.to_account_info(),
+            },
+        )
+    }
+
+    /// Change the metadata for one of the pool's kinds of loan notes
+    fn set_loan_metadata(
+        &self,
+        metadata_account: &UncheckedAccount<'info>,
+        note_mint: Pubkey,
+        params: LoanMetadataParams,
+    ) -> Result<()> {
+        let authority = [&self.authority.seed[..]];
+        let mut metadata = Account::<PositionTokenMetadata>::try_from(metadata_account)?;
+        let mut data = vec![];
+
+        if metadata.position_token_mint != note_mint {
+            msg!("the metadata is not for the notes {}", note_mint);
+            return err!(ErrorCode::InvalidPositionMetadata);
+        }
+
+        if params.token_kind != TokenKind::Claim {
+            msg!("loan notes can only be claims");
+            return err!(ErrorCode::InvalidLoanMetadata);
+        }
+
+        if params.borrow_weight < 10_000 {
+            msg!(
+                "the borrow weight {} is less than 100%",
+                params.borrow_weight
+            );
+            return err!(ErrorCode::InvalidLoanMetadata);
+        }
+
+        metadata.token_kind = params.token_kind;
+        metadata.collateral_weight = params.borrow_weight;
+        metadata.collateral_max_staleness = params.max_staleness;
+
+        metadata.try_serialize(&mut data)?;
+
+        jet_metadata::cpi::set_entry(
+            self.set_loan_metadata_context(metadata_account)
+                .with_signer(&[&authority]),
+            0,
+            data,
+        )
+    }
 }
 
 pub fn configure_token_handler(
     ctx: Context<ConfigureToken>,
     metadata: Option<TokenMetadataParams>,
+    loan_metadata: Option<LoanMetadataParams>,
+    term_loan_metadata: Option<LoanMetadataParams>,
     pool_param: Option<MarginPoolParams>,
     pool_config: Option<MarginPoolConfig>,
+    oracle_source: Option<OracleSource>,
 ) -> Result<()> {
     let authority = [&ctx.accounts.authority.seed[..]];
 
-    if *ctx.accounts.pyth_price.key != Pubkey::default()
-        || pool_param.is_some()
-        || pool_config.is_some()
-    {
+    // check the oracle before it's recorded in the token metadata, and not
+    // only when it's applied to the pool
+    if let Some(source) = &oracle_source {
+        jet_margin_pool::validate_source(
+            source,
+            &ctx.accounts.token_mint,
+            &ctx.accounts.pyth_product,
+            &ctx.accounts.price_oracle,
+        )?;
+    }
+
+    if oracle_source.is_some() || pool_param.is_some() || pool_config.is_some() {
         let fee_destination = pool_param.map(|p| p.fee_destination);
 
         jet_margin_pool::cpi::configure(
@@ -125,15 +225,17 @@ pub fn configure_token_handler(
                 .with_signer(&[&authority]),
             fee_destination,
             pool_config,
+            oracle_source,
         )?;
     }
 
-    if *ctx.accounts.pyth_price.key != Pubkey::default() {
+    if let Some(source) = oracle_source {
         let mut metadata = ctx.accounts.token_metadata.clone();
         let mut data = vec![];
 
+        metadata.oracle_source = source;
         metadata.pyth_product = ctx.accounts.pyth_product.key();
-        metadata.pyth_price = ctx.accounts.pyth_price.key();
+        metadata.price_oracle = ctx.accounts.price_oracle.key();
 
         metadata.try_serialize(&mut data)?;
 
@@ -153,6 +255,7 @@ pub fn configure_token_handler(
         metadata.token_kind = params.token_kind;
         metadata.collateral_weight = params.collateral_weight;
         metadata.collateral_max_staleness = params.collateral_max_staleness;
+        metadata.conservative_valuation = params.conservative_valuation;
 
         metadata.try_serialize(&mut data)?;
 
@@ -165,5 +268,22 @@ pub fn configure_token_handler(
         )?;
     }
 
+    // positions in the loan notes pick up the change when they're next refreshed
+    if let Some(params) = loan_metadata {
+        ctx.accounts.set_loan_metadata(
+            &ctx.accounts.loan_metadata,
+            ctx.accounts.margin_pool.loan_note_mint,
+            params,
+        )?;
+    }
+
+    if let Some(params) = term_loan_metadata {
+        ctx.accounts.set_loan_metadata(
+            &ctx.accounts.term_loan_metadata,
+            ctx.accounts.margin_pool.term_loan_note_mint,
+            params,
+        )?;
+    }
+
     Ok(())
 }
@@ -24,7 +24,7 @@ pub struct CreateAuthority<'info> {
         seeds = [],
         bump,
         payer = payer,
-        space = 8 + std::mem::size_of::<Authority>(),
+        space = Authority::SPACE,
     )]
     authority: Account<'info, Authority>,
 
@@ -38,9 +38,54 @@ pub struct CreateAuthority<'info> {
 #[derive(Default)]
 pub struct Authority {
     pub seed: [u8; 1],
+
+    /// The address allowed to make privileged changes to the protocol
+    pub root: Pubkey,
+
+    /// An address proposed to replace the root, which it does once the
+    /// proposed address accepts
+    pub pending_root: Pubkey,
+
+    /// An address allowed to pause pools during an emergency, without
+    /// needing the root authority
+    pub emergency_pauser: Pubkey,
+}
+
+impl Authority {
+    pub const SPACE: usize = 8 + std::mem::size_of::<Authority>();
+
+    /// Check if the key is allowed to act as the root authority
+    ///
+    /// Devnet builds accept any key, until the root has been handed over to
+    /// the governance account.
+    pub fn is_root(&self, key: &Pubkey) -> bool {
+        if *key == self.root {
+            return true;
+        }
+
+        cfg!(feature = "devnet") && self.root != governance_address()
+    }
+}
+
+/// The address of the governance account, which may not exist yet
+pub fn governance_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"governance".as_ref()], &crate::ID).0
 }
 
 pub fn create_authority_handler(ctx: Context<CreateAuthority>) -> Result<()> {
-    ctx.accounts.authority.seed[0] = *ctx.bumps.get("authority").unwrap();
+    let authority = &mut ctx.accounts.authority;
+
+    authority.seed[0] = *ctx.bumps.get("authority").unwrap();
+    authority.root = initial_root(&ctx.accounts.payer);
     Ok(())
 }
+
+#[cfg(not(feature = "devnet"))]
+pub(crate) fn initial_root(_payer: &Signer) -> Pubkey {
+    crate::ROOT_AUTHORITY
+}
+
+#[cfg(feature = "devnet")]
+pub(crate) fn initial_root(payer: &Signer) -> Pubkey {
+    payer.key()
+}
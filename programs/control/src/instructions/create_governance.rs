@@ -0,0 +1,134 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+
+use super::Authority;
+use crate::ErrorCode;
+
+#[derive(Accounts)]
+pub struct CreateGovernance<'info> {
+    #[account(constraint = authority.is_root(requester.key) @ ErrorCode::Unauthorized)]
+    pub requester: Signer<'info>,
+
+    /// The authority, whose root is handed over to the new governance
+    #[account(mut)]
+    pub authority: Account<'info, Authority>,
+
+    #[account(
+        init,
+        seeds = [b"governance".as_ref()],
+        bump,
+        payer = payer,
+        space = Governance::SPACE,
+    )]
+    pub governance: Account<'info, Governance>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// The council that proposes and approves changes to the protocol
+#[account]
+#[derive(Default)]
+pub struct Governance {
+    pub seed: [u8; 1],
+
+    /// The keys allowed to create and approve proposals
+    pub council: Vec<Pubkey>,
+
+    /// The number of council approvals needed before a proposal can be executed
+    pub threshold: u8,
+
+    /// The number of seconds between a proposal being approved and when it
+    /// can be executed, unless the proposal is an emergency action
+    pub timelock: i64,
+
+    /// The number of proposals created, used to derive the next proposal address
+    pub proposal_count: u64,
+}
+
+impl Governance {
+    /// The largest number of keys allowed on the council
+    pub const MAX_COUNCIL_SIZE: usize = 16;
+
+    pub const SPACE: usize = 8 + 1 + (4 + 32 * Self::MAX_COUNCIL_SIZE) + 1 + 8 + 8;
+
+    pub fn signer_seeds(&self) -> [&[u8]; 2] {
+        [b"governance".as_ref(), &self.seed]
+    }
+
+    pub fn is_member(&self, key: &Pubkey) -> bool {
+        self.council.contains(key)
+    }
+
+    /// Replace the council and its approval rules
+    pub fn configure(&mut self, council: Vec<Pubkey>, threshold: u8, timelock: i64) -> Result<()> {
+        let mut unique = council.clone();
+        unique.sort();
+        unique.dedup();
+
+        if council.is_empty()
+            || council.len() > Self::MAX_COUNCIL_SIZE
+            || unique.len() != council.len()
+        {
+            msg!(
+                "the council must have between 1 and {} unique keys",
+                Self::MAX_COUNCIL_SIZE
+            );
+            return err!(ErrorCode::InvalidGovernance);
+        }
+
+        if threshold == 0 || threshold as usize > council.len() {
+            msg!("the threshold must be between 1 and the size of the council");
+            return err!(ErrorCode::InvalidGovernance);
+        }
+
+        if timelock < 0 {
+            msg!("the timelock cannot be negative");
+            return err!(ErrorCode::InvalidGovernance);
+        }
+
+        self.council = council;
+        self.threshold = threshold;
+        self.timelock = timelock;
+
+        Ok(())
+    }
+}
+
+pub fn create_governance_handler(
+    ctx: Context<CreateGovernance>,
+    council: Vec<Pubkey>,
+    threshold: u8,
+    timelock: i64,
+) -> Result<()> {
+    let governance = &mut ctx.accounts.governance;
+
+    governance.seed[0] = *ctx.bumps.get("governance").unwrap();
+    governance.configure(council, threshold, timelock)?;
+
+    // From now on the privileged instructions can only be run through proposals
+    let authority = &mut ctx.accounts.authority;
+
+    authority.root = governance.key();
+    authority.pending_root = Pubkey::default();
+
+    Ok(())
+}
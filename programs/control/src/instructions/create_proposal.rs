@@ -0,0 +1,164 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::AccountMeta;
+use anchor_lang::Discriminator;
+
+use super::Governance;
+use crate::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(accounts: Vec<ProposalAccountMeta>, data: Vec<u8>)]
+pub struct CreateProposal<'info> {
+    /// The council member making the proposal
+    pub proposer: Signer<'info>,
+
+    #[account(mut, seeds = [b"governance".as_ref()], bump = governance.seed[0])]
+    pub governance: Account<'info, Governance>,
+
+    #[account(
+        init,
+        seeds = [b"proposal".as_ref(), &governance.proposal_count.to_le_bytes()],
+        bump,
+        payer = payer,
+        space = Proposal::space(accounts.len(), data.len()),
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// An account used by a proposed instruction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default)]
+pub struct ProposalAccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl From<&ProposalAccountMeta> for AccountMeta {
+    fn from(meta: &ProposalAccountMeta) -> AccountMeta {
+        AccountMeta {
+            pubkey: meta.pubkey,
+            is_signer: meta.is_signer,
+            is_writable: meta.is_writable,
+        }
+    }
+}
+
+/// A proposed instruction for the control program, which is signed by the
+/// governance account once approved by the council
+#[account]
+#[derive(Default)]
+pub struct Proposal {
+    /// The sequence number of the proposal
+    pub id: u64,
+
+    /// The council member that created the proposal
+    pub proposer: Pubkey,
+
+    /// The accounts for the proposed instruction
+    pub accounts: Vec<ProposalAccountMeta>,
+
+    /// The data for the proposed instruction
+    pub data: Vec<u8>,
+
+    /// The council members that have approved the proposal
+    pub approvers: Vec<Pubkey>,
+
+    /// The time at which the proposal had enough approvals to pass
+    pub approved_at: Option<i64>,
+
+    /// Whether the proposal has been executed
+    pub executed: bool,
+}
+
+impl Proposal {
+    pub fn space(accounts: usize, data: usize) -> usize {
+        8 + 8
+            + 32
+            + (4 + 34 * accounts)
+            + (4 + data)
+            + (4 + 32 * Governance::MAX_COUNCIL_SIZE)
+            + 9
+            + 1
+    }
+
+    /// Record the approval of a council member, marking the proposal as
+    /// approved once enough current members have approved it
+    pub fn approve(&mut self, governance: &Governance, member: Pubkey, now: i64) -> Result<()> {
+        if !governance.is_member(&member) {
+            msg!("{} is not a member of the council", member);
+            return err!(ErrorCode::Unauthorized);
+        }
+
+        if self.approvers.contains(&member) {
+            msg!("{} has already approved this proposal", member);
+            return err!(ErrorCode::AlreadyApproved);
+        }
+
+        self.approvers.push(member);
+
+        let approvals = self
+            .approvers
+            .iter()
+            .filter(|approver| governance.is_member(approver))
+            .count();
+
+        if self.approved_at.is_none() && approvals >= governance.threshold as usize {
+            self.approved_at = Some(now);
+        }
+
+        Ok(())
+    }
+
+    /// Emergency actions, which only pause pools, are not delayed by the timelock
+    pub fn is_emergency(&self) -> bool {
+        if self.data.len() < 8 || self.data[..8] != crate::instruction::PausePool::discriminator() {
+            return false;
+        }
+
+        match crate::instruction::PausePool::deserialize(&mut &self.data[8..]) {
+            Ok(pause) => pause.paused,
+            Err(_) => false,
+        }
+    }
+}
+
+pub fn create_proposal_handler(
+    ctx: Context<CreateProposal>,
+    accounts: Vec<ProposalAccountMeta>,
+    data: Vec<u8>,
+) -> Result<()> {
+    let proposer = ctx.accounts.proposer.key();
+    let governance = &mut ctx.accounts.governance;
+    let proposal = &mut ctx.accounts.proposal;
+
+    proposal.id = governance.proposal_count;
+    proposal.proposer = proposer;
+    proposal.accounts = accounts;
+    proposal.data = data;
+    proposal.approve(governance, proposer, Clock::get()?.unix_timestamp)?;
+
+    governance.proposal_count += 1;
+
+    Ok(())
+}
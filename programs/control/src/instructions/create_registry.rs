@@ -0,0 +1,118 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+
+use super::Authority;
+use crate::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(kind: RegistryKind)]
+pub struct CreateRegistry<'info> {
+    #[account(constraint = authority.is_root(requester.key) @ ErrorCode::Unauthorized)]
+    pub requester: Signer<'info>,
+    pub authority: Account<'info, Authority>,
+
+    #[account(
+        init,
+        seeds = [b"registry".as_ref(), kind.seed()],
+        bump,
+        payer = payer,
+        space = Registry::SPACE,
+    )]
+    pub registry: Account<'info, Registry>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// The kinds of addresses that are registered with the control program
+#[derive(AnchorSerialize, AnchorDeserialize, Eq, PartialEq, Clone, Copy, Debug)]
+pub enum RegistryKind {
+    /// Token mints with a margin pool, from `register_token`
+    Tokens,
+
+    /// Programs allowed as margin adapters, from `register_adapter`
+    Adapters,
+
+    /// Addresses allowed to liquidate, from `register_liquidator`
+    Liquidators,
+
+    /// Programs allowed as liquidation adapters, from `register_liquidation_adapter`
+    LiquidationAdapters,
+}
+
+impl RegistryKind {
+    pub fn seed(&self) -> &'static [u8] {
+        match self {
+            RegistryKind::Tokens => b"tokens",
+            RegistryKind::Adapters => b"adapters",
+            RegistryKind::Liquidators => b"liquidators",
+            RegistryKind::LiquidationAdapters => b"liquidation-adapters",
+        }
+    }
+}
+
+impl Default for RegistryKind {
+    fn default() -> RegistryKind {
+        Self::Tokens
+    }
+}
+
+/// A list of the addresses registered for some purpose, so they can be
+/// found without scanning all the metadata accounts
+#[account]
+#[derive(Default)]
+pub struct Registry {
+    pub kind: RegistryKind,
+
+    /// The registered addresses, which are the keys for their metadata
+    pub entries: Vec<Pubkey>,
+}
+
+impl Registry {
+    /// The largest number of addresses a registry can hold
+    pub const MAX_ENTRIES: usize = 256;
+
+    pub const SPACE: usize = 8 + 1 + (4 + 32 * Self::MAX_ENTRIES);
+
+    /// Add an address to the registry, if it isn't already registered
+    pub fn add(&mut self, key: Pubkey) -> Result<()> {
+        if self.entries.contains(&key) {
+            return Ok(());
+        }
+
+        if self.entries.len() >= Self::MAX_ENTRIES {
+            msg!("the {:?} registry is full", self.kind);
+            return err!(ErrorCode::RegistryFull);
+        }
+
+        self.entries.push(key);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, key: &Pubkey) {
+        self.entries.retain(|entry| entry != key);
+    }
+}
+
+pub fn create_registry_handler(ctx: Context<CreateRegistry>, kind: RegistryKind) -> Result<()> {
+    ctx.accounts.registry.kind = kind;
+    Ok(())
+}
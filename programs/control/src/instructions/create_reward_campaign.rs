@@ -0,0 +1,101 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::Token;
+
+use jet_margin_pool::cpi::accounts::CreateRewardCampaign as CreatePoolRewardCampaign;
+use jet_margin_pool::program::JetMarginPool;
+use jet_margin_pool::RewardCampaignParams;
+
+use super::Authority;
+use crate::ErrorCode;
+
+#[derive(Accounts)]
+pub struct CreateRewardCampaign<'info> {
+    #[account(constraint = authority.is_root(requester.key) @ ErrorCode::Unauthorized)]
+    pub requester: Signer<'info>,
+    pub authority: Account<'info, Authority>,
+
+    /// CHECK:
+    pub margin_pool: UncheckedAccount<'info>,
+
+    /// CHECK:
+    #[account(mut)]
+    pub campaign: UncheckedAccount<'info>,
+
+    /// CHECK:
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+
+    /// CHECK:
+    #[account(mut)]
+    pub stake_vault: UncheckedAccount<'info>,
+
+    /// CHECK:
+    pub note_mint: UncheckedAccount<'info>,
+
+    /// CHECK:
+    pub reward_mint: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub margin_pool_program: Program<'info, JetMarginPool>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+impl<'info> CreateRewardCampaign<'info> {
+    fn create_campaign_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, CreatePoolRewardCampaign<'info>> {
+        CpiContext::new(
+            self.margin_pool_program.to_account_info(),
+            CreatePoolRewardCampaign {
+                margin_pool: self.margin_pool.to_account_info(),
+                authority: self.authority.to_account_info(),
+                campaign: self.campaign.to_account_info(),
+                vault: self.vault.to_account_info(),
+                stake_vault: self.stake_vault.to_account_info(),
+                note_mint: self.note_mint.to_account_info(),
+                reward_mint: self.reward_mint.to_account_info(),
+                payer: self.payer.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+                system_program: self.system_program.to_account_info(),
+                rent: self.rent.to_account_info(),
+            },
+        )
+    }
+}
+
+pub fn create_reward_campaign_handler(
+    ctx: Context<CreateRewardCampaign>,
+    params: RewardCampaignParams,
+) -> Result<()> {
+    let authority = [&ctx.accounts.authority.seed[..]];
+
+    jet_margin_pool::cpi::create_reward_campaign(
+        ctx.accounts
+            .create_campaign_context()
+            .with_signer(&[&authority]),
+        params,
+    )?;
+
+    Ok(())
+}
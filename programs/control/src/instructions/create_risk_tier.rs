@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+
+use jet_margin_pool::MarginPoolConfig;
+
+use super::{Authority, TokenMetadataParams};
+use crate::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct CreateRiskTier<'info> {
+    #[account(constraint = authority.is_root(requester.key) @ ErrorCode::Unauthorized)]
+    pub requester: Signer<'info>,
+    pub authority: Account<'info, Authority>,
+
+    #[account(
+        init,
+        seeds = [b"risk-tier".as_ref(), name.as_bytes()],
+        bump,
+        payer = payer,
+        space = RiskTier::SPACE,
+    )]
+    pub risk_tier: Account<'info, RiskTier>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// A named set of parameters applied to a token when it's registered,
+/// such as a tier for stablecoins, major tokens or long-tail tokens
+#[account]
+#[derive(Default)]
+pub struct RiskTier {
+    /// The name identifying the tier
+    pub name: String,
+
+    /// The configuration for the pool, with its interest curve, fees and
+    /// deposit and borrow caps
+    pub pool_config: MarginPoolConfig,
+
+    /// The parameters for the pool's deposit notes when used as a position
+    pub token_params: TokenMetadataParams,
+}
+
+impl RiskTier {
+    /// The longest name allowed, which is limited by its use as a seed
+    pub const MAX_NAME_LEN: usize = 32;
+
+    /// The space for the account, leaving room for the pool configuration
+    /// and token parameters to grow
+    pub const SPACE: usize = 8 + (4 + Self::MAX_NAME_LEN) + 128 + 64;
+
+    pub fn configure(&mut self, pool_config: MarginPoolConfig, token_params: TokenMetadataParams) {
+        self.pool_config = pool_config;
+        self.token_params = token_params;
+    }
+}
+
+pub fn create_risk_tier_handler(
+    ctx: Context<CreateRiskTier>,
+    name: String,
+    pool_config: MarginPoolConfig,
+    token_params: TokenMetadataParams,
+) -> Result<()> {
+    if name.is_empty() || name.len() > RiskTier::MAX_NAME_LEN {
+        msg!(
+            "a risk tier name must have between 1 and {} bytes",
+            RiskTier::MAX_NAME_LEN
+        );
+        return err!(ErrorCode::InvalidRiskTier);
+    }
+
+    let risk_tier = &mut ctx.accounts.risk_tier;
+
+    risk_tier.name = name;
+    risk_tier.configure(pool_config, token_params);
+
+    Ok(())
+}
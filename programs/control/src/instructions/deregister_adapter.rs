@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+
+use jet_metadata::cpi::accounts::RemoveEntry;
+use jet_metadata::program::JetMetadata;
+use jet_metadata::MarginAdapterMetadata;
+
+use super::{Authority, Registry};
+use crate::ErrorCode;
+
+#[derive(Accounts)]
+pub struct DeregisterAdapter<'info> {
+    #[account(constraint = authority.is_root(requester.key) @ ErrorCode::Unauthorized)]
+    pub requester: Signer<'info>,
+    pub authority: Account<'info, Authority>,
+
+    /// CHECK:
+    pub adapter: AccountInfo<'info>,
+
+    #[account(mut, constraint = metadata_account.adapter_program == adapter.key())]
+    pub metadata_account: Account<'info, MarginAdapterMetadata>,
+
+    /// CHECK:
+    #[account(mut)]
+    pub receiver: AccountInfo<'info>,
+
+    #[account(mut, seeds = [b"registry".as_ref(), b"adapters".as_ref()], bump)]
+    pub registry: Account<'info, Registry>,
+
+    pub metadata_program: Program<'info, JetMetadata>,
+}
+
+impl<'info> DeregisterAdapter<'info> {
+    fn remove_metadata_context(&self) -> CpiContext<'_, '_, '_, 'info, RemoveEntry<'info>> {
+        CpiContext::new(
+            self.metadata_program.to_account_info(),
+            RemoveEntry {
+                metadata_account: self.metadata_account.to_account_info(),
+                authority: self.authority.to_account_info(),
+                receiver: self.receiver.to_account_info(),
+            },
+        )
+    }
+}
+
+pub fn deregister_adapter_handler(ctx: Context<DeregisterAdapter>) -> Result<()> {
+    let authority = [&ctx.accounts.authority.seed[..]];
+
+    jet_metadata::cpi::remove_entry(
+        ctx.accounts
+            .remove_metadata_context()
+            .with_signer(&[&authority]),
+    )?;
+
+    ctx.accounts.registry.remove(&ctx.accounts.adapter.key());
+
+    Ok(())
+}
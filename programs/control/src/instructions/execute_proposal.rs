@@ -0,0 +1,90 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::invoke_signed;
+
+use super::{Governance, Proposal};
+use crate::program::JetControl;
+use crate::ErrorCode;
+
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    /// The governance account, which signs the proposed instruction
+    ///
+    /// This is only deserialized in the handler, since the proposed
+    /// instruction may change it.
+    /// CHECK:
+    #[account(mut, seeds = [b"governance".as_ref()], bump)]
+    pub governance: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    pub control_program: Program<'info, JetControl>,
+}
+
+pub fn execute_proposal_handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, ExecuteProposal<'info>>,
+) -> Result<()> {
+    let governance_info = ctx.accounts.governance.to_account_info();
+    let governance = Account::<Governance>::try_from(&governance_info)?;
+    let proposal = &mut ctx.accounts.proposal;
+
+    if proposal.executed {
+        msg!("the proposal has already been executed");
+        return err!(ErrorCode::ProposalExecuted);
+    }
+
+    let approved_at = match proposal.approved_at {
+        Some(approved_at) => approved_at,
+        None => {
+            msg!("the proposal does not have enough approvals");
+            return err!(ErrorCode::ProposalNotApproved);
+        }
+    };
+
+    let delay = match proposal.is_emergency() {
+        true => 0,
+        false => governance.timelock,
+    };
+
+    if Clock::get()?.unix_timestamp < approved_at + delay {
+        msg!(
+            "the proposal cannot be executed until {}",
+            approved_at + delay
+        );
+        return err!(ErrorCode::TimelockActive);
+    }
+
+    proposal.executed = true;
+
+    let instruction = Instruction {
+        program_id: crate::ID,
+        accounts: proposal.accounts.iter().map(Into::into).collect(),
+        data: proposal.data.clone(),
+    };
+
+    let mut account_infos = ctx.remaining_accounts.to_vec();
+    account_infos.push(governance_info.clone());
+    account_infos.push(ctx.accounts.control_program.to_account_info());
+
+    invoke_signed(&instruction, &account_infos, &[&governance.signer_seeds()])?;
+
+    Ok(())
+}
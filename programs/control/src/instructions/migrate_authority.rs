@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{program::invoke, system_instruction};
+use anchor_lang::Discriminator;
+
+use super::{initial_root, Authority};
+use crate::ErrorCode;
+
+#[derive(Accounts)]
+pub struct MigrateAuthority<'info> {
+    /// The authority account, created before it stored the root authority
+    ///
+    /// This is only deserialized in the handler, once it has been resized.
+    /// CHECK:
+    #[account(mut, seeds = [], bump, owner = crate::ID)]
+    pub authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn migrate_authority_handler(ctx: Context<MigrateAuthority>) -> Result<()> {
+    let authority = ctx.accounts.authority.to_account_info();
+    let payer = &ctx.accounts.payer;
+
+    {
+        let data = authority.try_borrow_data()?;
+
+        if data.len() >= Authority::SPACE {
+            msg!("the authority account already stores the root authority");
+            return err!(ErrorCode::AuthorityMigrated);
+        }
+
+        if data.len() < 9 || data[..8] != Authority::discriminator() {
+            return err!(anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch);
+        }
+    }
+
+    let rent = Rent::get()?.minimum_balance(Authority::SPACE);
+    let balance = authority.lamports();
+
+    if rent > balance {
+        invoke(
+            &system_instruction::transfer(payer.key, authority.key, rent - balance),
+            &[
+                payer.to_account_info(),
+                authority.clone(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+    }
+
+    authority.realloc(Authority::SPACE, true)?;
+
+    // the seed is kept, and the new fields start out zeroed
+    let mut migrated = Account::<Authority>::try_from(&authority)?;
+
+    migrated.root = initial_root(payer);
+    migrated.exit(&crate::ID)?;
+
+    Ok(())
+}
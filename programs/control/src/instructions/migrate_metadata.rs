@@ -0,0 +1,154 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+use jet_metadata::cpi::accounts::{ResizeEntry, SetEntry};
+use jet_metadata::program::JetMetadata;
+use jet_metadata::{
+    LiquidatorAdapterMetadata, LiquidatorMetadata, MarginAdapterMetadata, PositionTokenMetadata,
+    TokenMetadata,
+};
+
+use super::Authority;
+use crate::ErrorCode;
+
+#[derive(Accounts)]
+pub struct MigrateMetadata<'info> {
+    #[account(constraint = authority.is_root(requester.key) @ ErrorCode::Unauthorized)]
+    pub requester: Signer<'info>,
+    pub authority: Account<'info, Authority>,
+
+    /// CHECK:
+    #[account(mut, owner = jet_metadata::ID)]
+    pub metadata_account: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub metadata_program: Program<'info, JetMetadata>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateMetadata<'info> {
+    fn resize_metadata_context(&self) -> CpiContext<'_, '_, '_, 'info, ResizeEntry<'info>> {
+        CpiContext::new(
+            self.metadata_program.to_account_info(),
+            ResizeEntry {
+                metadata_account: self.metadata_account.to_account_info(),
+                authority: self.authority.to_account_info(),
+                payer: self.payer.to_account_info(),
+                system_program: self.system_program.to_account_info(),
+            },
+        )
+    }
+
+    fn set_metadata_context(&self) -> CpiContext<'_, '_, '_, 'info, SetEntry<'info>> {
+        CpiContext::new(
+            self.metadata_program.to_account_info(),
+            SetEntry {
+                metadata_account: self.metadata_account.to_account_info(),
+                authority: self.authority.to_account_info(),
+            },
+        )
+    }
+}
+
+/// Decode an entry and encode it again with the current version
+fn upgrade<T: AccountSerialize + AccountDeserialize>(
+    entry: &[u8],
+    current: u8,
+    version: fn(&mut T) -> &mut u8,
+) -> Result<Vec<u8>> {
+    let mut metadata = T::try_deserialize(&mut &entry[..])?;
+
+    if *version(&mut metadata) >= current {
+        return err!(ErrorCode::MetadataMigrated);
+    }
+
+    *version(&mut metadata) = current;
+
+    let mut data = vec![];
+    metadata.try_serialize(&mut data)?;
+
+    Ok(data)
+}
+
+pub fn migrate_metadata_handler(ctx: Context<MigrateMetadata>) -> Result<()> {
+    let authority = [&ctx.accounts.authority.seed[..]];
+
+    let data = {
+        let entry = ctx.accounts.metadata_account.try_borrow_data()?;
+        let discriminator = match entry.get(..8) {
+            Some(discriminator) => discriminator,
+            None => return err!(anchor_lang::error::ErrorCode::AccountDiscriminatorNotFound),
+        };
+
+        if discriminator == PositionTokenMetadata::discriminator() {
+            upgrade(
+                &entry,
+                PositionTokenMetadata::VERSION,
+                |m: &mut PositionTokenMetadata| &mut m.version,
+            )?
+        } else if discriminator == TokenMetadata::discriminator() {
+            upgrade(&entry, TokenMetadata::VERSION, |m: &mut TokenMetadata| {
+                &mut m.version
+            })?
+        } else if discriminator == MarginAdapterMetadata::discriminator() {
+            upgrade(
+                &entry,
+                MarginAdapterMetadata::VERSION,
+                |m: &mut MarginAdapterMetadata| &mut m.version,
+            )?
+        } else if discriminator == LiquidatorAdapterMetadata::discriminator() {
+            upgrade(
+                &entry,
+                LiquidatorAdapterMetadata::VERSION,
+                |m: &mut LiquidatorAdapterMetadata| &mut m.version,
+            )?
+        } else if discriminator == LiquidatorMetadata::discriminator() {
+            upgrade(
+                &entry,
+                LiquidatorMetadata::VERSION,
+                |m: &mut LiquidatorMetadata| &mut m.version,
+            )?
+        } else {
+            return err!(anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch);
+        }
+    };
+
+    // older versions of the metadata were smaller
+    if ctx.accounts.metadata_account.data_len() < data.len() {
+        jet_metadata::cpi::resize_entry(
+            ctx.accounts
+                .resize_metadata_context()
+                .with_signer(&[&authority]),
+            data.len().try_into().unwrap(),
+        )?;
+    }
+
+    jet_metadata::cpi::set_entry(
+        ctx.accounts
+            .set_metadata_context()
+            .with_signer(&[&authority]),
+        0,
+        data,
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+
+use jet_margin_pool::cpi::accounts::Pause;
+use jet_margin_pool::program::JetMarginPool;
+
+use super::Authority;
+use crate::ErrorCode;
+
+#[derive(Accounts)]
+pub struct PausePool<'info> {
+    /// Either the root authority, or the emergency pauser when only pausing
+    pub requester: Signer<'info>,
+    pub authority: Account<'info, Authority>,
+
+    /// CHECK:
+    #[account(mut)]
+    pub margin_pool: UncheckedAccount<'info>,
+
+    pub margin_pool_program: Program<'info, JetMarginPool>,
+}
+
+impl<'info> PausePool<'info> {
+    fn pause_context(&self) -> CpiContext<'_, '_, '_, 'info, Pause<'info>> {
+        CpiContext::new(
+            self.margin_pool_program.to_account_info(),
+            Pause {
+                margin_pool: self.margin_pool.to_account_info(),
+                authority: self.authority.to_account_info(),
+            },
+        )
+    }
+}
+
+pub fn pause_pool_handler(ctx: Context<PausePool>, flags: u64, paused: bool) -> Result<()> {
+    let is_root = ctx.accounts.authority.is_root(ctx.accounts.requester.key);
+    let is_pauser = ctx.accounts.requester.key() == ctx.accounts.authority.emergency_pauser;
+
+    // the emergency pauser can only pause, resuming requires the root authority
+    if !is_root && !(paused && is_pauser) {
+        msg!("the requester is not allowed to change the paused actions");
+        return err!(ErrorCode::Unauthorized);
+    }
+
+    let authority = [&ctx.accounts.authority.seed[..]];
+
+    jet_margin_pool::cpi::pause(
+        ctx.accounts.pause_context().with_signer(&[&authority]),
+        flags,
+        paused,
+    )?;
+
+    Ok(())
+}
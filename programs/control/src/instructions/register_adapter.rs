@@ -22,11 +22,12 @@ use jet_metadata::cpi::accounts::{CreateEntry, SetEntry};
 use jet_metadata::program::JetMetadata;
 use jet_metadata::MarginAdapterMetadata;
 
-use super::Authority;
+use super::{Authority, Registry};
+use crate::ErrorCode;
 
 #[derive(Accounts)]
 pub struct RegisterAdapter<'info> {
-    #[cfg_attr(not(feature = "devnet"), account(address = crate::ROOT_AUTHORITY))]
+    #[account(constraint = authority.is_root(requester.key) @ ErrorCode::Unauthorized)]
     pub requester: Signer<'info>,
     pub authority: Account<'info, Authority>,
 
@@ -40,6 +41,9 @@ pub struct RegisterAdapter<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
 
+    #[account(mut, seeds = [b"registry".as_ref(), b"adapters".as_ref()], bump)]
+    pub registry: Account<'info, Registry>,
+
     pub metadata_program: Program<'info, JetMetadata>,
     pub system_program: Program<'info, System>,
 }
@@ -75,6 +79,7 @@ pub fn register_adapter_handler(ctx: Context<RegisterAdapter>) -> Result<()> {
 
     let mut data = vec![];
     let metadata = MarginAdapterMetadata {
+        version: MarginAdapterMetadata::VERSION,
         adapter_program: ctx.accounts.adapter.key(),
     };
 
@@ -95,5 +100,7 @@ pub fn register_adapter_handler(ctx: Context<RegisterAdapter>) -> Result<()> {
         data,
     )?;
 
+    ctx.accounts.registry.add(ctx.accounts.adapter.key())?;
+
     Ok(())
 }
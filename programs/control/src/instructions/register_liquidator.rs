@@ -0,0 +1,134 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use std::convert::TryInto;
+
+use jet_metadata::cpi::accounts::{CreateEntry, ResizeEntry, SetEntry};
+use jet_metadata::program::JetMetadata;
+use jet_metadata::{LiquidationAdapterPolicy, LiquidatorMetadata};
+
+use super::{Authority, Registry};
+use crate::ErrorCode;
+
+#[derive(Accounts)]
+pub struct RegisterLiquidator<'info> {
+    #[account(constraint = authority.is_root(requester.key) @ ErrorCode::Unauthorized)]
+    pub requester: Signer<'info>,
+    pub authority: Account<'info, Authority>,
+
+    /// CHECK:
+    pub liquidator: AccountInfo<'info>,
+
+    /// CHECK:
+    #[account(mut)]
+    pub metadata_account: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut, seeds = [b"registry".as_ref(), b"liquidators".as_ref()], bump)]
+    pub registry: Account<'info, Registry>,
+
+    pub metadata_program: Program<'info, JetMetadata>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RegisterLiquidator<'info> {
+    fn create_metadata_context(&self) -> CpiContext<'_, '_, '_, 'info, CreateEntry<'info>> {
+        CpiContext::new(
+            self.metadata_program.to_account_info(),
+            CreateEntry {
+                key_account: self.liquidator.to_account_info(),
+                metadata_account: self.metadata_account.to_account_info(),
+                authority: self.authority.to_account_info(),
+
+                payer: self.payer.to_account_info(),
+                system_program: self.system_program.to_account_info(),
+            },
+        )
+    }
+
+    fn resize_metadata_context(&self) -> CpiContext<'_, '_, '_, 'info, ResizeEntry<'info>> {
+        CpiContext::new(
+            self.metadata_program.to_account_info(),
+            ResizeEntry {
+                metadata_account: self.metadata_account.to_account_info(),
+                authority: self.authority.to_account_info(),
+                payer: self.payer.to_account_info(),
+                system_program: self.system_program.to_account_info(),
+            },
+        )
+    }
+
+    fn set_metadata_context(&self) -> CpiContext<'_, '_, '_, 'info, SetEntry<'info>> {
+        CpiContext::new(
+            self.metadata_program.to_account_info(),
+            SetEntry {
+                metadata_account: self.metadata_account.to_account_info(),
+                authority: self.authority.to_account_info(),
+            },
+        )
+    }
+}
+
+pub fn register_liquidator_handler(
+    ctx: Context<RegisterLiquidator>,
+    adapter_policy: LiquidationAdapterPolicy,
+) -> Result<()> {
+    let authority = [&ctx.accounts.authority.seed[..]];
+
+    let mut data = vec![];
+    let metadata = LiquidatorMetadata {
+        version: LiquidatorMetadata::VERSION,
+        liquidator: ctx.accounts.liquidator.key(),
+        adapter_policy,
+    };
+
+    metadata.try_serialize(&mut data)?;
+
+    // a liquidator that was previously revoked still has its metadata account
+    if ctx.accounts.metadata_account.data_is_empty() {
+        jet_metadata::cpi::create_entry(
+            ctx.accounts
+                .create_metadata_context()
+                .with_signer(&[&authority]),
+            String::new(),
+            data.len().try_into().unwrap(),
+        )?;
+    } else if ctx.accounts.metadata_account.data_len() < data.len() {
+        // registered with an older version of the metadata, which was smaller
+        jet_metadata::cpi::resize_entry(
+            ctx.accounts
+                .resize_metadata_context()
+                .with_signer(&[&authority]),
+            data.len().try_into().unwrap(),
+        )?;
+    }
+
+    jet_metadata::cpi::set_entry(
+        ctx.accounts
+            .set_metadata_context()
+            .with_signer(&[&authority]),
+        0,
+        data,
+    )?;
+
+    ctx.accounts.registry.add(ctx.accounts.liquidator.key())?;
+
+    Ok(())
+}
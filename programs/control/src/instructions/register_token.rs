@@ -19,17 +19,17 @@ use anchor_lang::prelude::*;
 use anchor_spl::token::Token;
 use std::convert::TryInto;
 
-use jet_margin_pool::cpi::accounts::CreatePool;
+use jet_margin_pool::cpi::accounts::{Configure, CreatePool};
 use jet_margin_pool::program::JetMarginPool;
 use jet_metadata::cpi::accounts::{CreateEntry, SetEntry};
 use jet_metadata::program::JetMetadata;
 use jet_metadata::{PositionTokenMetadata, TokenKind, TokenMetadata};
 
-use super::Authority;
+use super::{Authority, Registry, RiskTier};
 
 #[derive(Accounts)]
 pub struct RegisterToken<'info> {
-    #[account(mut)]
+    #[account(constraint = authority.is_root(requester.key) @ crate::ErrorCode::Unauthorized)]
     requester: Signer<'info>,
     authority: Account<'info, Authority>,
 
@@ -49,6 +49,14 @@ pub struct RegisterToken<'info> {
     #[account(mut)]
     loan_note_mint: UncheckedAccount<'info>,
 
+    /// CHECK:
+    #[account(mut)]
+    term_loan_note_mint: UncheckedAccount<'info>,
+
+    /// CHECK:
+    #[account(mut)]
+    oracle: UncheckedAccount<'info>,
+
     /// CHECK:
     token_mint: UncheckedAccount<'info>,
 
@@ -64,9 +72,28 @@ pub struct RegisterToken<'info> {
     #[account(mut)]
     loan_note_metadata: UncheckedAccount<'info>,
 
+    /// CHECK:
+    #[account(mut)]
+    term_loan_note_metadata: UncheckedAccount<'info>,
+
+    #[account(mut, seeds = [b"registry".as_ref(), b"tokens".as_ref()], bump)]
+    registry: Account<'info, Registry>,
+
+    /// The tier with the parameters for the new pool
+    risk_tier: Account<'info, RiskTier>,
+
+    /// The address paying the rent for the new accounts, which can be
+    /// separate from a requester that can't hold lamports
+    #[account(mut)]
+    payer: Signer<'info>,
+
     margin_pool_program: Program<'info, JetMarginPool>,
     metadata_program: Program<'info, JetMetadata>,
     token_program: Program<'info, Token>,
+
+    /// CHECK:
+    pool_token_program: UncheckedAccount<'info>,
+
     system_program: Program<'info, System>,
     rent: Sysvar<'info, Rent>,
 }
@@ -80,16 +107,34 @@ impl<'info> RegisterToken<'info> {
                 vault: self.vault.to_account_info(),
                 deposit_note_mint: self.deposit_note_mint.to_account_info(),
                 loan_note_mint: self.loan_note_mint.to_account_info(),
+                term_loan_note_mint: self.term_loan_note_mint.to_account_info(),
+                oracle: self.oracle.to_account_info(),
                 token_mint: self.token_mint.to_account_info(),
                 authority: self.authority.to_account_info(),
-                payer: self.requester.to_account_info(),
+                payer: self.payer.to_account_info(),
                 token_program: self.token_program.to_account_info(),
+                pool_token_program: self.pool_token_program.to_account_info(),
                 system_program: self.system_program.to_account_info(),
                 rent: self.rent.to_account_info(),
             },
         )
     }
 
+    fn configure_pool_context(&self) -> CpiContext<'_, '_, '_, 'info, Configure<'info>> {
+        // the oracle source isn't changed, so the pyth accounts aren't used
+        CpiContext::new(
+            self.margin_pool_program.to_account_info(),
+            Configure {
+                margin_pool: self.margin_pool.to_account_info(),
+                authority: self.authority.to_account_info(),
+                oracle: self.oracle.to_account_info(),
+                token_mint: self.token_mint.to_account_info(),
+                pyth_product: self.token_mint.to_account_info(),
+                price_oracle: self.token_mint.to_account_info(),
+            },
+        )
+    }
+
     fn create_token_metadata_context(&self) -> CpiContext<'_, '_, '_, 'info, CreateEntry<'info>> {
         CpiContext::new(
             self.metadata_program.to_account_info(),
@@ -97,7 +142,7 @@ impl<'info> RegisterToken<'info> {
                 key_account: self.token_mint.to_account_info(),
                 metadata_account: self.token_metadata.to_account_info(),
                 authority: self.authority.to_account_info(),
-                payer: self.requester.to_account_info(),
+                payer: self.payer.to_account_info(),
                 system_program: self.system_program.to_account_info(),
             },
         )
@@ -120,7 +165,7 @@ impl<'info> RegisterToken<'info> {
                 key_account: self.deposit_note_mint.to_account_info(),
                 metadata_account: self.deposit_note_metadata.to_account_info(),
                 authority: self.authority.to_account_info(),
-                payer: self.requester.to_account_info(),
+                payer: self.payer.to_account_info(),
                 system_program: self.system_program.to_account_info(),
             },
         )
@@ -143,7 +188,7 @@ impl<'info> RegisterToken<'info> {
                 key_account: self.loan_note_mint.to_account_info(),
                 metadata_account: self.loan_note_metadata.to_account_info(),
                 authority: self.authority.to_account_info(),
-                payer: self.requester.to_account_info(),
+                payer: self.payer.to_account_info(),
                 system_program: self.system_program.to_account_info(),
             },
         )
@@ -158,6 +203,31 @@ impl<'info> RegisterToken<'info> {
             },
         )
     }
+
+    fn create_term_loan_metadata_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, CreateEntry<'info>> {
+        CpiContext::new(
+            self.metadata_program.to_account_info(),
+            CreateEntry {
+                key_account: self.term_loan_note_mint.to_account_info(),
+                metadata_account: self.term_loan_note_metadata.to_account_info(),
+                authority: self.authority.to_account_info(),
+                payer: self.payer.to_account_info(),
+                system_program: self.system_program.to_account_info(),
+            },
+        )
+    }
+
+    fn set_term_loan_metadata_context(&self) -> CpiContext<'_, '_, '_, 'info, SetEntry<'info>> {
+        CpiContext::new(
+            self.metadata_program.to_account_info(),
+            SetEntry {
+                metadata_account: self.term_loan_note_metadata.to_account_info(),
+                authority: self.authority.to_account_info(),
+            },
+        )
+    }
 }
 
 pub fn register_token_handler(ctx: Context<RegisterToken>) -> Result<()> {
@@ -170,26 +240,54 @@ pub fn register_token_handler(ctx: Context<RegisterToken>) -> Result<()> {
             .with_signer(&[&authority]),
     )?;
 
+    // apply the tier's interest curve and fees to the new pool
+    let risk_tier = &ctx.accounts.risk_tier;
+
+    jet_margin_pool::cpi::configure(
+        ctx.accounts
+            .configure_pool_context()
+            .with_signer(&[&authority]),
+        None,
+        Some(risk_tier.pool_config.clone()),
+        None,
+    )?;
+
     // set metadata for the deposit/loan tokens to be used as positions
     let deposit_note_metadata = PositionTokenMetadata {
+        version: PositionTokenMetadata::VERSION,
         underlying_token_mint: ctx.accounts.token_mint.key(),
         position_token_mint: ctx.accounts.deposit_note_mint.key(),
         adapter_program: ctx.accounts.margin_pool_program.key(),
-        token_kind: TokenKind::NonCollateral,
-        collateral_weight: 0,
-        collateral_max_staleness: 0,
+        token_kind: risk_tier.token_params.token_kind,
+        collateral_weight: risk_tier.token_params.collateral_weight,
+        collateral_max_staleness: risk_tier.token_params.collateral_max_staleness,
+        conservative_valuation: risk_tier.token_params.conservative_valuation,
     };
 
     let loan_note_metadata = PositionTokenMetadata {
+        version: PositionTokenMetadata::VERSION,
         underlying_token_mint: ctx.accounts.token_mint.key(),
         position_token_mint: ctx.accounts.loan_note_mint.key(),
         adapter_program: ctx.accounts.margin_pool_program.key(),
         token_kind: TokenKind::Claim,
         collateral_weight: 0,
         collateral_max_staleness: 0,
+        conservative_valuation: false,
+    };
+
+    let term_loan_note_metadata = PositionTokenMetadata {
+        version: PositionTokenMetadata::VERSION,
+        underlying_token_mint: ctx.accounts.token_mint.key(),
+        position_token_mint: ctx.accounts.term_loan_note_mint.key(),
+        adapter_program: ctx.accounts.margin_pool_program.key(),
+        token_kind: TokenKind::Claim,
+        collateral_weight: 0,
+        collateral_max_staleness: 0,
+        conservative_valuation: false,
     };
 
     let token_metadata = TokenMetadata {
+        version: TokenMetadata::VERSION,
         token_mint: ctx.accounts.token_mint.key(),
         ..Default::default()
     };
@@ -197,9 +295,11 @@ pub fn register_token_handler(ctx: Context<RegisterToken>) -> Result<()> {
     let mut token_md_data = vec![];
     let mut deposit_md_data = vec![];
     let mut loan_md_data = vec![];
+    let mut term_loan_md_data = vec![];
 
     deposit_note_metadata.try_serialize(&mut deposit_md_data)?;
     loan_note_metadata.try_serialize(&mut loan_md_data)?;
+    term_loan_note_metadata.try_serialize(&mut term_loan_md_data)?;
     token_metadata.try_serialize(&mut token_md_data)?;
 
     jet_metadata::cpi::create_entry(
@@ -234,12 +334,32 @@ pub fn register_token_handler(ctx: Context<RegisterToken>) -> Result<()> {
         loan_md_data,
     )?;
 
+    jet_metadata::cpi::create_entry(
+        ctx.accounts
+            .create_term_loan_metadata_context()
+            .with_signer(&[&authority]),
+        String::new(),
+        term_loan_md_data.len().try_into().unwrap(),
+    )?;
+
+    jet_metadata::cpi::set_entry(
+        ctx.accounts
+            .set_term_loan_metadata_context()
+            .with_signer(&[&authority]),
+        0,
+        term_loan_md_data,
+    )?;
+
+    // the token metadata can grow when configured with a different oracle source,
+    // so reserve enough space for the largest variant
     jet_metadata::cpi::create_entry(
         ctx.accounts
             .create_token_metadata_context()
             .with_signer(&[&authority]),
         String::new(),
-        token_md_data.len().try_into().unwrap(),
+        (8 + std::mem::size_of::<TokenMetadata>())
+            .try_into()
+            .unwrap(),
     )?;
 
     jet_metadata::cpi::set_entry(
@@ -250,5 +370,7 @@ pub fn register_token_handler(ctx: Context<RegisterToken>) -> Result<()> {
         token_md_data,
     )?;
 
+    ctx.accounts.registry.add(ctx.accounts.token_mint.key())?;
+
     Ok(())
 }
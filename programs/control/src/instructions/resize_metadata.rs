@@ -0,0 +1,68 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+
+use jet_metadata::cpi::accounts::ResizeEntry;
+use jet_metadata::program::JetMetadata;
+
+use super::Authority;
+use crate::ErrorCode;
+
+#[derive(Accounts)]
+pub struct ResizeMetadata<'info> {
+    #[account(constraint = authority.is_root(requester.key) @ ErrorCode::Unauthorized)]
+    pub requester: Signer<'info>,
+    pub authority: Account<'info, Authority>,
+
+    /// CHECK:
+    #[account(mut)]
+    pub metadata_account: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub metadata_program: Program<'info, JetMetadata>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ResizeMetadata<'info> {
+    fn resize_metadata_context(&self) -> CpiContext<'_, '_, '_, 'info, ResizeEntry<'info>> {
+        CpiContext::new(
+            self.metadata_program.to_account_info(),
+            ResizeEntry {
+                metadata_account: self.metadata_account.to_account_info(),
+                authority: self.authority.to_account_info(),
+                payer: self.payer.to_account_info(),
+                system_program: self.system_program.to_account_info(),
+            },
+        )
+    }
+}
+
+pub fn resize_metadata_handler(ctx: Context<ResizeMetadata>, space: u64) -> Result<()> {
+    let authority = [&ctx.accounts.authority.seed[..]];
+
+    jet_metadata::cpi::resize_entry(
+        ctx.accounts
+            .resize_metadata_context()
+            .with_signer(&[&authority]),
+        space,
+    )?;
+
+    Ok(())
+}
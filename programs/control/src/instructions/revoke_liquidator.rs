@@ -0,0 +1,78 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+use jet_metadata::cpi::accounts::SetEntry;
+use jet_metadata::program::JetMetadata;
+use jet_metadata::LiquidatorMetadata;
+
+use super::{Authority, Registry};
+use crate::ErrorCode;
+
+#[derive(Accounts)]
+pub struct RevokeLiquidator<'info> {
+    #[account(constraint = authority.is_root(requester.key) @ ErrorCode::Unauthorized)]
+    pub requester: Signer<'info>,
+    pub authority: Account<'info, Authority>,
+
+    /// CHECK:
+    pub liquidator: AccountInfo<'info>,
+
+    #[account(mut, has_one = liquidator)]
+    pub metadata_account: Account<'info, LiquidatorMetadata>,
+
+    #[account(mut, seeds = [b"registry".as_ref(), b"liquidators".as_ref()], bump)]
+    pub registry: Account<'info, Registry>,
+
+    pub metadata_program: Program<'info, JetMetadata>,
+}
+
+impl<'info> RevokeLiquidator<'info> {
+    fn set_metadata_context(&self) -> CpiContext<'_, '_, '_, 'info, SetEntry<'info>> {
+        CpiContext::new(
+            self.metadata_program.to_account_info(),
+            SetEntry {
+                metadata_account: self.metadata_account.to_account_info(),
+                authority: self.authority.to_account_info(),
+            },
+        )
+    }
+}
+
+pub fn revoke_liquidator_handler(ctx: Context<RevokeLiquidator>) -> Result<()> {
+    let authority = [&ctx.accounts.authority.seed[..]];
+
+    // The metadata no longer names the liquidator, so it can't begin new
+    // liquidations or continue with any it already started. Only the
+    // liquidator field is cleared, since entries registered with an older
+    // version of the metadata are smaller than the current layout.
+    let offset = LiquidatorMetadata::discriminator().len() as u64;
+
+    jet_metadata::cpi::set_entry(
+        ctx.accounts
+            .set_metadata_context()
+            .with_signer(&[&authority]),
+        offset,
+        Pubkey::default().to_bytes().to_vec(),
+    )?;
+
+    ctx.accounts.registry.remove(&ctx.accounts.liquidator.key());
+
+    Ok(())
+}
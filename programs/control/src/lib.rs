@@ -19,15 +19,21 @@ use anchor_lang::prelude::*;
 #[cfg(not(feature = "devnet"))]
 use anchor_lang::solana_program::pubkey;
 
-use jet_margin_pool::MarginPoolConfig;
+use jet_margin_pool::{MarginPoolConfig, RewardCampaignParams};
+use jet_metadata::{LiquidationAdapterPolicy, OracleSource};
 
 mod instructions;
 use instructions::*;
 
-pub use instructions::{MarginPoolParams, TokenMetadataParams};
+pub use instructions::{
+    Authority, Governance, LoanMetadataParams, MarginPoolParams, Proposal, ProposalAccountMeta,
+    Registry, RegistryKind, RiskTier, TokenMetadataParams,
+};
 
 declare_id!("JPCtrLreUqsEbdhtxZ8zpd8wBydKz4nuEjX5u9Eg5H8");
 
+/// The root authority set when the authority account is created, which can
+/// later be changed with `propose_root_authority`
 #[cfg(not(feature = "devnet"))]
 static ROOT_AUTHORITY: Pubkey = pubkey!("FqXoGb9Zxy4uzG12N1jvHyktNG3Zsez367vAzJeiyMF1");
 
@@ -41,10 +47,52 @@ mod jet_control {
         instructions::create_authority_handler(ctx)
     }
 
+    /// Resize an authority account created before it stored the root authority,
+    /// setting the root to the initial root authority
+    pub fn migrate_authority(ctx: Context<MigrateAuthority>) -> Result<()> {
+        instructions::migrate_authority_handler(ctx)
+    }
+
+    /// Create the registry listing the addresses of one kind registered
+    /// with the control program
+    pub fn create_registry(ctx: Context<CreateRegistry>, kind: RegistryKind) -> Result<()> {
+        instructions::create_registry_handler(ctx, kind)
+    }
+
+    /// Add an address registered before the registries were created to the
+    /// registry of its kind
+    pub fn add_registry_entry(ctx: Context<AddRegistryEntry>) -> Result<()> {
+        instructions::add_registry_entry_handler(ctx)
+    }
+
+    /// Create a named risk tier, with the parameters applied to the tokens
+    /// registered in it
+    pub fn create_risk_tier(
+        ctx: Context<CreateRiskTier>,
+        name: String,
+        pool_config: MarginPoolConfig,
+        token_params: TokenMetadataParams,
+    ) -> Result<()> {
+        instructions::create_risk_tier_handler(ctx, name, pool_config, token_params)
+    }
+
+    /// Change the parameters of a risk tier
+    ///
+    /// Tokens already registered in the tier keep their existing configuration.
+    pub fn configure_risk_tier(
+        ctx: Context<ConfigureRiskTier>,
+        pool_config: MarginPoolConfig,
+        token_params: TokenMetadataParams,
+    ) -> Result<()> {
+        instructions::configure_risk_tier_handler(ctx, pool_config, token_params)
+    }
+
     /// Register an SPL token for use with the protocol, by creating
-    /// a margin pool which can accept deposits for the token.
+    /// a margin pool which can accept deposits for the token, configured
+    /// with the parameters of a risk tier.
     ///
-    /// Does not require special permission
+    /// Requires the root authority, with the rent for the new accounts
+    /// paid by a separate payer.
     pub fn register_token(ctx: Context<RegisterToken>) -> Result<()> {
         instructions::register_token_handler(ctx)
     }
@@ -55,13 +103,246 @@ mod jet_control {
         instructions::register_adapter_handler(ctx)
     }
 
+    /// Remove a program from the adapters allowed for use with margin
+    /// accounts, refunding the rent for its metadata to the receiver
+    pub fn deregister_adapter(ctx: Context<DeregisterAdapter>) -> Result<()> {
+        instructions::deregister_adapter_handler(ctx)
+    }
+
+    /// Change the size of a metadata entry, so it can hold a newer version
+    /// of its metadata
+    pub fn resize_metadata(ctx: Context<ResizeMetadata>, space: u64) -> Result<()> {
+        instructions::resize_metadata_handler(ctx, space)
+    }
+
+    /// Rewrite a metadata entry written by an older version of the program
+    /// with the current version of its layout, resizing it when needed
+    pub fn migrate_metadata(ctx: Context<MigrateMetadata>) -> Result<()> {
+        instructions::migrate_metadata_handler(ctx)
+    }
+
+    /// Register a program to be allowed for use as an adapter while
+    /// liquidating a margin account
+    pub fn register_liquidation_adapter(ctx: Context<RegisterLiquidationAdapter>) -> Result<()> {
+        instructions::register_liquidation_adapter_handler(ctx)
+    }
+
+    /// Remove a program from the adapters allowed for use in liquidations,
+    /// refunding the rent for its metadata to the receiver
+    pub fn deregister_liquidation_adapter(
+        ctx: Context<DeregisterLiquidationAdapter>,
+    ) -> Result<()> {
+        instructions::deregister_liquidation_adapter_handler(ctx)
+    }
+
+    /// Register an address to be allowed to liquidate unhealthy margin accounts
+    ///
+    /// The policy decides which registered adapters the liquidator can invoke.
+    /// Registering an existing liquidator again updates its policy.
+    pub fn register_liquidator(
+        ctx: Context<RegisterLiquidator>,
+        adapter_policy: LiquidationAdapterPolicy,
+    ) -> Result<()> {
+        instructions::register_liquidator_handler(ctx, adapter_policy)
+    }
+
+    /// Revoke a liquidator, which also prevents it from continuing any
+    /// liquidation it has in progress
+    pub fn revoke_liquidator(ctx: Context<RevokeLiquidator>) -> Result<()> {
+        instructions::revoke_liquidator_handler(ctx)
+    }
+
     /// Configure details about a token
+    ///
+    /// Changes to the deposit or loan note metadata apply to existing margin
+    /// positions once they refresh their metadata. The loan and term loan note
+    /// metadata accounts are only read when their parameters are given.
     pub fn configure_token(
         ctx: Context<ConfigureToken>,
         metadata: Option<TokenMetadataParams>,
+        loan_metadata: Option<LoanMetadataParams>,
+        term_loan_metadata: Option<LoanMetadataParams>,
         pool_param: Option<MarginPoolParams>,
         pool_config: Option<MarginPoolConfig>,
+        oracle_source: Option<OracleSource>,
+    ) -> Result<()> {
+        instructions::configure_token_handler(
+            ctx,
+            metadata,
+            loan_metadata,
+            term_loan_metadata,
+            pool_param,
+            pool_config,
+            oracle_source,
+        )
+    }
+
+    /// Create a campaign distributing rewards to holders of a pool's notes
+    pub fn create_reward_campaign(
+        ctx: Context<CreateRewardCampaign>,
+        params: RewardCampaignParams,
+    ) -> Result<()> {
+        instructions::create_reward_campaign_handler(ctx, params)
+    }
+
+    /// Propose a new address to become the root authority
+    ///
+    /// The change only takes effect once the new address accepts it.
+    pub fn propose_root_authority(
+        ctx: Context<ProposeRootAuthority>,
+        new_root: Pubkey,
+    ) -> Result<()> {
+        instructions::propose_root_authority_handler(ctx, new_root)
+    }
+
+    /// Accept the proposal to become the root authority
+    pub fn accept_root_authority(ctx: Context<AcceptRootAuthority>) -> Result<()> {
+        instructions::accept_root_authority_handler(ctx)
+    }
+
+    /// Set the address allowed to pause pools without the root authority
+    pub fn set_emergency_pauser(ctx: Context<SetEmergencyPauser>, pauser: Pubkey) -> Result<()> {
+        instructions::set_emergency_pauser_handler(ctx, pauser)
+    }
+
+    /// Pause or resume individual actions on a pool
+    ///
+    /// The emergency pauser may pause actions, but only the root authority
+    /// can resume them.
+    pub fn pause_pool(ctx: Context<PausePool>, flags: u64, paused: bool) -> Result<()> {
+        instructions::pause_pool_handler(ctx, flags, paused)
+    }
+
+    /// Begin retiring the pool for a token that is being delisted, which
+    /// permanently stops new deposits and borrows
+    pub fn begin_pool_retirement(ctx: Context<BeginPoolRetirement>) -> Result<()> {
+        instructions::begin_pool_retirement_handler(ctx)
+    }
+
+    /// Settle the outstanding loans of a retiring pool at their final rate
+    pub fn settle_pool_retirement(ctx: Context<SettlePoolRetirement>) -> Result<()> {
+        instructions::settle_pool_retirement_handler(ctx)
+    }
+
+    /// Write off the settled loans of a retiring pool that were never repaid,
+    /// so that the depositors can redeem what's left and the pool can be closed
+    pub fn write_off_pool_loans(ctx: Context<WriteOffPoolLoans>) -> Result<()> {
+        instructions::write_off_pool_loans_handler(ctx)
+    }
+
+    /// Close a retired pool once all deposits and loans have been redeemed
+    ///
+    /// The token can't be listed again afterwards, since the pool's note mints
+    /// remain at the addresses a new pool for the token would use.
+    pub fn close_pool(ctx: Context<ClosePool>) -> Result<()> {
+        instructions::close_pool_handler(ctx)
+    }
+
+    /// Create the council which governs changes to the protocol
+    ///
+    /// The governance account takes over as the root authority, so the privileged
+    /// instructions can only be run through approved proposals from then on.
+    pub fn create_governance(
+        ctx: Context<CreateGovernance>,
+        council: Vec<Pubkey>,
+        threshold: u8,
+        timelock: i64,
     ) -> Result<()> {
-        instructions::configure_token_handler(ctx, metadata, pool_param, pool_config)
+        instructions::create_governance_handler(ctx, council, threshold, timelock)
     }
+
+    /// Change the council and its approval rules
+    ///
+    /// Must be signed by the governance account, so is only possible
+    /// through an approved proposal.
+    pub fn configure_governance(
+        ctx: Context<ConfigureGovernance>,
+        council: Vec<Pubkey>,
+        threshold: u8,
+        timelock: i64,
+    ) -> Result<()> {
+        instructions::configure_governance_handler(ctx, council, threshold, timelock)
+    }
+
+    /// Propose an instruction for the control program, to be signed by the
+    /// governance account once approved by the council
+    ///
+    /// The proposer's approval is counted.
+    pub fn create_proposal(
+        ctx: Context<CreateProposal>,
+        accounts: Vec<ProposalAccountMeta>,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        instructions::create_proposal_handler(ctx, accounts, data)
+    }
+
+    /// Approve a proposal as a member of the council
+    pub fn approve_proposal(ctx: Context<ApproveProposal>) -> Result<()> {
+        instructions::approve_proposal_handler(ctx)
+    }
+
+    /// Execute an approved proposal, once its timelock has passed
+    ///
+    /// Proposals that only pause pools can be executed as soon as
+    /// they are approved.
+    pub fn execute_proposal<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteProposal<'info>>,
+    ) -> Result<()> {
+        instructions::execute_proposal_handler(ctx)
+    }
+}
+
+#[error_code]
+pub enum ErrorCode {
+    /// 141200 - The requester is not allowed to perform the action
+    #[msg("The requester is not allowed to perform this action")]
+    Unauthorized = 135_200,
+
+    /// 141201 - The council or its approval rules are invalid
+    #[msg("The council or its approval rules are invalid")]
+    InvalidGovernance,
+
+    /// 141202 - The council member has already approved the proposal
+    #[msg("The council member has already approved the proposal")]
+    AlreadyApproved,
+
+    /// 141203 - The proposal does not have enough approvals
+    #[msg("The proposal does not have enough approvals")]
+    ProposalNotApproved,
+
+    /// 141204 - The proposal is waiting for its timelock to pass
+    #[msg("The proposal is waiting for its timelock to pass")]
+    TimelockActive,
+
+    /// 141205 - The proposal has already been executed
+    #[msg("The proposal has already been executed")]
+    ProposalExecuted,
+
+    /// 141206 - The registry has no space for another entry
+    #[msg("The registry has no space for another entry")]
+    RegistryFull,
+
+    /// 141207 - The risk tier is invalid
+    #[msg("The risk tier is invalid")]
+    InvalidRiskTier,
+
+    /// 141208 - The authority account has already been migrated
+    #[msg("The authority account has already been migrated")]
+    AuthorityMigrated,
+
+    /// 141209 - The metadata entry already has the current version
+    #[msg("The metadata entry already has the current version")]
+    MetadataMigrated,
+
+    /// 141210 - The metadata does not register the address being added to a registry
+    #[msg("The metadata does not register the address being added to a registry")]
+    InvalidRegistryEntry,
+
+    /// 141211 - The metadata is not for the position the instruction expects
+    #[msg("The metadata is not for the position the instruction expects")]
+    InvalidPositionMetadata,
+
+    /// 141212 - Loan notes must be claims, with a borrow weight of at least 100%
+    #[msg("The loan metadata is invalid")]
+    InvalidLoanMetadata,
 }
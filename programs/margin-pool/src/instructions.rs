@@ -15,22 +15,70 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+mod begin_retirement;
+mod cancel_withdrawal;
+mod claim_rewards;
+mod close_pool;
 mod collect;
 mod configure;
+mod create_margin_reward_checkpoint;
 mod create_pool;
+mod create_pool_history;
+mod create_reward_campaign;
+mod create_reward_checkpoint;
+mod create_withdrawal_queue;
 mod deposit;
+mod fill_withdrawals;
 mod margin_borrow;
+mod margin_burn_loan_notes;
+mod margin_burn_term_loan_notes;
+mod margin_claim_rewards;
+mod margin_deposit;
 mod margin_refresh_position;
 mod margin_repay;
+mod margin_term_borrow;
+mod margin_term_repay;
 mod margin_withdraw;
+mod migrate_pool;
+mod pause;
+mod queue_withdrawal;
+mod repay;
+mod settle_retirement;
+mod stake_rewards;
+mod unstake_rewards;
 mod withdraw;
+mod write_off_loans;
 
+pub use begin_retirement::*;
+pub use cancel_withdrawal::*;
+pub use claim_rewards::*;
+pub use close_pool::*;
 pub use collect::*;
 pub use configure::*;
+pub use create_margin_reward_checkpoint::*;
 pub use create_pool::*;
+pub use create_pool_history::*;
+pub use create_reward_campaign::*;
+pub use create_reward_checkpoint::*;
+pub use create_withdrawal_queue::*;
 pub use deposit::*;
+pub use fill_withdrawals::*;
 pub use margin_borrow::*;
+pub use margin_burn_loan_notes::*;
+pub use margin_burn_term_loan_notes::*;
+pub use margin_claim_rewards::*;
+pub use margin_deposit::*;
 pub use margin_refresh_position::*;
 pub use margin_repay::*;
+pub use margin_term_borrow::*;
+pub use margin_term_repay::*;
 pub use margin_withdraw::*;
+pub use migrate_pool::*;
+pub use pause::*;
+pub use queue_withdrawal::*;
+pub use repay::*;
+pub use settle_retirement::*;
+pub use stake_rewards::*;
+pub use unstake_rewards::*;
 pub use withdraw::*;
+pub use write_off_loans::*;
@@ -0,0 +1,39 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+
+use jet_metadata::ControlAuthority;
+
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct BeginRetirement<'info> {
+    /// The pool to be retired
+    #[account(mut)]
+    pub margin_pool: Account<'info, MarginPool>,
+
+    /// The authority allowed to modify the pool, which must sign
+    #[cfg_attr(not(feature = "devnet"), account(signer))]
+    pub authority: Account<'info, ControlAuthority>,
+}
+
+pub fn begin_retirement_handler(ctx: Context<BeginRetirement>) -> Result<()> {
+    ctx.accounts.margin_pool.begin_retirement();
+
+    Ok(())
+}
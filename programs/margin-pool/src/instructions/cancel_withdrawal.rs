@@ -0,0 +1,90 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, Transfer};
+
+use crate::state::*;
+use crate::ErrorCode;
+
+#[derive(Accounts)]
+pub struct CancelWithdrawal<'info> {
+    /// The pool the withdrawal was queued for
+    #[account(mut)]
+    pub margin_pool: Account<'info, MarginPool>,
+
+    /// The queue of withdrawal requests for the pool
+    #[account(mut,
+              has_one = margin_pool,
+              has_one = escrow)]
+    pub withdrawal_queue: AccountLoader<'info, WithdrawalQueue>,
+
+    /// The token account holding the deposit notes of queued requests
+    /// CHECK:
+    #[account(mut)]
+    pub escrow: UncheckedAccount<'info>,
+
+    /// The address that queued the withdrawal
+    pub depositor: Signer<'info>,
+
+    /// The account to return the remaining deposit notes to
+    /// CHECK:
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> CancelWithdrawal<'info> {
+    fn transfer_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Transfer {
+                to: self.destination.to_account_info(),
+                from: self.escrow.to_account_info(),
+                authority: self.margin_pool.to_account_info(),
+            },
+        )
+    }
+}
+
+pub fn cancel_withdrawal_handler(ctx: Context<CancelWithdrawal>, sequence: u64) -> Result<()> {
+    let mut queue = ctx.accounts.withdrawal_queue.load_mut()?;
+    let request = queue.get_mut(sequence)?;
+
+    if request.depositor != ctx.accounts.depositor.key() || request.notes == 0 {
+        msg!("request {} cannot be cancelled by this depositor", sequence);
+        return err!(ErrorCode::InvalidWithdrawalRequest);
+    }
+
+    let notes = request.notes;
+    request.notes = 0;
+
+    // skipped requests are no longer counted as queued by the pool
+    if queue.is_queued(sequence) {
+        ctx.accounts.margin_pool.dequeue_withdrawal(notes)?;
+    }
+
+    drop(queue);
+
+    let pool = &ctx.accounts.margin_pool;
+    let signer = [&pool.signer_seeds()?[..]];
+
+    token::transfer(ctx.accounts.transfer_context().with_signer(&signer), notes)?;
+
+    Ok(())
+}
@@ -0,0 +1,162 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    /// The pool whose notes are rewarded
+    pub margin_pool: Account<'info, MarginPool>,
+
+    /// The campaign to claim rewards from
+    #[account(mut,
+              has_one = margin_pool,
+              has_one = vault)]
+    pub campaign: Account<'info, RewardCampaign>,
+
+    /// The checkpoint for the notes staked by the owner
+    #[account(mut,
+              has_one = campaign,
+              has_one = owner,
+              constraint = checkpoint.token_account == campaign.stake_vault)]
+    pub checkpoint: Account<'info, RewardCheckpoint>,
+
+    /// The owner of the staked notes
+    pub owner: Signer<'info>,
+
+    /// The token account funding the rewards
+    #[account(mut)]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// The token account to receive the rewards
+    /// CHECK:
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> ClaimRewards<'info> {
+    fn transfer_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Transfer {
+                to: self.destination.to_account_info(),
+                from: self.vault.to_account_info(),
+                authority: self.campaign.to_account_info(),
+            },
+        )
+    }
+}
+
+pub fn claim_rewards_handler(ctx: Context<ClaimRewards>) -> Result<()> {
+    let amount = settle_rewards(
+        &ctx.accounts.margin_pool,
+        &mut ctx.accounts.campaign,
+        &mut ctx.accounts.checkpoint,
+        None,
+        ctx.accounts.vault.amount,
+    )?;
+
+    let campaign = &ctx.accounts.campaign;
+
+    token::transfer(
+        ctx.accounts
+            .transfer_context()
+            .with_signer(&[&campaign.signer_seeds()]),
+        amount,
+    )?;
+
+    Ok(())
+}
+
+/// Bring a checkpoint up to date with its campaign
+///
+/// Staked notes are always held for the whole period since the last checkpoint,
+/// while a margin account position is only credited for the lower of its previous
+/// and current `balance`.
+pub fn sync_rewards(
+    pool: &MarginPool,
+    campaign: &mut RewardCampaign,
+    checkpoint: &mut RewardCheckpoint,
+    balance: Option<u64>,
+) -> Result<()> {
+    let clock = Clock::get()?;
+
+    campaign.accrue(
+        clock.unix_timestamp,
+        pool.notes_outstanding(&campaign.note_mint),
+    );
+    checkpoint.sync(campaign, balance.unwrap_or(checkpoint.balance));
+
+    Ok(())
+}
+
+/// Bring a checkpoint up to date with its campaign, and take the rewards to be paid out
+///
+/// Returns the amount of rewards to be paid out, which is limited to what the
+/// vault has been funded with. Anything beyond that is left to be claimed later.
+pub fn settle_rewards(
+    pool: &MarginPool,
+    campaign: &mut RewardCampaign,
+    checkpoint: &mut RewardCheckpoint,
+    balance: Option<u64>,
+    available: u64,
+) -> Result<u64> {
+    sync_rewards(pool, campaign, checkpoint, balance)?;
+
+    let amount = std::cmp::min(checkpoint.unclaimed, available);
+    checkpoint.unclaimed -= amount;
+
+    Ok(amount)
+}
+
+/// Settle the rewards earned by a margin account position before its notes are reduced
+///
+/// Positions aren't escrowed by the campaign, so every reduction in their balance
+/// has to be checkpointed first for the same notes not to be rewarded twice.
+/// Nothing needs to be done when the position has no checkpoint.
+pub fn settle_position_rewards<'info>(
+    pool: &MarginPool,
+    campaign: &AccountInfo<'info>,
+    checkpoint: &AccountInfo<'info>,
+    token_account: &AccountInfo<'info>,
+    reduction: u64,
+) -> Result<()> {
+    if checkpoint.data_is_empty() {
+        return Ok(());
+    }
+
+    let mut campaign = Account::<RewardCampaign>::try_from(campaign)?;
+    let mut checkpoint = Account::<RewardCheckpoint>::try_from(checkpoint)?;
+
+    if checkpoint.token_account != token_account.key() {
+        return Ok(());
+    }
+
+    let balance = token::accessor::amount(token_account)?;
+    sync_rewards(pool, &mut campaign, &mut checkpoint, Some(balance))?;
+    checkpoint.balance = balance.saturating_sub(reduction);
+
+    campaign.exit(&crate::ID)?;
+    checkpoint.exit(&crate::ID)?;
+
+    Ok(())
+}
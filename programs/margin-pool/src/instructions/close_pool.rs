@@ -0,0 +1,179 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_lang::AccountsClose;
+use anchor_spl::token::{self, CloseAccount, Token};
+
+use jet_metadata::ControlAuthority;
+
+use crate::pool_token::{self, CloseTokenAccount, TransferTokens};
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct ClosePool<'info> {
+    /// The retired pool to be closed
+    #[account(mut,
+              close = receiver,
+              has_one = token_mint,
+              has_one = vault)]
+    pub margin_pool: Account<'info, MarginPool>,
+
+    /// The cache of price information for the pool's token
+    #[account(mut,
+              close = receiver,
+              seeds = [
+                margin_pool.key().as_ref(),
+                b"oracle".as_ref()
+              ],
+              bump)]
+    pub oracle: Account<'info, MarginPoolOracle>,
+
+    /// The vault for the pool, where tokens are held
+    /// CHECK:
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+
+    /// The mint for the pool's token
+    /// CHECK:
+    #[account(mut)]
+    pub token_mint: UncheckedAccount<'info>,
+
+    /// The history of the pool's rates, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                b"history".as_ref()
+              ],
+              bump)]
+    pub history: AccountInfo<'info>,
+
+    /// The queue of withdrawal requests for the pool, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                b"withdrawal-queue".as_ref()
+              ],
+              bump)]
+    pub withdrawal_queue: AccountInfo<'info>,
+
+    /// The token account holding the deposit notes of queued requests, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                b"withdrawal-escrow".as_ref()
+              ],
+              bump)]
+    pub withdrawal_escrow: AccountInfo<'info>,
+
+    /// The token account to receive any tokens left in the vault
+    /// CHECK:
+    #[account(mut)]
+    pub remainder: UncheckedAccount<'info>,
+
+    /// The address to receive the rent from the closed accounts
+    /// CHECK:
+    #[account(mut)]
+    pub receiver: UncheckedAccount<'info>,
+
+    /// The authority allowed to modify the pool, which must sign
+    #[cfg_attr(not(feature = "devnet"), account(signer))]
+    pub authority: Account<'info, ControlAuthority>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// The program for the pool's token, which may be either the token or token-2022 program
+    /// CHECK:
+    pub pool_token_program: UncheckedAccount<'info>,
+}
+
+impl<'info> ClosePool<'info> {
+    fn transfer_remainder_accounts(&self) -> TransferTokens<'_, 'info> {
+        TransferTokens {
+            token_program: &self.pool_token_program,
+            mint: &self.token_mint,
+            source: &self.vault,
+            destination: &self.remainder,
+            authority: self.margin_pool.as_ref(),
+        }
+    }
+
+    fn close_vault_accounts(&self) -> CloseTokenAccount<'_, 'info> {
+        CloseTokenAccount {
+            token_program: &self.pool_token_program,
+            mint: &self.token_mint,
+            account: &self.vault,
+            destination: &self.receiver,
+            authority: self.margin_pool.as_ref(),
+        }
+    }
+
+    fn close_escrow_context(&self) -> CpiContext<'_, '_, '_, 'info, CloseAccount<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.withdrawal_escrow.to_account_info(),
+                destination: self.receiver.to_account_info(),
+                authority: self.margin_pool.to_account_info(),
+            },
+        )
+    }
+}
+
+pub fn close_pool_handler(ctx: Context<ClosePool>) -> Result<()> {
+    let pool = &ctx.accounts.margin_pool;
+
+    pool.check_closeable()?;
+
+    let signer = [&pool.signer_seeds()?[..]];
+
+    // Anything left in the vault is dust from rounding, or tokens sent to it directly
+    let remainder = pool_token::token_amount(&ctx.accounts.vault)?;
+
+    if remainder > 0 {
+        pool_token::transfer(
+            ctx.accounts.transfer_remainder_accounts(),
+            remainder,
+            &signer,
+        )?;
+    }
+
+    pool_token::close_token_account(ctx.accounts.close_vault_accounts(), &signer)?;
+
+    // The optional accounts are closed if they were ever created for the pool
+    if !ctx.accounts.withdrawal_escrow.data_is_empty() {
+        token::close_account(ctx.accounts.close_escrow_context().with_signer(&signer))?;
+    }
+
+    if !ctx.accounts.withdrawal_queue.data_is_empty() {
+        AccountLoader::<WithdrawalQueue>::try_from(&ctx.accounts.withdrawal_queue)?
+            .close(ctx.accounts.receiver.to_account_info())?;
+    }
+
+    if !ctx.accounts.history.data_is_empty() {
+        AccountLoader::<PoolHistory>::try_from(&ctx.accounts.history)?
+            .close(ctx.accounts.receiver.to_account_info())?;
+    }
+
+    // The note mints can't be closed by the token program, but with no notes
+    // outstanding and the pool closed, no more notes can ever be issued.
+
+    Ok(())
+}
@@ -44,6 +44,16 @@ pub struct Collect<'info> {
     #[account(mut)]
     pub deposit_note_mint: AccountInfo<'info>,
 
+    /// The history of rates for the pool, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                b"history".as_ref()
+              ],
+              bump)]
+    pub history: AccountInfo<'info>,
+
     pub token_program: Program<'info, Token>,
 }
 
@@ -79,5 +89,7 @@ pub fn collect_handler(ctx: Context<Collect>) -> Result<()> {
         fee_notes,
     )?;
 
+    pool.record_history(&ctx.accounts.history, clock.unix_timestamp)?;
+
     Ok(())
 }
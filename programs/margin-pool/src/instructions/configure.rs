@@ -17,8 +17,9 @@
 
 use anchor_lang::prelude::*;
 
+use crate::oracle::validate_source;
 use crate::state::*;
-use jet_metadata::ControlAuthority;
+use jet_metadata::{ControlAuthority, OracleSource};
 
 #[derive(Accounts)]
 pub struct Configure<'info> {
@@ -30,17 +31,32 @@ pub struct Configure<'info> {
     #[cfg_attr(not(feature = "devnet"), account(signer))]
     pub authority: Account<'info, ControlAuthority>,
 
+    /// The cache of price information for the pool's token
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                b"oracle".as_ref()
+              ],
+              bump)]
+    pub oracle: Account<'info, MarginPoolOracle>,
+
+    /// The mint for the pool's token, used to check the oracle's exponent
+    /// CHECK:
+    #[account(address = margin_pool.token_mint)]
+    pub token_mint: AccountInfo<'info>,
+
     /// CHECK:
     pub pyth_product: AccountInfo<'info>,
 
     /// CHECK:
-    pub pyth_price: AccountInfo<'info>,
+    pub price_oracle: AccountInfo<'info>,
 }
 
 pub fn configure_handler(
     ctx: Context<Configure>,
     fee_destination: Option<Pubkey>,
     config: Option<MarginPoolConfig>,
+    oracle_source: Option<OracleSource>,
 ) -> Result<()> {
     let pool = &mut ctx.accounts.margin_pool;
 
@@ -49,14 +65,25 @@ pub fn configure_handler(
     }
 
     if let Some(new_config) = config {
-        pool.config = new_config;
+        pool.configure(new_config);
     }
 
-    if *ctx.accounts.pyth_price.key != Pubkey::default() {
-        // FIXME: validate pyth product
+    if let Some(source) = oracle_source {
+        validate_source(
+            &source,
+            &ctx.accounts.token_mint,
+            &ctx.accounts.pyth_product,
+            &ctx.accounts.price_oracle,
+        )?;
+
+        pool.token_price_oracle = ctx.accounts.price_oracle.key();
+        ctx.accounts.oracle.set_source(
+            source,
+            ctx.accounts.pyth_product.key(),
+            pool.token_price_oracle,
+        );
 
-        pool.token_price_oracle = ctx.accounts.pyth_price.key();
-        msg!("oracle = {}", &pool.token_price_oracle);
+        msg!("oracle = {} ({:?})", &pool.token_price_oracle, source);
     }
 
     Ok(())
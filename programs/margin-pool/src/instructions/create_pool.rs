@@ -16,8 +16,9 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_spl::token::{Mint, Token};
 
+use crate::pool_token::{self, CreateTokenAccount};
 use crate::state::*;
 use jet_metadata::CONTROL_PROGRAM_ID;
 
@@ -29,21 +30,19 @@ pub struct CreatePool<'info> {
         seeds = [token_mint.key().as_ref()],
         bump,
         payer = payer,
-        space = 8 + std::mem::size_of::<MarginPool>(),
+        space = MarginPool::SPACE,
     )]
     pub margin_pool: Box<Account<'info, MarginPool>>,
 
     /// The token account holding the pool's deposited funds
-    #[account(init,
+    /// CHECK: created by the handler, since the token may belong to token-2022
+    #[account(mut,
               seeds = [
                 margin_pool.key().as_ref(),
                 b"vault".as_ref()
               ],
-              bump,
-              token::mint = token_mint,
-              token::authority = margin_pool,
-              payer = payer)]
-    pub vault: Box<Account<'info, TokenAccount>>,
+              bump)]
+    pub vault: UncheckedAccount<'info>,
 
     /// The mint for deposit notes
     #[account(init,
@@ -52,7 +51,7 @@ pub struct CreatePool<'info> {
                 b"deposit-notes".as_ref()
               ],
               bump,
-              mint::decimals = token_mint.decimals,
+              mint::decimals = pool_token::mint_decimals(&token_mint),
               mint::authority = margin_pool,
               payer = payer)]
     pub deposit_note_mint: Box<Account<'info, Mint>>,
@@ -64,13 +63,38 @@ pub struct CreatePool<'info> {
                 b"loan-notes".as_ref()
               ],
               bump,
-              mint::decimals = token_mint.decimals,
+              mint::decimals = pool_token::mint_decimals(&token_mint),
               mint::authority = margin_pool,
               payer = payer)]
     pub loan_note_mint: Box<Account<'info, Mint>>,
 
+    /// The mint for term loan notes
+    #[account(init,
+              seeds = [
+                margin_pool.key().as_ref(),
+                b"term-loan-notes".as_ref()
+              ],
+              bump,
+              mint::decimals = pool_token::mint_decimals(&token_mint),
+              mint::authority = margin_pool,
+              payer = payer)]
+    pub term_loan_note_mint: Box<Account<'info, Mint>>,
+
+    /// The cache of price information for the pool's token
+    #[account(init,
+              seeds = [
+                margin_pool.key().as_ref(),
+                b"oracle".as_ref()
+              ],
+              bump,
+              space = 8 + std::mem::size_of::<MarginPoolOracle>(),
+              payer = payer)]
+    pub oracle: Box<Account<'info, MarginPoolOracle>>,
+
     /// The mint for the token being custodied by the pool
-    pub token_mint: Box<Account<'info, Mint>>,
+    /// CHECK: may belong to either token program, validated by the handler
+    #[account(owner = pool_token_program.key())]
+    pub token_mint: UncheckedAccount<'info>,
 
     /// The authority to create pools, which must sign
     #[account(owner = CONTROL_PROGRAM_ID)]
@@ -81,11 +105,36 @@ pub struct CreatePool<'info> {
     pub payer: Signer<'info>,
 
     pub token_program: Program<'info, Token>,
+
+    /// The program for the pool's token, which may be either the token or token-2022 program
+    /// CHECK:
+    #[account(constraint = pool_token::is_token_program(pool_token_program.key))]
+    pub pool_token_program: UncheckedAccount<'info>,
+
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
 }
 
 pub fn create_pool_handler(ctx: Context<CreatePool>) -> Result<()> {
+    pool_token::check_mint_extensions(&ctx.accounts.token_mint)?;
+
+    let pool_key = ctx.accounts.margin_pool.key();
+    let vault_bump = [*ctx.bumps.get("vault").unwrap()];
+    let vault_seeds = [pool_key.as_ref(), b"vault".as_ref(), &vault_bump];
+
+    pool_token::create_token_account(
+        CreateTokenAccount {
+            token_program: &ctx.accounts.pool_token_program.to_account_info(),
+            system_program: &ctx.accounts.system_program.to_account_info(),
+            rent: &ctx.accounts.rent.to_account_info(),
+            payer: &ctx.accounts.payer.to_account_info(),
+            account: &ctx.accounts.vault.to_account_info(),
+            mint: &ctx.accounts.token_mint.to_account_info(),
+            authority: &ctx.accounts.margin_pool.to_account_info(),
+        },
+        &vault_seeds,
+    )?;
+
     let pool = &mut ctx.accounts.margin_pool;
 
     pool.address = pool.key();
@@ -94,6 +143,10 @@ pub fn create_pool_handler(ctx: Context<CreatePool>) -> Result<()> {
     pool.vault = ctx.accounts.vault.key();
     pool.deposit_note_mint = ctx.accounts.deposit_note_mint.key();
     pool.loan_note_mint = ctx.accounts.loan_note_mint.key();
+    pool.term_loan_note_mint = ctx.accounts.term_loan_note_mint.key();
+
+    let oracle = &mut ctx.accounts.oracle;
+    oracle.token_mint = ctx.accounts.token_mint.key();
 
     let clock = Clock::get()?;
     pool.accrued_until = clock.unix_timestamp;
@@ -0,0 +1,54 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct CreatePoolHistory<'info> {
+    /// The pool to record the history of
+    pub margin_pool: Account<'info, MarginPool>,
+
+    /// The account to record the pool's rates in
+    #[account(init,
+              seeds = [
+                margin_pool.key().as_ref(),
+                b"history".as_ref()
+              ],
+              bump,
+              space = 8 + std::mem::size_of::<PoolHistory>(),
+              payer = payer)]
+    pub history: AccountLoader<'info, PoolHistory>,
+
+    /// The payer of rent for the history account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn create_pool_history_handler(ctx: Context<CreatePoolHistory>) -> Result<()> {
+    let mut history = ctx.accounts.history.load_init()?;
+    let pool = &ctx.accounts.margin_pool;
+    let clock = Clock::get()?;
+
+    history.margin_pool = pool.key();
+    history.record(pool.history_entry(clock.unix_timestamp), 0);
+
+    Ok(())
+}
@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use jet_metadata::ControlAuthority;
+
+use crate::state::*;
+use crate::ErrorCode;
+
+/// The schedule for rewards emitted by a campaign
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, Copy)]
+pub struct RewardCampaignParams {
+    /// The time rewards start being emitted
+    pub start: i64,
+
+    /// The time rewards stop being emitted
+    pub end: i64,
+
+    /// The amount of reward tokens emitted per second, across all holders
+    pub emission_rate: u64,
+}
+
+#[derive(Accounts)]
+pub struct CreateRewardCampaign<'info> {
+    /// The pool whose notes are rewarded
+    pub margin_pool: Account<'info, MarginPool>,
+
+    /// The authority allowed to modify the pool, which must sign
+    #[cfg_attr(not(feature = "devnet"), account(signer))]
+    pub authority: Account<'info, ControlAuthority>,
+
+    /// The campaign to be created
+    #[account(init,
+              seeds = [
+                margin_pool.key().as_ref(),
+                note_mint.key().as_ref(),
+                b"reward-campaign".as_ref()
+              ],
+              bump,
+              space = 8 + std::mem::size_of::<RewardCampaign>(),
+              payer = payer)]
+    pub campaign: Account<'info, RewardCampaign>,
+
+    /// The token account funding the rewards
+    #[account(init,
+              seeds = [
+                campaign.key().as_ref(),
+                b"reward-vault".as_ref()
+              ],
+              bump,
+              token::mint = reward_mint,
+              token::authority = campaign,
+              payer = payer)]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// The token account holding the notes staked in the campaign
+    #[account(init,
+              seeds = [
+                campaign.key().as_ref(),
+                b"reward-stake".as_ref()
+              ],
+              bump,
+              token::mint = note_mint,
+              token::authority = campaign,
+              payer = payer)]
+    pub stake_vault: Account<'info, TokenAccount>,
+
+    /// The mint for the notes being rewarded, either the deposit or loan notes
+    #[account(constraint = note_mint.key() == margin_pool.deposit_note_mint
+                        || note_mint.key() == margin_pool.loan_note_mint)]
+    pub note_mint: Account<'info, Mint>,
+
+    /// The mint for the token being distributed as a reward
+    pub reward_mint: Account<'info, Mint>,
+
+    /// The payer of rent for new accounts
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+pub fn create_reward_campaign_handler(
+    ctx: Context<CreateRewardCampaign>,
+    params: RewardCampaignParams,
+) -> Result<()> {
+    if params.end <= params.start {
+        msg!("the campaign must end after it starts");
+        return err!(ErrorCode::InvalidRewardCampaign);
+    }
+
+    let campaign = &mut ctx.accounts.campaign;
+
+    campaign.margin_pool = ctx.accounts.margin_pool.key();
+    campaign.note_mint = ctx.accounts.note_mint.key();
+    campaign.reward_mint = ctx.accounts.reward_mint.key();
+    campaign.vault = ctx.accounts.vault.key();
+    campaign.stake_vault = ctx.accounts.stake_vault.key();
+    campaign.bump[0] = *ctx.bumps.get("campaign").unwrap();
+    campaign.start = params.start;
+    campaign.end = params.end;
+    campaign.emission_rate = params.emission_rate;
+    campaign.accrued_until = params.start;
+
+    Ok(())
+}
@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct CreateRewardCheckpoint<'info> {
+    /// The pool whose notes are rewarded
+    pub margin_pool: Account<'info, MarginPool>,
+
+    /// The campaign to start earning rewards from
+    #[account(mut, has_one = margin_pool)]
+    pub campaign: Account<'info, RewardCampaign>,
+
+    /// The owner of the notes to be staked
+    /// CHECK:
+    pub owner: UncheckedAccount<'info>,
+
+    /// The checkpoint to be created for the owner's staked notes
+    #[account(init,
+              seeds = [
+                campaign.key().as_ref(),
+                owner.key().as_ref(),
+                b"reward-checkpoint".as_ref()
+              ],
+              bump,
+              space = 8 + std::mem::size_of::<RewardCheckpoint>(),
+              payer = payer)]
+    pub checkpoint: Account<'info, RewardCheckpoint>,
+
+    /// The payer of rent for the checkpoint
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn create_reward_checkpoint_handler(ctx: Context<CreateRewardCheckpoint>) -> Result<()> {
+    let checkpoint = &mut ctx.accounts.checkpoint;
+
+    checkpoint.campaign = ctx.accounts.campaign.key();
+    checkpoint.token_account = ctx.accounts.campaign.stake_vault;
+    checkpoint.owner = ctx.accounts.owner.key();
+
+    // nothing is staked yet, so rewards are only earned from this point on
+    super::sync_rewards(
+        &ctx.accounts.margin_pool,
+        &mut ctx.accounts.campaign,
+        checkpoint,
+        None,
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount};
+
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct CreateWithdrawalQueue<'info> {
+    /// The pool to create a queue for
+    #[account(has_one = deposit_note_mint)]
+    pub margin_pool: Account<'info, MarginPool>,
+
+    /// The queue of withdrawal requests for the pool
+    #[account(init,
+              seeds = [
+                margin_pool.key().as_ref(),
+                b"withdrawal-queue".as_ref()
+              ],
+              bump,
+              space = 8 + std::mem::size_of::<WithdrawalQueue>(),
+              payer = payer)]
+    pub withdrawal_queue: AccountLoader<'info, WithdrawalQueue>,
+
+    /// The token account to hold the deposit notes of queued requests
+    #[account(init,
+              seeds = [
+                margin_pool.key().as_ref(),
+                b"withdrawal-escrow".as_ref()
+              ],
+              bump,
+              token::mint = deposit_note_mint,
+              token::authority = margin_pool,
+              payer = payer)]
+    pub escrow: Account<'info, TokenAccount>,
+
+    /// The mint for the deposit notes
+    /// CHECK:
+    pub deposit_note_mint: UncheckedAccount<'info>,
+
+    /// The payer of rent for new accounts
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+pub fn create_withdrawal_queue_handler(ctx: Context<CreateWithdrawalQueue>) -> Result<()> {
+    let mut queue = ctx.accounts.withdrawal_queue.load_init()?;
+
+    queue.margin_pool = ctx.accounts.margin_pool.key();
+    queue.escrow = ctx.accounts.escrow.key();
+
+    Ok(())
+}
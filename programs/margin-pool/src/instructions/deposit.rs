@@ -16,8 +16,9 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, MintTo, Token, Transfer};
+use anchor_spl::token::{self, MintTo, Token};
 
+use crate::pool_token::{self, TransferTokens};
 use crate::{state::*, AmountKind};
 use crate::{Amount, ErrorCode};
 
@@ -25,10 +26,15 @@ use crate::{Amount, ErrorCode};
 pub struct Deposit<'info> {
     /// The pool to deposit into
     #[account(mut,
+              has_one = token_mint,
               has_one = vault,
               has_one = deposit_note_mint)]
     pub margin_pool: Account<'info, MarginPool>,
 
+    /// The mint for the pool's token
+    /// CHECK:
+    pub token_mint: UncheckedAccount<'info>,
+
     /// The vault for the pool, where tokens are held
     /// CHECK:
     #[account(mut)]
@@ -52,19 +58,32 @@ pub struct Deposit<'info> {
     #[account(mut)]
     pub destination: UncheckedAccount<'info>,
 
+    /// The history of rates for the pool, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                b"history".as_ref()
+              ],
+              bump)]
+    pub history: AccountInfo<'info>,
+
     pub token_program: Program<'info, Token>,
+
+    /// The program for the pool's token, which may be either the token or token-2022 program
+    /// CHECK:
+    pub pool_token_program: UncheckedAccount<'info>,
 }
 
 impl<'info> Deposit<'info> {
-    fn transfer_source_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        CpiContext::new(
-            self.token_program.to_account_info(),
-            Transfer {
-                to: self.vault.to_account_info(),
-                from: self.source.to_account_info(),
-                authority: self.depositor.to_account_info(),
-            },
-        )
+    fn transfer_source_accounts(&self) -> TransferTokens<'_, 'info> {
+        TransferTokens {
+            token_program: &self.pool_token_program,
+            mint: &self.token_mint,
+            source: &self.source,
+            destination: &self.vault,
+            authority: &self.depositor,
+        }
     }
 
     fn mint_note_context(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
@@ -83,24 +102,30 @@ pub fn deposit_handler(ctx: Context<Deposit>, token_amount: u64) -> Result<()> {
     let pool = &mut ctx.accounts.margin_pool;
     let clock = Clock::get()?;
 
+    pool.check_action(PoolAction::Deposit)?;
+
     // Make sure interest accrual is up-to-date
     if !pool.accrue_interest(clock.unix_timestamp) {
         msg!("interest accrual is too far behind");
         return Err(ErrorCode::InterestAccrualBehind.into());
     }
 
+    pool.record_history(&ctx.accounts.history, clock.unix_timestamp)?;
+
+    // Transfer the tokens first, since the vault can receive less than the amount
+    // sent when the token charges a fee on transfers
+    let received =
+        pool_token::transfer(ctx.accounts.transfer_source_accounts(), token_amount, &[])?;
+
+    let pool = &mut ctx.accounts.margin_pool;
     let deposit_rounding = RoundingDirection::direction(PoolAction::Deposit, AmountKind::Tokens);
-    let deposit_amount =
-        pool.convert_deposit_amount(Amount::tokens(token_amount), deposit_rounding)?;
+    let deposit_amount = pool.convert_deposit_amount(Amount::tokens(received), deposit_rounding)?;
     pool.deposit(&deposit_amount);
+    pool.check_deposit_cap()?;
 
     let pool = &ctx.accounts.margin_pool;
     let signer = [&pool.signer_seeds()?[..]];
 
-    token::transfer(
-        ctx.accounts.transfer_source_context().with_signer(&signer),
-        deposit_amount.tokens,
-    )?;
     token::mint_to(
         ctx.accounts.mint_note_context().with_signer(&signer),
         deposit_amount.notes,
@@ -0,0 +1,209 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Burn, Token};
+
+use crate::pool_token::{self, TransferTokens};
+use crate::state::*;
+use crate::{Amount, AmountKind, ErrorCode};
+
+#[derive(Accounts)]
+pub struct FillWithdrawals<'info> {
+    /// The pool to withdraw from
+    #[account(mut,
+              has_one = token_mint,
+              has_one = vault,
+              has_one = deposit_note_mint)]
+    pub margin_pool: Account<'info, MarginPool>,
+
+    /// The queue of withdrawal requests for the pool
+    #[account(mut,
+              has_one = margin_pool,
+              has_one = escrow)]
+    pub withdrawal_queue: AccountLoader<'info, WithdrawalQueue>,
+
+    /// The token account holding the deposit notes of queued requests
+    /// CHECK:
+    #[account(mut)]
+    pub escrow: UncheckedAccount<'info>,
+
+    /// The mint for the pool's token
+    /// CHECK:
+    pub token_mint: UncheckedAccount<'info>,
+
+    /// The vault for the pool, where tokens are held
+    /// CHECK:
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+
+    /// The mint for the deposit notes
+    /// CHECK:
+    #[account(mut)]
+    pub deposit_note_mint: UncheckedAccount<'info>,
+
+    /// The history of rates for the pool, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                b"history".as_ref()
+              ],
+              bump)]
+    pub history: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// The program for the pool's token, which may be either the token or token-2022 program
+    /// CHECK:
+    pub pool_token_program: UncheckedAccount<'info>,
+    // The token accounts to receive the withdrawn tokens are provided as remaining accounts,
+    // in the same order as the requests in the queue.
+}
+
+impl<'info> FillWithdrawals<'info> {
+    fn transfer_accounts<'a>(
+        &'a self,
+        destination: &'a AccountInfo<'info>,
+    ) -> TransferTokens<'a, 'info> {
+        TransferTokens {
+            token_program: &self.pool_token_program,
+            mint: &self.token_mint,
+            source: &self.vault,
+            destination,
+            authority: self.margin_pool.as_ref(),
+        }
+    }
+
+    fn burn_note_context(&self) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Burn {
+                to: self.escrow.to_account_info(),
+                mint: self.deposit_note_mint.to_account_info(),
+                authority: self.margin_pool.to_account_info(),
+            },
+        )
+    }
+}
+
+pub fn fill_withdrawals_handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, FillWithdrawals<'info>>,
+) -> Result<()> {
+    let pool = &mut ctx.accounts.margin_pool;
+    let clock = Clock::get()?;
+
+    pool.check_action(PoolAction::Withdraw)?;
+
+    // Make sure interest accrual is up-to-date
+    if !pool.accrue_interest(clock.unix_timestamp) {
+        msg!("interest accrual is too far behind");
+        return Err(ErrorCode::InterestAccrualBehind.into());
+    }
+
+    pool.record_history(&ctx.accounts.history, clock.unix_timestamp)?;
+
+    let mut queue = ctx.accounts.withdrawal_queue.load_mut()?;
+    let mut fills = vec![];
+
+    for destination in ctx.remaining_accounts {
+        let request = match queue.front_mut() {
+            Some(request) => request,
+            None => break,
+        };
+
+        if request.destination != destination.key() {
+            msg!(
+                "expected destination {} for request {}",
+                request.destination,
+                request.sequence
+            );
+            return err!(ErrorCode::WithdrawalDestinationMismatch);
+        }
+
+        // a destination that can no longer receive tokens would hold up the rest
+        // of the queue, so the request is skipped and left for the depositor to cancel
+        if !pool_token::can_receive(destination, &ctx.accounts.token_mint) {
+            msg!("skipping request {}", request.sequence);
+            let notes = request.notes;
+
+            pool.dequeue_withdrawal(notes)?;
+            queue.pop_front();
+            continue;
+        }
+
+        let requested = pool.convert_deposit_amount(
+            Amount::notes(request.notes),
+            RoundingDirection::direction(PoolAction::Withdraw, AmountKind::Notes),
+        )?;
+
+        // only fill as much of the request as the pool has tokens available for
+        let amount = match requested.tokens <= pool.deposit_tokens {
+            true => requested,
+            false => {
+                let available = pool.convert_deposit_amount(
+                    Amount::tokens(pool.deposit_tokens),
+                    RoundingDirection::direction(PoolAction::Withdraw, AmountKind::Tokens),
+                )?;
+
+                FullAmount {
+                    tokens: available.tokens,
+                    notes: std::cmp::min(available.notes, request.notes),
+                }
+            }
+        };
+
+        if amount.tokens == 0 || amount.notes == 0 {
+            break;
+        }
+
+        pool.withdraw(&amount)?;
+        pool.dequeue_withdrawal(amount.notes)?;
+
+        request.notes -= amount.notes;
+        let filled = request.notes == 0;
+
+        if filled {
+            queue.pop_front();
+        }
+
+        fills.push((destination.clone(), amount));
+
+        if !filled {
+            break;
+        }
+    }
+
+    drop(queue);
+
+    let pool = &ctx.accounts.margin_pool;
+    let signer = [&pool.signer_seeds()?[..]];
+
+    for (destination, amount) in fills {
+        pool_token::transfer(
+            ctx.accounts.transfer_accounts(&destination),
+            amount.tokens,
+            &signer,
+        )?;
+        token::burn(
+            ctx.accounts.burn_note_context().with_signer(&signer),
+            amount.notes,
+        )?;
+    }
+
+    Ok(())
+}
@@ -53,6 +53,16 @@ pub struct MarginBorrow<'info> {
     #[account(mut, constraint = deposit_account.owner == margin_account.key())]
     pub deposit_account: Account<'info, TokenAccount>,
 
+    /// The history of rates for the pool, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                b"history".as_ref()
+              ],
+              bump)]
+    pub history: AccountInfo<'info>,
+
     pub token_program: Program<'info, Token>,
 }
 
@@ -89,25 +99,29 @@ impl<'info> MarginBorrow<'info> {
     }
 }
 
-pub fn margin_borrow_handler(ctx: Context<MarginBorrow>, token_amount: u64) -> Result<()> {
+pub fn margin_borrow_handler(ctx: Context<MarginBorrow>, amount: Amount) -> Result<()> {
     let pool = &mut ctx.accounts.margin_pool;
     let clock = Clock::get()?;
 
+    pool.check_action(PoolAction::Borrow)?;
+
     // Make sure interest accrual is up-to-date
     if !pool.accrue_interest(clock.unix_timestamp) {
         msg!("interest accrual is too far behind");
         return Err(ErrorCode::InterestAccrualBehind.into());
     }
 
+    pool.record_history(&ctx.accounts.history, clock.unix_timestamp)?;
+
     // First record a borrow of the tokens requested
-    let borrow_rounding = RoundingDirection::direction(PoolAction::Borrow, AmountKind::Tokens);
-    let borrow_amount = pool.convert_loan_amount(Amount::tokens(token_amount), borrow_rounding)?;
+    let borrow_rounding = RoundingDirection::direction(PoolAction::Borrow, amount.kind);
+    let borrow_amount = pool.convert_loan_amount(amount, borrow_rounding)?;
     pool.borrow(&borrow_amount)?;
 
     // Then record a deposit of the same borrowed tokens
     let deposit_rounding = RoundingDirection::direction(PoolAction::Deposit, AmountKind::Tokens);
     let deposit_amount =
-        pool.convert_deposit_amount(Amount::tokens(token_amount), deposit_rounding)?;
+        pool.convert_deposit_amount(Amount::tokens(borrow_amount.tokens), deposit_rounding)?;
     pool.deposit(&deposit_amount);
 
     // Finish by minting the loan and deposit notes
@@ -0,0 +1,110 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Burn, Token, TokenAccount};
+
+use jet_margin::{AdapterResult, MarginAccount};
+
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct MarginBurnLoanNotes<'info> {
+    /// The margin account being executed on
+    #[account(signer)]
+    pub margin_account: AccountLoader<'info, MarginAccount>,
+
+    /// The pool with the loans that were written off
+    #[account(mut, has_one = loan_note_mint)]
+    pub margin_pool: Account<'info, MarginPool>,
+
+    /// The mint for the notes representing loans from the pool
+    /// CHECK:
+    #[account(mut)]
+    pub loan_note_mint: AccountInfo<'info>,
+
+    /// The account with the loan notes to be burned
+    #[account(mut)]
+    pub loan_account: Account<'info, TokenAccount>,
+
+    /// The campaign rewarding holders of the loan notes, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                margin_pool.loan_note_mint.as_ref(),
+                b"reward-campaign".as_ref()
+              ],
+              bump)]
+    pub loan_reward_campaign: AccountInfo<'info>,
+
+    /// The rewards checkpoint for the loan notes, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                loan_reward_campaign.key().as_ref(),
+                loan_account.key().as_ref(),
+                b"reward-checkpoint".as_ref()
+              ],
+              bump)]
+    pub loan_reward_checkpoint: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> MarginBurnLoanNotes<'info> {
+    fn burn_loan_context(&self) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Burn {
+                mint: self.loan_note_mint.to_account_info(),
+                to: self.loan_account.to_account_info(),
+                authority: self.margin_account.to_account_info(),
+            },
+        )
+    }
+}
+
+pub fn margin_burn_loan_notes_handler(ctx: Context<MarginBurnLoanNotes>) -> Result<()> {
+    let pool = &mut ctx.accounts.margin_pool;
+    let notes = ctx.accounts.loan_account.amount;
+
+    // The position is reduced, so any rewards it has earned are settled first
+    super::settle_position_rewards(
+        pool,
+        &ctx.accounts.loan_reward_campaign,
+        &ctx.accounts.loan_reward_checkpoint,
+        &ctx.accounts.loan_account.to_account_info(),
+        notes,
+    )?;
+
+    // The loans have no value left to repay, so all the notes are burned
+    pool.burn_written_off_loan_notes(notes)?;
+
+    let pool = &ctx.accounts.margin_pool;
+    let signer = [&pool.signer_seeds()?[..]];
+
+    token::burn(ctx.accounts.burn_loan_context().with_signer(&signer), notes)?;
+
+    // Tell the margin program what accounts changed
+    jet_margin::write_adapter_result(&AdapterResult::NewBalanceChange(vec![ctx
+        .accounts
+        .loan_account
+        .key()]))?;
+
+    Ok(())
+}
@@ -0,0 +1,104 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_lang::AccountsClose;
+use anchor_spl::token::{self, Burn, Token, TokenAccount};
+
+use jet_margin::{AdapterResult, MarginAccount};
+
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct MarginBurnTermLoanNotes<'info> {
+    /// The margin account being executed on
+    #[account(signer)]
+    pub margin_account: AccountLoader<'info, MarginAccount>,
+
+    /// The pool with the term loans that were written off
+    #[account(mut, has_one = term_loan_note_mint)]
+    pub margin_pool: Account<'info, MarginPool>,
+
+    /// The account tracking the terms of the loan, which is closed
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                margin_account.key().as_ref(),
+                b"term-loan".as_ref()
+              ],
+              bump = term_loan.bump[0])]
+    pub term_loan: Account<'info, TermLoan>,
+
+    /// The mint for the notes representing term loans from the pool
+    /// CHECK:
+    #[account(mut)]
+    pub term_loan_note_mint: AccountInfo<'info>,
+
+    /// The account with the term loan notes to be burned
+    #[account(mut)]
+    pub term_loan_account: Account<'info, TokenAccount>,
+
+    /// The owner of the margin account, which receives the rent from the term
+    /// loan account
+    /// CHECK:
+    #[account(mut, constraint = owner.key() == margin_account.load().unwrap().owner)]
+    pub owner: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> MarginBurnTermLoanNotes<'info> {
+    fn burn_term_loan_context(&self) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Burn {
+                mint: self.term_loan_note_mint.to_account_info(),
+                to: self.term_loan_account.to_account_info(),
+                authority: self.margin_account.to_account_info(),
+            },
+        )
+    }
+}
+
+pub fn margin_burn_term_loan_notes_handler(ctx: Context<MarginBurnTermLoanNotes>) -> Result<()> {
+    let pool = &mut ctx.accounts.margin_pool;
+    let term_loan = &ctx.accounts.term_loan;
+
+    // The loan has no value left to repay, so its notes are all burned
+    pool.burn_written_off_term_loan(term_loan.principal)?;
+
+    let notes = term_loan.notes;
+    let pool = &ctx.accounts.margin_pool;
+    let signer = [&pool.signer_seeds()?[..]];
+
+    token::burn(
+        ctx.accounts.burn_term_loan_context().with_signer(&signer),
+        notes,
+    )?;
+
+    ctx.accounts
+        .term_loan
+        .close(ctx.accounts.owner.to_account_info())?;
+
+    // Tell the margin program what accounts changed
+    jet_margin::write_adapter_result(&AdapterResult::NewBalanceChange(vec![ctx
+        .accounts
+        .term_loan_account
+        .key()]))?;
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use jet_margin::{AdapterResult, MarginAccount};
+
+use crate::state::*;
+use crate::ErrorCode;
+
+#[derive(Accounts)]
+pub struct MarginClaimRewards<'info> {
+    /// The margin account being executed on
+    #[account(signer)]
+    pub margin_account: AccountLoader<'info, MarginAccount>,
+
+    /// The pool whose notes are rewarded
+    pub margin_pool: Account<'info, MarginPool>,
+
+    /// The campaign to claim rewards from
+    #[account(mut,
+              has_one = margin_pool,
+              has_one = vault)]
+    pub campaign: Account<'info, RewardCampaign>,
+
+    /// The checkpoint for the margin account's position
+    #[account(mut,
+              has_one = campaign,
+              has_one = token_account,
+              constraint = checkpoint.owner == margin_account.key())]
+    pub checkpoint: Account<'info, RewardCheckpoint>,
+
+    /// The position token account holding the notes
+    #[account(constraint = token_account.owner == margin_account.key())]
+    pub token_account: Account<'info, TokenAccount>,
+
+    /// The token account funding the rewards
+    #[account(mut)]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// The token account to receive the rewards, which must belong to either
+    /// the margin account or its owner
+    #[account(mut)]
+    pub destination: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> MarginClaimRewards<'info> {
+    fn transfer_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Transfer {
+                to: self.destination.to_account_info(),
+                from: self.vault.to_account_info(),
+                authority: self.campaign.to_account_info(),
+            },
+        )
+    }
+}
+
+pub fn margin_claim_rewards_handler(ctx: Context<MarginClaimRewards>) -> Result<()> {
+    let margin_account = ctx.accounts.margin_account.key();
+    let margin_account_owner = ctx.accounts.margin_account.load()?.owner;
+    let destination_owner = ctx.accounts.destination.owner;
+
+    if destination_owner != margin_account && destination_owner != margin_account_owner {
+        msg!("rewards can only be claimed to the margin account or its owner");
+        return err!(ErrorCode::InvalidRewardDestination);
+    }
+
+    let amount = super::settle_rewards(
+        &ctx.accounts.margin_pool,
+        &mut ctx.accounts.campaign,
+        &mut ctx.accounts.checkpoint,
+        Some(ctx.accounts.token_account.amount),
+        ctx.accounts.vault.amount,
+    )?;
+
+    let campaign = &ctx.accounts.campaign;
+
+    token::transfer(
+        ctx.accounts
+            .transfer_context()
+            .with_signer(&[&campaign.signer_seeds()]),
+        amount,
+    )?;
+
+    // The rewards may be received into one of the margin account's own positions
+    let modified = match destination_owner == margin_account {
+        true => vec![ctx.accounts.destination.key()],
+        false => vec![],
+    };
+
+    // Tell the margin program what accounts changed
+    jet_margin::write_adapter_result(&AdapterResult::NewBalanceChange(modified))?;
+
+    Ok(())
+}
@@ -0,0 +1,146 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, MintTo, Token, TokenAccount};
+
+use jet_margin::{AdapterResult, MarginAccount};
+
+use crate::pool_token::{self, TransferTokens};
+use crate::{state::*, AmountKind};
+use crate::{Amount, ErrorCode};
+
+#[derive(Accounts)]
+pub struct MarginDeposit<'info> {
+    /// The margin account being executed on
+    #[account(signer)]
+    pub margin_account: AccountLoader<'info, MarginAccount>,
+
+    /// The pool to deposit into
+    #[account(mut,
+              has_one = token_mint,
+              has_one = vault,
+              has_one = deposit_note_mint)]
+    pub margin_pool: Account<'info, MarginPool>,
+
+    /// The mint for the pool's token
+    /// CHECK:
+    pub token_mint: UncheckedAccount<'info>,
+
+    /// The vault for the pool, where tokens are held
+    /// CHECK:
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+
+    /// The mint for the deposit notes
+    /// CHECK:
+    #[account(mut)]
+    pub deposit_note_mint: UncheckedAccount<'info>,
+
+    /// The address with authority to deposit the tokens
+    pub depositor: Signer<'info>,
+
+    /// The source of the tokens to be deposited
+    /// CHECK:
+    #[account(mut)]
+    pub source: UncheckedAccount<'info>,
+
+    /// The margin account position to receive the deposit notes
+    #[account(mut, constraint = destination.owner == margin_account.key())]
+    pub destination: Account<'info, TokenAccount>,
+
+    /// The history of rates for the pool, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                b"history".as_ref()
+              ],
+              bump)]
+    pub history: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// The program for the pool's token, which may be either the token or token-2022 program
+    /// CHECK:
+    pub pool_token_program: UncheckedAccount<'info>,
+}
+
+impl<'info> MarginDeposit<'info> {
+    fn transfer_source_accounts(&self) -> TransferTokens<'_, 'info> {
+        TransferTokens {
+            token_program: &self.pool_token_program,
+            mint: &self.token_mint,
+            source: &self.source,
+            destination: &self.vault,
+            authority: &self.depositor,
+        }
+    }
+
+    fn mint_note_context(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            MintTo {
+                to: self.destination.to_account_info(),
+                mint: self.deposit_note_mint.to_account_info(),
+                authority: self.margin_pool.to_account_info(),
+            },
+        )
+    }
+}
+
+pub fn margin_deposit_handler(ctx: Context<MarginDeposit>, token_amount: u64) -> Result<()> {
+    let pool = &mut ctx.accounts.margin_pool;
+    let clock = Clock::get()?;
+
+    pool.check_action(PoolAction::Deposit)?;
+
+    // Make sure interest accrual is up-to-date
+    if !pool.accrue_interest(clock.unix_timestamp) {
+        msg!("interest accrual is too far behind");
+        return Err(ErrorCode::InterestAccrualBehind.into());
+    }
+
+    pool.record_history(&ctx.accounts.history, clock.unix_timestamp)?;
+
+    // Transfer the tokens first, since the vault can receive less than the amount
+    // sent when the token charges a fee on transfers
+    let received =
+        pool_token::transfer(ctx.accounts.transfer_source_accounts(), token_amount, &[])?;
+
+    let pool = &mut ctx.accounts.margin_pool;
+    let deposit_rounding = RoundingDirection::direction(PoolAction::Deposit, AmountKind::Tokens);
+    let deposit_amount = pool.convert_deposit_amount(Amount::tokens(received), deposit_rounding)?;
+    pool.deposit(&deposit_amount);
+    pool.check_deposit_cap()?;
+
+    let pool = &ctx.accounts.margin_pool;
+    let signer = [&pool.signer_seeds()?[..]];
+
+    token::mint_to(
+        ctx.accounts.mint_note_context().with_signer(&signer),
+        deposit_amount.notes,
+    )?;
+
+    // Tell the margin program what accounts changed
+    jet_margin::write_adapter_result(&AdapterResult::NewBalanceChange(vec![ctx
+        .accounts
+        .destination
+        .key()]))?;
+
+    Ok(())
+}
@@ -16,10 +16,11 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use anchor_lang::prelude::*;
-use pyth_client::Price;
 
-use jet_margin::{AdapterResult, MarginAccount, PriceChangeInfo};
+use jet_margin::{AdapterResult, MarginAccount, PriceChangeInfo, WeightedPriceChangeInfo};
+use jet_proto_math::Number;
 
+use crate::oracle::read_price;
 use crate::state::*;
 
 #[derive(Accounts)]
@@ -32,23 +33,54 @@ pub struct MarginRefreshPosition<'info> {
     #[account(has_one = token_price_oracle)]
     pub margin_pool: Account<'info, MarginPool>,
 
-    /// The pyth price account for the pool's token
+    /// The cache of price information for the pool's token
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                b"oracle".as_ref()
+              ],
+              bump)]
+    pub oracle: Account<'info, MarginPoolOracle>,
+
+    /// The oracle account with the price of the pool's token
     /// CHECK:
     pub token_price_oracle: AccountInfo<'info>,
+
+    /// The term loan for the margin account, which may not exist
+    /// CHECK:
+    #[account(seeds = [
+                margin_pool.key().as_ref(),
+                margin_account.key().as_ref(),
+                b"term-loan".as_ref()
+              ],
+              bump)]
+    pub term_loan: AccountInfo<'info>,
 }
 
 pub fn margin_refresh_position_handler(ctx: Context<MarginRefreshPosition>) -> Result<()> {
     let pool = &ctx.accounts.margin_pool;
+    let oracle = &mut ctx.accounts.oracle;
+
+    // update the cached price from whichever source the pool is configured with
+    let token_price = read_price(&oracle.source, &ctx.accounts.token_price_oracle)?;
 
-    // update the oracles with the pyth format
-    let token_oracle_data = ctx.accounts.token_price_oracle.try_borrow_data()?;
-    let token_oracle = bytemuck::from_bytes::<Price>(&token_oracle_data);
+    let clock = Clock::get()?;
+    oracle.update(&token_price, clock.unix_timestamp)?;
 
-    let prices = pool.calculate_prices(token_oracle);
+    // a term loan note is worth one token, unless the loan has matured
+    let term_loan_note_value = match ctx.accounts.term_loan.data_is_empty() {
+        true => Number::ONE,
+        false => {
+            let term_loan = Account::<TermLoan>::try_from(&ctx.accounts.term_loan)?;
+            term_loan.note_value(clock.unix_timestamp)
+        }
+    };
+
+    let prices = pool.calculate_prices(oracle, term_loan_note_value);
 
     let deposit_price_info = PriceChangeInfo {
-        slot: token_oracle.valid_slot,
-        exponent: token_oracle.expo,
+        slot: oracle.slot,
+        exponent: oracle.exponent,
         value: prices.deposit_note_price,
         confidence: prices.deposit_note_conf,
         twap: prices.deposit_note_twap,
@@ -56,18 +88,38 @@ pub fn margin_refresh_position_handler(ctx: Context<MarginRefreshPosition>) -> R
     };
 
     let loan_price_info = PriceChangeInfo {
-        slot: token_oracle.valid_slot,
-        exponent: token_oracle.expo,
+        slot: oracle.slot,
+        exponent: oracle.exponent,
         value: prices.loan_note_price,
         confidence: prices.loan_note_conf,
         twap: prices.loan_note_twap,
         mint: pool.loan_note_mint,
     };
 
-    // Tell the margin program what the current prices are
-    jet_margin::write_adapter_result(&AdapterResult::PriceChange(vec![
-        deposit_price_info,
-        loan_price_info,
+    let term_loan_price_info = PriceChangeInfo {
+        slot: oracle.slot,
+        exponent: oracle.exponent,
+        value: prices.term_loan_note_price,
+        confidence: prices.term_loan_note_conf,
+        twap: prices.term_loan_note_twap,
+        mint: pool.term_loan_note_mint,
+    };
+
+    // Tell the margin program what the current prices are, with the deposit
+    // notes' collateral weight reduced while the pool is highly utilized
+    jet_margin::write_adapter_result(&AdapterResult::WeightedPriceChange(vec![
+        WeightedPriceChangeInfo {
+            price: deposit_price_info,
+            collateral_weight_reduction: pool.collateral_weight_reduction(),
+        },
+        WeightedPriceChangeInfo {
+            price: loan_price_info,
+            collateral_weight_reduction: 0,
+        },
+        WeightedPriceChangeInfo {
+            price: term_loan_price_info,
+            collateral_weight_reduction: 0,
+        },
     ]))?;
 
     Ok(())
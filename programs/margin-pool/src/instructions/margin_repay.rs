@@ -53,6 +53,60 @@ pub struct MarginRepay<'info> {
     #[account(mut)]
     pub deposit_account: Account<'info, TokenAccount>,
 
+    /// The history of rates for the pool, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                b"history".as_ref()
+              ],
+              bump)]
+    pub history: AccountInfo<'info>,
+
+    /// The campaign rewarding holders of the deposit notes, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                margin_pool.deposit_note_mint.as_ref(),
+                b"reward-campaign".as_ref()
+              ],
+              bump)]
+    pub deposit_reward_campaign: AccountInfo<'info>,
+
+    /// The rewards checkpoint for the deposit notes, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                deposit_reward_campaign.key().as_ref(),
+                deposit_account.key().as_ref(),
+                b"reward-checkpoint".as_ref()
+              ],
+              bump)]
+    pub deposit_reward_checkpoint: AccountInfo<'info>,
+
+    /// The campaign rewarding holders of the loan notes, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                margin_pool.loan_note_mint.as_ref(),
+                b"reward-campaign".as_ref()
+              ],
+              bump)]
+    pub loan_reward_campaign: AccountInfo<'info>,
+
+    /// The rewards checkpoint for the loan notes, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                loan_reward_campaign.key().as_ref(),
+                loan_account.key().as_ref(),
+                b"reward-checkpoint".as_ref()
+              ],
+              bump)]
+    pub loan_reward_checkpoint: AccountInfo<'info>,
+
     pub token_program: Program<'info, Token>,
 }
 
@@ -84,20 +138,41 @@ pub fn margin_repay_handler(ctx: Context<MarginRepay>, amount: Amount) -> Result
     let pool = &mut ctx.accounts.margin_pool;
     let clock = Clock::get()?;
 
+    pool.check_action(PoolAction::Repay)?;
+
     // Make sure interest accrual is up-to-date
     if !pool.accrue_interest(clock.unix_timestamp) {
         msg!("interest accrual is too far behind");
         return Err(ErrorCode::InterestAccrualBehind.into());
     }
 
-    // First record a withdraw of the deposit to use for repaying
+    pool.record_history(&ctx.accounts.history, clock.unix_timestamp)?;
+
     let withdraw_rounding = RoundingDirection::direction(PoolAction::Withdraw, amount.kind);
     let withdraw_amount = pool.convert_deposit_amount(amount, withdraw_rounding)?;
+    let repay_rounding = RoundingDirection::direction(PoolAction::Repay, amount.kind);
+    let repay_amount = pool.convert_loan_amount(amount, repay_rounding)?;
+
+    // Both positions are reduced, so any rewards they've earned are settled first
+    super::settle_position_rewards(
+        pool,
+        &ctx.accounts.deposit_reward_campaign,
+        &ctx.accounts.deposit_reward_checkpoint,
+        &ctx.accounts.deposit_account.to_account_info(),
+        withdraw_amount.notes,
+    )?;
+    super::settle_position_rewards(
+        pool,
+        &ctx.accounts.loan_reward_campaign,
+        &ctx.accounts.loan_reward_checkpoint,
+        &ctx.accounts.loan_account.to_account_info(),
+        repay_amount.notes,
+    )?;
+
+    // First record a withdraw of the deposit to use for repaying
     pool.withdraw(&withdraw_amount)?;
 
     // Then record a repay using the withdrawn tokens
-    let repay_rounding = RoundingDirection::direction(PoolAction::Repay, amount.kind);
-    let repay_amount = pool.convert_loan_amount(amount, repay_rounding)?;
     pool.repay(&repay_amount)?;
 
     // Finish by burning the loan and deposit notes
@@ -0,0 +1,185 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, MintTo, Token, TokenAccount};
+
+use jet_margin::{AdapterResult, MarginAccount};
+
+use crate::{state::*, AmountKind};
+use crate::{Amount, ErrorCode};
+
+#[derive(Accounts)]
+pub struct MarginTermBorrow<'info> {
+    /// The margin account being executed on
+    #[account(signer)]
+    pub margin_account: AccountLoader<'info, MarginAccount>,
+
+    /// The pool to borrow from
+    #[account(mut,
+              has_one = term_loan_note_mint,
+              has_one = deposit_note_mint)]
+    pub margin_pool: Account<'info, MarginPool>,
+
+    /// The account tracking the terms of the loan, which is only closed once
+    /// the loan is fully repaid, so each margin account has at most one
+    /// term loan from the pool
+    #[account(init,
+              seeds = [
+                margin_pool.key().as_ref(),
+                margin_account.key().as_ref(),
+                b"term-loan".as_ref()
+              ],
+              bump,
+              space = 8 + std::mem::size_of::<TermLoan>(),
+              payer = payer)]
+    pub term_loan: Account<'info, TermLoan>,
+
+    /// The mint for the notes representing term loans from the pool
+    /// CHECK:
+    #[account(mut)]
+    pub term_loan_note_mint: AccountInfo<'info>,
+
+    /// The mint for the notes representing deposit into the pool
+    /// CHECK:
+    #[account(mut)]
+    pub deposit_note_mint: AccountInfo<'info>,
+
+    /// The account to receive the term loan notes
+    #[account(mut, constraint = term_loan_account.owner == margin_account.key())]
+    pub term_loan_account: Account<'info, TokenAccount>,
+
+    /// The account to receive the borrowed tokens (as deposit notes)
+    #[account(mut, constraint = deposit_account.owner == margin_account.key())]
+    pub deposit_account: Account<'info, TokenAccount>,
+
+    /// The payer of rent for the term loan account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The history of rates for the pool, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                b"history".as_ref()
+              ],
+              bump)]
+    pub history: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MarginTermBorrow<'info> {
+    fn mint_term_loan_context(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            MintTo {
+                mint: self.term_loan_note_mint.to_account_info(),
+                to: self.term_loan_account.to_account_info(),
+                authority: self.margin_pool.to_account_info(),
+            },
+        )
+    }
+
+    fn mint_deposit_context(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            MintTo {
+                to: self.deposit_account.to_account_info(),
+                mint: self.deposit_note_mint.to_account_info(),
+                authority: self.margin_pool.to_account_info(),
+            },
+        )
+    }
+}
+
+pub fn margin_term_borrow_handler(
+    ctx: Context<MarginTermBorrow>,
+    token_amount: u64,
+    term: i64,
+) -> Result<()> {
+    if term <= 0 || term > crate::MAX_TERM_LOAN_DURATION {
+        msg!(
+            "the term must be between 1 and {} seconds",
+            crate::MAX_TERM_LOAN_DURATION
+        );
+        return err!(ErrorCode::InvalidTerm);
+    }
+
+    let pool = &mut ctx.accounts.margin_pool;
+    let clock = Clock::get()?;
+
+    pool.check_action(PoolAction::Borrow)?;
+
+    // Make sure interest accrual is up-to-date
+    if !pool.accrue_interest(clock.unix_timestamp) {
+        msg!("interest accrual is too far behind");
+        return Err(ErrorCode::InterestAccrualBehind.into());
+    }
+
+    pool.record_history(&ctx.accounts.history, clock.unix_timestamp)?;
+
+    // First record a term loan of the tokens requested
+    pool.term_borrow(token_amount)?;
+
+    // Then record a deposit of the same borrowed tokens
+    let deposit_rounding = RoundingDirection::direction(PoolAction::Deposit, AmountKind::Tokens);
+    let deposit_amount =
+        pool.convert_deposit_amount(Amount::tokens(token_amount), deposit_rounding)?;
+    pool.deposit(&deposit_amount);
+
+    // The loan is charged the pool's rate, including the loan itself, for the whole term
+    let rate = pool.interest_rate();
+    let rate_bps = u16::try_from(rate.as_u64(-4)).map_err(|_| ErrorCode::InvalidInterestRate)?;
+    let repayment = TermLoan::repayment_amount(token_amount, rate, term);
+
+    pool.term_loan_originated(repayment - token_amount, term);
+
+    let term_loan = &mut ctx.accounts.term_loan;
+
+    term_loan.margin_account = ctx.accounts.margin_account.key();
+    term_loan.margin_pool = pool.key();
+    term_loan.bump[0] = *ctx.bumps.get("term_loan").unwrap();
+    term_loan.interest_rate = rate_bps;
+    term_loan.principal = token_amount;
+    term_loan.notes = repayment;
+    term_loan.originated_at = clock.unix_timestamp;
+    term_loan.maturity = clock.unix_timestamp + term;
+
+    // Finish by minting the term loan and deposit notes
+    let pool = &ctx.accounts.margin_pool;
+    let signer = [&pool.signer_seeds()?[..]];
+
+    token::mint_to(
+        ctx.accounts.mint_term_loan_context().with_signer(&signer),
+        repayment,
+    )?;
+    token::mint_to(
+        ctx.accounts.mint_deposit_context().with_signer(&signer),
+        deposit_amount.notes,
+    )?;
+
+    // Tell the margin program what accounts changed
+    jet_margin::write_adapter_result(&AdapterResult::NewBalanceChange(vec![
+        ctx.accounts.term_loan_account.key(),
+        ctx.accounts.deposit_account.key(),
+    ]))?;
+
+    Ok(())
+}
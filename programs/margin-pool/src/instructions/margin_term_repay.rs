@@ -0,0 +1,167 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_lang::AccountsClose;
+use anchor_spl::token::{self, Burn, Token, TokenAccount};
+
+use jet_margin::{AdapterResult, MarginAccount};
+use jet_proto_math::Number;
+
+use crate::{state::*, AmountKind};
+use crate::{Amount, ErrorCode};
+
+#[derive(Accounts)]
+pub struct MarginTermRepay<'info> {
+    /// The margin account being executed on
+    #[account(signer)]
+    pub margin_account: AccountLoader<'info, MarginAccount>,
+
+    /// The pool with the outstanding term loan
+    #[account(mut,
+              has_one = deposit_note_mint,
+              has_one = term_loan_note_mint)]
+    pub margin_pool: Account<'info, MarginPool>,
+
+    /// The account tracking the terms of the loan
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                margin_account.key().as_ref(),
+                b"term-loan".as_ref()
+              ],
+              bump = term_loan.bump[0])]
+    pub term_loan: Account<'info, TermLoan>,
+
+    /// The mint for the notes representing term loans from the pool
+    /// CHECK:
+    #[account(mut)]
+    pub term_loan_note_mint: AccountInfo<'info>,
+
+    /// The mint for the notes representing deposit into the pool
+    /// CHECK:
+    #[account(mut)]
+    pub deposit_note_mint: AccountInfo<'info>,
+
+    /// The account with the term loan notes
+    #[account(mut)]
+    pub term_loan_account: Account<'info, TokenAccount>,
+
+    /// The account with the deposit to pay off the loan with
+    #[account(mut)]
+    pub deposit_account: Account<'info, TokenAccount>,
+
+    /// The owner of the margin account, which receives the rent from the term
+    /// loan account once fully repaid
+    /// CHECK:
+    #[account(mut, constraint = owner.key() == margin_account.load().unwrap().owner)]
+    pub owner: AccountInfo<'info>,
+
+    /// The history of rates for the pool, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                b"history".as_ref()
+              ],
+              bump)]
+    pub history: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> MarginTermRepay<'info> {
+    fn burn_term_loan_context(&self) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Burn {
+                mint: self.term_loan_note_mint.to_account_info(),
+                to: self.term_loan_account.to_account_info(),
+                authority: self.margin_account.to_account_info(),
+            },
+        )
+    }
+
+    fn burn_deposit_context(&self) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Burn {
+                to: self.deposit_account.to_account_info(),
+                mint: self.deposit_note_mint.to_account_info(),
+                authority: self.margin_account.to_account_info(),
+            },
+        )
+    }
+}
+
+pub fn margin_term_repay_handler(ctx: Context<MarginTermRepay>, notes: u64) -> Result<()> {
+    let pool = &mut ctx.accounts.margin_pool;
+    let term_loan = &mut ctx.accounts.term_loan;
+    let clock = Clock::get()?;
+
+    pool.check_action(PoolAction::Repay)?;
+
+    // Make sure interest accrual is up-to-date
+    if !pool.accrue_interest(clock.unix_timestamp) {
+        msg!("interest accrual is too far behind");
+        return Err(ErrorCode::InterestAccrualBehind.into());
+    }
+
+    pool.record_history(&ctx.accounts.history, clock.unix_timestamp)?;
+
+    // The notes owe more once the loan has matured
+    let tokens_owed =
+        (term_loan.note_value(clock.unix_timestamp) * Number::from(notes)).as_u64_ceil(0);
+
+    // First record a withdraw of the deposit to use for repaying
+    let withdraw_rounding = RoundingDirection::direction(PoolAction::Withdraw, AmountKind::Tokens);
+    let withdraw_amount =
+        pool.convert_deposit_amount(Amount::tokens(tokens_owed), withdraw_rounding)?;
+    pool.withdraw(&withdraw_amount)?;
+
+    // Then record the repayment of the loan
+    let repaid = term_loan.repay(notes, clock.unix_timestamp)?;
+    pool.term_repay(&repaid, tokens_owed)?;
+
+    // Finish by burning the term loan and deposit notes
+    let pool = &ctx.accounts.margin_pool;
+    let signer = [&pool.signer_seeds()?[..]];
+
+    token::burn(
+        ctx.accounts.burn_term_loan_context().with_signer(&signer),
+        notes,
+    )?;
+    token::burn(
+        ctx.accounts.burn_deposit_context().with_signer(&signer),
+        withdraw_amount.notes,
+    )?;
+
+    // Once fully repaid the loan is no longer needed
+    if ctx.accounts.term_loan.notes == 0 {
+        ctx.accounts
+            .term_loan
+            .close(ctx.accounts.owner.to_account_info())?;
+    }
+
+    // Tell the margin program what accounts changed
+    jet_margin::write_adapter_result(&AdapterResult::NewBalanceChange(vec![
+        ctx.accounts.term_loan_account.key(),
+        ctx.accounts.deposit_account.key(),
+    ]))?;
+
+    Ok(())
+}
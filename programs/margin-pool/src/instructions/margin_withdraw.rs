@@ -33,10 +33,15 @@ pub struct MarginWithdraw<'info> {
 
     /// The pool to withdraw from
     #[account(mut,
+              has_one = token_mint,
               has_one = vault,
               has_one = deposit_note_mint)]
     pub margin_pool: Account<'info, MarginPool>,
 
+    /// The mint for the pool's token
+    /// CHECK:
+    pub token_mint: UncheckedAccount<'info>,
+
     /// The vault for the pool, where tokens are held
     /// CHECK:
     #[account(mut)]
@@ -57,7 +62,43 @@ pub struct MarginWithdraw<'info> {
     #[account(mut)]
     pub destination: UncheckedAccount<'info>,
 
+    /// The history of rates for the pool, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                b"history".as_ref()
+              ],
+              bump)]
+    pub history: AccountInfo<'info>,
+
+    /// The campaign rewarding holders of the deposit notes, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                margin_pool.deposit_note_mint.as_ref(),
+                b"reward-campaign".as_ref()
+              ],
+              bump)]
+    pub reward_campaign: AccountInfo<'info>,
+
+    /// The rewards checkpoint for the source of the notes, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                reward_campaign.key().as_ref(),
+                source.key().as_ref(),
+                b"reward-checkpoint".as_ref()
+              ],
+              bump)]
+    pub reward_checkpoint: AccountInfo<'info>,
+
     pub token_program: Program<'info, Token>,
+
+    /// The program for the pool's token, which may be either the token or token-2022 program
+    /// CHECK:
+    pub pool_token_program: UncheckedAccount<'info>,
 }
 
 pub fn margin_withdraw_handler(ctx: Context<MarginWithdraw>, amount: Amount) -> Result<()> {
@@ -68,12 +109,17 @@ pub fn margin_withdraw_handler(ctx: Context<MarginWithdraw>, amount: Amount) ->
             ctx.program_id,
             &mut super::Withdraw {
                 margin_pool: ctx.accounts.margin_pool.clone(),
+                token_mint: ctx.accounts.token_mint.clone(),
                 vault: ctx.accounts.vault.clone(),
                 deposit_note_mint: ctx.accounts.deposit_note_mint.clone(),
                 depositor: Signer::try_from(&ctx.accounts.margin_account.to_account_info())?,
                 source: ctx.accounts.source.clone(),
                 destination: ctx.accounts.destination.clone(),
+                history: ctx.accounts.history.clone(),
+                reward_campaign: ctx.accounts.reward_campaign.clone(),
+                reward_checkpoint: ctx.accounts.reward_checkpoint.clone(),
                 token_program: ctx.accounts.token_program.clone(),
+                pool_token_program: ctx.accounts.pool_token_program.clone(),
             },
             &[],
             BTreeMap::new(),
@@ -0,0 +1,195 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{program::invoke, system_instruction};
+use anchor_lang::Discriminator;
+use anchor_spl::token::{Mint, Token};
+
+use jet_metadata::OracleSource;
+
+use crate::pool_token;
+use crate::state::*;
+use crate::ErrorCode;
+
+#[derive(Accounts)]
+pub struct MigratePool<'info> {
+    /// The pool to migrate, created before the pool had term loans
+    ///
+    /// This is only deserialized in the handler, since it still has the
+    /// original layout.
+    /// CHECK:
+    #[account(mut, seeds = [token_mint.key().as_ref()], bump, owner = crate::ID)]
+    pub margin_pool: UncheckedAccount<'info>,
+
+    /// The mint for term loan notes, which the pool didn't have yet
+    #[account(init,
+              seeds = [
+                margin_pool.key().as_ref(),
+                b"term-loan-notes".as_ref()
+              ],
+              bump,
+              mint::decimals = pool_token::mint_decimals(&token_mint),
+              mint::authority = margin_pool,
+              payer = payer)]
+    pub term_loan_note_mint: Box<Account<'info, Mint>>,
+
+    /// The cache of price information for the pool's token, which the
+    /// pool didn't have yet
+    #[account(init,
+              seeds = [
+                margin_pool.key().as_ref(),
+                b"oracle".as_ref()
+              ],
+              bump,
+              space = 8 + std::mem::size_of::<MarginPoolOracle>(),
+              payer = payer)]
+    pub oracle: Box<Account<'info, MarginPoolOracle>>,
+
+    /// The mint for the token being custodied by the pool
+    /// CHECK:
+    pub token_mint: UncheckedAccount<'info>,
+
+    /// The payer of rent for the new accounts and the larger pool
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// The configuration of a pool with the original layout
+#[derive(AnchorDeserialize)]
+struct LegacyMarginPoolConfig {
+    flags: u64,
+    utilization_rate_1: u16,
+    utilization_rate_2: u16,
+    borrow_rate_0: u16,
+    borrow_rate_1: u16,
+    borrow_rate_2: u16,
+    borrow_rate_3: u16,
+    management_fee_rate: u16,
+    management_fee_collect_threshold: u64,
+}
+
+/// A pool with the original layout, before the configuration gained new
+/// fields and the term loan fields were appended
+#[derive(AnchorDeserialize)]
+struct LegacyMarginPool {
+    version: u8,
+    pool_bump: [u8; 1],
+    vault: Pubkey,
+    fee_destination: Pubkey,
+    deposit_note_mint: Pubkey,
+    loan_note_mint: Pubkey,
+    token_mint: Pubkey,
+    token_price_oracle: Pubkey,
+    address: Pubkey,
+    config: LegacyMarginPoolConfig,
+    borrowed_tokens: [u8; 24],
+    uncollected_fees: [u8; 24],
+    deposit_tokens: u64,
+    deposit_notes: u64,
+    loan_notes: u64,
+    accrued_until: i64,
+}
+
+pub fn migrate_pool_handler(ctx: Context<MigratePool>) -> Result<()> {
+    let margin_pool = ctx.accounts.margin_pool.to_account_info();
+    let payer = &ctx.accounts.payer;
+
+    let legacy = {
+        let data = margin_pool.try_borrow_data()?;
+
+        if data.len() >= MarginPool::SPACE {
+            msg!("the pool already has the current layout");
+            return err!(ErrorCode::PoolMigrated);
+        }
+
+        if data.len() < 8 || data[..8] != MarginPool::discriminator() {
+            return err!(anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch);
+        }
+
+        LegacyMarginPool::deserialize(&mut &data[8..])
+            .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotDeserialize)?
+    };
+
+    // the new configuration fields start out disabled, and the pool has no
+    // term loans or queued withdrawals yet
+    let pool = MarginPool {
+        version: legacy.version,
+        pool_bump: legacy.pool_bump,
+        vault: legacy.vault,
+        fee_destination: legacy.fee_destination,
+        deposit_note_mint: legacy.deposit_note_mint,
+        loan_note_mint: legacy.loan_note_mint,
+        token_mint: legacy.token_mint,
+        token_price_oracle: legacy.token_price_oracle,
+        address: legacy.address,
+        config: MarginPoolConfig {
+            flags: legacy.config.flags,
+            utilization_rate_1: legacy.config.utilization_rate_1,
+            utilization_rate_2: legacy.config.utilization_rate_2,
+            borrow_rate_0: legacy.config.borrow_rate_0,
+            borrow_rate_1: legacy.config.borrow_rate_1,
+            borrow_rate_2: legacy.config.borrow_rate_2,
+            borrow_rate_3: legacy.config.borrow_rate_3,
+            management_fee_rate: legacy.config.management_fee_rate,
+            management_fee_collect_threshold: legacy.config.management_fee_collect_threshold,
+            ..Default::default()
+        },
+        borrowed_tokens: legacy.borrowed_tokens,
+        uncollected_fees: legacy.uncollected_fees,
+        deposit_tokens: legacy.deposit_tokens,
+        deposit_notes: legacy.deposit_notes,
+        loan_notes: legacy.loan_notes,
+        accrued_until: legacy.accrued_until,
+        term_loan_note_mint: ctx.accounts.term_loan_note_mint.key(),
+        ..Default::default()
+    };
+
+    let rent = Rent::get()?.minimum_balance(MarginPool::SPACE);
+    let balance = margin_pool.lamports();
+
+    if rent > balance {
+        invoke(
+            &system_instruction::transfer(payer.key, margin_pool.key, rent - balance),
+            &[
+                payer.to_account_info(),
+                margin_pool.clone(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+    }
+
+    margin_pool.realloc(MarginPool::SPACE, true)?;
+    pool.try_serialize(&mut &mut margin_pool.try_borrow_mut_data()?[..])?;
+
+    // pools from before the cache were always priced by pyth, and the
+    // product can be recorded by configuring the pool's oracle again
+    let oracle = &mut ctx.accounts.oracle;
+
+    oracle.token_mint = legacy.token_mint;
+    oracle.set_source(
+        OracleSource::Pyth,
+        Pubkey::default(),
+        legacy.token_price_oracle,
+    );
+
+    Ok(())
+}
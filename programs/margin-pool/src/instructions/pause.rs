@@ -0,0 +1,48 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+
+use jet_metadata::ControlAuthority;
+
+use crate::state::*;
+use crate::ErrorCode;
+
+#[derive(Accounts)]
+pub struct Pause<'info> {
+    /// The pool to pause actions for
+    #[account(mut)]
+    pub margin_pool: Account<'info, MarginPool>,
+
+    /// The authority allowed to modify the pool, which must sign
+    #[cfg_attr(not(feature = "devnet"), account(signer))]
+    pub authority: Account<'info, ControlAuthority>,
+}
+
+pub fn pause_handler(ctx: Context<Pause>, flags: u64, paused: bool) -> Result<()> {
+    let flags = match PoolFlags::from_bits(flags) {
+        Some(flags) if !flags.is_empty() && PoolFlags::PAUSE_ALL.contains(flags) => flags,
+        _ => {
+            msg!("only the pause flags can be changed");
+            return err!(ErrorCode::InvalidPauseFlags);
+        }
+    };
+
+    ctx.accounts.margin_pool.set_paused(flags, paused);
+
+    Ok(())
+}
@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, Transfer};
+
+use crate::pool_token;
+use crate::state::*;
+use crate::{Amount, AmountKind, ErrorCode};
+
+#[derive(Accounts)]
+pub struct QueueWithdrawal<'info> {
+    /// The pool to withdraw from
+    #[account(mut, has_one = token_mint)]
+    pub margin_pool: Account<'info, MarginPool>,
+
+    /// The mint for the pool's token
+    /// CHECK:
+    pub token_mint: UncheckedAccount<'info>,
+
+    /// The queue of withdrawal requests for the pool
+    #[account(mut,
+              has_one = margin_pool,
+              has_one = escrow)]
+    pub withdrawal_queue: AccountLoader<'info, WithdrawalQueue>,
+
+    /// The token account holding the deposit notes of queued requests
+    /// CHECK:
+    #[account(mut)]
+    pub escrow: UncheckedAccount<'info>,
+
+    /// The address with authority over the deposit notes
+    pub depositor: Signer<'info>,
+
+    /// The source of the deposit notes to be redeemed
+    /// CHECK:
+    #[account(mut)]
+    pub source: UncheckedAccount<'info>,
+
+    /// The destination for the tokens once the withdrawal is filled
+    /// CHECK:
+    pub destination: UncheckedAccount<'info>,
+
+    /// The campaign rewarding holders of the deposit notes, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                margin_pool.deposit_note_mint.as_ref(),
+                b"reward-campaign".as_ref()
+              ],
+              bump)]
+    pub reward_campaign: AccountInfo<'info>,
+
+    /// The rewards checkpoint for the source of the notes, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                reward_campaign.key().as_ref(),
+                source.key().as_ref(),
+                b"reward-checkpoint".as_ref()
+              ],
+              bump)]
+    pub reward_checkpoint: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> QueueWithdrawal<'info> {
+    fn transfer_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Transfer {
+                to: self.escrow.to_account_info(),
+                from: self.source.to_account_info(),
+                authority: self.depositor.to_account_info(),
+            },
+        )
+    }
+}
+
+pub fn queue_withdrawal_handler(ctx: Context<QueueWithdrawal>, notes: u64) -> Result<()> {
+    if notes == 0 {
+        return err!(ErrorCode::InvalidAmount);
+    }
+
+    let pool = &ctx.accounts.margin_pool;
+    let requested = pool.convert_deposit_amount(
+        Amount::notes(notes),
+        RoundingDirection::direction(PoolAction::Withdraw, AmountKind::Notes),
+    )?;
+
+    if requested.tokens < pool.config.min_queued_withdrawal {
+        msg!(
+            "at least {} tokens must be requested",
+            pool.config.min_queued_withdrawal
+        );
+        return err!(ErrorCode::WithdrawalRequestTooSmall);
+    }
+
+    if !pool_token::can_receive(&ctx.accounts.destination, &ctx.accounts.token_mint) {
+        msg!(
+            "{} is not a token account for the pool's token",
+            ctx.accounts.destination.key()
+        );
+        return err!(ErrorCode::InvalidWithdrawalDestination);
+    }
+
+    super::settle_position_rewards(
+        pool,
+        &ctx.accounts.reward_campaign,
+        &ctx.accounts.reward_checkpoint,
+        &ctx.accounts.source,
+        notes,
+    )?;
+
+    let mut queue = ctx.accounts.withdrawal_queue.load_mut()?;
+    let sequence = queue.push(
+        ctx.accounts.depositor.key(),
+        ctx.accounts.destination.key(),
+        notes,
+    )?;
+    drop(queue);
+
+    ctx.accounts.margin_pool.queue_withdrawal(notes);
+
+    token::transfer(ctx.accounts.transfer_context(), notes)?;
+
+    msg!("queued withdrawal request {}", sequence);
+    Ok(())
+}
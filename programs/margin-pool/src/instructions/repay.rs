@@ -0,0 +1,190 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Burn, Token, TokenAccount};
+
+use jet_margin::{AdapterResult, MarginAccount};
+
+use crate::pool_token::{self, TransferTokens};
+use crate::{state::*, AmountKind};
+use crate::{Amount, ErrorCode};
+
+#[derive(Accounts)]
+pub struct Repay<'info> {
+    /// The margin account with the loan to be repaid
+    #[account(signer)]
+    pub margin_account: AccountLoader<'info, MarginAccount>,
+
+    /// The pool with the outstanding loan
+    #[account(mut,
+              has_one = token_mint,
+              has_one = vault,
+              has_one = loan_note_mint)]
+    pub margin_pool: Account<'info, MarginPool>,
+
+    /// The mint for the pool's token
+    /// CHECK:
+    pub token_mint: UncheckedAccount<'info>,
+
+    /// The vault for the pool, where tokens are held
+    /// CHECK:
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+
+    /// The mint for the notes representing loans from the pool
+    /// CHECK:
+    #[account(mut)]
+    pub loan_note_mint: UncheckedAccount<'info>,
+
+    /// The margin account position with the loan notes
+    #[account(mut, constraint = loan_account.owner == margin_account.key())]
+    pub loan_account: Account<'info, TokenAccount>,
+
+    /// The address with authority to pay with the tokens, which does not need
+    /// to be the owner of the margin account
+    pub repayment_account_authority: Signer<'info>,
+
+    /// The source of the tokens to repay the loan with
+    /// CHECK:
+    #[account(mut)]
+    pub repayment_token_account: UncheckedAccount<'info>,
+
+    /// The history of rates for the pool, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                b"history".as_ref()
+              ],
+              bump)]
+    pub history: AccountInfo<'info>,
+
+    /// The campaign rewarding holders of the loan notes, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                margin_pool.loan_note_mint.as_ref(),
+                b"reward-campaign".as_ref()
+              ],
+              bump)]
+    pub reward_campaign: AccountInfo<'info>,
+
+    /// The rewards checkpoint for the loan notes, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                reward_campaign.key().as_ref(),
+                loan_account.key().as_ref(),
+                b"reward-checkpoint".as_ref()
+              ],
+              bump)]
+    pub reward_checkpoint: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// The program for the pool's token, which may be either the token or token-2022 program
+    /// CHECK:
+    pub pool_token_program: UncheckedAccount<'info>,
+}
+
+impl<'info> Repay<'info> {
+    fn transfer_source_accounts(&self) -> TransferTokens<'_, 'info> {
+        TransferTokens {
+            token_program: &self.pool_token_program,
+            mint: &self.token_mint,
+            source: &self.repayment_token_account,
+            destination: &self.vault,
+            authority: &self.repayment_account_authority,
+        }
+    }
+
+    fn burn_loan_context(&self) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Burn {
+                mint: self.loan_note_mint.to_account_info(),
+                to: self.loan_account.to_account_info(),
+                authority: self.margin_account.to_account_info(),
+            },
+        )
+    }
+}
+
+pub fn repay_handler(ctx: Context<Repay>, amount: Amount) -> Result<()> {
+    let pool = &mut ctx.accounts.margin_pool;
+    let clock = Clock::get()?;
+
+    pool.check_action(PoolAction::Repay)?;
+
+    // Make sure interest accrual is up-to-date
+    if !pool.accrue_interest(clock.unix_timestamp) {
+        msg!("interest accrual is too far behind");
+        return Err(ErrorCode::InterestAccrualBehind.into());
+    }
+
+    pool.record_history(&ctx.accounts.history, clock.unix_timestamp)?;
+
+    let repay_rounding = RoundingDirection::direction(PoolAction::Repay, amount.kind);
+    let requested_amount = pool.convert_loan_amount(amount, repay_rounding)?;
+
+    // Transfer the tokens first, since the vault can receive less than the amount
+    // sent when the token charges a fee on transfers
+    let received = pool_token::transfer(
+        ctx.accounts.transfer_source_accounts(),
+        requested_amount.tokens,
+        &[],
+    )?;
+
+    // Only the tokens that actually arrived in the vault count towards the loan
+    let pool = &mut ctx.accounts.margin_pool;
+    let repay_amount = match received == requested_amount.tokens {
+        true => requested_amount,
+        false => {
+            let repay_rounding =
+                RoundingDirection::direction(PoolAction::Repay, AmountKind::Tokens);
+            pool.convert_loan_amount(Amount::tokens(received), repay_rounding)?
+        }
+    };
+
+    super::settle_position_rewards(
+        pool,
+        &ctx.accounts.reward_campaign,
+        &ctx.accounts.reward_checkpoint,
+        &ctx.accounts.loan_account.to_account_info(),
+        repay_amount.notes,
+    )?;
+
+    pool.repay(&repay_amount)?;
+
+    let pool = &ctx.accounts.margin_pool;
+    let signer = [&pool.signer_seeds()?[..]];
+
+    token::burn(
+        ctx.accounts.burn_loan_context().with_signer(&signer),
+        repay_amount.notes,
+    )?;
+
+    // Tell the margin program what accounts changed
+    jet_margin::write_adapter_result(&AdapterResult::NewBalanceChange(vec![ctx
+        .accounts
+        .loan_account
+        .key()]))?;
+
+    Ok(())
+}
@@ -0,0 +1,88 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::state::*;
+use crate::ErrorCode;
+
+#[derive(Accounts)]
+pub struct StakeRewards<'info> {
+    /// The pool whose notes are rewarded
+    pub margin_pool: Account<'info, MarginPool>,
+
+    /// The campaign to earn rewards from
+    #[account(mut,
+              has_one = margin_pool,
+              has_one = stake_vault)]
+    pub campaign: Account<'info, RewardCampaign>,
+
+    /// The checkpoint for the notes staked by the owner
+    #[account(mut,
+              has_one = campaign,
+              has_one = owner,
+              constraint = checkpoint.token_account == stake_vault.key())]
+    pub checkpoint: Account<'info, RewardCheckpoint>,
+
+    /// The owner of the notes being staked
+    pub owner: Signer<'info>,
+
+    /// The token account to take the notes from
+    #[account(mut)]
+    pub source: Account<'info, TokenAccount>,
+
+    /// The token account holding the notes staked in the campaign
+    #[account(mut)]
+    pub stake_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> StakeRewards<'info> {
+    fn transfer_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Transfer {
+                to: self.stake_vault.to_account_info(),
+                from: self.source.to_account_info(),
+                authority: self.owner.to_account_info(),
+            },
+        )
+    }
+}
+
+pub fn stake_rewards_handler(ctx: Context<StakeRewards>, notes: u64) -> Result<()> {
+    if notes == 0 {
+        return err!(ErrorCode::InvalidAmount);
+    }
+
+    // The notes only start earning once the rewards so far have been checkpointed
+    super::sync_rewards(
+        &ctx.accounts.margin_pool,
+        &mut ctx.accounts.campaign,
+        &mut ctx.accounts.checkpoint,
+        None,
+    )?;
+
+    let checkpoint = &mut ctx.accounts.checkpoint;
+    checkpoint.balance = checkpoint.balance.checked_add(notes).unwrap();
+
+    token::transfer(ctx.accounts.transfer_context(), notes)?;
+
+    Ok(())
+}
@@ -16,8 +16,9 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Burn, Token, Transfer};
+use anchor_spl::token::{self, Burn, Token};
 
+use crate::pool_token::{self, TransferTokens};
 use crate::state::*;
 use crate::{Amount, ErrorCode};
 
@@ -25,10 +26,15 @@ use crate::{Amount, ErrorCode};
 pub struct Withdraw<'info> {
     /// The pool to withdraw from
     #[account(mut,
+              has_one = token_mint,
               has_one = vault,
               has_one = deposit_note_mint)]
     pub margin_pool: Account<'info, MarginPool>,
 
+    /// The mint for the pool's token
+    /// CHECK:
+    pub token_mint: UncheckedAccount<'info>,
+
     /// The vault for the pool, where tokens are held
     /// CHECK:
     #[account(mut)]
@@ -52,19 +58,54 @@ pub struct Withdraw<'info> {
     #[account(mut)]
     pub destination: UncheckedAccount<'info>,
 
+    /// The history of rates for the pool, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                b"history".as_ref()
+              ],
+              bump)]
+    pub history: AccountInfo<'info>,
+
+    /// The campaign rewarding holders of the deposit notes, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                margin_pool.key().as_ref(),
+                margin_pool.deposit_note_mint.as_ref(),
+                b"reward-campaign".as_ref()
+              ],
+              bump)]
+    pub reward_campaign: AccountInfo<'info>,
+
+    /// The rewards checkpoint for the source of the notes, which may not exist
+    /// CHECK:
+    #[account(mut,
+              seeds = [
+                reward_campaign.key().as_ref(),
+                source.key().as_ref(),
+                b"reward-checkpoint".as_ref()
+              ],
+              bump)]
+    pub reward_checkpoint: AccountInfo<'info>,
+
     pub token_program: Program<'info, Token>,
+
+    /// The program for the pool's token, which may be either the token or token-2022 program
+    /// CHECK:
+    pub pool_token_program: UncheckedAccount<'info>,
 }
 
 impl<'info> Withdraw<'info> {
-    fn transfer_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        CpiContext::new(
-            self.token_program.to_account_info(),
-            Transfer {
-                to: self.destination.to_account_info(),
-                from: self.vault.to_account_info(),
-                authority: self.margin_pool.to_account_info(),
-            },
-        )
+    fn transfer_accounts(&self) -> TransferTokens<'_, 'info> {
+        TransferTokens {
+            token_program: &self.pool_token_program,
+            mint: &self.token_mint,
+            source: &self.vault,
+            destination: &self.destination,
+            authority: self.margin_pool.as_ref(),
+        }
     }
 
     fn burn_note_context(&self) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
@@ -83,22 +124,36 @@ pub fn withdraw_handler(ctx: Context<Withdraw>, amount: Amount) -> Result<()> {
     let pool = &mut ctx.accounts.margin_pool;
     let clock = Clock::get()?;
 
+    pool.check_action(PoolAction::Withdraw)?;
+
     // Make sure interest accrual is up-to-date
     if !pool.accrue_interest(clock.unix_timestamp) {
         msg!("interest accrual is too far behind");
         return Err(ErrorCode::InterestAccrualBehind.into());
     }
 
+    pool.record_history(&ctx.accounts.history, clock.unix_timestamp)?;
+
     let withdraw_rounding = RoundingDirection::direction(PoolAction::Withdraw, amount.kind);
     let withdraw_amount = pool.convert_deposit_amount(amount, withdraw_rounding)?;
-    pool.withdraw(&withdraw_amount)?;
+
+    super::settle_position_rewards(
+        pool,
+        &ctx.accounts.reward_campaign,
+        &ctx.accounts.reward_checkpoint,
+        &ctx.accounts.source,
+        withdraw_amount.notes,
+    )?;
+
+    pool.withdraw_unreserved(&withdraw_amount)?;
 
     let pool = &ctx.accounts.margin_pool;
     let signer = [&pool.signer_seeds()?[..]];
 
-    token::transfer(
-        ctx.accounts.transfer_context().with_signer(&signer),
+    pool_token::transfer(
+        ctx.accounts.transfer_accounts(),
         withdraw_amount.tokens,
+        &signer,
     )?;
     token::burn(
         ctx.accounts.burn_note_context().with_signer(&signer),
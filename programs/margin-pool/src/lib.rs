@@ -18,11 +18,19 @@
 use anchor_lang::prelude::*;
 
 mod instructions;
+mod oracle;
+mod pool_token;
 mod state;
 mod util;
 use instructions::*;
 
-pub use state::{MarginPool, MarginPoolConfig, PoolFlags};
+pub use instructions::RewardCampaignParams;
+pub use jet_metadata::OracleSource;
+pub use oracle::{validate_source, OraclePrice};
+pub use state::{
+    MarginPool, MarginPoolConfig, MarginPoolOracle, PoolFlags, PoolHistory, PoolHistoryEntry,
+    RewardCampaign, RewardCheckpoint, TermLoan, WithdrawalQueue, WithdrawalRequest,
+};
 
 declare_id!("JPPooLEqRo3NCSx82EdE2VZY5vUaSsgskpZPBHNGVLZ");
 
@@ -32,6 +40,41 @@ pub mod authority {
     declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 }
 
+/// The maximum change in the price of a token between updates of a pool's oracle
+/// cache, in basis points
+#[constant]
+pub const MAX_ORACLE_PRICE_CHANGE: u16 = 2_500;
+
+/// The maximum age of a cached price that later updates are compared against,
+/// in seconds
+#[constant]
+pub const MAX_ORACLE_CACHE_AGE: i64 = 3_600;
+
+/// The maximum age of the latest round of a switchboard aggregator that a
+/// price is read from, in seconds
+#[constant]
+pub const MAX_ORACLE_ROUND_AGE: i64 = 60;
+
+/// The longest term allowed for a term loan, in seconds
+#[constant]
+pub const MAX_TERM_LOAN_DURATION: i64 = 31_536_000;
+
+/// The additional value owed on a term loan once it has matured, in basis points
+#[constant]
+pub const TERM_LOAN_LATE_PENALTY: u16 = 500;
+
+/// The minimum time between entries recorded in a pool's history, in seconds
+#[constant]
+pub const POOL_HISTORY_INTERVAL: i64 = 21_600;
+
+/// The currency that pyth prices for pool tokens must be quoted in
+pub const PRICE_QUOTE_CURRENCY: &str = "USD";
+
+/// The most decimal places allowed for a token's price and its amounts combined,
+/// which limits the precision needed to value the smallest unit of the token
+#[constant]
+pub const MAX_PRICE_DECIMALS: u32 = 24;
+
 #[program]
 mod jet_margin_pool {
     use super::*;
@@ -41,20 +84,72 @@ mod jet_margin_pool {
         instructions::create_pool_handler(ctx)
     }
 
+    /// Migrate a pool created before the pool had term loans to the current
+    /// layout, creating its term loan note mint and price cache
+    ///
+    /// This is permissionless, since the pool keeps its existing state, and
+    /// the new configuration fields start out disabled.
+    pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+        instructions::migrate_pool_handler(ctx)
+    }
+
     /// Configure an existing pool
     pub fn configure(
         ctx: Context<Configure>,
         fee_destination: Option<Pubkey>,
         config: Option<MarginPoolConfig>,
+        oracle_source: Option<OracleSource>,
     ) -> Result<()> {
-        instructions::configure_handler(ctx, fee_destination, config)
+        instructions::configure_handler(ctx, fee_destination, config, oracle_source)
+    }
+
+    /// Pause or resume individual actions on a pool
+    ///
+    /// `flags` can only contain the pause flags from [PoolFlags].
+    pub fn pause(ctx: Context<Pause>, flags: u64, paused: bool) -> Result<()> {
+        instructions::pause_handler(ctx, flags, paused)
+    }
+
+    /// Begin retiring a pool, which permanently stops new deposits and borrows
+    ///
+    /// Withdrawals and repayments of loans continue to be allowed.
+    pub fn begin_retirement(ctx: Context<BeginRetirement>) -> Result<()> {
+        instructions::begin_retirement_handler(ctx)
+    }
+
+    /// Settle the outstanding loans of a retiring pool, which stops the accrual
+    /// of interest and fixes the value of the loans at their final rate
+    pub fn settle_retirement(ctx: Context<SettleRetirement>) -> Result<()> {
+        instructions::settle_retirement_handler(ctx)
+    }
+
+    /// Write off the settled loans of a retiring pool that haven't been repaid,
+    /// with the loss shared by the depositors in proportion to their deposits
+    pub fn write_off_loans(ctx: Context<WriteOffLoans>) -> Result<()> {
+        instructions::write_off_loans_handler(ctx)
+    }
+
+    /// Close a settled pool once all of its deposits and loans have been redeemed,
+    /// reclaiming the rent for its accounts
+    ///
+    /// The note mints can't be closed, and keep their addresses derived from the
+    /// token, so a new pool can never be created for the same token.
+    pub fn close_pool(ctx: Context<ClosePool>) -> Result<()> {
+        instructions::close_pool_handler(ctx)
     }
 
     /// Accrue interest on the pool, and collect any fees.
+    ///
+    /// The current rates are also recorded, if the pool has a history account.
     pub fn collect(ctx: Context<Collect>) -> Result<()> {
         instructions::collect_handler(ctx)
     }
 
+    /// Create an account to record the history of rates for a pool
+    pub fn create_pool_history(ctx: Context<CreatePoolHistory>) -> Result<()> {
+        instructions::create_pool_history_handler(ctx)
+    }
+
     /// Deposit tokens into the pool in exchange for notes
     pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
         instructions::deposit_handler(ctx, amount)
@@ -66,8 +161,38 @@ mod jet_margin_pool {
         instructions::withdraw_handler(ctx, amount)
     }
 
+    /// Create the queue for withdrawals waiting on liquidity in a pool
+    pub fn create_withdrawal_queue(ctx: Context<CreateWithdrawalQueue>) -> Result<()> {
+        instructions::create_withdrawal_queue_handler(ctx)
+    }
+
+    /// Escrow deposit notes in the withdrawal queue, to be redeemed once
+    /// the pool has liquidity available.
+    pub fn queue_withdrawal(ctx: Context<QueueWithdrawal>, notes: u64) -> Result<()> {
+        instructions::queue_withdrawal_handler(ctx, notes)
+    }
+
+    /// Fill requests in the withdrawal queue, in the order they were made,
+    /// using the liquidity currently available in the pool.
+    pub fn fill_withdrawals<'info>(
+        ctx: Context<'_, '_, '_, 'info, FillWithdrawals<'info>>,
+    ) -> Result<()> {
+        instructions::fill_withdrawals_handler(ctx)
+    }
+
+    /// Cancel a request in the withdrawal queue, returning the remaining notes
+    pub fn cancel_withdrawal(ctx: Context<CancelWithdrawal>, sequence: u64) -> Result<()> {
+        instructions::cancel_withdrawal_handler(ctx, sequence)
+    }
+
+    /// Deposit tokens from a wallet into the pool, with the notes being received
+    /// by a margin account.
+    pub fn margin_deposit(ctx: Context<MarginDeposit>, amount: u64) -> Result<()> {
+        instructions::margin_deposit_handler(ctx, amount)
+    }
+
     /// Borrow tokens using a margin account
-    pub fn margin_borrow(ctx: Context<MarginBorrow>, amount: u64) -> Result<()> {
+    pub fn margin_borrow(ctx: Context<MarginBorrow>, amount: Amount) -> Result<()> {
         instructions::margin_borrow_handler(ctx, amount)
     }
 
@@ -76,16 +201,91 @@ mod jet_margin_pool {
         instructions::margin_repay_handler(ctx, amount)
     }
 
+    /// Repay a loan owed by a margin account with tokens from a wallet. Anyone can
+    /// repay on behalf of a margin account.
+    pub fn repay(ctx: Context<Repay>, amount: Amount) -> Result<()> {
+        instructions::repay_handler(ctx, amount)
+    }
+
     /// Withdraw tokens from the pool, exchanging in notes stored by a
     /// margin account.
     pub fn margin_withdraw(ctx: Context<MarginWithdraw>, amount: Amount) -> Result<()> {
         instructions::margin_withdraw_handler(ctx, amount)
     }
 
-    /// Update the pool position on a margin account
+    /// Borrow tokens using a margin account, at a fixed rate for a fixed term
+    ///
+    /// A margin account can only have one term loan from each pool at a time,
+    /// since the loan's address is derived from the pool and margin account.
+    /// Another term loan can be taken once the current one is fully repaid.
+    pub fn margin_term_borrow(
+        ctx: Context<MarginTermBorrow>,
+        amount: u64,
+        term: i64,
+    ) -> Result<()> {
+        instructions::margin_term_borrow_handler(ctx, amount, term)
+    }
+
+    /// Repay a term loan, using deposit notes held by the margin account
+    pub fn margin_term_repay(ctx: Context<MarginTermRepay>, notes: u64) -> Result<()> {
+        instructions::margin_term_repay_handler(ctx, notes)
+    }
+
+    /// Burn the loan notes held by a margin account, without payment, once the
+    /// pool's loans have been written off
+    pub fn margin_burn_loan_notes(ctx: Context<MarginBurnLoanNotes>) -> Result<()> {
+        instructions::margin_burn_loan_notes_handler(ctx)
+    }
+
+    /// Burn the notes for a margin account's term loan and close the loan,
+    /// without payment, once the pool's loans have been written off
+    pub fn margin_burn_term_loan_notes(ctx: Context<MarginBurnTermLoanNotes>) -> Result<()> {
+        instructions::margin_burn_term_loan_notes_handler(ctx)
+    }
+
     pub fn margin_refresh_position(ctx: Context<MarginRefreshPosition>) -> Result<()> {
         instructions::margin_refresh_position_handler(ctx)
     }
+
+    /// Create a campaign to distribute rewards to holders of the pool's notes
+    pub fn create_reward_campaign(
+        ctx: Context<CreateRewardCampaign>,
+        params: RewardCampaignParams,
+    ) -> Result<()> {
+        instructions::create_reward_campaign_handler(ctx, params)
+    }
+
+    /// Create the checkpoint tracking the rewards earned by notes an owner stakes in a campaign
+    pub fn create_reward_checkpoint(ctx: Context<CreateRewardCheckpoint>) -> Result<()> {
+        instructions::create_reward_checkpoint_handler(ctx)
+    }
+
+    /// Start earning rewards from a campaign for the notes in a margin account position
+    pub fn create_margin_reward_checkpoint(
+        ctx: Context<CreateMarginRewardCheckpoint>,
+    ) -> Result<()> {
+        instructions::create_margin_reward_checkpoint_handler(ctx)
+    }
+
+    /// Stake notes in a campaign, to earn rewards while they're held there
+    pub fn stake_rewards(ctx: Context<StakeRewards>, notes: u64) -> Result<()> {
+        instructions::stake_rewards_handler(ctx, notes)
+    }
+
+    /// Withdraw notes staked in a campaign
+    pub fn unstake_rewards(ctx: Context<UnstakeRewards>, notes: u64) -> Result<()> {
+        instructions::unstake_rewards_handler(ctx, notes)
+    }
+
+    /// Claim the rewards earned by staked notes
+    pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+        instructions::claim_rewards_handler(ctx)
+    }
+
+    /// Claim the rewards earned by the notes held in a margin account position
+    pub fn margin_claim_rewards(ctx: Context<MarginClaimRewards>) -> Result<()> {
+        instructions::margin_claim_rewards_handler(ctx)
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, Copy)]
@@ -140,4 +340,100 @@ pub enum ErrorCode {
     /// This is used when a `TokenAmount` has an invalid value
     #[msg("An invalid amount has been supplied")]
     InvalidAmount,
+
+    /// 141105 - The oracle accounts supplied are not valid for the pool
+    #[msg("The oracle accounts supplied are not valid")]
+    InvalidOracle,
+
+    /// 141106 - The oracle price moved too far since the last update
+    #[msg("The oracle price changed too much since the last update")]
+    OraclePriceJump,
+
+    /// 141107 - The term requested for a loan is not allowed
+    #[msg("The term requested for the loan is not allowed")]
+    InvalidTerm,
+
+    /// 141108 - The withdrawal queue has no space for more requests
+    #[msg("The withdrawal queue is full")]
+    WithdrawalQueueFull,
+
+    /// 141109 - The withdrawal request does not exist or cannot be changed
+    #[msg("The withdrawal request is not valid")]
+    InvalidWithdrawalRequest,
+
+    /// 141110 - The account supplied to fill a withdrawal is not the requested destination
+    #[msg("The destination does not match the withdrawal request")]
+    WithdrawalDestinationMismatch,
+
+    /// 141111 - The parameters for a reward campaign are not valid
+    #[msg("The reward campaign is not valid")]
+    InvalidRewardCampaign,
+
+    /// 141112 - The action being attempted has been paused for the pool
+    #[msg("The action is currently paused for the pool")]
+    ActionPaused,
+
+    /// 141113 - The flags supplied are not pause flags
+    #[msg("The flags supplied can not be paused")]
+    InvalidPauseFlags,
+
+    /// 141114 - The token program supplied can not be used for the pool's token
+    #[msg("The token program is not valid for the pool's token")]
+    InvalidTokenProgram,
+
+    /// 141115 - The pool's token uses an extension that pools can't support
+    #[msg("The token uses an extension that is not supported")]
+    UnsupportedTokenExtension,
+
+    /// 141116 - The pool is not being retired
+    #[msg("The pool is not being retired")]
+    NotRetiring,
+
+    /// 141117 - The pool still has deposits or loans outstanding
+    #[msg("The pool still has outstanding deposits or loans")]
+    PoolNotEmpty,
+
+    /// 141118 - The oracle has not published a price recently enough
+    #[msg("The oracle price is stale")]
+    StaleOracle,
+
+    /// 141119 - The interest rate is too high to be fixed for a term loan
+    #[msg("The interest rate is too high for a term loan")]
+    InvalidInterestRate,
+
+    /// 141120 - The amount requested through the withdrawal queue is below the pool's minimum
+    #[msg("The withdrawal request is too small")]
+    WithdrawalRequestTooSmall,
+
+    /// 141121 - The destination for a queued withdrawal can't receive the pool's token
+    #[msg("The withdrawal destination is not a token account for the pool's token")]
+    InvalidWithdrawalDestination,
+
+    /// 141122 - The pool is being retired, so it no longer accepts deposits or borrows
+    #[msg("The pool is being retired")]
+    Retiring,
+
+    /// 141123 - The pool's loans have been written off, so they can't be repaid
+    #[msg("The pool's loans have been written off")]
+    LoansWrittenOff,
+
+    /// 141124 - Rewards claimed for a margin account can't be sent to anyone else
+    #[msg("The rewards must be claimed to the margin account or its owner")]
+    InvalidRewardDestination,
+
+    /// 141125 - The pool already has the current layout
+    #[msg("The pool has already been migrated")]
+    PoolMigrated,
+
+    /// 141126 - The pool's loans have to be repaid, since they haven't been written off
+    #[msg("The pool's loans have not been written off")]
+    LoansNotWrittenOff,
+
+    /// 141127 - The deposit would take the pool above its deposit cap
+    #[msg("The pool's deposit cap has been reached")]
+    DepositCapExceeded,
+
+    /// 141128 - The borrow would take the pool above its borrow cap
+    #[msg("The pool's borrow cap has been reached")]
+    BorrowCapExceeded,
 }
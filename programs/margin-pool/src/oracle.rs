@@ -0,0 +1,237 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cell::Ref;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+
+use anchor_lang::prelude::*;
+use pyth_client::{AccountType, Price, Product};
+use switchboard_v2::{AggregatorAccountData, SwitchboardDecimal};
+
+use jet_metadata::OracleSource;
+
+use crate::pool_token;
+use crate::{ErrorCode, MAX_ORACLE_ROUND_AGE, MAX_PRICE_DECIMALS, PRICE_QUOTE_CURRENCY};
+
+/// Price information for a token, as read from any of the supported oracle sources
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OraclePrice {
+    /// The current price of the token
+    pub price: i64,
+
+    /// The confidence interval around the price
+    pub confidence: u64,
+
+    /// The recent average price of the token
+    pub twap: i64,
+
+    /// The exponent for the price values
+    pub exponent: i32,
+
+    /// The slot the price was published in
+    pub slot: u64,
+}
+
+/// Check that the oracle accounts are valid for the given source, and provide
+/// a usable price for the token
+pub fn validate_source(
+    source: &OracleSource,
+    token_mint: &AccountInfo,
+    pyth_product: &AccountInfo,
+    price_oracle: &AccountInfo,
+) -> Result<()> {
+    let token_decimals = pool_token::mint_decimals(token_mint);
+
+    match source {
+        OracleSource::Pyth => {
+            let product_data = pyth_product.try_borrow_data()?;
+            let price_data = price_oracle.try_borrow_data()?;
+
+            let product = pyth_client::load_product(&product_data)
+                .map_err(|_| error!(ErrorCode::InvalidOracle))?;
+            let price = pyth_client::load_price(&price_data)
+                .map_err(|_| error!(ErrorCode::InvalidOracle))?;
+
+            validate_pyth_accounts(pyth_product.key, product, price_oracle.key, price)?;
+            validate_exponent(price.expo, token_decimals)
+        }
+
+        OracleSource::Switchboard => {
+            load_aggregator(price_oracle)?;
+
+            Ok(())
+        }
+
+        OracleSource::Fixed { price, exponent } => {
+            if *price <= 0 {
+                msg!("a fixed price must be positive");
+                return err!(ErrorCode::InvalidOracle);
+            }
+
+            validate_exponent(*exponent, token_decimals)
+        }
+    }
+}
+
+/// Read the current price from an oracle
+pub fn read_price(source: &OracleSource, price_oracle: &AccountInfo) -> Result<OraclePrice> {
+    match source {
+        OracleSource::Pyth => {
+            let price_data = price_oracle.try_borrow_data()?;
+            let price = pyth_client::load_price(&price_data)
+                .map_err(|_| error!(ErrorCode::InvalidOracle))?;
+
+            Ok(OraclePrice {
+                price: price.agg.price,
+                confidence: price.agg.conf,
+                twap: price.twap.val,
+                exponent: price.expo,
+                slot: price.valid_slot,
+            })
+        }
+
+        OracleSource::Switchboard => {
+            let aggregator = load_aggregator(price_oracle)?;
+            let round = &aggregator.latest_confirmed_round;
+            let clock = Clock::get()?;
+
+            if clock.unix_timestamp - round.round_open_timestamp > MAX_ORACLE_ROUND_AGE {
+                msg!(
+                    "the latest switchboard round opened at {}, which is too old",
+                    round.round_open_timestamp
+                );
+                return err!(ErrorCode::StaleOracle);
+            }
+
+            let scale = round.result.scale;
+            let price = i64::try_from(round.result.mantissa)
+                .map_err(|_| error!(ErrorCode::InvalidOracle))?;
+            let confidence = switchboard_value_with_scale(&round.std_deviation, scale)?;
+
+            // switchboard does not provide an average, so the latest result is used
+            Ok(OraclePrice {
+                price,
+                confidence,
+                twap: price,
+                exponent: -(scale as i32),
+                slot: round.round_open_slot,
+            })
+        }
+
+        OracleSource::Fixed { price, exponent } => Ok(OraclePrice {
+            price: *price,
+            confidence: 0,
+            twap: *price,
+            exponent: *exponent,
+            slot: Clock::get()?.slot,
+        }),
+    }
+}
+
+/// Load a switchboard aggregator, which has to be owned by the switchboard program
+fn load_aggregator<'a>(price_oracle: &'a AccountInfo) -> Result<Ref<'a, AggregatorAccountData>> {
+    if *price_oracle.owner != switchboard_v2::ID {
+        msg!("the price oracle is not owned by the switchboard program");
+        return err!(ErrorCode::InvalidOracle);
+    }
+
+    AggregatorAccountData::new(price_oracle).map_err(|_| {
+        msg!("the price oracle is not a switchboard aggregator");
+        error!(ErrorCode::InvalidOracle)
+    })
+}
+
+/// Check that the pyth product and price accounts refer to each other
+fn validate_pyth_accounts(
+    product_address: &Pubkey,
+    product: &Product,
+    price_address: &Pubkey,
+    price: &Price,
+) -> Result<()> {
+    if product.magic != pyth_client::MAGIC
+        || product.ver != pyth_client::VERSION_2
+        || product.atype != AccountType::Product as u32
+    {
+        msg!("the pyth product is not a version 2 product account");
+        return err!(ErrorCode::InvalidOracle);
+    }
+
+    if price.magic != pyth_client::MAGIC
+        || price.ver != pyth_client::VERSION_2
+        || price.atype != AccountType::Price as u32
+    {
+        msg!("the pyth price is not a version 2 price account");
+        return err!(ErrorCode::InvalidOracle);
+    }
+
+    if product.px_acc.val != price_address.to_bytes() {
+        msg!("the pyth product does not refer to the price account");
+        return err!(ErrorCode::InvalidOracle);
+    }
+
+    if price.prod.val != product_address.to_bytes() {
+        msg!("the pyth price does not refer to the product account");
+        return err!(ErrorCode::InvalidOracle);
+    }
+
+    let quote_currency = product
+        .iter()
+        .find(|(key, _)| *key == "quote_currency")
+        .map(|(_, value)| value);
+
+    if quote_currency != Some(PRICE_QUOTE_CURRENCY) {
+        msg!(
+            "the pyth product is quoted in {:?} rather than {}",
+            quote_currency,
+            PRICE_QUOTE_CURRENCY
+        );
+        return err!(ErrorCode::InvalidOracle);
+    }
+
+    Ok(())
+}
+
+/// Check the price exponent can be used to value the token, which needs the
+/// decimals of the price and of the token together to be within the limit
+fn validate_exponent(exponent: i32, token_decimals: u8) -> Result<()> {
+    let max_exponent_decimals = MAX_PRICE_DECIMALS.saturating_sub(token_decimals as u32);
+
+    if exponent > 0 || exponent.unsigned_abs() > max_exponent_decimals {
+        msg!(
+            "the price exponent {} is not compatible with a token of {} decimals",
+            exponent,
+            token_decimals
+        );
+        return err!(ErrorCode::InvalidOracle);
+    }
+
+    Ok(())
+}
+
+/// Convert a switchboard decimal to an integer value with the given scale
+fn switchboard_value_with_scale(value: &SwitchboardDecimal, scale: u32) -> Result<u64> {
+    let mantissa = match value.scale.cmp(&scale) {
+        Ordering::Less => value.mantissa.checked_mul(10i128.pow(scale - value.scale)),
+        Ordering::Greater => Some(value.mantissa / 10i128.pow(value.scale - scale)),
+        Ordering::Equal => Some(value.mantissa),
+    };
+
+    mantissa
+        .and_then(|m| u64::try_from(m).ok())
+        .ok_or_else(|| error!(ErrorCode::InvalidOracle))
+}
@@ -0,0 +1,271 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Support for the token held by a pool, which may belong to either the original
+//! token program or the token-2022 program.
+//!
+//! Anchor's token wrappers only support the original token program, so any
+//! handling of the pool's underlying token goes through here instead. The notes
+//! issued by a pool always belong to the original token program.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_lang::solana_program::system_instruction;
+
+use spl_token_2022::extension::{transfer_fee, ExtensionType, StateWithExtensions};
+use spl_token_2022::state::{Account as TokenAccount, AccountState, Mint};
+
+use crate::ErrorCode;
+
+/// The mint extensions that a pool can safely hold tokens for
+///
+/// Transfer fees are supported by only crediting the tokens actually received
+/// by the pool's vault.
+const SUPPORTED_MINT_EXTENSIONS: &[ExtensionType] = &[ExtensionType::TransferFeeConfig];
+
+/// Check if an address is a program that can own the token held by a pool
+pub fn is_token_program(program: &Pubkey) -> bool {
+    *program == anchor_spl::token::ID || *program == spl_token_2022::ID
+}
+
+/// Read the decimals for a mint, or zero if the account is not a mint
+pub fn mint_decimals(mint: &AccountInfo) -> u8 {
+    mint.try_borrow_data()
+        .ok()
+        .and_then(|data| {
+            StateWithExtensions::<Mint>::unpack(&data)
+                .ok()
+                .map(|m| m.base.decimals)
+        })
+        .unwrap_or_default()
+}
+
+/// Check that a mint does not use any extensions that a pool can't support
+pub fn check_mint_extensions(mint: &AccountInfo) -> Result<()> {
+    if !is_token_program(mint.owner) {
+        msg!("mint {} is not owned by a token program", mint.key);
+        return Err(ErrorCode::InvalidTokenProgram.into());
+    }
+
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<Mint>::unpack(&data)?;
+
+    for extension in state.get_extension_types()? {
+        if !SUPPORTED_MINT_EXTENSIONS.contains(&extension) {
+            msg!("mint extension {:?} is not supported", extension);
+            return Err(ErrorCode::UnsupportedTokenExtension.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Check if a mint charges a fee on transfers
+fn has_transfer_fee(mint: &AccountInfo) -> Result<bool> {
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<Mint>::unpack(&data)?;
+
+    Ok(state
+        .get_extension_types()?
+        .contains(&ExtensionType::TransferFeeConfig))
+}
+
+/// Read the balance of a token account
+pub fn token_amount(account: &AccountInfo) -> Result<u64> {
+    let data = account.try_borrow_data()?;
+    let state = StateWithExtensions::<TokenAccount>::unpack(&data)?;
+
+    Ok(state.base.amount)
+}
+
+/// Check if an account is able to receive the pool's token, by being an
+/// initialized token account for the mint that isn't frozen
+pub fn can_receive(account: &AccountInfo, mint: &AccountInfo) -> bool {
+    if account.owner != mint.owner || !is_token_program(account.owner) {
+        return false;
+    }
+
+    account
+        .try_borrow_data()
+        .ok()
+        .and_then(|data| {
+            StateWithExtensions::<TokenAccount>::unpack(&data)
+                .ok()
+                .map(|a| a.base.mint == *mint.key && a.base.state == AccountState::Initialized)
+        })
+        .unwrap_or_default()
+}
+
+/// The accounts needed to create a token account for the pool's token
+pub struct CreateTokenAccount<'a, 'info> {
+    pub token_program: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+    pub rent: &'a AccountInfo<'info>,
+    pub payer: &'a AccountInfo<'info>,
+    pub account: &'a AccountInfo<'info>,
+    pub mint: &'a AccountInfo<'info>,
+    pub authority: &'a AccountInfo<'info>,
+}
+
+/// Create a token account at a program address, with space for any extensions
+/// required by the mint
+pub fn create_token_account(accounts: CreateTokenAccount, seeds: &[&[u8]]) -> Result<()> {
+    let required_extensions = {
+        let data = accounts.mint.try_borrow_data()?;
+        let state = StateWithExtensions::<Mint>::unpack(&data)?;
+
+        ExtensionType::get_required_init_account_extensions(&state.get_extension_types()?)
+    };
+
+    let space = ExtensionType::get_account_len::<TokenAccount>(&required_extensions);
+    let lamports = Rent::get()?.minimum_balance(space);
+
+    invoke_signed(
+        &system_instruction::create_account(
+            accounts.payer.key,
+            accounts.account.key,
+            lamports,
+            space as u64,
+            accounts.token_program.key,
+        ),
+        &[
+            accounts.payer.clone(),
+            accounts.account.clone(),
+            accounts.system_program.clone(),
+        ],
+        &[seeds],
+    )?;
+
+    invoke(
+        &spl_token_2022::instruction::initialize_account(
+            accounts.token_program.key,
+            accounts.account.key,
+            accounts.mint.key,
+            accounts.authority.key,
+        )?,
+        &[
+            accounts.account.clone(),
+            accounts.mint.clone(),
+            accounts.authority.clone(),
+            accounts.rent.clone(),
+            accounts.token_program.clone(),
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// The accounts needed to transfer the pool's token
+pub struct TransferTokens<'a, 'info> {
+    pub token_program: &'a AccountInfo<'info>,
+    pub mint: &'a AccountInfo<'info>,
+    pub source: &'a AccountInfo<'info>,
+    pub destination: &'a AccountInfo<'info>,
+    pub authority: &'a AccountInfo<'info>,
+}
+
+/// Transfer tokens, returning the amount actually received by the destination
+///
+/// The amount received can be less than the amount sent when the mint charges
+/// a fee on transfers.
+pub fn transfer(accounts: TransferTokens, amount: u64, signer: &[&[&[u8]]]) -> Result<u64> {
+    if !is_token_program(accounts.token_program.key)
+        || accounts.mint.owner != accounts.token_program.key
+    {
+        msg!(
+            "token program {} is not valid for the mint",
+            accounts.token_program.key
+        );
+        return Err(ErrorCode::InvalidTokenProgram.into());
+    }
+
+    let balance_before = token_amount(accounts.destination)?;
+
+    invoke_signed(
+        &spl_token_2022::instruction::transfer_checked(
+            accounts.token_program.key,
+            accounts.source.key,
+            accounts.mint.key,
+            accounts.destination.key,
+            accounts.authority.key,
+            &[],
+            amount,
+            mint_decimals(accounts.mint),
+        )?,
+        &[
+            accounts.source.clone(),
+            accounts.mint.clone(),
+            accounts.destination.clone(),
+            accounts.authority.clone(),
+            accounts.token_program.clone(),
+        ],
+        signer,
+    )?;
+
+    let balance_after = token_amount(accounts.destination)?;
+
+    Ok(balance_after.saturating_sub(balance_before))
+}
+
+/// The accounts needed to close a token account for the pool's token
+pub struct CloseTokenAccount<'a, 'info> {
+    pub token_program: &'a AccountInfo<'info>,
+    pub mint: &'a AccountInfo<'info>,
+    pub account: &'a AccountInfo<'info>,
+    pub destination: &'a AccountInfo<'info>,
+    pub authority: &'a AccountInfo<'info>,
+}
+
+/// Close an empty token account, returning its rent to the destination
+///
+/// Any transfer fees withheld in a token-2022 account are first harvested to
+/// the mint, since the account can't be closed while holding them.
+pub fn close_token_account(accounts: CloseTokenAccount, signer: &[&[&[u8]]]) -> Result<()> {
+    if has_transfer_fee(accounts.mint)? {
+        invoke(
+            &transfer_fee::instruction::harvest_withheld_tokens_to_mint(
+                accounts.token_program.key,
+                accounts.mint.key,
+                &[accounts.account.key],
+            )?,
+            &[
+                accounts.mint.clone(),
+                accounts.account.clone(),
+                accounts.token_program.clone(),
+            ],
+        )?;
+    }
+
+    invoke_signed(
+        &spl_token_2022::instruction::close_account(
+            accounts.token_program.key,
+            accounts.account.key,
+            accounts.destination.key,
+            accounts.authority.key,
+            &[],
+        )?,
+        &[
+            accounts.account.clone(),
+            accounts.destination.clone(),
+            accounts.authority.clone(),
+            accounts.token_program.clone(),
+        ],
+        signer,
+    )?;
+
+    Ok(())
+}
@@ -19,10 +19,10 @@ use std::cmp::Ordering;
 
 use anchor_lang::{prelude::*, solana_program::clock::UnixTimestamp};
 
+use jet_metadata::OracleSource;
 use jet_proto_math::Number;
-use pyth_client::Price;
 
-use crate::{util, Amount, AmountKind, ErrorCode};
+use crate::{oracle::OraclePrice, util, Amount, AmountKind, ErrorCode};
 
 /// Account containing information about a margin pool, which
 /// services lending/borrowing operations.
@@ -80,9 +80,34 @@ pub struct MarginPool {
 
     /// The time the interest was last accrued up to
     pub accrued_until: i64,
+
+    /// The address of the mint for term loan notes
+    pub term_loan_note_mint: Pubkey,
+
+    /// The total amount of tokens lent out through term loans, which have
+    /// yet to be repaid
+    pub term_loan_tokens: u64,
+
+    /// The total amount of deposit notes waiting in the withdrawal queue
+    pub queued_withdrawal_notes: u64,
+
+    /// The total amount of interest owed on outstanding term loans, excluding
+    /// any penalties for late repayment
+    pub term_loan_interest_owed: u64,
+
+    /// The interest on term loans that has been earned by the pool so far, but
+    /// not yet repaid
+    pub term_loan_interest: [u8; 24],
+
+    /// The combined rate at which interest is earned on outstanding term loans,
+    /// in tokens per second
+    pub term_loan_interest_rate: [u8; 24],
 }
 
 impl MarginPool {
+    /// The space for the account
+    pub const SPACE: usize = 8 + std::mem::size_of::<MarginPool>();
+
     /// Get the seeds needed to sign for the vault
     pub fn signer_seeds(&self) -> Result<[&[u8]; 2]> {
         if self.flags().contains(PoolFlags::DISABLED) {
@@ -120,6 +145,8 @@ impl MarginPool {
             return err!(ErrorCode::DepositsOnly);
         }
 
+        self.check_borrow_cap(amount.tokens)?;
+
         self.deposit_tokens = self
             .deposit_tokens
             .checked_sub(amount.tokens)
@@ -131,6 +158,31 @@ impl MarginPool {
         Ok(())
     }
 
+    /// Check that a deposit hasn't taken the pool above its deposit cap
+    pub fn check_deposit_cap(&self) -> Result<()> {
+        let cap = self.config.deposit_cap;
+
+        if cap > 0 && self.total_value() > Number::from(cap) {
+            msg!("the pool can hold at most {} tokens", cap);
+            return err!(ErrorCode::DepositCapExceeded);
+        }
+
+        Ok(())
+    }
+
+    /// Check that lending some more tokens won't take the pool above its
+    /// borrow cap
+    fn check_borrow_cap(&self, tokens: u64) -> Result<()> {
+        let cap = self.config.borrow_cap;
+
+        if cap > 0 && self.total_lent() + Number::from(tokens) > Number::from(cap) {
+            msg!("the pool can lend out at most {} tokens", cap);
+            return err!(ErrorCode::BorrowCapExceeded);
+        }
+
+        Ok(())
+    }
+
     /// Record a repayment of a loan
     pub fn repay(&mut self, amount: &FullAmount) -> Result<()> {
         self.deposit_tokens = self.deposit_tokens.checked_add(amount.tokens).unwrap();
@@ -144,11 +196,74 @@ impl MarginPool {
         Ok(())
     }
 
+    /// Record a term loan from the pool
+    ///
+    /// The interest owed on the loan is recorded separately with `term_loan_originated`,
+    /// once the rate for the loan is known.
+    pub fn term_borrow(&mut self, tokens: u64) -> Result<()> {
+        if !self.flags().contains(PoolFlags::ALLOW_LENDING) {
+            msg!("this pool only allows deposits");
+            return err!(ErrorCode::DepositsOnly);
+        }
+
+        self.check_borrow_cap(tokens)?;
+
+        self.deposit_tokens = self
+            .deposit_tokens
+            .checked_sub(tokens)
+            .ok_or(ErrorCode::InsufficientLiquidity)?;
+        self.term_loan_tokens = self.term_loan_tokens.checked_add(tokens).unwrap();
+
+        Ok(())
+    }
+
+    /// Record the interest owed on a new term loan, which the pool earns
+    /// evenly over the term of the loan
+    pub fn term_loan_originated(&mut self, interest: u64, term: UnixTimestamp) {
+        self.term_loan_interest_owed = self.term_loan_interest_owed.checked_add(interest).unwrap();
+        *self.term_loan_interest_rate_mut() += Number::from(interest) / term;
+    }
+
+    /// Record a repayment of a term loan
+    ///
+    /// Any tokens paid beyond the principal and the interest already earned by
+    /// the pool, such as a late penalty, are earned by the pool when repaid.
+    pub fn term_repay(&mut self, repayment: &TermLoanRepayment, tokens: u64) -> Result<()> {
+        self.term_loan_tokens = self
+            .term_loan_tokens
+            .checked_sub(repayment.principal)
+            .ok_or(ErrorCode::InvalidAmount)?;
+        self.term_loan_interest_owed = self
+            .term_loan_interest_owed
+            .saturating_sub(repayment.interest);
+        self.deposit_tokens = self.deposit_tokens.checked_add(tokens).unwrap();
+
+        let accrued = *self.term_loan_interest();
+        let rate = *self.term_loan_interest_rate();
+        *self.term_loan_interest_mut() = util::saturating_sub(accrued, repayment.accrued_interest);
+        *self.term_loan_interest_rate_mut() = util::saturating_sub(rate, repayment.interest_rate);
+
+        let earned = util::saturating_sub(
+            Number::from(tokens),
+            Number::from(repayment.principal) + repayment.accrued_interest,
+        );
+        *self.total_uncollected_fees_mut() +=
+            earned * Number::from_bps(self.config.management_fee_rate);
+
+        Ok(())
+    }
+
     /// Accrue interest charges on outstanding borrows
     ///
     /// Returns true if the interest was fully accumulated, false if it was
     /// only partially accumulated (due to significant time drift).
     pub fn accrue_interest(&mut self, time: UnixTimestamp) -> bool {
+        // loans in a settled pool have a final value, and no longer accrue interest
+        if self.flags().contains(PoolFlags::SETTLED) {
+            self.accrued_until = std::cmp::max(self.accrued_until, time);
+            return true;
+        }
+
         let time_behind = time - self.accrued_until;
         let time_to_accrue = std::cmp::min(time_behind, util::MAX_ACCRUAL_SECONDS);
 
@@ -163,8 +278,17 @@ impl MarginPool {
                 let new_interest_accrued = *self.total_borrowed() * compound_rate;
                 let fee_to_collect = new_interest_accrued * interest_fee_rate;
 
+                // interest on term loans is earned evenly over their terms, up to the
+                // amount the loans will repay
+                let term_interest_accrued = std::cmp::min(
+                    *self.term_loan_interest_rate() * time_to_accrue,
+                    self.term_loan_interest_unearned(),
+                );
+                let term_fee_to_collect = term_interest_accrued * interest_fee_rate;
+
                 *self.total_borrowed_mut() += new_interest_accrued;
-                *self.total_uncollected_fees_mut() += fee_to_collect;
+                *self.term_loan_interest_mut() += term_interest_accrued;
+                *self.total_uncollected_fees_mut() += fee_to_collect + term_fee_to_collect;
 
                 self.accrued_until = self.accrued_until.checked_add(time_to_accrue).unwrap();
 
@@ -216,7 +340,134 @@ impl MarginPool {
 
     /// Gets the current utilization rate of the pool
     pub fn utilization_rate(&self) -> Number {
-        *self.total_borrowed() / self.total_value()
+        if self.flags().contains(PoolFlags::QUEUE_AFFECTS_RATE) {
+            // tokens waiting to be withdrawn are treated as already spoken for
+            return (self.total_lent() + self.queued_withdrawal_tokens()) / self.total_value();
+        }
+
+        self.lent_rate()
+    }
+
+    /// Gets the share of the pool's value that is lent out, which is what
+    /// depositors earn interest on
+    pub fn lent_rate(&self) -> Number {
+        self.total_lent() / self.total_value()
+    }
+
+    fn total_lent(&self) -> Number {
+        *self.total_borrowed() + self.total_term_loans()
+    }
+
+    /// Gets the reduction to apply to the collateral weight of deposit notes, in basis
+    /// points, which grows linearly as the utilization rate rises above the threshold
+    pub fn collateral_weight_reduction(&self) -> u16 {
+        let max_reduction = std::cmp::min(self.config.max_collateral_weight_reduction, 10_000);
+
+        // Catch the edge case of empty pool
+        if max_reduction == 0 || self.deposit_notes == 0 {
+            return 0;
+        }
+
+        let util_rate = self.utilization_rate();
+        let threshold = Number::from_bps(self.config.collateral_utilization_threshold);
+
+        if util_rate <= threshold {
+            return 0;
+        }
+
+        if util_rate >= Number::ONE {
+            return max_reduction;
+        }
+
+        let reduction = util::interpolate(
+            util_rate,
+            threshold,
+            Number::ONE,
+            Number::ZERO,
+            Number::from_bps(max_reduction),
+        );
+
+        reduction.as_u64(-4) as u16
+    }
+
+    /// Gets the amount of tokens waiting to be withdrawn through the queue
+    pub fn queued_withdrawal_tokens(&self) -> Number {
+        Number::from(self.queued_withdrawal_notes) * self.deposit_note_exchange_rate()
+    }
+
+    /// Record a withdrawal from the pool, which has to leave enough tokens
+    /// for the requests waiting in the withdrawal queue
+    pub fn withdraw_unreserved(&mut self, amount: &FullAmount) -> Result<()> {
+        let reserved = self.queued_withdrawal_tokens().as_u64_ceil(0);
+
+        if amount.tokens > self.deposit_tokens.saturating_sub(reserved) {
+            msg!("{} tokens are reserved for queued withdrawals", reserved);
+            return err!(ErrorCode::InsufficientLiquidity);
+        }
+
+        self.withdraw(amount)
+    }
+
+    /// Record deposit notes being placed into the withdrawal queue
+    pub fn queue_withdrawal(&mut self, notes: u64) {
+        self.queued_withdrawal_notes = self.queued_withdrawal_notes.checked_add(notes).unwrap();
+    }
+
+    /// Record deposit notes leaving the withdrawal queue, either by being
+    /// filled or cancelled
+    pub fn dequeue_withdrawal(&mut self, notes: u64) -> Result<()> {
+        self.queued_withdrawal_notes = self
+            .queued_withdrawal_notes
+            .checked_sub(notes)
+            .ok_or(ErrorCode::InvalidAmount)?;
+
+        Ok(())
+    }
+
+    /// Gets the current rate of interest earned by depositors into this pool
+    pub fn deposit_rate(&self) -> Number {
+        // Catch the edge case of empty pool
+        if self.deposit_notes == 0 {
+            return Number::ZERO;
+        }
+
+        let fee_rate = Number::from_bps(self.config.management_fee_rate);
+
+        // tokens waiting in the withdrawal queue may raise the interest rate,
+        // but only the tokens actually lent out earn it
+        self.interest_rate() * self.lent_rate() * (Number::ONE - fee_rate)
+    }
+
+    /// Keep a record of the current rates, if the pool has a history account
+    pub fn record_history(&self, history: &AccountInfo, time: UnixTimestamp) -> Result<()> {
+        if history.data_is_empty() {
+            return Ok(());
+        }
+
+        AccountLoader::<PoolHistory>::try_from(history)?
+            .load_mut()?
+            .record(self.history_entry(time), crate::POOL_HISTORY_INTERVAL);
+
+        Ok(())
+    }
+
+    /// Get a snapshot of the rates for the pool, to be recorded in its history
+    pub fn history_entry(&self, timestamp: UnixTimestamp) -> PoolHistoryEntry {
+        let mut entry = PoolHistoryEntry {
+            timestamp,
+            ..Default::default()
+        };
+
+        if self.deposit_notes > 0 {
+            entry.utilization_rate = bytemuck::cast(self.utilization_rate());
+        }
+
+        entry.borrow_rate = bytemuck::cast(self.interest_rate());
+        entry.deposit_rate = bytemuck::cast(self.deposit_rate());
+        entry.deposit_note_exchange_rate = bytemuck::cast(self.deposit_note_exchange_rate());
+        entry.loan_note_exchange_rate = bytemuck::cast(self.loan_note_exchange_rate());
+
+        entry
     }
 
     /// Collect any fees accumulated from interest
@@ -239,25 +490,44 @@ impl MarginPool {
         fee_notes
     }
 
-    /// Calculate the prices for the deposit and loan notes, based on
-    /// the price of the underlying token.
-    pub fn calculate_prices(&self, pyth_price: &Price) -> PriceResult {
-        let price_value = Number::from_decimal(pyth_price.agg.price, pyth_price.expo);
-        let twap_value = Number::from_decimal(pyth_price.twap.val, pyth_price.expo);
-        let conf_value = Number::from_decimal(pyth_price.agg.conf, pyth_price.expo);
+    /// Calculate the prices for the deposit, loan and term loan notes, based on
+    /// the cached price of the underlying token.
+    ///
+    /// The `term_loan_note_value` is the amount of tokens owed for each term
+    /// loan note.
+    pub fn calculate_prices(
+        &self,
+        oracle: &MarginPoolOracle,
+        term_loan_note_value: Number,
+    ) -> PriceResult {
+        let term_loan_note_value = match self.flags().contains(PoolFlags::WRITTEN_OFF) {
+            true => Number::ZERO,
+            false => term_loan_note_value,
+        };
+
+        let exponent = oracle.exponent;
+        let price_value = *oracle.price();
+        let twap_value = *oracle.twap();
+        let conf_value = oracle.confidence();
 
-        let deposit_note_price = (price_value * self.deposit_note_exchange_rate())
-            .as_u64_rounded(pyth_price.expo) as i64;
+        let deposit_note_price =
+            (price_value * self.deposit_note_exchange_rate()).as_u64_rounded(exponent) as i64;
         let deposit_note_conf =
-            (conf_value * self.deposit_note_exchange_rate()).as_u64_rounded(pyth_price.expo) as u64;
+            (conf_value * self.deposit_note_exchange_rate()).as_u64_rounded(exponent) as u64;
         let deposit_note_twap =
-            (twap_value * self.deposit_note_exchange_rate()).as_u64_rounded(pyth_price.expo) as i64;
+            (twap_value * self.deposit_note_exchange_rate()).as_u64_rounded(exponent) as i64;
         let loan_note_price =
-            (price_value * self.loan_note_exchange_rate()).as_u64_rounded(pyth_price.expo) as i64;
+            (price_value * self.loan_note_exchange_rate()).as_u64_rounded(exponent) as i64;
         let loan_note_conf =
-            (conf_value * self.loan_note_exchange_rate()).as_u64_rounded(pyth_price.expo) as u64;
+            (conf_value * self.loan_note_exchange_rate()).as_u64_rounded(exponent) as u64;
         let loan_note_twap =
-            (twap_value * self.loan_note_exchange_rate()).as_u64_rounded(pyth_price.expo) as i64;
+            (twap_value * self.loan_note_exchange_rate()).as_u64_rounded(exponent) as i64;
+        let term_loan_note_price =
+            (price_value * term_loan_note_value).as_u64_rounded(exponent) as i64;
+        let term_loan_note_conf =
+            (conf_value * term_loan_note_value).as_u64_rounded(exponent) as u64;
+        let term_loan_note_twap =
+            (twap_value * term_loan_note_value).as_u64_rounded(exponent) as i64;
 
         PriceResult {
             deposit_note_price,
@@ -266,6 +536,9 @@ impl MarginPool {
             loan_note_price,
             loan_note_conf,
             loan_note_twap,
+            term_loan_note_price,
+            term_loan_note_conf,
+            term_loan_note_twap,
         }
     }
 
@@ -349,6 +622,10 @@ impl MarginPool {
 
     /// Get the exchange rate for loan note -> token
     fn loan_note_exchange_rate(&self) -> Number {
+        if self.flags().contains(PoolFlags::WRITTEN_OFF) {
+            return Number::ZERO;
+        }
+
         let loan_notes = std::cmp::max(1, self.loan_notes);
         let total_borrowed = std::cmp::max(Number::ONE, *self.total_borrowed());
         total_borrowed / Number::from(loan_notes)
@@ -356,7 +633,40 @@ impl MarginPool {
 
     /// Gets the total value of assets owned by/owed to the pool.
     fn total_value(&self) -> Number {
-        *self.total_borrowed() + Number::from(self.deposit_tokens)
+        *self.total_borrowed() + Number::from(self.deposit_tokens) + self.total_term_loans()
+    }
+
+    /// Gets the value of outstanding term loans, including the interest earned so far
+    fn total_term_loans(&self) -> Number {
+        if self.flags().contains(PoolFlags::WRITTEN_OFF) {
+            return Number::ZERO;
+        }
+
+        Number::from(self.term_loan_tokens) + *self.term_loan_interest()
+    }
+
+    /// Gets the interest owed on term loans that has yet to be earned by the pool
+    fn term_loan_interest_unearned(&self) -> Number {
+        util::saturating_sub(
+            Number::from(self.term_loan_interest_owed),
+            *self.term_loan_interest(),
+        )
+    }
+
+    fn term_loan_interest_mut(&mut self) -> &mut Number {
+        bytemuck::from_bytes_mut(&mut self.term_loan_interest)
+    }
+
+    fn term_loan_interest(&self) -> &Number {
+        bytemuck::from_bytes(&self.term_loan_interest)
+    }
+
+    fn term_loan_interest_rate_mut(&mut self) -> &mut Number {
+        bytemuck::from_bytes_mut(&mut self.term_loan_interest_rate)
+    }
+
+    fn term_loan_interest_rate(&self) -> &Number {
+        bytemuck::from_bytes(&self.term_loan_interest_rate)
     }
 
     fn total_uncollected_fees_mut(&mut self) -> &mut Number {
@@ -378,6 +688,186 @@ impl MarginPool {
     fn flags(&self) -> PoolFlags {
         PoolFlags::from_bits_truncate(self.config.flags)
     }
+
+    /// Check that an action is not currently paused for the pool
+    ///
+    /// Deposits and borrows are never allowed once the pool is being retired,
+    /// and loans can't be repaid once they've been written off.
+    pub fn check_action(&self, action: PoolAction) -> Result<()> {
+        let flags = self.flags();
+
+        if flags.contains(PoolFlags::RETIRING)
+            && matches!(action, PoolAction::Deposit | PoolAction::Borrow)
+        {
+            msg!("the {:?} action is not allowed in a retiring pool", action);
+            return err!(ErrorCode::Retiring);
+        }
+
+        if flags.contains(PoolFlags::WRITTEN_OFF) && matches!(action, PoolAction::Repay) {
+            msg!("the pool's loans have been written off");
+            return err!(ErrorCode::LoansWrittenOff);
+        }
+
+        let pause_flag = match action {
+            PoolAction::Borrow => PoolFlags::PAUSE_BORROWS,
+            PoolAction::Deposit => PoolFlags::PAUSE_DEPOSITS,
+            PoolAction::Repay => PoolFlags::PAUSE_REPAYS,
+            PoolAction::Withdraw => PoolFlags::PAUSE_WITHDRAWALS,
+        };
+
+        if flags.contains(pause_flag) {
+            msg!("the {:?} action is currently paused", action);
+            return err!(ErrorCode::ActionPaused);
+        }
+
+        Ok(())
+    }
+
+    /// Pause or resume the actions represented by some pause flags
+    pub fn set_paused(&mut self, flags: PoolFlags, paused: bool) {
+        let mut current = self.flags();
+
+        current.set(flags & PoolFlags::PAUSE_ALL, paused);
+        self.config.flags = current.bits();
+    }
+
+    /// Change the configuration of the pool
+    ///
+    /// The pause flags and the flags for an ongoing retirement are kept regardless
+    /// of the new config, since those can only be changed by pausing or retiring the pool.
+    pub fn configure(&mut self, config: MarginPoolConfig) {
+        let retained = PoolFlags::RETIREMENT | PoolFlags::PAUSE_ALL;
+        let current = self.flags() & retained;
+
+        self.config = config;
+        self.config.flags = (self.config.flags & !retained.bits()) | current.bits();
+    }
+
+    /// Begin retiring the pool, which stops any new deposits and borrows
+    pub fn begin_retirement(&mut self) {
+        let mut current = self.flags();
+
+        current.insert(PoolFlags::RETIRING);
+        self.config.flags = current.bits();
+    }
+
+    /// Settle the outstanding loans of a retiring pool, which fixes their value
+    /// at the final rate accrued up to the given time
+    pub fn settle_retirement(&mut self, time: UnixTimestamp) -> Result<()> {
+        if !self.flags().contains(PoolFlags::RETIRING) {
+            msg!("the pool must be retiring before settling loans");
+            return err!(ErrorCode::NotRetiring);
+        }
+
+        if !self.accrue_interest(time) {
+            msg!("interest accrual is too far behind");
+            return err!(ErrorCode::InterestAccrualBehind);
+        }
+
+        let mut current = self.flags();
+
+        current.insert(PoolFlags::SETTLED);
+        self.config.flags = current.bits();
+
+        Ok(())
+    }
+
+    /// Write off the settled loans of a retiring pool that haven't been repaid
+    ///
+    /// The loss is shared by the depositors in proportion to their deposits,
+    /// since the notes are redeemed against what's left in the vault. The fees
+    /// earned on the loans are never collected, and the loan notes still held
+    /// by borrowers no longer have any value. The notes are still counted, until
+    /// the borrowers burn them without payment.
+    pub fn write_off_loans(&mut self) -> Result<()> {
+        if !self
+            .flags()
+            .contains(PoolFlags::RETIRING | PoolFlags::SETTLED)
+        {
+            msg!("the pool's loans must be settled before they're written off");
+            return err!(ErrorCode::NotRetiring);
+        }
+
+        *self.total_borrowed_mut() = Number::ZERO;
+        *self.total_uncollected_fees_mut() = Number::ZERO;
+        *self.term_loan_interest_mut() = Number::ZERO;
+        *self.term_loan_interest_rate_mut() = Number::ZERO;
+        self.term_loan_interest_owed = 0;
+
+        let mut current = self.flags();
+
+        current.insert(PoolFlags::WRITTEN_OFF);
+        self.config.flags = current.bits();
+
+        Ok(())
+    }
+
+    /// Record loan notes being burned without payment, once the pool's loans
+    /// have been written off
+    pub fn burn_written_off_loan_notes(&mut self, notes: u64) -> Result<()> {
+        self.check_written_off()?;
+
+        self.loan_notes = self
+            .loan_notes
+            .checked_sub(notes)
+            .ok_or(ErrorCode::InvalidAmount)?;
+
+        Ok(())
+    }
+
+    /// Record a term loan being cleared without payment, once the pool's loans
+    /// have been written off
+    pub fn burn_written_off_term_loan(&mut self, principal: u64) -> Result<()> {
+        self.check_written_off()?;
+
+        self.term_loan_tokens = self
+            .term_loan_tokens
+            .checked_sub(principal)
+            .ok_or(ErrorCode::InvalidAmount)?;
+
+        Ok(())
+    }
+
+    fn check_written_off(&self) -> Result<()> {
+        if !self.flags().contains(PoolFlags::WRITTEN_OFF) {
+            msg!("the pool's loans have not been written off, and must be repaid");
+            return err!(ErrorCode::LoansNotWrittenOff);
+        }
+
+        Ok(())
+    }
+
+    /// Check that the pool has been settled and fully redeemed, so that its
+    /// accounts can be closed
+    ///
+    /// Loans that have been written off don't need to be repaid first, but
+    /// their notes have to be burned so that no margin account still holds a
+    /// position priced by the pool.
+    pub fn check_closeable(&self) -> Result<()> {
+        let flags = self.flags();
+
+        if !flags.contains(PoolFlags::RETIRING | PoolFlags::SETTLED) {
+            msg!("the pool must be settled before it can be closed");
+            return err!(ErrorCode::NotRetiring);
+        }
+
+        let loans_outstanding = self.loan_notes > 0 || self.term_loan_tokens > 0;
+
+        if self.deposit_notes > 0 || loans_outstanding || self.queued_withdrawal_notes > 0 {
+            msg!("the pool still has outstanding deposits or loans");
+            return err!(ErrorCode::PoolNotEmpty);
+        }
+
+        Ok(())
+    }
+
+    /// Get the total amount of notes issued for one of the pool's note mints
+    pub fn notes_outstanding(&self, note_mint: &Pubkey) -> u64 {
+        match *note_mint == self.loan_note_mint {
+            true => self.loan_notes,
+            false => self.deposit_notes,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -388,7 +878,7 @@ pub struct FullAmount {
 
 /// Represents the primary pool actions, used in determining the
 /// rounding direction between tokens and notes.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub enum PoolAction {
     Borrow,
     Deposit,
@@ -453,6 +943,9 @@ pub struct PriceResult {
     pub loan_note_price: i64,
     pub loan_note_conf: u64,
     pub loan_note_twap: i64,
+    pub term_loan_note_price: i64,
+    pub term_loan_note_conf: u64,
+    pub term_loan_note_twap: i64,
 }
 
 /// Configuration for a margin pool
@@ -484,6 +977,24 @@ pub struct MarginPoolConfig {
 
     /// The threshold for fee collection
     pub management_fee_collect_threshold: u64,
+
+    /// The utilization rate above which the collateral weight of deposit notes
+    /// starts being reduced
+    pub collateral_utilization_threshold: u16,
+
+    /// The reduction to the collateral weight of deposit notes when the pool is
+    /// fully utilized, with no reduction applied when zero
+    pub max_collateral_weight_reduction: u16,
+
+    /// The smallest amount of tokens that can be requested through the
+    /// withdrawal queue
+    pub min_queued_withdrawal: u64,
+
+    /// The most tokens the pool can hold in deposits, with no limit when zero
+    pub deposit_cap: u64,
+
+    /// The most tokens the pool can have lent out, with no limit when zero
+    pub borrow_cap: u64,
 }
 
 bitflags::bitflags! {
@@ -494,81 +1005,724 @@ bitflags::bitflags! {
 
         /// The pool is allowed to lend out deposits for borrowing
         const ALLOW_LENDING = 1 << 1;
-    }
-}
 
-#[account]
-pub struct MarginPoolOracle {
-    /// The mint for the token being priced
-    token_mint: Pubkey,
+        /// Deposits waiting in the withdrawal queue are counted towards
+        /// the utilization rate of the pool
+        const QUEUE_AFFECTS_RATE = 1 << 2;
 
-    /// The current price/value of the token
-    price: [u8; 24],
+        /// New deposits into the pool are paused, which includes swaps into the token
+        const PAUSE_DEPOSITS = 1 << 3;
 
-    /// The lower bound of the price based on the confidence
-    price_lower: [u8; 24],
+        /// Withdrawals from the pool are paused, which includes swaps out of the token
+        const PAUSE_WITHDRAWALS = 1 << 4;
 
-    /// The upper bound of the price based on the confidence
-    price_upper: [u8; 24],
-}
+        /// New loans from the pool are paused
+        const PAUSE_BORROWS = 1 << 5;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        /// Repayment of loans to the pool is paused
+        const PAUSE_REPAYS = 1 << 6;
 
-    #[test]
-    fn test_deposit_note_rounding() -> Result<()> {
-        let mut margin_pool = MarginPool::default();
+        /// All the flags that pause individual pool actions
+        const PAUSE_ALL = Self::PAUSE_DEPOSITS.bits
+            | Self::PAUSE_WITHDRAWALS.bits
+            | Self::PAUSE_BORROWS.bits
+            | Self::PAUSE_REPAYS.bits;
 
-        margin_pool.deposit(&FullAmount {
-            tokens: 1_000_000,
-            notes: 900_000,
-        });
+        /// The pool is being retired, and will never allow new deposits or borrows
+        const RETIRING = 1 << 7;
 
-        // Deposit note exchange rate is 1.111111_.
-        // If a user withdraws 9 notes, they should get 9 or 10 tokens back
-        // depending on the rounding.
+        /// The outstanding loans of a retiring pool have been settled, and no
+        /// longer accrue interest
+        const SETTLED = 1 << 8;
 
-        assert_eq!(
-            margin_pool.deposit_note_exchange_rate().as_u64(-9),
-            1111111111
-        );
+        /// The settled loans of a retiring pool that weren't repaid have been
+        /// written off against the deposits
+        const WRITTEN_OFF = 1 << 9;
 
-        let deposit_amount =
-            margin_pool.convert_deposit_amount(Amount::notes(12), RoundingDirection::Down)?;
+        /// All the flags that are set while retiring a pool
+        const RETIREMENT = Self::RETIRING.bits | Self::SETTLED.bits | Self::WRITTEN_OFF.bits;
+    }
+}
 
-        assert_eq!(deposit_amount.notes, 12);
-        assert_eq!(deposit_amount.tokens, 13); // ref [0]
+/// The maximum number of requests that can be waiting in a withdrawal queue
+pub const WITHDRAWAL_QUEUE_CAPACITY: usize = 64;
 
-        let deposit_amount =
-            margin_pool.convert_deposit_amount(Amount::notes(18), RoundingDirection::Down)?;
+/// A queue of requests to withdraw from a pool, which are filled in the order
+/// they were made as liquidity becomes available.
+#[account(zero_copy)]
+#[repr(C)]
+// bytemuck requires a higher alignment than 1 for unit tests to run.
+#[cfg_attr(not(target_arch = "bpf"), repr(align(8)))]
+pub struct WithdrawalQueue {
+    /// The pool the queue is for
+    pub margin_pool: Pubkey,
 
-        assert_eq!(deposit_amount.notes, 18);
-        assert_eq!(deposit_amount.tokens, 19);
+    /// The token account holding the deposit notes of queued requests
+    pub escrow: Pubkey,
 
-        let deposit_amount =
-            margin_pool.convert_deposit_amount(Amount::notes(12), RoundingDirection::Up)?;
+    /// The sequence number of the oldest request in the queue
+    pub head: u64,
 
-        assert_eq!(deposit_amount.notes, 12);
-        assert_eq!(deposit_amount.tokens, 14); // ref [1]
+    /// The sequence number for the next request added to the queue
+    pub tail: u64,
 
-        // A user requesting 1 note should never get 0 tokens back,
-        // or 1 token should never get 0 notes back
+    /// The storage for requests, indexed by their sequence number
+    pub requests: [WithdrawalRequest; WITHDRAWAL_QUEUE_CAPACITY],
+}
 
-        let deposit_amount =
-            margin_pool.convert_deposit_amount(Amount::notes(1), RoundingDirection::Down)?;
+impl WithdrawalQueue {
+    /// The number of request slots in use
+    pub fn len(&self) -> usize {
+        (self.tail - self.head) as usize
+    }
 
-        // When depositing, 1:1 would be advantageous to the user
-        assert_eq!(deposit_amount.notes, 1);
-        assert_eq!(deposit_amount.tokens, 1);
+    /// Check if there are no requests waiting
+    pub fn is_empty(&self) -> bool {
+        self.head == self.tail
+    }
 
-        let deposit_amount =
-            margin_pool.convert_deposit_amount(Amount::notes(1), RoundingDirection::Up)?;
+    /// Add a request to the back of the queue
+    ///
+    /// Returns the sequence number for the request.
+    pub fn push(&mut self, depositor: Pubkey, destination: Pubkey, notes: u64) -> Result<u64> {
+        // a skipped request keeps its slot until it's cancelled
+        if self.len() >= WITHDRAWAL_QUEUE_CAPACITY || self.slot(self.tail).notes > 0 {
+            msg!("the withdrawal queue is full");
+            return err!(ErrorCode::WithdrawalQueueFull);
+        }
 
-        // Depositing 2 tokens for 1 note is disadvantageous to the user
-        // and protects the protocol's average exchange rate
-        assert_eq!(deposit_amount.notes, 1);
-        assert_eq!(deposit_amount.tokens, 2);
+        let sequence = self.tail;
+        *self.slot_mut(sequence) = WithdrawalRequest {
+            depositor,
+            destination,
+            notes,
+            sequence,
+        };
+        self.tail += 1;
+
+        Ok(sequence)
+    }
+
+    /// Get the oldest request still waiting to be filled
+    ///
+    /// Any cancelled requests at the front of the queue are discarded.
+    pub fn front_mut(&mut self) -> Option<&mut WithdrawalRequest> {
+        while !self.is_empty() && self.slot(self.head).notes == 0 {
+            self.head += 1;
+        }
+
+        match self.is_empty() {
+            true => None,
+            false => Some(self.slot_mut(self.head)),
+        }
+    }
+
+    /// Remove the oldest request from the queue
+    pub fn pop_front(&mut self) {
+        if !self.is_empty() {
+            self.head += 1;
+        }
+    }
+
+    /// Check if a request is still waiting in the queue, rather than having
+    /// been filled or skipped
+    pub fn is_queued(&self, sequence: u64) -> bool {
+        sequence >= self.head && sequence < self.tail
+    }
+
+    /// Get a request by its sequence number, while it's still in the queue or
+    /// was skipped over without being filled
+    pub fn get_mut(&mut self, sequence: u64) -> Result<&mut WithdrawalRequest> {
+        if sequence >= self.tail || self.slot(sequence).sequence != sequence {
+            return err!(ErrorCode::InvalidWithdrawalRequest);
+        }
+
+        Ok(self.slot_mut(sequence))
+    }
+
+    fn slot(&self, sequence: u64) -> &WithdrawalRequest {
+        &self.requests[sequence as usize % WITHDRAWAL_QUEUE_CAPACITY]
+    }
+
+    fn slot_mut(&mut self, sequence: u64) -> &mut WithdrawalRequest {
+        &mut self.requests[sequence as usize % WITHDRAWAL_QUEUE_CAPACITY]
+    }
+}
+
+/// A request to withdraw deposit notes from a pool, waiting in the queue
+#[zero_copy]
+#[derive(Default, Debug)]
+pub struct WithdrawalRequest {
+    /// The address that queued the request, and may cancel it
+    pub depositor: Pubkey,
+
+    /// The token account to receive the withdrawn tokens
+    pub destination: Pubkey,
+
+    /// The amount of deposit notes remaining to be withdrawn
+    pub notes: u64,
+
+    /// The position of the request in the queue
+    pub sequence: u64,
+}
+
+/// The number of entries kept in the history for a pool
+pub const POOL_HISTORY_CAPACITY: usize = 64;
+
+/// A record of the rates for a pool over time, with the oldest entries being
+/// overwritten as new ones are added.
+#[account(zero_copy)]
+#[repr(C)]
+// bytemuck requires a higher alignment than 1 for unit tests to run.
+#[cfg_attr(not(target_arch = "bpf"), repr(align(8)))]
+pub struct PoolHistory {
+    /// The pool the history is for
+    pub margin_pool: Pubkey,
+
+    /// The total number of entries ever recorded
+    pub count: u64,
+
+    /// The storage for entries, indexed by the order they were recorded in
+    pub entries: [PoolHistoryEntry; POOL_HISTORY_CAPACITY],
+}
+
+impl PoolHistory {
+    /// Add an entry to the history, if enough time has passed since the last one
+    ///
+    /// Returns true if the entry was recorded.
+    pub fn record(&mut self, entry: PoolHistoryEntry, min_interval: i64) -> bool {
+        if let Some(latest) = self.latest() {
+            if entry.timestamp < latest.timestamp + min_interval {
+                return false;
+            }
+        }
+
+        self.entries[self.count as usize % POOL_HISTORY_CAPACITY] = entry;
+        self.count += 1;
+
+        true
+    }
+
+    /// The most recently recorded entry
+    pub fn latest(&self) -> Option<&PoolHistoryEntry> {
+        match self.count {
+            0 => None,
+            n => Some(&self.entries[(n - 1) as usize % POOL_HISTORY_CAPACITY]),
+        }
+    }
+
+    /// The entries currently held, from oldest to newest
+    pub fn entries(&self) -> impl Iterator<Item = &PoolHistoryEntry> {
+        let len = std::cmp::min(self.count, POOL_HISTORY_CAPACITY as u64);
+
+        (self.count - len..self.count)
+            .map(move |n| &self.entries[n as usize % POOL_HISTORY_CAPACITY])
+    }
+}
+
+/// A snapshot of the rates for a pool at some point in time
+#[zero_copy]
+#[derive(Default, Debug)]
+pub struct PoolHistoryEntry {
+    /// The time the snapshot was taken
+    pub timestamp: i64,
+
+    utilization_rate: [u8; 24],
+    borrow_rate: [u8; 24],
+    deposit_rate: [u8; 24],
+    deposit_note_exchange_rate: [u8; 24],
+    loan_note_exchange_rate: [u8; 24],
+}
+
+impl PoolHistoryEntry {
+    /// The utilization rate of the pool
+    pub fn utilization_rate(&self) -> &Number {
+        bytemuck::from_bytes(&self.utilization_rate)
+    }
+
+    /// The annual interest rate charged to borrowers
+    pub fn borrow_rate(&self) -> &Number {
+        bytemuck::from_bytes(&self.borrow_rate)
+    }
+
+    /// The annual interest rate earned by depositors
+    pub fn deposit_rate(&self) -> &Number {
+        bytemuck::from_bytes(&self.deposit_rate)
+    }
+
+    /// The number of tokens each deposit note could be exchanged for
+    pub fn deposit_note_exchange_rate(&self) -> &Number {
+        bytemuck::from_bytes(&self.deposit_note_exchange_rate)
+    }
+
+    /// The number of tokens owed for each loan note
+    pub fn loan_note_exchange_rate(&self) -> &Number {
+        bytemuck::from_bytes(&self.loan_note_exchange_rate)
+    }
+}
+
+/// A loan from a pool to a margin account, with an interest rate fixed at origination
+/// and a known maturity.
+///
+/// Each term loan note represents one token owed to the pool by the maturity. Once the
+/// loan matures, the notes are valued with an additional penalty until repaid.
+#[account]
+#[derive(Default)]
+pub struct TermLoan {
+    /// The margin account that borrowed the tokens
+    pub margin_account: Pubkey,
+
+    /// The pool the tokens were borrowed from
+    pub margin_pool: Pubkey,
+
+    /// The bump seed used to create the loan address
+    pub bump: [u8; 1],
+
+    /// The interest rate fixed for the loan at origination, in basis points
+    pub interest_rate: u16,
+
+    /// The amount of borrowed tokens yet to be repaid
+    pub principal: u64,
+
+    /// The amount of term loan notes outstanding
+    pub notes: u64,
+
+    /// The time the loan was originated
+    pub originated_at: i64,
+
+    /// The time the loan must be repaid by
+    pub maturity: i64,
+}
+
+impl TermLoan {
+    /// Calculate the amount of tokens to be repaid at maturity, for a loan of
+    /// the given amount of tokens
+    pub fn repayment_amount(principal: u64, rate: Number, term: UnixTimestamp) -> u64 {
+        let interest = rate * term / util::SECONDS_PER_YEAR;
+
+        (Number::from(principal) * (Number::ONE + interest)).as_u64_ceil(0)
+    }
+
+    /// Check if the loan is past its maturity
+    pub fn is_mature(&self, time: UnixTimestamp) -> bool {
+        time >= self.maturity
+    }
+
+    /// The amount of tokens owed for each note
+    pub fn note_value(&self, time: UnixTimestamp) -> Number {
+        match self.is_mature(time) {
+            true => Number::ONE + Number::from_bps(crate::TERM_LOAN_LATE_PENALTY),
+            false => Number::ONE,
+        }
+    }
+
+    /// The interest owed on the loan, excluding any penalty for late repayment
+    pub fn interest(&self) -> u64 {
+        self.notes.saturating_sub(self.principal)
+    }
+
+    /// The rate at which the pool earns the interest owed, in tokens per second
+    pub fn interest_rate_per_second(&self) -> Number {
+        Number::from(self.interest()) / (self.maturity - self.originated_at)
+    }
+
+    /// The portion of the interest owed that has been earned by the pool so far
+    pub fn accrued_interest(&self, time: UnixTimestamp) -> Number {
+        let term = self.maturity - self.originated_at;
+        let elapsed = (time - self.originated_at).clamp(0, term);
+
+        Number::from(self.interest()) * elapsed / term
+    }
+
+    /// Record a repayment of some of the notes
+    ///
+    /// Returns the portion of the original principal and of the interest that
+    /// has been repaid.
+    pub fn repay(&mut self, notes: u64, time: UnixTimestamp) -> Result<TermLoanRepayment> {
+        if notes == 0 || notes > self.notes {
+            return err!(ErrorCode::InvalidAmount);
+        }
+
+        let principal = match notes == self.notes {
+            true => self.principal,
+            false => ((self.principal as u128 * notes as u128) / self.notes as u128) as u64,
+        };
+
+        let interest = self.interest();
+        let accrued_interest = self.accrued_interest(time);
+        let interest_rate = self.interest_rate_per_second();
+
+        self.notes -= notes;
+        self.principal -= principal;
+
+        Ok(TermLoanRepayment {
+            principal,
+            interest: interest - self.interest(),
+            accrued_interest: accrued_interest - self.accrued_interest(time),
+            interest_rate: interest_rate - self.interest_rate_per_second(),
+        })
+    }
+}
+
+/// The portion of a term loan that has been repaid
+#[derive(Clone, Copy)]
+pub struct TermLoanRepayment {
+    /// The amount of the original principal repaid
+    pub principal: u64,
+
+    /// The amount of interest owed that was repaid
+    pub interest: u64,
+
+    /// The amount of the repaid interest already earned by the pool
+    pub accrued_interest: Number,
+
+    /// The reduction in the rate the pool earns interest on the loan
+    pub interest_rate: Number,
+}
+
+/// A cache of the price information for a pool's token, which is maintained
+/// by the pool from the oracle it has been configured with.
+#[account]
+#[derive(Default)]
+pub struct MarginPoolOracle {
+    /// The mint for the token being priced
+    pub token_mint: Pubkey,
+
+    /// The kind of oracle the cache is updated from
+    pub source: OracleSource,
+
+    /// The address of the pyth product account for the token, if the source is pyth
+    pub pyth_product: Pubkey,
+
+    /// The address of the oracle account the cache is updated from
+    pub price_oracle: Pubkey,
+
+    /// The current price/value of the token
+    price: [u8; 24],
+
+    /// The lower bound of the price based on the confidence
+    price_lower: [u8; 24],
+
+    /// The upper bound of the price based on the confidence
+    price_upper: [u8; 24],
+
+    /// The recent time-weighted average of the price
+    twap: [u8; 24],
+
+    /// The exponent used when reporting the cached prices
+    pub exponent: i32,
+
+    /// The slot the cached price was published in
+    pub slot: u64,
+
+    /// The time the cache was last updated
+    pub last_updated: i64,
+}
+
+impl MarginPoolOracle {
+    /// Change the oracle that the cache is updated from.
+    ///
+    /// Any previously cached price is discarded, so the next update is not
+    /// compared against a price from a different source.
+    pub fn set_source(&mut self, source: OracleSource, pyth_product: Pubkey, price_oracle: Pubkey) {
+        self.source = source;
+        self.pyth_product = pyth_product;
+        self.price_oracle = price_oracle;
+        self.last_updated = 0;
+    }
+
+    /// Update the cached price from the latest oracle price
+    ///
+    /// If the price, average price or confidence has moved too far from a
+    /// recently cached value, the update is rejected and the cache keeps its
+    /// previous slot. Positions priced from the cache then become stale,
+    /// until either the oracle returns to the band or the cache is old enough
+    /// that it no longer restricts the update.
+    pub fn update(&mut self, oracle_price: &OraclePrice, time: UnixTimestamp) -> Result<()> {
+        if oracle_price.price <= 0 {
+            msg!("oracle price {} is not positive", oracle_price.price);
+            return err!(ErrorCode::InvalidOracle);
+        }
+
+        let price = Number::from_decimal(oracle_price.price, oracle_price.exponent);
+        let conf = Number::from_decimal(oracle_price.confidence, oracle_price.exponent);
+        let twap = Number::from_decimal(oracle_price.twap, oracle_price.exponent);
+
+        if self.is_recent(time) {
+            let price_band = *self.price() * Number::from_bps(crate::MAX_ORACLE_PRICE_CHANGE);
+            let twap_band = *self.twap() * Number::from_bps(crate::MAX_ORACLE_PRICE_CHANGE);
+
+            let within_band = abs_diff(price, *self.price()) <= price_band
+                && abs_diff(twap, *self.twap()) <= twap_band
+                && abs_diff(conf, self.confidence()) <= price_band;
+
+            if !within_band {
+                msg!(
+                    "oracle price changed by more than the limit of {} bps, keeping the cached price",
+                    crate::MAX_ORACLE_PRICE_CHANGE
+                );
+                return Ok(());
+            }
+        }
+
+        *self.price_mut() = price;
+        *self.price_lower_mut() = match conf < price {
+            true => price - conf,
+            false => Number::ZERO,
+        };
+        *self.price_upper_mut() = price + conf;
+        *self.twap_mut() = twap;
+
+        self.exponent = oracle_price.exponent;
+        self.slot = oracle_price.slot;
+        self.last_updated = time;
+
+        Ok(())
+    }
+
+    /// Check if the cache has been updated recently enough to be used as
+    /// a reference for later updates.
+    pub fn is_recent(&self, time: UnixTimestamp) -> bool {
+        self.last_updated > 0 && time - self.last_updated <= crate::MAX_ORACLE_CACHE_AGE
+    }
+
+    /// The current price of the token
+    pub fn price(&self) -> &Number {
+        bytemuck::from_bytes(&self.price)
+    }
+
+    /// The lower bound of the price based on the confidence
+    pub fn price_lower(&self) -> &Number {
+        bytemuck::from_bytes(&self.price_lower)
+    }
+
+    /// The upper bound of the price based on the confidence
+    pub fn price_upper(&self) -> &Number {
+        bytemuck::from_bytes(&self.price_upper)
+    }
+
+    /// The recent average price of the token
+    pub fn twap(&self) -> &Number {
+        bytemuck::from_bytes(&self.twap)
+    }
+
+    /// The confidence interval around the current price
+    pub fn confidence(&self) -> Number {
+        *self.price_upper() - *self.price()
+    }
+
+    fn price_mut(&mut self) -> &mut Number {
+        bytemuck::from_bytes_mut(&mut self.price)
+    }
+
+    fn price_lower_mut(&mut self) -> &mut Number {
+        bytemuck::from_bytes_mut(&mut self.price_lower)
+    }
+
+    fn price_upper_mut(&mut self) -> &mut Number {
+        bytemuck::from_bytes_mut(&mut self.price_upper)
+    }
+
+    fn twap_mut(&mut self) -> &mut Number {
+        bytemuck::from_bytes_mut(&mut self.twap)
+    }
+}
+
+fn abs_diff(a: Number, b: Number) -> Number {
+    match a > b {
+        true => a - b,
+        false => b - a,
+    }
+}
+
+/// A campaign distributing a reward token to holders of a pool's notes, pro rata
+/// over time.
+///
+/// Rewards are tracked with an accumulator of the rewards earned per note, which
+/// holders compare against their last [RewardCheckpoint] to determine what they've earned.
+///
+/// Each of the pool's note mints can have a single campaign. Notes held outside of a
+/// margin account only earn rewards while staked in the campaign's stake vault.
+#[account]
+#[derive(Default, Debug)]
+pub struct RewardCampaign {
+    /// The pool whose notes are rewarded
+    pub margin_pool: Pubkey,
+
+    /// The mint for the notes being rewarded, either the deposit or loan notes
+    pub note_mint: Pubkey,
+
+    /// The mint for the token being distributed as a reward
+    pub reward_mint: Pubkey,
+
+    /// The token account funding the rewards
+    pub vault: Pubkey,
+
+    /// The token account holding the notes staked in the campaign
+    pub stake_vault: Pubkey,
+
+    /// The bump seed used to create the campaign address
+    pub bump: [u8; 1],
+
+    /// The time rewards start being emitted
+    pub start: i64,
+
+    /// The time rewards stop being emitted
+    pub end: i64,
+
+    /// The amount of reward tokens emitted per second, across all holders
+    pub emission_rate: u64,
+
+    /// The time rewards have been accrued up to
+    pub accrued_until: i64,
+
+    /// The total rewards earned by a single note over the life of the campaign
+    reward_per_note: [u8; 24],
+}
+
+impl RewardCampaign {
+    /// Accrue the rewards emitted since the last update, spread across the
+    /// notes currently outstanding
+    pub fn accrue(&mut self, time: UnixTimestamp, total_notes: u64) {
+        let from = std::cmp::max(self.accrued_until, self.start);
+        let to = std::cmp::min(time, self.end);
+
+        // rewards emitted while no notes are outstanding stay in the vault
+        if to > from && total_notes > 0 {
+            let emitted = Number::from(self.emission_rate) * Number::from((to - from) as u64);
+            *self.reward_per_note_mut() += emitted / Number::from(total_notes);
+        }
+
+        self.accrued_until = std::cmp::max(self.accrued_until, time);
+    }
+
+    /// Get the seeds needed to sign for the campaign's vaults
+    pub fn signer_seeds(&self) -> [&[u8]; 4] {
+        [
+            self.margin_pool.as_ref(),
+            self.note_mint.as_ref(),
+            b"reward-campaign".as_ref(),
+            self.bump.as_ref(),
+        ]
+    }
+
+    /// The total rewards earned by a single note over the life of the campaign
+    pub fn reward_per_note(&self) -> &Number {
+        bytemuck::from_bytes(&self.reward_per_note)
+    }
+
+    fn reward_per_note_mut(&mut self) -> &mut Number {
+        bytemuck::from_bytes_mut(&mut self.reward_per_note)
+    }
+}
+
+/// The state of rewards for notes earning from a campaign
+///
+/// A checkpoint either tracks a margin account position, or the notes an owner has
+/// staked in the campaign's stake vault.
+#[account]
+#[derive(Default, Debug)]
+pub struct RewardCheckpoint {
+    /// The campaign the rewards are from
+    pub campaign: Pubkey,
+
+    /// The margin account position holding the notes, or the campaign's stake
+    /// vault for staked notes
+    pub token_account: Pubkey,
+
+    /// The owner of the notes, which may claim the rewards
+    pub owner: Pubkey,
+
+    /// The balance of notes as of the checkpoint
+    pub balance: u64,
+
+    /// The rewards earned but not yet paid out
+    pub unclaimed: u64,
+
+    /// The rewards earned per note by the campaign as of the checkpoint
+    reward_per_note: [u8; 24],
+}
+
+impl RewardCheckpoint {
+    /// Move the checkpoint up to the current state of the campaign
+    ///
+    /// Only the lower of the previous and current balances earns rewards for the
+    /// period, so notes can't be moved in just before a checkpoint to collect
+    /// rewards they weren't held for. This relies on the checkpoint being synced
+    /// before every reduction in the balance.
+    pub fn sync(&mut self, campaign: &RewardCampaign, balance: u64) {
+        let eligible = std::cmp::min(self.balance, balance);
+        let earned =
+            (*campaign.reward_per_note() - *self.reward_per_note()) * Number::from(eligible);
+
+        self.unclaimed = self.unclaimed.checked_add(earned.as_u64(0)).unwrap();
+        self.balance = balance;
+        self.reward_per_note = bytemuck::cast(*campaign.reward_per_note());
+    }
+
+    /// The rewards earned per note by the campaign as of the checkpoint
+    pub fn reward_per_note(&self) -> &Number {
+        bytemuck::from_bytes(&self.reward_per_note)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deposit_note_rounding() -> Result<()> {
+        let mut margin_pool = MarginPool::default();
+
+        margin_pool.deposit(&FullAmount {
+            tokens: 1_000_000,
+            notes: 900_000,
+        });
+
+        // Deposit note exchange rate is 1.111111_.
+        // If a user withdraws 9 notes, they should get 9 or 10 tokens back
+        // depending on the rounding.
+
+        assert_eq!(
+            margin_pool.deposit_note_exchange_rate().as_u64(-9),
+            1111111111
+        );
+
+        let deposit_amount =
+            margin_pool.convert_deposit_amount(Amount::notes(12), RoundingDirection::Down)?;
+
+        assert_eq!(deposit_amount.notes, 12);
+        assert_eq!(deposit_amount.tokens, 13); // ref [0]
+
+        let deposit_amount =
+            margin_pool.convert_deposit_amount(Amount::notes(18), RoundingDirection::Down)?;
+
+        assert_eq!(deposit_amount.notes, 18);
+        assert_eq!(deposit_amount.tokens, 19);
+
+        let deposit_amount =
+            margin_pool.convert_deposit_amount(Amount::notes(12), RoundingDirection::Up)?;
+
+        assert_eq!(deposit_amount.notes, 12);
+        assert_eq!(deposit_amount.tokens, 14); // ref [1]
+
+        // A user requesting 1 note should never get 0 tokens back,
+        // or 1 token should never get 0 notes back
+
+        let deposit_amount =
+            margin_pool.convert_deposit_amount(Amount::notes(1), RoundingDirection::Down)?;
+
+        // When depositing, 1:1 would be advantageous to the user
+        assert_eq!(deposit_amount.notes, 1);
+        assert_eq!(deposit_amount.tokens, 1);
+
+        let deposit_amount =
+            margin_pool.convert_deposit_amount(Amount::notes(1), RoundingDirection::Up)?;
+
+        // Depositing 2 tokens for 1 note is disadvantageous to the user
+        // and protects the protocol's average exchange rate
+        assert_eq!(deposit_amount.notes, 1);
+        assert_eq!(deposit_amount.tokens, 2);
 
         // Check the default rounding for depositing notes, as it is disadvantageous
         // to the user per the previous observation.
@@ -763,4 +1917,574 @@ mod tests {
 
         Ok(())
     }
+
+    fn test_price(price: i64, confidence: u64) -> OraclePrice {
+        OraclePrice {
+            price,
+            confidence,
+            twap: price,
+            exponent: -8,
+            slot: 0,
+        }
+    }
+
+    #[test]
+    fn test_oracle_cache_update() -> Result<()> {
+        let mut oracle = MarginPoolOracle::default();
+
+        oracle.update(&test_price(100_00000000, 5_00000000), 1)?;
+
+        assert_eq!(oracle.price().as_u64(-8), 100_00000000);
+        assert_eq!(oracle.price_lower().as_u64(-8), 95_00000000);
+        assert_eq!(oracle.price_upper().as_u64(-8), 105_00000000);
+        assert_eq!(oracle.confidence().as_u64(-8), 5_00000000);
+
+        // A confidence wider than the price should not underflow the lower bound
+        oracle.update(&test_price(100_00000000, 200_00000000), 2)?;
+        assert_eq!(oracle.price_lower().as_u64(-8), 0);
+
+        // Prices that aren't positive are never accepted
+        assert!(oracle.update(&test_price(0, 0), 3).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_oracle_cache_rejects_price_jump() -> Result<()> {
+        let mut oracle = MarginPoolOracle::default();
+        let price_at = |price, slot| OraclePrice {
+            slot,
+            ..test_price(price, 0)
+        };
+
+        oracle.update(&price_at(100_00000000, 1), 1)?;
+
+        // A change within the limit is accepted
+        oracle.update(&price_at(80_00000000, 2), 2)?;
+        assert_eq!(oracle.price().as_u64(-8), 80_00000000);
+        assert_eq!(oracle.slot, 2);
+
+        // A change beyond the limit of the recently cached price is rejected,
+        // so the cache keeps the previous price and slot
+        oracle.update(&price_at(40_00000000, 3), 3)?;
+        assert_eq!(oracle.price().as_u64(-8), 80_00000000);
+        assert_eq!(oracle.slot, 2);
+        assert_eq!(oracle.last_updated, 2);
+
+        // The average price is limited the same way
+        oracle.update(
+            &OraclePrice {
+                twap: 40_00000000,
+                ..price_at(80_00000000, 4)
+            },
+            4,
+        )?;
+        assert_eq!(oracle.twap().as_u64(-8), 80_00000000);
+        assert_eq!(oracle.slot, 2);
+
+        // And so is the confidence
+        oracle.update(&test_price(80_00000000, 40_00000000), 5)?;
+        assert_eq!(oracle.confidence().as_u64(-8), 0);
+        assert_eq!(oracle.slot, 2);
+
+        // Once the cached price is old, it no longer restricts the update
+        let time = 2 + crate::MAX_ORACLE_CACHE_AGE + 1;
+        oracle.update(&price_at(10_00000000, 5), time)?;
+        assert_eq!(oracle.price().as_u64(-8), 10_00000000);
+        assert_eq!(oracle.slot, 5);
+
+        // Changing the source discards the cached price as a reference
+        oracle.set_source(OracleSource::Pyth, Pubkey::default(), Pubkey::default());
+        oracle.update(&price_at(1_00000000, 6), time + 1)?;
+        assert_eq!(oracle.price().as_u64(-8), 1_00000000);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_term_loan_repayment() -> Result<()> {
+        // 10% for half a year
+        let rate = Number::from_bps(1_000);
+        let repayment = TermLoan::repayment_amount(1_000_000, rate, util::SECONDS_PER_YEAR / 2);
+        assert_eq!(repayment, 1_050_000);
+
+        let mut term_loan = TermLoan {
+            principal: 1_000_000,
+            notes: repayment,
+            maturity: 100,
+            ..Default::default()
+        };
+
+        // notes are valued at one token until maturity, and with a penalty after
+        assert_eq!(term_loan.note_value(99).as_u64(-4), 10_000);
+        assert_eq!(term_loan.note_value(100).as_u64(-4), 10_500);
+
+        // interest is earned evenly over the term
+        assert_eq!(term_loan.accrued_interest(25).as_u64(0), 12_500);
+        assert_eq!(term_loan.accrued_interest(200).as_u64(0), 50_000);
+
+        // partial repayments reduce the principal and interest proportionally
+        let repaid = term_loan.repay(525_000, 50)?;
+        assert_eq!(repaid.principal, 500_000);
+        assert_eq!(repaid.interest, 25_000);
+        assert_eq!(repaid.accrued_interest.as_u64(0), 12_500);
+        assert_eq!(repaid.interest_rate.as_u64(0), 250);
+        assert_eq!(term_loan.principal, 500_000);
+
+        // can't repay more than owed
+        assert!(term_loan.repay(525_001, 50).is_err());
+
+        // the final repayment clears the remaining principal
+        let repaid = term_loan.repay(525_000, 100)?;
+        assert_eq!(repaid.principal, 500_000);
+        assert_eq!(repaid.accrued_interest.as_u64(0), 25_000);
+        assert_eq!(term_loan.principal, 0);
+        assert_eq!(term_loan.notes, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_term_loan_pool_accounting() -> Result<()> {
+        let mut margin_pool = MarginPool::default();
+        margin_pool.config.flags = PoolFlags::ALLOW_LENDING.bits();
+
+        margin_pool.deposit(&FullAmount {
+            tokens: 1_000_000,
+            notes: 1_000_000,
+        });
+
+        margin_pool.term_borrow(400_000)?;
+        margin_pool.term_loan_originated(20_000, 100);
+        assert_eq!(margin_pool.deposit_tokens, 600_000);
+        assert_eq!(margin_pool.term_loan_tokens, 400_000);
+
+        // lent tokens still count towards the value of deposits, and are not
+        // available to other borrowers
+        assert_eq!(margin_pool.deposit_note_exchange_rate().as_u64(0), 1);
+        assert_eq!(margin_pool.utilization_rate().as_u64(-2), 40);
+
+        // interest is earned by depositors over the term of the loan
+        assert!(margin_pool.accrue_interest(50));
+        assert_eq!(margin_pool.deposit_note_exchange_rate().as_u64(-2), 101);
+
+        // but no more than the loan will repay
+        assert!(margin_pool.accrue_interest(200));
+        assert_eq!(margin_pool.deposit_note_exchange_rate().as_u64(-2), 102);
+
+        let mut term_loan = TermLoan {
+            principal: 400_000,
+            notes: 420_000,
+            maturity: 100,
+            ..Default::default()
+        };
+        let repaid = term_loan.repay(420_000, 200)?;
+
+        // repaying the loan doesn't change the value of deposits again
+        margin_pool.term_repay(&repaid, 420_000)?;
+        assert_eq!(margin_pool.deposit_tokens, 1_020_000);
+        assert_eq!(margin_pool.term_loan_tokens, 0);
+        assert_eq!(margin_pool.term_loan_interest_owed, 0);
+        assert_eq!(margin_pool.deposit_note_exchange_rate().as_u64(-2), 102);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_withdrawal_queue_order() -> Result<()> {
+        let mut queue = <WithdrawalQueue as bytemuck::Zeroable>::zeroed();
+        let depositor = Pubkey::new_unique();
+
+        assert!(queue.front_mut().is_none());
+
+        let first = queue.push(depositor, Pubkey::new_unique(), 100)?;
+        let second = queue.push(depositor, Pubkey::new_unique(), 200)?;
+        let third = queue.push(depositor, Pubkey::new_unique(), 300)?;
+        assert_eq!(queue.len(), 3);
+
+        // requests are filled in the order they were made
+        let front = queue.front_mut().unwrap();
+        assert_eq!(front.sequence, first);
+        front.notes = 0;
+        queue.pop_front();
+
+        // cancelled requests are skipped over
+        queue.get_mut(second)?.notes = 0;
+        assert_eq!(queue.front_mut().unwrap().sequence, third);
+
+        // a request skipped without being filled can still be cancelled
+        queue.pop_front();
+        assert!(queue.front_mut().is_none());
+        assert!(!queue.is_queued(third));
+        assert_eq!(queue.get_mut(third)?.notes, 300);
+        assert!(queue.get_mut(third + 1).is_err());
+
+        // the storage is reused once requests are removed, except for the
+        // slot still held by the skipped request
+        for _ in 0..WITHDRAWAL_QUEUE_CAPACITY - 1 {
+            queue.push(depositor, Pubkey::new_unique(), 1)?;
+        }
+        assert!(queue.push(depositor, Pubkey::new_unique(), 1).is_err());
+
+        queue.get_mut(third)?.notes = 0;
+        queue.push(depositor, Pubkey::new_unique(), 1)?;
+        assert!(queue.push(depositor, Pubkey::new_unique(), 1).is_err());
+        assert!(queue.get_mut(third).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_queue_affects_utilization() {
+        let mut margin_pool = MarginPool::default();
+
+        margin_pool.deposit(&FullAmount {
+            tokens: 1_000_000,
+            notes: 1_000_000,
+        });
+        *margin_pool.total_borrowed_mut() = Number::from(250_000);
+        margin_pool.deposit_tokens = 750_000;
+        margin_pool.queue_withdrawal(250_000);
+
+        assert_eq!(margin_pool.utilization_rate().as_u64(-2), 25);
+
+        margin_pool.config.utilization_rate_1 = 5_000;
+        margin_pool.config.utilization_rate_2 = 8_000;
+        margin_pool.config.borrow_rate_1 = 1_000;
+        margin_pool.config.borrow_rate_2 = 2_000;
+        margin_pool.config.borrow_rate_3 = 4_000;
+
+        let deposit_rate = margin_pool.deposit_rate().as_u64(-6);
+        assert_eq!(
+            deposit_rate,
+            (margin_pool.interest_rate() * Number::from_bps(2_500)).as_u64(-6)
+        );
+
+        margin_pool.config.flags = PoolFlags::QUEUE_AFFECTS_RATE.bits();
+        assert_eq!(margin_pool.utilization_rate().as_u64(-2), 50);
+
+        // the queue raises the borrow rate, but depositors still only earn
+        // interest on the borrowed share of the pool
+        assert!(margin_pool.deposit_rate().as_u64(-6) > deposit_rate);
+        assert_eq!(
+            margin_pool.deposit_rate().as_u64(-6),
+            (margin_pool.interest_rate() * Number::from_bps(2_500)).as_u64(-6)
+        );
+    }
+
+    #[test]
+    fn test_pool_caps() -> Result<()> {
+        let mut margin_pool = MarginPool::default();
+
+        margin_pool.config.flags = PoolFlags::ALLOW_LENDING.bits();
+        margin_pool.config.deposit_cap = 1_000_000;
+        margin_pool.config.borrow_cap = 500_000;
+
+        margin_pool.deposit(&FullAmount {
+            tokens: 1_000_000,
+            notes: 1_000_000,
+        });
+        margin_pool.check_deposit_cap()?;
+
+        margin_pool.deposit(&FullAmount {
+            tokens: 1,
+            notes: 1,
+        });
+        assert!(margin_pool.check_deposit_cap().is_err());
+
+        // term loans count towards the same borrow cap
+        margin_pool.borrow(&FullAmount {
+            tokens: 300_000,
+            notes: 300_000,
+        })?;
+        margin_pool.term_borrow(200_000)?;
+        assert!(margin_pool.term_borrow(1).is_err());
+        assert!(margin_pool
+            .borrow(&FullAmount {
+                tokens: 1,
+                notes: 1
+            })
+            .is_err());
+
+        // no limits apply when the caps are zero
+        margin_pool.config.deposit_cap = 0;
+        margin_pool.config.borrow_cap = 0;
+        margin_pool.check_deposit_cap()?;
+        margin_pool.term_borrow(1)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_collateral_weight_reduction() {
+        let mut margin_pool = MarginPool::default();
+
+        margin_pool.deposit(&FullAmount {
+            tokens: 1_000_000,
+            notes: 1_000_000,
+        });
+
+        // no reduction unless configured
+        *margin_pool.total_borrowed_mut() = Number::from(900_000);
+        margin_pool.deposit_tokens = 100_000;
+        assert_eq!(margin_pool.collateral_weight_reduction(), 0);
+
+        margin_pool.config.collateral_utilization_threshold = 8_000;
+        margin_pool.config.max_collateral_weight_reduction = 5_000;
+
+        // no reduction at or below the threshold
+        *margin_pool.total_borrowed_mut() = Number::from(800_000);
+        margin_pool.deposit_tokens = 200_000;
+        assert_eq!(margin_pool.collateral_weight_reduction(), 0);
+
+        // halfway between the threshold and full utilization
+        *margin_pool.total_borrowed_mut() = Number::from(900_000);
+        margin_pool.deposit_tokens = 100_000;
+        assert_eq!(margin_pool.collateral_weight_reduction(), 2_500);
+
+        // the full reduction once the pool is fully utilized
+        *margin_pool.total_borrowed_mut() = Number::from(1_000_000);
+        margin_pool.deposit_tokens = 0;
+        assert_eq!(margin_pool.collateral_weight_reduction(), 5_000);
+    }
+
+    #[test]
+    fn test_pool_history_record() {
+        let mut history = <PoolHistory as bytemuck::Zeroable>::zeroed();
+        let margin_pool = MarginPool::default();
+
+        assert!(history.latest().is_none());
+        assert!(history.record(margin_pool.history_entry(100), 50));
+
+        // entries too close together are skipped
+        assert!(!history.record(margin_pool.history_entry(120), 50));
+        assert!(history.record(margin_pool.history_entry(150), 50));
+        assert_eq!(history.latest().unwrap().timestamp, 150);
+
+        // the oldest entries are overwritten once the history is full
+        for n in 0..POOL_HISTORY_CAPACITY as i64 {
+            history.record(margin_pool.history_entry(200 + n), 0);
+        }
+
+        let timestamps = history.entries().map(|e| e.timestamp).collect::<Vec<_>>();
+        assert_eq!(timestamps.len(), POOL_HISTORY_CAPACITY);
+        assert_eq!(timestamps[0], 200);
+        assert_eq!(
+            *timestamps.last().unwrap(),
+            199 + POOL_HISTORY_CAPACITY as i64
+        );
+    }
+
+    #[test]
+    fn test_reward_distribution() {
+        let mut campaign = RewardCampaign {
+            start: 100,
+            end: 200,
+            emission_rate: 1_000,
+            ..Default::default()
+        };
+
+        let mut holder = RewardCheckpoint {
+            balance: 250,
+            ..Default::default()
+        };
+
+        // nothing is emitted before the campaign starts
+        campaign.accrue(50, 1_000);
+        holder.sync(&campaign, 250);
+        assert_eq!(holder.unclaimed, 0);
+
+        // the holder has a quarter of the notes for the first half
+        campaign.accrue(150, 1_000);
+        holder.sync(&campaign, 500);
+        assert_eq!(holder.unclaimed, 12_500);
+
+        // the increased balance only counts once it has been held for a period,
+        // and nothing is emitted after the campaign ends
+        campaign.accrue(300, 1_000);
+        holder.sync(&campaign, 500);
+        assert_eq!(holder.unclaimed, 37_500);
+    }
+
+    #[test]
+    fn test_pause_actions() -> Result<()> {
+        let mut margin_pool = MarginPool::default();
+
+        margin_pool.config.flags = PoolFlags::ALLOW_LENDING.bits();
+        margin_pool.set_paused(PoolFlags::PAUSE_BORROWS | PoolFlags::PAUSE_DEPOSITS, true);
+
+        assert!(margin_pool.check_action(PoolAction::Borrow).is_err());
+        assert!(margin_pool.check_action(PoolAction::Deposit).is_err());
+        margin_pool.check_action(PoolAction::Repay)?;
+        margin_pool.check_action(PoolAction::Withdraw)?;
+
+        // other flags can't be changed through pausing
+        margin_pool.set_paused(PoolFlags::ALLOW_LENDING | PoolFlags::PAUSE_DEPOSITS, false);
+
+        margin_pool.check_action(PoolAction::Deposit)?;
+        assert!(margin_pool.check_action(PoolAction::Borrow).is_err());
+        assert!(margin_pool.flags().contains(PoolFlags::ALLOW_LENDING));
+
+        // reconfiguring the pool neither resumes nor pauses any actions
+        margin_pool.configure(MarginPoolConfig {
+            flags: PoolFlags::PAUSE_DEPOSITS.bits(),
+            ..margin_pool.config.clone()
+        });
+
+        margin_pool.check_action(PoolAction::Deposit)?;
+        assert!(margin_pool.check_action(PoolAction::Borrow).is_err());
+        assert!(!margin_pool.flags().contains(PoolFlags::ALLOW_LENDING));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pool_retirement() -> Result<()> {
+        let mut margin_pool = MarginPool::default();
+
+        margin_pool.config.flags = PoolFlags::ALLOW_LENDING.bits();
+        margin_pool.config.borrow_rate_2 = 10_000;
+        margin_pool.config.borrow_rate_3 = 10_000;
+
+        margin_pool.deposit(&FullAmount {
+            tokens: 1_000_000,
+            notes: 1_000_000,
+        });
+        margin_pool.borrow(&FullAmount {
+            tokens: 500_000,
+            notes: 500_000,
+        })?;
+
+        // can't settle or close a pool that isn't being retired
+        assert!(margin_pool.settle_retirement(0).is_err());
+        assert!(margin_pool.check_closeable().is_err());
+
+        margin_pool.begin_retirement();
+
+        assert!(margin_pool.check_action(PoolAction::Deposit).is_err());
+        assert!(margin_pool.check_action(PoolAction::Borrow).is_err());
+        margin_pool.check_action(PoolAction::Withdraw)?;
+        margin_pool.check_action(PoolAction::Repay)?;
+
+        // deposits and borrows can't be resumed, even by reconfiguring the pool
+        margin_pool.set_paused(PoolFlags::PAUSE_ALL, false);
+        margin_pool.configure(MarginPoolConfig {
+            flags: PoolFlags::ALLOW_LENDING.bits(),
+            ..margin_pool.config.clone()
+        });
+
+        assert!(margin_pool.check_action(PoolAction::Deposit).is_err());
+        assert!(margin_pool.check_action(PoolAction::Borrow).is_err());
+
+        // interest stops accruing once the loans are settled
+        margin_pool.settle_retirement(3_600)?;
+        let final_value = margin_pool.total_borrowed().as_u64(0);
+        assert!(final_value > 500_000);
+
+        assert!(margin_pool.accrue_interest(3_600 * 24 * 30));
+        assert_eq!(margin_pool.total_borrowed().as_u64(0), final_value);
+
+        // the pool can only be closed once everything is redeemed
+        assert!(margin_pool.check_closeable().is_err());
+
+        margin_pool.repay(&FullAmount {
+            tokens: final_value,
+            notes: 500_000,
+        })?;
+        margin_pool.withdraw(&FullAmount {
+            tokens: margin_pool.deposit_tokens,
+            notes: 1_000_000,
+        })?;
+
+        margin_pool.check_closeable()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_off_loans() -> Result<()> {
+        let mut margin_pool = MarginPool::default();
+
+        margin_pool.config.flags = PoolFlags::ALLOW_LENDING.bits();
+
+        margin_pool.deposit(&FullAmount {
+            tokens: 1_000_000,
+            notes: 1_000_000,
+        });
+        margin_pool.borrow(&FullAmount {
+            tokens: 500_000,
+            notes: 500_000,
+        })?;
+
+        // loans can only be written off once they're settled, and their notes
+        // can only be burned without payment once written off
+        margin_pool.begin_retirement();
+        assert!(margin_pool.write_off_loans().is_err());
+        assert!(margin_pool.burn_written_off_loan_notes(500_000).is_err());
+
+        margin_pool.settle_retirement(0)?;
+        margin_pool.write_off_loans()?;
+
+        // the depositors share the loss, and the loans can't be repaid
+        assert_eq!(
+            margin_pool.deposit_note_exchange_rate(),
+            Number::from_decimal(5, -1)
+        );
+        assert_eq!(margin_pool.loan_note_exchange_rate(), Number::ZERO);
+        assert!(margin_pool.check_action(PoolAction::Repay).is_err());
+        margin_pool.check_action(PoolAction::Withdraw)?;
+
+        // the pool can only be closed once the deposits are redeemed, and the
+        // borrowers have burned their loan notes
+        margin_pool.withdraw(&FullAmount {
+            tokens: 500_000,
+            notes: 1_000_000,
+        })?;
+
+        assert_eq!(margin_pool.loan_notes, 500_000);
+        assert!(margin_pool.check_closeable().is_err());
+
+        margin_pool.burn_written_off_loan_notes(500_000)?;
+        margin_pool.check_closeable()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_burn_written_off_term_loan() -> Result<()> {
+        let mut margin_pool = MarginPool::default();
+
+        margin_pool.config.flags = PoolFlags::ALLOW_LENDING.bits();
+
+        margin_pool.deposit(&FullAmount {
+            tokens: 1_000_000,
+            notes: 1_000_000,
+        });
+        margin_pool.term_borrow(400_000)?;
+        margin_pool.term_loan_originated(20_000, 100);
+
+        margin_pool.begin_retirement();
+        margin_pool.settle_retirement(0)?;
+        margin_pool.write_off_loans()?;
+
+        // the term loan no longer has any value for the depositors
+        assert_eq!(
+            margin_pool.deposit_note_exchange_rate(),
+            Number::from_decimal(6, -1)
+        );
+
+        margin_pool.withdraw(&FullAmount {
+            tokens: 600_000,
+            notes: 1_000_000,
+        })?;
+
+        // the loan is still counted until it's cleared by burning its notes
+        assert_eq!(margin_pool.term_loan_tokens, 400_000);
+        assert!(margin_pool.check_closeable().is_err());
+
+        margin_pool.burn_written_off_term_loan(400_000)?;
+        margin_pool.check_closeable()?;
+
+        Ok(())
+    }
 }
@@ -68,3 +68,12 @@ pub fn interpolate(x: Number, x0: Number, x1: Number, y0: Number, y1: Number) ->
 
     y0 + ((x - x0) * (y1 - y0)) / (x1 - x0)
 }
+
+/// Subtraction that stops at zero, rather than underflowing.
+pub fn saturating_sub(x: Number, y: Number) -> Number {
+    if x > y {
+        x - y
+    } else {
+        Number::ZERO
+    }
+}
@@ -49,13 +49,24 @@ pub struct MarginSplSwap<'info> {
     /// The accounts relevant to the source margin pool
     pub source_margin_pool: MarginPoolInfo<'info>,
 
+    /// The campaign rewarding holders of the source pool's deposit notes
+    /// CHECK:
+    #[account(mut)]
+    pub source_reward_campaign: AccountInfo<'info>,
+
+    /// The rewards checkpoint for the source deposit, which is settled by the margin pool
+    /// CHECK:
+    #[account(mut)]
+    pub source_reward_checkpoint: AccountInfo<'info>,
+
     /// The accounts relevant to the destination margin pool
     pub destination_margin_pool: MarginPoolInfo<'info>,
 
     pub margin_pool_program: Program<'info, JetMarginPool>,
 
-    /// CHECK:
-    pub token_program: UncheckedAccount<'info>,
+    /// The token program for the swap, which is also used for the tokens in both margin pools,
+    /// since the swap program only supports tokens from the original token program
+    pub token_program: Program<'info, Token>,
 }
 
 impl<'info> MarginSplSwap<'info> {
@@ -64,12 +75,17 @@ impl<'info> MarginSplSwap<'info> {
             self.margin_pool_program.to_account_info(),
             Withdraw {
                 margin_pool: self.source_margin_pool.margin_pool.to_account_info(),
+                token_mint: self.source_margin_pool.token_mint.to_account_info(),
                 vault: self.source_margin_pool.vault.to_account_info(),
                 deposit_note_mint: self.source_margin_pool.deposit_note_mint.to_account_info(),
                 depositor: self.margin_account.to_account_info(),
                 source: self.source_account.to_account_info(),
                 destination: self.transit_source_account.to_account_info(),
+                history: self.source_margin_pool.history.to_account_info(),
+                reward_campaign: self.source_reward_campaign.to_account_info(),
+                reward_checkpoint: self.source_reward_checkpoint.to_account_info(),
                 token_program: self.token_program.to_account_info(),
+                pool_token_program: self.token_program.to_account_info(),
             },
         )
     }
@@ -79,6 +95,7 @@ impl<'info> MarginSplSwap<'info> {
             self.margin_pool_program.to_account_info(),
             Deposit {
                 margin_pool: self.destination_margin_pool.margin_pool.to_account_info(),
+                token_mint: self.destination_margin_pool.token_mint.to_account_info(),
                 vault: self.destination_margin_pool.vault.to_account_info(),
                 deposit_note_mint: self
                     .destination_margin_pool
@@ -87,7 +104,9 @@ impl<'info> MarginSplSwap<'info> {
                 depositor: self.margin_account.to_account_info(),
                 source: self.transit_destination_account.to_account_info(),
                 destination: self.destination_account.to_account_info(),
+                history: self.destination_margin_pool.history.to_account_info(),
                 token_program: self.token_program.to_account_info(),
+                pool_token_program: self.token_program.to_account_info(),
             },
         )
     }
@@ -126,6 +145,19 @@ pub fn margin_spl_swap_handler(
     amount_in: u64,
     minimum_amount_out: u64,
 ) -> Result<()> {
+    for pool in [
+        &ctx.accounts.source_margin_pool,
+        &ctx.accounts.destination_margin_pool,
+    ] {
+        if *pool.token_mint.owner != token::ID {
+            msg!(
+                "the swap does not support the token {}",
+                pool.token_mint.key()
+            );
+            return Err(ErrorCode::UnsupportedTokenProgram.into());
+        }
+    }
+
     jet_margin_pool::cpi::withdraw(
         ctx.accounts.withdraw_source_context(),
         Amount::tokens(amount_in),
@@ -17,7 +17,7 @@
 
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::program::invoke;
-use anchor_spl::token;
+use anchor_spl::token::{self, Token};
 
 use jet_margin::{AdapterResult, MarginAccount};
 use jet_margin_pool::{
@@ -50,6 +50,9 @@ pub struct MarginPoolInfo<'info> {
     #[account(mut)]
     pub margin_pool: UncheckedAccount<'info>,
 
+    /// CHECK:
+    pub token_mint: UncheckedAccount<'info>,
+
     /// CHECK:
     #[account(mut)]
     pub vault: UncheckedAccount<'info>,
@@ -57,6 +60,10 @@ pub struct MarginPoolInfo<'info> {
     /// CHECK:
     #[account(mut)]
     pub deposit_note_mint: UncheckedAccount<'info>,
+
+    /// CHECK:
+    #[account(mut)]
+    pub history: UncheckedAccount<'info>,
 }
 
 /// Create an SPL Token Swap `Program` wrapper for validation
@@ -68,3 +75,10 @@ impl Id for SplTokenSwap {
         spl_token_swap::id()
     }
 }
+
+#[error_code]
+pub enum ErrorCode {
+    /// 141400 - A pool's token belongs to a token program that the swap can't use
+    #[msg("The swap only supports tokens from the original token program")]
+    UnsupportedTokenProgram = 135_400,
+}
@@ -19,11 +19,13 @@ use anchor_lang::{
     prelude::*,
     solana_program::{instruction::Instruction, program},
 };
-use anchor_spl::token::TokenAccount;
 
 use jet_proto_math::Number128;
 
-use crate::{ErrorCode, MarginAccount, PriceInfo, MAX_ORACLE_CONFIDENCE, MAX_ORACLE_STALENESS};
+use crate::util::read_token_account;
+use crate::{
+    ErrorCode, MarginAccount, PositionKind, PriceInfo, MAX_ORACLE_CONFIDENCE, MAX_ORACLE_STALENESS,
+};
 
 pub struct InvokeAdapter<'a, 'info> {
     /// The margin account to proxy an action for
@@ -55,6 +57,13 @@ pub enum AdapterResult {
 
     /// Indicates the price/value of some positions should change
     PriceChange(Vec<PriceChangeInfo>),
+
+    /// Indicates the price/value of some positions should change, along with a
+    /// reduction to their collateral weight
+    ///
+    /// This is a separate variant so that the encoding of [AdapterResult::PriceChange]
+    /// stays the same for adapters built before collateral weights could be reduced.
+    WeightedPriceChange(Vec<WeightedPriceChangeInfo>),
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
@@ -78,6 +87,40 @@ pub struct PriceChangeInfo {
     pub exponent: i32,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct WeightedPriceChangeInfo {
+    /// The price change for the position
+    pub price: PriceChangeInfo,
+
+    /// A reduction to the collateral weight of the position, in basis points,
+    /// such as when the asset is currently difficult to withdraw
+    pub collateral_weight_reduction: u16,
+}
+
+impl PriceChangeInfo {
+    /// The least favourable value for a position of the given kind, out of the
+    /// spot price, its confidence bounds and the recent average price.
+    ///
+    /// Deposits take the lowest value and claims take the highest, so that a
+    /// short-lived price movement can't be used to inflate an account's health.
+    pub fn conservative_value(&self, kind: PositionKind) -> i64 {
+        let confidence = std::cmp::min(self.confidence, i64::MAX as u64) as i64;
+
+        match kind {
+            PositionKind::Deposit => self
+                .value
+                .min(self.twap)
+                .min(self.value.saturating_sub(confidence))
+                .max(0),
+            PositionKind::Claim => self
+                .value
+                .max(self.twap)
+                .max(self.value.saturating_add(confidence)),
+            PositionKind::NoValue => self.value,
+        }
+    }
+}
+
 pub fn invoke(
     ctx: &InvokeAdapter,
     account_metas: Vec<CompactAccountMeta>,
@@ -136,8 +179,7 @@ pub fn invoke(
         | AdapterResult::PriorBalanceChange(ref modified_accounts) => {
             for modified in modified_accounts {
                 let account_info = account_infos.iter().find(|a| a.key == modified).unwrap();
-                let account =
-                    TokenAccount::try_deserialize(&mut &**account_info.try_borrow_data()?)?;
+                let account = read_token_account(account_info)?;
 
                 // sanity check that this account is actually owned by the margin program
                 if account.owner != ctx.margin_account.key() {
@@ -154,40 +196,134 @@ pub fn invoke(
         }
 
         AdapterResult::PriceChange(ref price_list) => {
-            let clock = Clock::get()?;
-            let max_confidence = Number128::from_bps(MAX_ORACLE_CONFIDENCE);
+            for entry in price_list {
+                update_position_price(&mut margin_account, ctx.adapter_program.key, entry, 0)?;
+            }
+        }
 
+        AdapterResult::WeightedPriceChange(ref price_list) => {
             for entry in price_list {
-                let twap = Number128::from_decimal(entry.twap, entry.exponent);
-                let confidence = Number128::from_decimal(entry.confidence, entry.exponent);
-
-                let price = match (confidence, entry.slot) {
-                    (c, _) if (c / twap) > max_confidence => PriceInfo::new_invalid(),
-                    (_, slot) if (clock.slot - slot) > MAX_ORACLE_STALENESS => {
-                        PriceInfo::new_invalid()
-                    }
-                    _ => PriceInfo::new_valid(
-                        entry.exponent,
-                        entry.value,
-                        clock.unix_timestamp as u64,
-                    ),
-                };
-
-                match margin_account.set_position_price(
-                    &entry.mint,
+                update_position_price(
+                    &mut margin_account,
                     ctx.adapter_program.key,
-                    &price,
-                ) {
-                    Err(Error::AnchorError(e))
-                        if e.error_code_number
-                            == (ErrorCode::UnknownPosition as u32
-                                + anchor_lang::error::ERROR_CODE_OFFSET) => {}
-                    Err(e) => return Err(e),
-                    Ok(()) => (),
-                }
+                    &entry.price,
+                    entry.collateral_weight_reduction,
+                )?;
             }
         }
     }
 
     Ok(result)
 }
+
+fn update_position_price(
+    margin_account: &mut MarginAccount,
+    adapter: &Pubkey,
+    entry: &PriceChangeInfo,
+    collateral_weight_reduction: u16,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let max_confidence = Number128::from_bps(MAX_ORACLE_CONFIDENCE);
+
+    let twap = Number128::from_decimal(entry.twap, entry.exponent);
+    let confidence = Number128::from_decimal(entry.confidence, entry.exponent);
+
+    let price = match (confidence, entry.slot) {
+        (c, _) if (c / twap) > max_confidence => PriceInfo::new_invalid(),
+        (_, slot) if (clock.slot - slot) > MAX_ORACLE_STALENESS => PriceInfo::new_invalid(),
+        _ => match margin_account.get_position(&entry.mint) {
+            Some(position) if position.is_conservative() => PriceInfo::new_conservative(
+                entry.exponent,
+                entry.conservative_value(position.kind()?),
+                clock.unix_timestamp as u64,
+            ),
+            _ => PriceInfo::new_valid(entry.exponent, entry.value, clock.unix_timestamp as u64),
+        },
+    };
+    let price = price.with_collateral_weight_reduction(collateral_weight_reduction);
+
+    match margin_account.set_position_price(&entry.mint, adapter, &price) {
+        Err(Error::AnchorError(e))
+            if e.error_code_number
+                == (ErrorCode::UnknownPosition as u32 + anchor_lang::error::ERROR_CODE_OFFSET) => {}
+        Err(e) => return Err(e),
+        Ok(()) => (),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price_change(value: i64, confidence: u64, twap: i64) -> PriceChangeInfo {
+        PriceChangeInfo {
+            mint: Pubkey::default(),
+            value,
+            confidence,
+            twap,
+            slot: 0,
+            exponent: 0,
+        }
+    }
+
+    #[test]
+    fn test_conservative_value_uses_confidence_bounds() {
+        let price = price_change(100, 5, 100);
+
+        assert_eq!(price.conservative_value(PositionKind::Deposit), 95);
+        assert_eq!(price.conservative_value(PositionKind::Claim), 105);
+        assert_eq!(price.conservative_value(PositionKind::NoValue), 100);
+    }
+
+    #[test]
+    fn test_conservative_value_uses_twap() {
+        // the average is further from the price than the confidence bounds
+        assert_eq!(
+            price_change(100, 5, 90).conservative_value(PositionKind::Deposit),
+            90
+        );
+        assert_eq!(
+            price_change(100, 5, 110).conservative_value(PositionKind::Claim),
+            110
+        );
+
+        // an average on the favourable side of the price isn't used
+        assert_eq!(
+            price_change(100, 5, 110).conservative_value(PositionKind::Deposit),
+            95
+        );
+        assert_eq!(
+            price_change(100, 5, 90).conservative_value(PositionKind::Claim),
+            105
+        );
+    }
+
+    #[test]
+    fn test_price_change_encoding_is_unchanged() {
+        let result = AdapterResult::PriceChange(vec![price_change(100, 5, 100)]);
+        let data = result.try_to_vec().unwrap();
+
+        // variant, list length, and the mint, value, confidence, twap, slot and exponent
+        assert_eq!(data.len(), 1 + 4 + 32 + 8 + 8 + 8 + 8 + 4);
+        assert_eq!(data[0], 2);
+
+        let weighted = AdapterResult::WeightedPriceChange(vec![WeightedPriceChangeInfo {
+            price: price_change(100, 5, 100),
+            collateral_weight_reduction: 2_500,
+        }]);
+        let data = weighted.try_to_vec().unwrap();
+
+        assert_eq!(data.len(), 1 + 4 + 32 + 8 + 8 + 8 + 8 + 4 + 2);
+        assert_eq!(data[0], 3);
+    }
+
+    #[test]
+    fn test_conservative_value_bounds() {
+        let price = price_change(100, u64::MAX, 100);
+
+        assert_eq!(price.conservative_value(PositionKind::Deposit), 0);
+        assert_eq!(price.conservative_value(PositionKind::Claim), i64::MAX);
+    }
+}
@@ -23,6 +23,7 @@ mod create_account;
 mod liquidate_begin;
 mod liquidate_end;
 mod liquidator_invoke;
+mod refresh_position_metadata;
 mod register_position;
 mod update_position_balance;
 mod verify_healthy;
@@ -35,6 +36,7 @@ pub use create_account::*;
 pub use liquidate_begin::*;
 pub use liquidate_end::*;
 pub use liquidator_invoke::*;
+pub use refresh_position_metadata::*;
 pub use register_position::*;
 pub use update_position_balance::*;
 pub use verify_healthy::*;
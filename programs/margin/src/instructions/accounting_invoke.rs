@@ -20,7 +20,8 @@ use anchor_lang::prelude::*;
 use jet_metadata::MarginAdapterMetadata;
 
 use crate::adapter::{self, CompactAccountMeta, InvokeAdapter};
-use crate::{AdapterResult, ErrorCode, MarginAccount};
+use crate::util::read_token_account;
+use crate::{AccountPosition, AdapterResult, ErrorCode, MarginAccount, PositionKind};
 
 #[derive(Accounts)]
 pub struct AccountingInvoke<'info> {
@@ -42,6 +43,15 @@ pub fn accounting_invoke_handler<'info>(
     account_metas: Vec<CompactAccountMeta>,
     data: Vec<u8>,
 ) -> Result<()> {
+    let prior_positions = ctx
+        .accounts
+        .margin_account
+        .load()?
+        .positions()
+        .copied()
+        .collect::<Vec<_>>();
+    let prior_amounts = position_token_amounts(&prior_positions, ctx.remaining_accounts);
+
     let result = adapter::invoke(
         &InvokeAdapter {
             margin_account: &ctx.accounts.margin_account,
@@ -52,12 +62,77 @@ pub fn accounting_invoke_handler<'info>(
         data,
     )?;
 
-    match result {
-        AdapterResult::NewBalanceChange(_) => {
-            msg!("New balance changes may only be realized through either adapter_invoke or liquidate_invoke, depending on context.");
-            err!(ErrorCode::UnauthorizedInvocation)
+    let reported: &[Pubkey] = match result {
+        AdapterResult::NewBalanceChange(ref modified_accounts) => {
+            verify_claims_reduced(
+                &ctx.accounts.margin_account,
+                &prior_positions,
+                modified_accounts,
+            )?;
+            modified_accounts
         }
-        AdapterResult::PriceChange(_) => Ok(()),
-        AdapterResult::PriorBalanceChange(_) => Ok(()),
+        AdapterResult::PriorBalanceChange(ref modified_accounts) => modified_accounts,
+        AdapterResult::PriceChange(_) | AdapterResult::WeightedPriceChange(_) => &[],
+    };
+
+    // Every position the adapter changed has to be reported, otherwise a change
+    // could be made to the account without being checked above
+    let current_amounts = position_token_amounts(&prior_positions, ctx.remaining_accounts);
+
+    for ((address, prior), (_, current)) in prior_amounts.iter().zip(current_amounts.iter()) {
+        if prior != current && !reported.contains(address) {
+            msg!("position {} was changed without being reported", address);
+            return err!(ErrorCode::UnauthorizedInvocation);
+        }
+    }
+
+    Ok(())
+}
+
+/// Anyone may pay down the debts of a margin account, so the only new balance
+/// changes that can be realized without the owner are claims that did not grow.
+fn verify_claims_reduced(
+    margin_account: &AccountLoader<MarginAccount>,
+    prior_positions: &[AccountPosition],
+    modified_accounts: &[Pubkey],
+) -> Result<()> {
+    if modified_accounts.is_empty() {
+        msg!("New balance changes must report the positions they modify");
+        return err!(ErrorCode::UnauthorizedInvocation);
     }
+
+    let margin_account = margin_account.load()?;
+
+    for modified in modified_accounts {
+        let prior = prior_positions.iter().find(|p| p.address == *modified);
+        let current = margin_account.positions().find(|p| p.address == *modified);
+
+        match (prior, current) {
+            (Some(prior), Some(current))
+                if current.kind()? == PositionKind::Claim && current.balance <= prior.balance => {}
+            _ => {
+                msg!("New balance changes may only be realized through either adapter_invoke or liquidate_invoke, depending on context.");
+                return err!(ErrorCode::UnauthorizedInvocation);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The token balances of the position accounts passed to the adapter, with
+/// `None` for an account that isn't a readable token account, such as when
+/// it has been closed
+fn position_token_amounts(
+    positions: &[AccountPosition],
+    accounts: &[AccountInfo],
+) -> Vec<(Pubkey, Option<u64>)> {
+    positions
+        .iter()
+        .filter_map(|position| accounts.iter().find(|a| *a.key == position.address))
+        .map(|account| {
+            let amount = read_token_account(account).ok().map(|a| a.amount);
+            (account.key(), amount)
+        })
+        .collect()
 }
@@ -45,10 +45,13 @@ pub fn adapter_invoke_handler<'info>(
     account_metas: Vec<CompactAccountMeta>,
     data: Vec<u8>,
 ) -> Result<()> {
-    if ctx.accounts.margin_account.load()?.liquidation != Pubkey::default() {
-        msg!("account is being liquidated");
-        return Err(ErrorCode::Liquidating.into());
-    }
+    let (liquidating, balances) = {
+        let account = ctx.accounts.margin_account.load()?;
+        (
+            account.liquidation != Pubkey::default(),
+            account.position_balances(),
+        )
+    };
 
     let result = adapter::invoke(
         &InvokeAdapter {
@@ -62,9 +65,23 @@ pub fn adapter_invoke_handler<'info>(
 
     let margin_account = ctx.accounts.margin_account.load_mut()?;
 
+    // Adding collateral can only help the account, so it's allowed even when the
+    // account is unhealthy or being liquidated
+    if let AdapterResult::NewBalanceChange(_) = result {
+        if margin_account.only_added_collateral(&balances)? {
+            return Ok(());
+        }
+    }
+
+    if liquidating {
+        msg!("account is being liquidated");
+        return Err(ErrorCode::Liquidating.into());
+    }
+
     match result {
         AdapterResult::NewBalanceChange(_) => margin_account.verify_healthy_positions()?,
         AdapterResult::PriceChange(_) => (),
+        AdapterResult::WeightedPriceChange(_) => (),
         AdapterResult::PriorBalanceChange(_) => (),
     }
 
@@ -18,12 +18,14 @@
 use anchor_lang::prelude::*;
 use anchor_lang::AccountsClose;
 
+use jet_metadata::LiquidatorMetadata;
+
 use crate::{ErrorCode, Liquidation, MarginAccount, LIQUIDATION_TIMEOUT};
 
 #[derive(Accounts)]
 pub struct LiquidateEnd<'info> {
-    /// If the liquidation is timed out, this can be any account
-    /// If the liquidation is not timed out, this must be the liquidator, and it must be a signer
+    /// If the liquidation is timed out, or the liquidator has been revoked, this can be any account
+    /// Otherwise this must be the liquidator, and it must be a signer
     pub authority: Signer<'info>,
 
     /// The account in need of liquidation
@@ -33,13 +35,20 @@ pub struct LiquidateEnd<'info> {
     /// Account to persist the state of the liquidation
     #[account(mut)]
     pub liquidation: AccountLoader<'info, Liquidation>,
+
+    /// The metadata for the liquidator of the margin account
+    /// CHECK: the address is verified in the handler
+    pub liquidator_metadata: AccountInfo<'info>,
 }
 
 pub fn liquidate_end_handler(ctx: Context<LiquidateEnd>) -> Result<()> {
     let mut account = ctx.accounts.margin_account.load_mut()?;
     let start_time = ctx.accounts.liquidation.load()?.start_time;
+    let revoked =
+        !is_registered_liquidator(&ctx.accounts.liquidator_metadata, &account.liquidator)?;
 
     if (account.liquidator != ctx.accounts.authority.key())
+        && !revoked
         && Clock::get()?.unix_timestamp - start_time < LIQUIDATION_TIMEOUT
     {
         msg!(
@@ -57,3 +66,23 @@ pub fn liquidate_end_handler(ctx: Context<LiquidateEnd>) -> Result<()> {
 
     Ok(())
 }
+
+/// Check whether the metadata still names the liquidator, which is no longer the
+/// case after the liquidator has been revoked or its metadata was closed
+fn is_registered_liquidator(metadata: &AccountInfo, liquidator: &Pubkey) -> Result<bool> {
+    let (address, _) = Pubkey::find_program_address(&[liquidator.as_ref()], &jet_metadata::ID);
+
+    if metadata.key() != address {
+        msg!("the metadata account is not for liquidator {}", liquidator);
+        return err!(ErrorCode::UnauthorizedLiquidator);
+    }
+
+    if metadata.data_is_empty() || metadata.lamports() == 0 || *metadata.owner != jet_metadata::ID {
+        return Ok(false);
+    }
+
+    // any other metadata that can't be read is an error, rather than a revocation
+    let metadata = Account::<LiquidatorMetadata>::try_from(metadata)?;
+
+    Ok(metadata.liquidator == *liquidator)
+}
@@ -17,7 +17,9 @@
 
 use anchor_lang::prelude::*;
 
-use jet_metadata::MarginAdapterMetadata;
+use jet_metadata::{
+    LiquidationAdapterPolicy, LiquidatorAdapterMetadata, LiquidatorMetadata, MarginAdapterMetadata,
+};
 use jet_proto_math::Number128;
 
 use crate::adapter::{self, CompactAccountMeta, InvokeAdapter};
@@ -31,6 +33,11 @@ pub struct LiquidatorInvoke<'info> {
     /// The liquidator processing the margin account
     pub liquidator: Signer<'info>,
 
+    /// The metadata describing the liquidator, which stops the liquidation from
+    /// continuing if the liquidator has been revoked
+    #[account(has_one = liquidator)]
+    pub liquidator_metadata: Account<'info, LiquidatorMetadata>,
+
     /// Account to persist the state of the liquidation
     #[account(mut)]
     pub liquidation: AccountLoader<'info, Liquidation>,
@@ -45,9 +52,40 @@ pub struct LiquidatorInvoke<'info> {
     /// CHECK:
     pub adapter_program: AccountInfo<'info>,
 
-    /// The metadata about the proxy program
-    #[account(has_one = adapter_program)]
-    pub adapter_metadata: Account<'info, MarginAdapterMetadata>,
+    /// The metadata about the proxy program, registering it as either a
+    /// margin adapter or a liquidation adapter
+    /// CHECK: verified against the liquidator's adapter policy
+    pub adapter_metadata: AccountInfo<'info>,
+}
+
+impl<'info> LiquidatorInvoke<'info> {
+    /// Check that the adapter program is registered on a list that's
+    /// allowed by the liquidator's adapter policy
+    fn verify_adapter(&self) -> Result<()> {
+        let adapter_program = self.adapter_program.key();
+
+        if let Ok(metadata) = Account::<LiquidatorAdapterMetadata>::try_from(&self.adapter_metadata)
+        {
+            if metadata.adapter_program == adapter_program {
+                return Ok(());
+            }
+        }
+
+        if self.liquidator_metadata.adapter_policy == LiquidationAdapterPolicy::Any {
+            if let Ok(metadata) = Account::<MarginAdapterMetadata>::try_from(&self.adapter_metadata)
+            {
+                if metadata.adapter_program == adapter_program {
+                    return Ok(());
+                }
+            }
+        }
+
+        msg!(
+            "program {} is not an adapter the liquidator is allowed to invoke",
+            adapter_program
+        );
+        err!(ErrorCode::UnauthorizedLiquidationAdapter)
+    }
 }
 
 pub fn liquidator_invoke_handler<'info>(
@@ -55,6 +93,8 @@ pub fn liquidator_invoke_handler<'info>(
     account_metas: Vec<CompactAccountMeta>,
     data: Vec<u8>,
 ) -> Result<()> {
+    ctx.accounts.verify_adapter()?;
+
     let margin_account = &ctx.accounts.margin_account;
     let start_value = margin_account.load()?.valuation()?;
 
@@ -85,6 +125,7 @@ pub fn liquidator_invoke_handler<'info>(
             verify_liquidation_step_is_allowed(&liquidation, end_value)
         }
         AdapterResult::PriceChange(_) => Ok(()),
+        AdapterResult::WeightedPriceChange(_) => Ok(()),
         AdapterResult::PriorBalanceChange(_) => Ok(()),
     }
 }
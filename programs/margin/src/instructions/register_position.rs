@@ -80,6 +80,7 @@ pub fn register_position_handler(ctx: Context<RegisterPosition>) -> Result<()> {
         kind,
         metadata.collateral_weight,
         metadata.collateral_max_staleness,
+        metadata.conservative_valuation,
     )?;
 
     Ok(())
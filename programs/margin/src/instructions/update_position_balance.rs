@@ -16,8 +16,8 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use anchor_lang::prelude::*;
-use anchor_spl::token::TokenAccount;
 
+use crate::util::read_token_account;
 use crate::MarginAccount;
 
 #[derive(Accounts)]
@@ -27,16 +27,17 @@ pub struct UpdatePositionBalance<'info> {
     pub margin_account: AccountLoader<'info, MarginAccount>,
 
     /// The token account to update the balance for
-    pub token_account: Account<'info, TokenAccount>,
+    /// CHECK: may belong to either token program, validated by the handler
+    pub token_account: AccountInfo<'info>,
 }
 
 pub fn update_position_balance_handler(ctx: Context<UpdatePositionBalance>) -> Result<()> {
     let mut margin_account = ctx.accounts.margin_account.load_mut()?;
-    let token_account = &ctx.accounts.token_account;
+    let token_account = read_token_account(&ctx.accounts.token_account)?;
 
     margin_account.set_position_balance(
         &token_account.mint,
-        &token_account.key(),
+        ctx.accounts.token_account.key,
         token_account.amount,
     )?;
 
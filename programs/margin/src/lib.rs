@@ -29,7 +29,7 @@ pub(crate) mod util;
 use instructions::*;
 pub use state::*;
 
-pub use adapter::{AdapterResult, CompactAccountMeta, PriceChangeInfo};
+pub use adapter::{AdapterResult, CompactAccountMeta, PriceChangeInfo, WeightedPriceChangeInfo};
 
 /// The minimum collateral ratio that a margin account must maintain before
 /// being subject to liquidation
@@ -101,6 +101,15 @@ pub mod jet_margin {
         update_position_balance_handler(ctx)
     }
 
+    /// Update a position stored in the margin account with the latest
+    /// configuration from its token metadata.
+    ///
+    /// This is permissionless, so that changes to the metadata can be applied
+    /// to positions that were registered before the change.
+    pub fn refresh_position_metadata(ctx: Context<RefreshPositionMetadata>) -> Result<()> {
+        refresh_position_metadata_handler(ctx)
+    }
+
     /// Close out a position, freeing up space in the account.
     pub fn close_position(ctx: Context<ClosePosition>) -> Result<()> {
         close_position_handler(ctx)
@@ -125,6 +134,10 @@ pub mod jet_margin {
     /// Perform an action by invoking other programs, allowing them only to
     /// refresh the state of the margin account to be consistent with the actual
     /// underlying prices or positions, but not permitting new position changes.
+    ///
+    /// The one exception is reducing the balance of a claim, so that anyone may
+    /// repay the debts of a margin account. Every position changed by the
+    /// invoked program must be reported in its result.
     pub fn accounting_invoke<'info>(
         ctx: Context<'_, '_, '_, 'info, AccountingInvoke<'info>>,
         account_metas: Vec<CompactAccountMeta>,
@@ -190,6 +203,10 @@ pub enum ErrorCode {
     #[msg("attempting to use un-owned position")]
     PositionNotOwned,
 
+    /// 141016 - A position is recorded with a kind that isn't known
+    #[msg("the position kind is not valid")]
+    InvalidPositionKind,
+
     /// 141020 - The adapter providing a price value is incorrect for an asset
     #[msg("wrong adapter to provide the price")]
     InvalidPriceAdapter = 135_020,
@@ -240,6 +257,10 @@ pub enum ErrorCode {
     /// 141043
     #[msg("increased the c-ratio too high during liquidation")]
     LiquidationTooHealthy,
+
+    /// 141044
+    #[msg("the adapter is not allowed to be invoked by the liquidator")]
+    UnauthorizedLiquidationAdapter,
 }
 
 pub fn write_adapter_result(result: &AdapterResult) -> Result<()> {
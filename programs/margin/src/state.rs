@@ -25,6 +25,7 @@ use jet_proto_math::Number128;
 use jet_proto_proc_macros::assert_size;
 
 const POS_PRICE_VALID: u8 = 1;
+const POS_PRICE_CONSERVATIVE: u8 = 1;
 
 #[account(zero_copy)]
 #[repr(C)]
@@ -130,6 +131,34 @@ impl MarginAccount {
             .filter(|p| p.address != Pubkey::default())
     }
 
+    /// Get the balance of each of the account's positions, by the position's token
+    pub fn position_balances(&self) -> Vec<(Pubkey, u64)> {
+        self.positions().map(|p| (p.token, p.balance)).collect()
+    }
+
+    /// Check whether the only changes to the positions since the `previous` balances
+    /// are increases in positions that aren't claims, which can't make the account
+    /// any less healthy
+    pub fn only_added_collateral(&self, previous: &[(Pubkey, u64)]) -> Result<bool> {
+        for position in self.positions() {
+            let before = previous
+                .iter()
+                .find(|(token, _)| *token == position.token)
+                .map(|(_, balance)| *balance)
+                .unwrap_or_default();
+
+            if position.balance < before {
+                return Ok(false);
+            }
+
+            if position.balance > before && position.kind()? == PositionKind::Claim {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
     /// Register the space for a new position into this account
     #[allow(clippy::too_many_arguments)]
     pub fn register_position(
@@ -141,6 +170,7 @@ impl MarginAccount {
         kind: PositionKind,
         collateral_weight: u16,
         collateral_max_staleness: u64,
+        conservative_valuation: bool,
     ) -> Result<()> {
         let free_position = self.position_list_mut().add(token)?;
 
@@ -151,6 +181,26 @@ impl MarginAccount {
         free_position.balance = 0;
         free_position.collateral_weight = collateral_weight;
         free_position.collateral_max_staleness = collateral_max_staleness;
+        free_position.conservative_valuation = conservative_valuation as u8;
+
+        Ok(())
+    }
+
+    /// Update a registered position with the current configuration from its metadata
+    pub fn refresh_position_metadata(
+        &mut self,
+        mint: &Pubkey,
+        kind: PositionKind,
+        collateral_weight: u16,
+        collateral_max_staleness: u64,
+        conservative_valuation: bool,
+    ) -> Result<()> {
+        let position = self.position_list_mut().get_mut(mint)?;
+
+        position.kind = kind.into_integer();
+        position.collateral_weight = collateral_weight;
+        position.collateral_max_staleness = collateral_max_staleness;
+        position.conservative_valuation = conservative_valuation as u8;
 
         Ok(())
     }
@@ -183,6 +233,11 @@ impl MarginAccount {
         Ok(())
     }
 
+    /// Get the position for a token, if it has been registered
+    pub fn get_position(&self, mint: &Pubkey) -> Option<&AccountPosition> {
+        self.position_list().get(mint).ok()
+    }
+
     /// Change the current price value of a position
     pub fn set_position_price(
         &mut self,
@@ -244,7 +299,7 @@ impl MarginAccount {
         let mut stale_collateral_list = vec![];
 
         for position in self.positions() {
-            let kind = PositionKind::from_integer(position.kind).unwrap();
+            let kind = position.kind()?;
             let stale_reason = {
                 let balance_age = timestamp - position.balance_timestamp;
                 let price_quote_age = timestamp - position.price.timestamp;
@@ -267,7 +322,7 @@ impl MarginAccount {
 
             match (kind, stale_reason) {
                 (PositionKind::NoValue, _) => (),
-                (PositionKind::Claim, None) => claims += position.value(),
+                (PositionKind::Claim, None) => claims += position.claim_value(),
                 (PositionKind::Claim, Some(error)) => return Err(error!(error)),
 
                 (PositionKind::Deposit, None) => fresh_collateral += position.collateral_value(),
@@ -318,8 +373,13 @@ pub struct PriceInfo {
     /// Flag indicating if the price is valid for the position
     pub is_valid: u8,
 
-    #[cfg_attr(any(test, feature = "cli"), serde(skip_serializing))]
-    pub _reserved: [u8; 3],
+    /// Flag indicating if the price was chosen conservatively from the spot price,
+    /// its confidence and its recent average
+    pub is_conservative: u8,
+
+    /// A reduction to the collateral weight of the position, in basis points,
+    /// reported by the adapter along with the price
+    pub collateral_weight_reduction: u16,
 }
 
 impl PriceInfo {
@@ -329,7 +389,15 @@ impl PriceInfo {
             exponent,
             timestamp,
             is_valid: POS_PRICE_VALID,
-            _reserved: [0u8; 3],
+            is_conservative: 0,
+            collateral_weight_reduction: 0,
+        }
+    }
+
+    pub fn new_conservative(exponent: i32, value: i64, timestamp: u64) -> Self {
+        Self {
+            is_conservative: POS_PRICE_CONSERVATIVE,
+            ..Self::new_valid(exponent, value, timestamp)
         }
     }
 
@@ -339,7 +407,16 @@ impl PriceInfo {
             exponent: 0,
             timestamp: 0,
             is_valid: 0,
-            _reserved: [0u8; 3],
+            is_conservative: 0,
+            collateral_weight_reduction: 0,
+        }
+    }
+
+    /// Reduce the collateral weight of the position valued at this price
+    pub fn with_collateral_weight_reduction(self, reduction: u16) -> Self {
+        Self {
+            collateral_weight_reduction: reduction,
+            ..self
         }
     }
 }
@@ -394,7 +471,11 @@ pub struct AccountPosition {
     /// The max staleness for the account balance (seconds)
     pub collateral_max_staleness: u64,
 
-    _reserved: [u8; 24],
+    /// Flag indicating if the position should be valued conservatively, using
+    /// the least favourable of the spot price, its confidence and its recent average
+    pub conservative_valuation: u8,
+
+    _reserved: [u8; 23],
 }
 
 impl AccountPosition {
@@ -409,7 +490,23 @@ impl AccountPosition {
     }
 
     pub fn collateral_value(&self) -> Number128 {
-        Number128::from_bps(self.collateral_weight) * self.value()
+        Number128::from_bps(self.effective_collateral_weight()) * self.value()
+    }
+
+    /// The value of a claim, scaled by its borrow weight. For claims the collateral
+    /// weight is the borrow weight, and weights below 100% are treated as 100%
+    /// so that loans registered without a weight are valued as they are.
+    pub fn claim_value(&self) -> Number128 {
+        let borrow_weight = std::cmp::max(self.collateral_weight, 10_000);
+
+        Number128::from_bps(borrow_weight) * self.value()
+    }
+
+    /// The collateral weight after applying any reduction reported with the price
+    pub fn effective_collateral_weight(&self) -> u16 {
+        let reduction = std::cmp::min(self.price.collateral_weight_reduction, 10_000) as u32;
+
+        (self.collateral_weight as u32 * (10_000 - reduction) / 10_000) as u16
     }
 
     /// Update the balance for this position
@@ -419,6 +516,16 @@ impl AccountPosition {
         self.calculate_value();
     }
 
+    /// The kind of balance this position contains
+    pub fn kind(&self) -> Result<PositionKind> {
+        PositionKind::from_integer(self.kind).ok_or_else(|| error!(ErrorCode::InvalidPositionKind))
+    }
+
+    /// Check if the position is configured to be valued conservatively
+    pub fn is_conservative(&self) -> bool {
+        self.conservative_valuation != 0
+    }
+
     /// Update the price for this position
     fn set_price(&mut self, adapter: &Pubkey, price: &PriceInfo) -> Result<()> {
         if self.adapter != *adapter {
@@ -445,7 +552,8 @@ impl std::fmt::Debug for AccountPosition {
             .field("kind", &self.kind)
             .field("exponent", &self.exponent)
             .field("collateral_weight", &self.collateral_weight)
-            .field("collateral_max_staleness", &self.collateral_max_staleness);
+            .field("collateral_max_staleness", &self.collateral_max_staleness)
+            .field("conservative_valuation", &self.conservative_valuation);
 
         acc.finish()
     }
@@ -457,7 +565,7 @@ impl Serialize for AccountPosition {
     where
         S: Serializer,
     {
-        let mut s = serializer.serialize_struct("AccountPosition", 11)?;
+        let mut s = serializer.serialize_struct("AccountPosition", 12)?;
         s.serialize_field("address", &self.address.to_string())?;
         s.serialize_field("token", &self.token.to_string())?;
         s.serialize_field("adapter", &self.adapter.to_string())?;
@@ -469,6 +577,7 @@ impl Serialize for AccountPosition {
         s.serialize_field("exponent", &self.exponent)?;
         s.serialize_field("collateralWeight", &self.collateral_weight)?;
         s.serialize_field("collateralMaxStaleness", &self.collateral_max_staleness)?;
+        s.serialize_field("conservativeValuation", &self.conservative_valuation)?;
         s.end()
     }
 }
@@ -677,9 +786,9 @@ mod tests {
         // use a non-default pubkey
         let key = crate::id();
 
-        acc.register_position(key, 2, key, key, PositionKind::NoValue, 5000, 1000)
+        acc.register_position(key, 2, key, key, PositionKind::NoValue, 5000, 1000, false)
             .unwrap();
-        let position = "AccountPosition { token: JPMRGNgRk3w2pzBM1RLNBnpGxQYsFQ3yXKpuk4tTXVZ, address: JPMRGNgRk3w2pzBM1RLNBnpGxQYsFQ3yXKpuk4tTXVZ, adapter: JPMRGNgRk3w2pzBM1RLNBnpGxQYsFQ3yXKpuk4tTXVZ, value: \"0.0\", balance: 0, balance_timestamp: 0, price: PriceInfo { value: 0, timestamp: 0, exponent: 0, is_valid: 0, _reserved: [0, 0, 0] }, kind: 0, exponent: -2, collateral_weight: 5000, collateral_max_staleness: 1000 }";
+        let position = "AccountPosition { token: JPMRGNgRk3w2pzBM1RLNBnpGxQYsFQ3yXKpuk4tTXVZ, address: JPMRGNgRk3w2pzBM1RLNBnpGxQYsFQ3yXKpuk4tTXVZ, adapter: JPMRGNgRk3w2pzBM1RLNBnpGxQYsFQ3yXKpuk4tTXVZ, value: \"0.0\", balance: 0, balance_timestamp: 0, price: PriceInfo { value: 0, timestamp: 0, exponent: 0, is_valid: 0, is_conservative: 0, collateral_weight_reduction: 0 }, kind: 0, exponent: -2, collateral_weight: 5000, collateral_max_staleness: 1000, conservative_valuation: 0 }";
         let output = output.replace("positions: []", &format!("positions: [{}]", position));
         assert_eq!(&output, &format!("{:?}", acc));
     }
@@ -734,7 +843,8 @@ mod tests {
             exponent: i16::default(),
             collateral_weight: u16::default(),
             collateral_max_staleness: u64::default(),
-            _reserved: [0; 24],
+            conservative_valuation: u8::default(),
+            _reserved: [0; 23],
         };
 
         assert_ser_tokens(
@@ -742,7 +852,7 @@ mod tests {
             &[
                 Token::Struct {
                     name: "AccountPosition",
-                    len: 11,
+                    len: 12,
                 },
                 Token::Str("address"),
                 Token::Str("11111111111111111111111111111111"),
@@ -759,7 +869,7 @@ mod tests {
                 Token::Str("price"),
                 Token::Struct {
                     name: "PriceInfo",
-                    len: 4,
+                    len: 6,
                 },
                 Token::Str("value"),
                 Token::I64(0),
@@ -769,6 +879,10 @@ mod tests {
                 Token::I32(0),
                 Token::Str("isValid"),
                 Token::U8(0),
+                Token::Str("isConservative"),
+                Token::U8(0),
+                Token::Str("collateralWeightReduction"),
+                Token::U16(0),
                 Token::StructEnd,
                 Token::Str("kind"),
                 Token::U32(0),
@@ -778,6 +892,8 @@ mod tests {
                 Token::U16(0),
                 Token::Str("collateralMaxStaleness"),
                 Token::U64(0),
+                Token::Str("conservativeValuation"),
+                Token::U8(0),
                 Token::StructEnd,
             ],
         )
@@ -806,15 +922,42 @@ mod tests {
         let (token_b, address_b) = create_position_input(&margin_address);
 
         margin_account
-            .register_position(token_a, 6, address_a, adapter, PositionKind::Deposit, 0, 0)
+            .register_position(
+                token_a,
+                6,
+                address_a,
+                adapter,
+                PositionKind::Deposit,
+                0,
+                0,
+                false,
+            )
             .unwrap();
 
         margin_account
-            .register_position(token_b, 6, address_b, adapter, PositionKind::Claim, 0, 0)
+            .register_position(
+                token_b,
+                6,
+                address_b,
+                adapter,
+                PositionKind::Claim,
+                0,
+                0,
+                false,
+            )
             .unwrap();
 
         margin_account
-            .register_position(token_c, 6, address_c, adapter, PositionKind::Deposit, 0, 0)
+            .register_position(
+                token_c,
+                6,
+                address_c,
+                adapter,
+                PositionKind::Deposit,
+                0,
+                0,
+                false,
+            )
             .unwrap();
 
         // Set and unset a position's balance
@@ -844,6 +987,7 @@ mod tests {
                 PositionKind::NoValue,
                 0,
                 100,
+                false,
             )
             .unwrap();
         assert_eq!(margin_account.positions().count(), 2);
@@ -857,6 +1001,7 @@ mod tests {
                 PositionKind::NoValue,
                 0,
                 100,
+                false,
             )
             .unwrap();
         assert_eq!(margin_account.positions().count(), 3);
@@ -880,4 +1025,173 @@ mod tests {
         assert_eq!(margin_account.positions().count(), 0);
         assert_eq!(margin_account.positions, [0; 7432]);
     }
+
+    #[test]
+    fn test_only_added_collateral() {
+        let margin_address = Pubkey::new_unique();
+        let adapter = Pubkey::new_unique();
+        let mut margin_account = MarginAccount {
+            version: 1,
+            bump_seed: [0],
+            user_seed: [0; 2],
+            reserved0: [0; 4],
+            owner: Pubkey::new_unique(),
+            liquidation: Pubkey::default(),
+            liquidator: Pubkey::default(),
+            positions: [0; 7432],
+        };
+
+        let (deposit_token, deposit_address) = create_position_input(&margin_address);
+        let (claim_token, claim_address) = create_position_input(&margin_address);
+
+        margin_account
+            .register_position(
+                deposit_token,
+                6,
+                deposit_address,
+                adapter,
+                PositionKind::Deposit,
+                10_000,
+                0,
+                false,
+            )
+            .unwrap();
+        margin_account
+            .register_position(
+                claim_token,
+                6,
+                claim_address,
+                adapter,
+                PositionKind::Claim,
+                10_000,
+                0,
+                false,
+            )
+            .unwrap();
+        margin_account
+            .set_position_balance(&deposit_token, &deposit_address, 100)
+            .unwrap();
+        margin_account
+            .set_position_balance(&claim_token, &claim_address, 100)
+            .unwrap();
+
+        let previous = margin_account.position_balances();
+        assert!(margin_account.only_added_collateral(&previous).unwrap());
+
+        // depositing more is fine
+        margin_account
+            .set_position_balance(&deposit_token, &deposit_address, 150)
+            .unwrap();
+        assert!(margin_account.only_added_collateral(&previous).unwrap());
+
+        // but not when it comes with more debt
+        margin_account
+            .set_position_balance(&claim_token, &claim_address, 101)
+            .unwrap();
+        assert!(!margin_account.only_added_collateral(&previous).unwrap());
+
+        // or when anything is taken out
+        margin_account
+            .set_position_balance(&claim_token, &claim_address, 100)
+            .unwrap();
+        margin_account
+            .set_position_balance(&deposit_token, &deposit_address, 99)
+            .unwrap();
+        assert!(!margin_account.only_added_collateral(&previous).unwrap());
+    }
+
+    #[test]
+    fn test_collateral_weight_reduction() {
+        let mut position = AccountPosition {
+            balance: 100,
+            collateral_weight: 8_000,
+            price: PriceInfo::new_valid(0, 1, 0),
+            ..Default::default()
+        };
+        position.calculate_value();
+
+        assert_eq!(position.effective_collateral_weight(), 8_000);
+        assert_eq!(
+            position.collateral_value(),
+            Number128::from_bps(8_000) * position.value()
+        );
+
+        position.price = position.price.with_collateral_weight_reduction(2_500);
+        assert_eq!(position.effective_collateral_weight(), 6_000);
+        assert_eq!(
+            position.collateral_value(),
+            Number128::from_bps(6_000) * position.value()
+        );
+
+        // the weight can't be reduced below zero
+        position.price = position.price.with_collateral_weight_reduction(20_000);
+        assert_eq!(position.effective_collateral_weight(), 0);
+    }
+
+    #[test]
+    fn test_claim_borrow_weight() {
+        let mut position = AccountPosition {
+            balance: 100,
+            kind: PositionKind::Claim.into_integer(),
+            price: PriceInfo::new_valid(0, 1, 0),
+            ..Default::default()
+        };
+        position.calculate_value();
+
+        // claims registered without a borrow weight keep their value
+        assert_eq!(position.claim_value(), position.value());
+
+        position.collateral_weight = 5_000;
+        assert_eq!(position.claim_value(), position.value());
+
+        position.collateral_weight = 12_500;
+        assert_eq!(
+            position.claim_value(),
+            Number128::from_bps(12_500) * position.value()
+        );
+    }
+
+    #[test]
+    fn test_refresh_position_metadata() {
+        let mut margin_account = MarginAccount {
+            version: 1,
+            bump_seed: [0],
+            user_seed: [0; 2],
+            reserved0: [0; 4],
+            owner: Pubkey::default(),
+            liquidation: Pubkey::default(),
+            liquidator: Pubkey::default(),
+            positions: [0; 7432],
+        };
+        let token = Pubkey::new_unique();
+        let address = Pubkey::new_unique();
+
+        margin_account
+            .register_position(
+                token,
+                6,
+                address,
+                Pubkey::new_unique(),
+                PositionKind::Claim,
+                0,
+                0,
+                false,
+            )
+            .unwrap();
+
+        margin_account
+            .refresh_position_metadata(&token, PositionKind::Claim, 12_000, 60, true)
+            .unwrap();
+
+        let position = margin_account.get_position(&token).unwrap();
+        assert_eq!(position.kind().unwrap(), PositionKind::Claim);
+        assert_eq!(position.collateral_weight, 12_000);
+        assert_eq!(position.collateral_max_staleness, 60);
+        assert!(position.is_conservative());
+
+        // a position has to be registered before it can be refreshed
+        assert!(margin_account
+            .refresh_position_metadata(&Pubkey::new_unique(), PositionKind::Claim, 0, 0, false)
+            .is_err());
+    }
 }
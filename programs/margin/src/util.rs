@@ -17,7 +17,9 @@
 
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use anchor_lang::prelude::{Clock, SolanaSysvar};
+use anchor_lang::prelude::{AccountInfo, Clock, Result, SolanaSysvar};
+use spl_token_2022::extension::StateWithExtensions;
+use spl_token_2022::state::Account as TokenAccount;
 
 /// Get the current timestamp in seconds since Unix epoch
 ///
@@ -41,3 +43,18 @@ pub fn get_timestamp() -> u64 {
         }
     }
 }
+
+/// Read the state of a token account owned by either the token or token-2022 program
+///
+/// Any extensions on a token-2022 account are ignored, only the base account state
+/// is returned.
+pub fn read_token_account(account: &AccountInfo) -> Result<TokenAccount> {
+    if *account.owner != anchor_spl::token::ID && *account.owner != spl_token_2022::ID {
+        return Err(anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram.into());
+    }
+
+    let data = account.try_borrow_data()?;
+    let state = StateWithExtensions::<TokenAccount>::unpack(&data)?;
+
+    Ok(state.base)
+}
@@ -17,7 +17,8 @@
 
 use anchor_lang::prelude::*;
 use anchor_lang::Discriminator;
-use solana_program::pubkey;
+use solana_program::program::invoke;
+use solana_program::{pubkey, system_instruction};
 
 declare_id!("JPMetawzxw7WyH3qHUVScYHWFBGhjwqDnM2R9qVbRLp");
 
@@ -69,6 +70,42 @@ pub struct SetEntry<'info> {
     pub authority: Account<'info, ControlAuthority>,
 }
 
+#[derive(Accounts)]
+pub struct ResizeEntry<'info> {
+    /// The account containing the metadata to resize
+    /// CHECK:
+    #[account(mut)]
+    pub metadata_account: AccountInfo<'info>,
+
+    /// The authority that must sign to make this change
+    #[cfg_attr(not(feature = "devnet"), account(signer))]
+    pub authority: Account<'info, ControlAuthority>,
+
+    /// The address paying any extra rent for the account, which also
+    /// receives the excess rent when the account shrinks
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveEntry<'info> {
+    /// The account containing the metadata to remove
+    /// CHECK:
+    #[account(mut)]
+    pub metadata_account: AccountInfo<'info>,
+
+    /// The authority that must sign to make this change
+    #[cfg_attr(not(feature = "devnet"), account(signer))]
+    pub authority: Account<'info, ControlAuthority>,
+
+    /// The address receiving the rent for the account
+    /// CHECK:
+    #[account(mut)]
+    pub receiver: AccountInfo<'info>,
+}
+
 #[program]
 mod jet_metadata {
     use super::*;
@@ -83,9 +120,118 @@ mod jet_metadata {
         let mut metadata = ctx.accounts.metadata_account.data.borrow_mut();
 
         let offset: usize = offset as usize;
-        (&mut metadata[offset..offset + data.len()]).copy_from_slice(&data);
+        let end = match offset.checked_add(data.len()) {
+            Some(end) if end <= metadata.len() => end,
+            _ => {
+                msg!(
+                    "cannot write {} bytes at offset {} to an entry of {} bytes",
+                    data.len(),
+                    offset,
+                    metadata.len()
+                );
+                return err!(ErrorCode::WriteOutOfBounds);
+            }
+        };
+
+        (&mut metadata[offset..end]).copy_from_slice(&data);
+        Ok(())
+    }
+
+    pub fn resize_entry(ctx: Context<ResizeEntry>, space: u64) -> Result<()> {
+        let metadata = &ctx.accounts.metadata_account;
+        let payer = &ctx.accounts.payer;
+
+        let space: usize = space as usize;
+        let rent = Rent::get()?.minimum_balance(space);
+        let balance = metadata.lamports();
+
+        if rent > balance {
+            invoke(
+                &system_instruction::transfer(payer.key, metadata.key, rent - balance),
+                &[
+                    payer.to_account_info(),
+                    metadata.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        } else {
+            **payer.to_account_info().lamports.borrow_mut() += balance - rent;
+            **metadata.lamports.borrow_mut() = rent;
+        }
+
+        metadata.realloc(space, true)?;
         Ok(())
     }
+
+    pub fn remove_entry(ctx: Context<RemoveEntry>) -> Result<()> {
+        let metadata = &ctx.accounts.metadata_account;
+        let receiver = &ctx.accounts.receiver;
+
+        **receiver.lamports.borrow_mut() += metadata.lamports();
+        **metadata.lamports.borrow_mut() = 0;
+
+        metadata.data.borrow_mut().fill(0);
+        Ok(())
+    }
+}
+
+/// Implements the account traits for a metadata entry.
+///
+/// Entries are sized to the data written when they were registered, so one
+/// written by an older version of the program can be shorter than the current
+/// layout. Fields are only ever appended to the layouts, and any that are missing
+/// from an entry are read as zeroes, so older entries decode with the newer fields
+/// at their defaults.
+macro_rules! metadata_account {
+    ($name:ident, $discriminator:expr) => {
+        impl anchor_lang::Discriminator for $name {
+            fn discriminator() -> [u8; 8] {
+                $discriminator
+            }
+        }
+
+        impl anchor_lang::Owner for $name {
+            fn owner() -> Pubkey {
+                crate::ID
+            }
+        }
+
+        impl anchor_lang::AccountSerialize for $name {
+            fn try_serialize<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+                writer
+                    .write_all(&Self::discriminator())
+                    .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotSerialize)?;
+                AnchorSerialize::serialize(self, writer)
+                    .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotSerialize)?;
+
+                Ok(())
+            }
+        }
+
+        impl anchor_lang::AccountDeserialize for $name {
+            fn try_deserialize(buf: &mut &[u8]) -> Result<Self> {
+                if buf.len() < 8 {
+                    return err!(anchor_lang::error::ErrorCode::AccountDiscriminatorNotFound);
+                }
+                if buf[..8] != Self::discriminator() {
+                    return err!(anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch);
+                }
+
+                Self::try_deserialize_unchecked(buf)
+            }
+
+            fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+                let mut data = buf[8..].to_vec();
+
+                if data.len() < std::mem::size_of::<Self>() {
+                    data.resize(std::mem::size_of::<Self>(), 0);
+                }
+
+                AnchorDeserialize::deserialize(&mut &data[..])
+                    .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotDeserialize.into())
+            }
+        }
+    };
 }
 
 /// Description of the token's usage
@@ -108,8 +254,7 @@ impl Default for TokenKind {
 }
 
 /// A metadata account referencing information about a position token
-#[account]
-#[derive(Default)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
 pub struct PositionTokenMetadata {
     /// The mint for the position token
     pub position_token_mint: Pubkey,
@@ -124,51 +269,160 @@ pub struct PositionTokenMetadata {
     pub token_kind: TokenKind,
 
     /// The weight of the asset's value relative to other tokens when used as collateral.
+    /// For claims this is the borrow weight, which increases the value of the claim.
     pub collateral_weight: u16,
 
     /// The maximum staleness (seconds) that's acceptable for this token when used as collateral.
     pub collateral_max_staleness: u64,
+
+    /// Whether the position should be valued conservatively, using the least favourable
+    /// of the spot price, its confidence bounds and the recent average price.
+    pub conservative_valuation: bool,
+
+    /// The version of the layout of this metadata. Entries written before
+    /// the version was recorded read as version 0.
+    pub version: u8,
+}
+
+metadata_account!(
+    PositionTokenMetadata,
+    [189, 153, 52, 227, 140, 138, 253, 244]
+);
+
+impl PositionTokenMetadata {
+    pub const VERSION: u8 = 1;
+}
+
+/// The kind of oracle providing the price of a token
+#[derive(AnchorSerialize, AnchorDeserialize, Eq, PartialEq, Clone, Copy, Debug)]
+pub enum OracleSource {
+    /// The price is read from a pyth price account
+    Pyth,
+
+    /// The price is read from a switchboard (v2) aggregator account
+    Switchboard,
+
+    /// The price is fixed, which is useful for pegged tokens in testing
+    Fixed { price: i64, exponent: i32 },
+}
+
+impl Default for OracleSource {
+    fn default() -> OracleSource {
+        Self::Pyth
+    }
 }
 
 /// An account that references information about a token's price oracle
-#[account]
-#[derive(Default)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
 pub struct TokenMetadata {
     /// The address of the mint for the token being referenced
     pub token_mint: Pubkey,
 
     /// The address of the price oracle which contains the price data for
-    /// the associated token.
-    pub pyth_price: Pubkey,
+    /// the associated token. For a switchboard source this is the aggregator.
+    pub price_oracle: Pubkey,
 
     /// The address of the pyth product metadata associated with the price oracle
     pub pyth_product: Pubkey,
+
+    /// The kind of oracle that provides the price of the token
+    pub oracle_source: OracleSource,
+
+    /// The version of the layout of this metadata. Entries written before
+    /// the version was recorded read as version 0.
+    pub version: u8,
+}
+
+metadata_account!(TokenMetadata, [237, 215, 132, 182, 24, 127, 175, 173]);
+
+impl TokenMetadata {
+    pub const VERSION: u8 = 1;
 }
 
 /// An account that references a program that's allowed to be invoked by
 /// proxy via a margin account.
-#[account]
-#[derive(Default)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
 pub struct MarginAdapterMetadata {
     /// The address of the allowed program
     pub adapter_program: Pubkey,
+
+    /// The version of the layout of this metadata. Entries written before
+    /// the version was recorded read as version 0.
+    pub version: u8,
+}
+
+metadata_account!(MarginAdapterMetadata, [15, 128, 163, 173, 34, 83, 67, 248]);
+
+impl MarginAdapterMetadata {
+    pub const VERSION: u8 = 1;
 }
 
 /// An account that references a program that's allowed to be invoked by
 /// proxy via a margin account for liquidation purposes.
-#[account]
-#[derive(Default)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
 pub struct LiquidatorAdapterMetadata {
     /// The address of the allowed program
     pub adapter_program: Pubkey,
+
+    /// The version of the layout of this metadata. Entries written before
+    /// the version was recorded read as version 0.
+    pub version: u8,
+}
+
+metadata_account!(
+    LiquidatorAdapterMetadata,
+    [11, 251, 46, 234, 164, 14, 82, 188]
+);
+
+impl LiquidatorAdapterMetadata {
+    pub const VERSION: u8 = 1;
+
+    /// The seed for the entry, which keeps it separate from the
+    /// `MarginAdapterMetadata` entry for the same program
+    pub const SEED: &'static str = "liquidation";
+}
+
+/// The adapters a liquidator is allowed to invoke during a liquidation
+#[derive(AnchorSerialize, AnchorDeserialize, Eq, PartialEq, Clone, Copy, Debug)]
+pub enum LiquidationAdapterPolicy {
+    /// Programs registered either as a margin adapter or as a liquidation adapter
+    Any,
+
+    /// Only programs registered as a liquidation adapter
+    LiquidationOnly,
+}
+
+impl Default for LiquidationAdapterPolicy {
+    fn default() -> LiquidationAdapterPolicy {
+        Self::Any
+    }
 }
 
 /// An account referencing a liquidator, allowed to use the liquidation
 /// instructions on margin accounts.
-#[account]
-#[derive(Default)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
 pub struct LiquidatorMetadata {
     pub liquidator: Pubkey,
+
+    /// The adapters the liquidator may invoke through a margin account
+    pub adapter_policy: LiquidationAdapterPolicy,
+
+    /// The version of the layout of this metadata. Entries written before
+    /// the version was recorded read as version 0.
+    pub version: u8,
+}
+
+metadata_account!(LiquidatorMetadata, [33, 155, 248, 240, 150, 89, 226, 123]);
+
+impl LiquidatorMetadata {
+    pub const VERSION: u8 = 2;
+}
+
+#[error_code]
+pub enum ErrorCode {
+    /// 135300 - The data written to an entry does not fit in the account
+    #[msg("The data written to an entry does not fit in the account")]
+    WriteOutOfBounds = 135_300,
 }
 
 /// An account representing the Control program's authority
@@ -0,0 +1,90 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use switchboard_v2::{AggregatorAccountData, SwitchboardDecimal};
+
+// Uses the address of the switchboard program, so the aggregators it creates
+// are owned the same way as real ones
+declare_id!("SW1TCH7qEPTdLsDHRgPuMQjbQxKdH2aBStViMFnt64f");
+
+/// The anchor discriminator for the switchboard aggregator account
+const AGGREGATOR_DISCRIMINATOR: [u8; 8] = [217, 230, 65, 101, 201, 162, 27, 125];
+
+#[program]
+pub mod mock_switchboard {
+    use super::*;
+
+    pub fn initialize(ctx: Context<Initialize>, price: i64, expo: i32, conf: u64) -> Result<()> {
+        let mut data = ctx.accounts.aggregator.try_borrow_mut_data()?;
+        data[..8].copy_from_slice(&AGGREGATOR_DISCRIMINATOR);
+
+        let aggregator = load_aggregator(&mut data);
+
+        aggregator.min_oracle_results = 1;
+        aggregator.latest_confirmed_round.num_success = 1;
+        set_result(aggregator, price, -expo as u32, conf);
+
+        Ok(())
+    }
+
+    pub fn set_price(ctx: Context<SetPrice>, price: i64, conf: u64) -> Result<()> {
+        let mut data = ctx.accounts.aggregator.try_borrow_mut_data()?;
+        let aggregator = load_aggregator(&mut data);
+
+        let scale = aggregator.latest_confirmed_round.result.scale;
+        set_result(aggregator, price, scale, conf);
+
+        Ok(())
+    }
+}
+
+fn load_aggregator(data: &mut [u8]) -> &mut AggregatorAccountData {
+    let size = std::mem::size_of::<AggregatorAccountData>();
+    bytemuck::from_bytes_mut(&mut data[8..8 + size])
+}
+
+fn set_result(aggregator: &mut AggregatorAccountData, price: i64, scale: u32, conf: u64) {
+    let clock = Clock::get().unwrap();
+    let round = &mut aggregator.latest_confirmed_round;
+
+    round.result = SwitchboardDecimal {
+        mantissa: price as i128,
+        scale,
+    };
+    round.std_deviation = SwitchboardDecimal {
+        mantissa: conf as i128,
+        scale,
+    };
+
+    round.round_open_slot = clock.slot;
+    round.round_open_timestamp = clock.unix_timestamp;
+}
+
+#[derive(Accounts)]
+pub struct SetPrice<'info> {
+    #[account(mut)]
+    /// CHECK: Only used for testing.
+    pub aggregator: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(mut)]
+    /// CHECK: Only used for testing.
+    pub aggregator: AccountInfo<'info>,
+}
@@ -11,7 +11,7 @@ use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::{Keypair, Signer};
 
 use jet_margin_pool::MarginPoolConfig;
-use jet_metadata::TokenKind;
+use jet_metadata::{LiquidationAdapterPolicy, TokenKind};
 
 use jet_simulation::tokens::TokenManager;
 use jet_simulation::TestRuntime;
@@ -56,7 +56,8 @@ impl MarginTestContext {
             jet_margin,
             jet_metadata,
             jet_margin_pool,
-            jet_margin_swap
+            jet_margin_swap,
+            mock_switchboard
         ]);
         let payer = Keypair::from_bytes(&runtime.payer().to_bytes()).unwrap();
         let rng = MockRng(StepRng::new(0, 1));
@@ -91,7 +92,7 @@ impl MarginTestContext {
         let liquidator = self.create_wallet(sol_amount).await?;
 
         self.margin
-            .set_liquidator_metadata(liquidator.pubkey())
+            .register_liquidator(&liquidator.pubkey(), LiquidationAdapterPolicy::Any)
             .await?;
         Ok(liquidator)
     }
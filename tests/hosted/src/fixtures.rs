@@ -0,0 +1,80 @@
+use anyhow::Error;
+
+use solana_sdk::clock::Clock;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+
+use jet_control::TokenMetadataParams;
+use jet_margin_pool::MarginPoolConfig;
+use jet_margin_sdk::instructions::control::{TokenConfiguration, TokenPriceOracle};
+use jet_metadata::TokenKind;
+use jet_simulation::margin::MarginPoolSetupInfo;
+
+use crate::context::MarginTestContext;
+
+/// Send a single instruction, signed by the payer and any other signers
+pub async fn send(
+    ctx: &MarginTestContext,
+    signers: &[&Keypair],
+    ix: Instruction,
+) -> Result<(), Error> {
+    let tx = ctx.rpc.create_transaction(signers, &[ix]).await?;
+    ctx.rpc.send_and_confirm_transaction(&tx).await?;
+
+    Ok(())
+}
+
+/// Move the runtime's clock forward
+pub fn advance_clock(ctx: &MarginTestContext, seconds: i64) {
+    let mut clock: Clock = ctx.rpc.get_clock().unwrap();
+    clock.unix_timestamp += seconds;
+    ctx.rpc.set_clock(clock);
+}
+
+/// Create a new USDC-like token with a pool and pyth oracle, configured as
+/// full weight collateral
+pub async fn setup_usdc_pool(
+    ctx: &MarginTestContext,
+    config: MarginPoolConfig,
+) -> Result<Pubkey, Error> {
+    let usdc = ctx.tokens.create_token(6, None, None).await?;
+    let usdc_fees = ctx
+        .tokens
+        .create_account(&usdc, &ctx.authority.pubkey())
+        .await?;
+    let usdc_oracle = ctx.tokens.create_oracle(&usdc).await?;
+
+    ctx.margin
+        .create_pool(&MarginPoolSetupInfo {
+            token: usdc,
+            fee_destination: usdc_fees,
+            token_kind: TokenKind::Collateral,
+            collateral_weight: 10_000,
+            config: config.clone(),
+            oracle: usdc_oracle,
+        })
+        .await?;
+
+    ctx.margin
+        .configure_token(
+            &usdc,
+            &TokenConfiguration {
+                oracle: Some(TokenPriceOracle::Pyth {
+                    product: usdc_oracle.product,
+                    price: usdc_oracle.price,
+                }),
+                pool_config: Some(config),
+                metadata: Some(TokenMetadataParams {
+                    token_kind: TokenKind::Collateral,
+                    collateral_weight: 10_000,
+                    collateral_max_staleness: 0,
+                    conservative_valuation: false,
+                }),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    Ok(usdc)
+}
@@ -0,0 +1,623 @@
+use std::sync::Arc;
+
+use anchor_lang::{AccountDeserialize, Discriminator, InstructionData, ToAccountMetas};
+use anyhow::Error;
+
+use jet_control::{LoanMetadataParams, RegistryKind, TokenMetadataParams};
+use jet_margin_sdk::accounts::{
+    list_adapters, list_liquidation_adapters, list_liquidators, list_tokens, MarginPoolAccounts,
+};
+use jet_margin_sdk::instructions::control::{
+    self, get_authority_address, TokenConfiguration, TokenPriceOracle,
+};
+use jet_margin_sdk::instructions::metadata::get_metadata_address;
+use jet_margin_sdk::ix_builder::MarginPoolIxBuilder;
+use jet_simulation::tokens::TokenPrice;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+use solana_sdk::transaction::Transaction;
+
+use hosted_tests::context::{test_context, MarginTestContext};
+use hosted_tests::fixtures::send;
+
+use jet_margin_pool::{MarginPoolConfig, PoolFlags};
+use jet_metadata::{
+    LiquidationAdapterPolicy, LiquidatorMetadata, MarginAdapterMetadata, PositionTokenMetadata,
+    TokenKind,
+};
+use jet_simulation::margin::{MarginClient, MarginPoolSetupInfo, DEFAULT_RISK_TIER};
+use jet_simulation::{assert_program_error_code, create_wallet, TestRuntime};
+use jet_solana_rpc_api::SolanaRpcClient;
+
+const ONE_USDC: u64 = 1_000_000;
+
+const DEFAULT_POOL_CONFIG: MarginPoolConfig = MarginPoolConfig {
+    borrow_rate_0: 10,
+    borrow_rate_1: 20,
+    borrow_rate_2: 30,
+    borrow_rate_3: 40,
+    utilization_rate_1: 10,
+    utilization_rate_2: 20,
+    management_fee_rate: 10,
+    management_fee_collect_threshold: 100,
+    collateral_utilization_threshold: 0,
+    max_collateral_weight_reduction: 0,
+    min_queued_withdrawal: 0,
+    deposit_cap: 0,
+    borrow_cap: 0,
+    flags: PoolFlags::ALLOW_LENDING.bits(),
+};
+
+struct TestEnv {
+    usdc: Pubkey,
+}
+
+async fn setup_environment(ctx: &MarginTestContext) -> Result<TestEnv, Error> {
+    let usdc = ctx.tokens.create_token(6, None, None).await?;
+    let usdc_fees = ctx
+        .tokens
+        .create_account(&usdc, &ctx.authority.pubkey())
+        .await?;
+    let usdc_oracle = ctx.tokens.create_oracle(&usdc).await?;
+
+    ctx.margin
+        .create_pool(&MarginPoolSetupInfo {
+            token: usdc,
+            fee_destination: usdc_fees,
+            token_kind: TokenKind::Collateral,
+            collateral_weight: 10_000,
+            config: DEFAULT_POOL_CONFIG,
+            oracle: usdc_oracle,
+        })
+        .await?;
+
+    ctx.margin
+        .configure_token(
+            &usdc,
+            &TokenConfiguration {
+                oracle: Some(TokenPriceOracle::Pyth {
+                    product: usdc_oracle.product,
+                    price: usdc_oracle.price,
+                }),
+                pool_config: Some(DEFAULT_POOL_CONFIG),
+                metadata: Some(TokenMetadataParams {
+                    token_kind: TokenKind::Collateral,
+                    collateral_weight: 10_000,
+                    collateral_max_staleness: 0,
+                    conservative_valuation: false,
+                }),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    ctx.tokens
+        .set_price(
+            // Set price to 1 USD +- 0.01
+            &usdc,
+            &TokenPrice {
+                exponent: -8,
+                price: 100_000_000,
+                confidence: 1_000_000,
+                twap: 100_000_000,
+            },
+        )
+        .await?;
+
+    Ok(TestEnv { usdc })
+}
+
+/// A deregistered adapter can no longer be invoked through a margin account,
+/// until it is registered again
+#[tokio::test]
+async fn deregistered_adapter_cannot_be_invoked() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let env = setup_environment(ctx).await?;
+
+    let wallet = create_wallet(&ctx.rpc, 10 * LAMPORTS_PER_SOL).await?;
+    let user = ctx.margin.user(&wallet).await?;
+    user.create_account().await?;
+
+    let user_usdc_account = ctx
+        .tokens
+        .create_account_funded(&env.usdc, &wallet.pubkey(), 1_000 * ONE_USDC)
+        .await?;
+
+    ctx.margin.deregister_adapter(&jet_margin_pool::ID).await?;
+
+    // The metadata account is closed, so the margin program rejects the adapter
+    let result = user
+        .deposit(&env.usdc, &user_usdc_account, 500 * ONE_USDC)
+        .await;
+    assert!(result.is_err());
+    assert_eq!(
+        1_000 * ONE_USDC,
+        ctx.tokens.get_balance(&user_usdc_account).await?
+    );
+
+    ctx.margin.register_adapter(&jet_margin_pool::ID).await?;
+
+    user.deposit(&env.usdc, &user_usdc_account, 500 * ONE_USDC)
+        .await?;
+    assert_eq!(
+        500 * ONE_USDC,
+        ctx.tokens.get_balance(&user_usdc_account).await?
+    );
+
+    Ok(())
+}
+
+/// Writes past the end of a metadata entry are rejected with an error
+#[tokio::test]
+async fn metadata_writes_are_bounds_checked() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let metadata_account = get_metadata_address(&jet_margin_swap::ID);
+    let entry_len = 8 + std::mem::size_of::<MarginAdapterMetadata>() as u64;
+
+    for (offset, len) in [(entry_len, 1), (entry_len - 1, 2), (u64::MAX, 1)] {
+        let ix = Instruction {
+            program_id: jet_metadata::ID,
+            data: jet_metadata::instruction::SetEntry {
+                offset,
+                data: vec![0; len],
+            }
+            .data(),
+            accounts: jet_metadata::accounts::SetEntry {
+                metadata_account,
+                authority: get_authority_address(),
+            }
+            .to_account_metas(None),
+        };
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&ctx.payer.pubkey()),
+            &[&ctx.payer],
+            ctx.rpc.get_latest_blockhash().await?,
+        );
+
+        let result = ctx.rpc.send_and_confirm_transaction(&tx).await;
+        assert_program_error_code!(jet_metadata::ErrorCode::WriteOutOfBounds.into(), result);
+    }
+
+    Ok(())
+}
+
+/// The registries list the tokens, adapters, liquidation adapters and
+/// liquidators registered through the control program
+#[tokio::test]
+async fn registries_list_registered_addresses() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let env = setup_environment(ctx).await?;
+
+    let tokens = list_tokens(ctx.rpc.as_ref()).await?;
+    assert!(tokens.iter().any(|token| token.token_mint == env.usdc));
+
+    let adapters = list_adapters(ctx.rpc.as_ref()).await?;
+    assert!(adapters
+        .iter()
+        .any(|adapter| adapter.adapter_program == jet_margin_swap::ID));
+
+    let liquidator = ctx.create_liquidator(1).await?;
+    let liquidators = list_liquidators(ctx.rpc.as_ref()).await?;
+    assert!(liquidators
+        .iter()
+        .any(|metadata| metadata.liquidator == liquidator.pubkey()));
+
+    ctx.margin.revoke_liquidator(&liquidator.pubkey()).await?;
+    let liquidators = list_liquidators(ctx.rpc.as_ref()).await?;
+    assert!(liquidators
+        .iter()
+        .all(|metadata| metadata.liquidator != liquidator.pubkey()));
+
+    ctx.margin
+        .register_liquidation_adapter(&jet_margin_swap::ID)
+        .await?;
+    let liquidation_adapters = list_liquidation_adapters(ctx.rpc.as_ref()).await?;
+    assert!(liquidation_adapters
+        .iter()
+        .any(|adapter| adapter.adapter_program == jet_margin_swap::ID));
+
+    ctx.margin
+        .deregister_liquidation_adapter(&jet_margin_swap::ID)
+        .await?;
+    let liquidation_adapters = list_liquidation_adapters(ctx.rpc.as_ref()).await?;
+    assert!(liquidation_adapters
+        .iter()
+        .all(|adapter| adapter.adapter_program != jet_margin_swap::ID));
+
+    Ok(())
+}
+
+/// Registering a token in a risk tier configures its pool, including its caps,
+/// and the metadata for its deposit notes in the same instruction
+#[tokio::test]
+async fn registered_token_applies_risk_tier() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let token_params = TokenMetadataParams {
+        token_kind: TokenKind::Collateral,
+        collateral_weight: 8_000,
+        collateral_max_staleness: 30,
+        conservative_valuation: true,
+    };
+
+    let pool_config = MarginPoolConfig {
+        deposit_cap: 1_000 * ONE_USDC,
+        borrow_cap: 500 * ONE_USDC,
+        ..DEFAULT_POOL_CONFIG
+    };
+
+    ctx.margin
+        .create_risk_tier("major", pool_config, token_params.clone())
+        .await?;
+
+    let token = ctx.tokens.create_token(6, None, None).await?;
+    ctx.margin.register_token(&token, "major").await?;
+
+    let pool = ctx.margin.get_pool(&token).await?;
+    assert_eq!(DEFAULT_POOL_CONFIG.flags, pool.config.flags);
+    assert_eq!(1_000 * ONE_USDC, pool.config.deposit_cap);
+    assert_eq!(500 * ONE_USDC, pool.config.borrow_cap);
+    assert_eq!(DEFAULT_POOL_CONFIG.borrow_rate_3, pool.config.borrow_rate_3);
+    assert_eq!(
+        DEFAULT_POOL_CONFIG.management_fee_rate,
+        pool.config.management_fee_rate
+    );
+
+    let address = get_metadata_address(&pool.deposit_note_mint);
+    let account = ctx.rpc.get_account(&address).await?.unwrap();
+    let metadata = PositionTokenMetadata::try_deserialize(&mut &account.data[..])?;
+
+    assert_eq!(token_params.token_kind, metadata.token_kind);
+    assert_eq!(token_params.collateral_weight, metadata.collateral_weight);
+    assert_eq!(
+        token_params.collateral_max_staleness,
+        metadata.collateral_max_staleness
+    );
+    assert!(metadata.conservative_valuation);
+
+    // the pool accepts deposits up to its cap
+    let pool_ix = MarginPoolIxBuilder::new(token);
+    let wallet = create_wallet(&ctx.rpc, 10 * LAMPORTS_PER_SOL).await?;
+    let source = ctx
+        .tokens
+        .create_account_funded(&token, &wallet.pubkey(), 1_001 * ONE_USDC)
+        .await?;
+    let notes = ctx
+        .tokens
+        .create_account(&pool.deposit_note_mint, &wallet.pubkey())
+        .await?;
+
+    send(
+        ctx,
+        &[&wallet],
+        pool_ix.deposit(wallet.pubkey(), source, notes, 1_000 * ONE_USDC),
+    )
+    .await?;
+
+    let result = send(
+        ctx,
+        &[&wallet],
+        pool_ix.deposit(wallet.pubkey(), source, notes, ONE_USDC),
+    )
+    .await;
+    assert_program_error_code!(
+        jet_margin_pool::ErrorCode::DepositCapExceeded.into(),
+        result
+    );
+
+    Ok(())
+}
+
+/// Oracles are checked before being used to price a token, so one quoted in
+/// the wrong currency or made of unrelated accounts is rejected
+#[tokio::test]
+async fn misconfigured_oracles_are_rejected() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+
+    let token = ctx.tokens.create_token(6, None, None).await?;
+    let other_token = ctx.tokens.create_token(6, None, None).await?;
+    let eur_oracle = ctx.tokens.create_oracle_with_quote(&token, "EUR").await?;
+    let other_oracle = ctx.tokens.create_oracle(&other_token).await?;
+
+    ctx.margin.register_token(&token, DEFAULT_RISK_TIER).await?;
+
+    for (product, price) in [
+        (eur_oracle.product, eur_oracle.price),
+        (eur_oracle.product, other_oracle.price),
+    ] {
+        let result = ctx
+            .margin
+            .configure_token(
+                &token,
+                &TokenConfiguration {
+                    oracle: Some(TokenPriceOracle::Pyth { product, price }),
+                    ..Default::default()
+                },
+            )
+            .await;
+        assert_program_error_code!(jet_margin_pool::ErrorCode::InvalidOracle.into(), result);
+    }
+
+    Ok(())
+}
+
+/// Metadata entries written before the layouts were versioned are still
+/// read, and can be migrated to the current version
+#[tokio::test]
+async fn legacy_metadata_is_migrated() -> Result<(), anyhow::Error> {
+    let runtime = Arc::new(jet_simulation::create_test_runtime![
+        jet_control,
+        jet_metadata
+    ]);
+    let margin = MarginClient::new(runtime.clone());
+
+    margin.create_authority().await?;
+
+    // the layout of the position metadata before it had a version
+    let position_token_mint = Pubkey::new_unique();
+    let underlying_token_mint = Pubkey::new_unique();
+    let mut legacy_data = PositionTokenMetadata::discriminator().to_vec();
+
+    legacy_data.extend_from_slice(position_token_mint.as_ref());
+    legacy_data.extend_from_slice(underlying_token_mint.as_ref());
+    legacy_data.extend_from_slice(jet_margin_pool::ID.as_ref());
+    legacy_data.push(TokenKind::Collateral as u8);
+    legacy_data.extend_from_slice(&9_000u16.to_le_bytes());
+    legacy_data.extend_from_slice(&30u64.to_le_bytes());
+
+    let address = get_metadata_address(&position_token_mint);
+    runtime.create_account_rent_exempt(address, jet_metadata::ID, legacy_data.clone());
+
+    let account = runtime.get_account(&address).await?.unwrap();
+    let metadata = PositionTokenMetadata::try_deserialize(&mut &account.data[..])?;
+
+    assert_eq!(0, metadata.version);
+    assert_eq!(underlying_token_mint, metadata.underlying_token_mint);
+    assert_eq!(TokenKind::Collateral, metadata.token_kind);
+    assert_eq!(9_000, metadata.collateral_weight);
+    assert_eq!(30, metadata.collateral_max_staleness);
+    assert!(!metadata.conservative_valuation);
+
+    margin.migrate_metadata(&address).await?;
+
+    let account = runtime.get_account(&address).await?.unwrap();
+    let migrated = PositionTokenMetadata::try_deserialize(&mut &account.data[..])?;
+
+    assert!(account.data.len() > legacy_data.len());
+    assert_eq!(PositionTokenMetadata::VERSION, migrated.version);
+    assert_eq!(position_token_mint, migrated.position_token_mint);
+    assert_eq!(underlying_token_mint, migrated.underlying_token_mint);
+    assert_eq!(9_000, migrated.collateral_weight);
+    assert_eq!(30, migrated.collateral_max_staleness);
+
+    // an entry can only be migrated once
+    let result = margin.migrate_metadata(&address).await;
+    assert_program_error_code!(jet_control::ErrorCode::MetadataMigrated.into(), result);
+
+    Ok(())
+}
+
+/// Addresses registered before the registries were created can be added to
+/// them, as long as their metadata still registers them
+#[tokio::test]
+async fn registered_addresses_are_added_to_registries() -> Result<(), anyhow::Error> {
+    let runtime = Arc::new(jet_simulation::create_test_runtime![
+        jet_control,
+        jet_metadata
+    ]);
+    let margin = MarginClient::new(runtime.clone());
+
+    margin.create_authority().await?;
+
+    // entries registered before the registries existed, with the layouts
+    // from before the metadata had a version
+    let adapter = Pubkey::new_unique();
+    let mut adapter_data = MarginAdapterMetadata::discriminator().to_vec();
+    adapter_data.extend_from_slice(adapter.as_ref());
+
+    runtime.create_account_rent_exempt(
+        get_metadata_address(&adapter),
+        jet_metadata::ID,
+        adapter_data,
+    );
+
+    let revoked_liquidator = Pubkey::new_unique();
+    let mut liquidator_data = LiquidatorMetadata::discriminator().to_vec();
+    liquidator_data.extend_from_slice(Pubkey::default().as_ref());
+
+    runtime.create_account_rent_exempt(
+        get_metadata_address(&revoked_liquidator),
+        jet_metadata::ID,
+        liquidator_data,
+    );
+
+    margin
+        .add_registry_entry(RegistryKind::Adapters, &adapter)
+        .await?;
+
+    let adapters = list_adapters(runtime.as_ref()).await?;
+    assert_eq!(1, adapters.len());
+    assert_eq!(adapter, adapters[0].adapter_program);
+
+    // the metadata of a revoked liquidator no longer names it
+    let result = margin
+        .add_registry_entry(RegistryKind::Liquidators, &revoked_liquidator)
+        .await;
+    assert_program_error_code!(jet_control::ErrorCode::InvalidRegistryEntry.into(), result);
+
+    Ok(())
+}
+
+/// Liquidators registered before they had an adapter policy may use any
+/// adapter, until they're registered again with a policy
+#[tokio::test]
+async fn legacy_liquidator_uses_default_policy() -> Result<(), anyhow::Error> {
+    let runtime = Arc::new(jet_simulation::create_test_runtime![
+        jet_control,
+        jet_metadata
+    ]);
+    let margin = MarginClient::new(runtime.clone());
+
+    margin.create_authority().await?;
+
+    let liquidator = Pubkey::new_unique();
+    let address = get_metadata_address(&liquidator);
+    let mut legacy_data = LiquidatorMetadata::discriminator().to_vec();
+    legacy_data.extend_from_slice(liquidator.as_ref());
+
+    runtime.create_account_rent_exempt(address, jet_metadata::ID, legacy_data);
+
+    let account = runtime.get_account(&address).await?.unwrap();
+    let metadata = LiquidatorMetadata::try_deserialize(&mut &account.data[..])?;
+
+    assert_eq!(liquidator, metadata.liquidator);
+    assert_eq!(LiquidationAdapterPolicy::Any, metadata.adapter_policy);
+
+    margin
+        .register_liquidator(&liquidator, LiquidationAdapterPolicy::LiquidationOnly)
+        .await?;
+
+    let account = runtime.get_account(&address).await?.unwrap();
+    let metadata = LiquidatorMetadata::try_deserialize(&mut &account.data[..])?;
+
+    assert_eq!(LiquidatorMetadata::VERSION, metadata.version);
+    assert_eq!(
+        LiquidationAdapterPolicy::LiquidationOnly,
+        metadata.adapter_policy
+    );
+
+    Ok(())
+}
+
+/// Liquidators registered before the metadata had a version can still be
+/// revoked, without their smaller entry being resized
+#[tokio::test]
+async fn legacy_liquidator_is_revoked() -> Result<(), anyhow::Error> {
+    let runtime = Arc::new(jet_simulation::create_test_runtime![
+        jet_control,
+        jet_metadata
+    ]);
+    let margin = MarginClient::new(runtime.clone());
+
+    margin.create_authority().await?;
+
+    let liquidator = Pubkey::new_unique();
+    let address = get_metadata_address(&liquidator);
+    let mut legacy_data = LiquidatorMetadata::discriminator().to_vec();
+    legacy_data.extend_from_slice(liquidator.as_ref());
+
+    runtime.create_account_rent_exempt(address, jet_metadata::ID, legacy_data.clone());
+
+    margin
+        .add_registry_entry(RegistryKind::Liquidators, &liquidator)
+        .await?;
+    assert_eq!(1, list_liquidators(runtime.as_ref()).await?.len());
+
+    margin.revoke_liquidator(&liquidator).await?;
+
+    let account = runtime.get_account(&address).await?.unwrap();
+    let metadata = LiquidatorMetadata::try_deserialize(&mut &account.data[..])?;
+
+    assert_eq!(legacy_data.len(), account.data.len());
+    assert_eq!(Pubkey::default(), metadata.liquidator);
+    assert!(list_liquidators(runtime.as_ref()).await?.is_empty());
+
+    Ok(())
+}
+
+/// The metadata for each kind of loan note is configured separately, and only
+/// the pool's own metadata for those notes can be changed
+#[tokio::test]
+async fn loan_note_metadata_is_configured() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let token = ctx.tokens.create_token(6, None, None).await?;
+
+    ctx.margin.register_token(&token, DEFAULT_RISK_TIER).await?;
+
+    let pool = MarginPoolAccounts::derive_from_token(token);
+    let term_loan_params = LoanMetadataParams {
+        token_kind: TokenKind::Claim,
+        borrow_weight: 12_000,
+        max_staleness: 10,
+    };
+
+    ctx.margin
+        .configure_token(
+            &token,
+            &TokenConfiguration {
+                term_loan_metadata: Some(term_loan_params.clone()),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    let address = get_metadata_address(&pool.term_loan_note_mint);
+    let account = ctx.rpc.get_account(&address).await?.unwrap();
+    let metadata = PositionTokenMetadata::try_deserialize(&mut &account.data[..])?;
+
+    assert_eq!(pool.term_loan_note_mint, metadata.position_token_mint);
+    assert_eq!(TokenKind::Claim, metadata.token_kind);
+    assert_eq!(term_loan_params.borrow_weight, metadata.collateral_weight);
+    assert_eq!(
+        term_loan_params.max_staleness,
+        metadata.collateral_max_staleness
+    );
+
+    // loan notes can only be claims, weighted at 100% or more
+    for params in [
+        LoanMetadataParams {
+            token_kind: TokenKind::Collateral,
+            ..term_loan_params.clone()
+        },
+        LoanMetadataParams {
+            borrow_weight: 9_999,
+            ..term_loan_params.clone()
+        },
+    ] {
+        let result = ctx
+            .margin
+            .configure_token(
+                &token,
+                &TokenConfiguration {
+                    loan_metadata: Some(params),
+                    ..Default::default()
+                },
+            )
+            .await;
+        assert_program_error_code!(jet_control::ErrorCode::InvalidLoanMetadata.into(), result);
+    }
+
+    // the deposit note metadata can't be passed off as the loan note metadata
+    let mut ix = control::configure_token(
+        &pool,
+        &ctx.payer.pubkey(),
+        &TokenConfiguration {
+            loan_metadata: Some(term_loan_params),
+            ..Default::default()
+        },
+    );
+    let loan_metadata = get_metadata_address(&pool.loan_note_mint);
+
+    for meta in ix.accounts.iter_mut() {
+        if meta.pubkey == loan_metadata {
+            meta.pubkey = get_metadata_address(&pool.deposit_note_mint);
+        }
+    }
+
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&ctx.payer.pubkey()),
+        &[&ctx.payer],
+        ctx.rpc.get_latest_blockhash().await?,
+    );
+
+    let result = ctx.rpc.send_and_confirm_transaction(&tx).await;
+    assert_program_error_code!(
+        jet_control::ErrorCode::InvalidPositionMetadata.into(),
+        result
+    );
+
+    Ok(())
+}
@@ -0,0 +1,320 @@
+use std::sync::Arc;
+
+use anchor_lang::Discriminator;
+use anyhow::Error;
+
+use jet_margin_sdk::accounts::MarginPoolAccounts;
+use jet_margin_sdk::instructions::control::{self, get_governance_address, TokenConfiguration};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+
+use hosted_tests::context::MarginTestContext;
+use hosted_tests::fixtures::advance_clock;
+
+use jet_control::{Authority, ErrorCode};
+use jet_margin_pool::{MarginPool, MarginPoolConfig, PoolFlags};
+use jet_metadata::TokenKind;
+use jet_simulation::assert_program_error_code;
+use jet_simulation::margin::{MarginClient, MarginPoolSetupInfo, DEFAULT_RISK_TIER};
+use jet_simulation::TestRuntime;
+use jet_solana_rpc_api::SolanaRpcClient;
+
+const TIMELOCK: i64 = 24 * 60 * 60;
+
+const DEFAULT_POOL_CONFIG: MarginPoolConfig = MarginPoolConfig {
+    borrow_rate_0: 10,
+    borrow_rate_1: 20,
+    borrow_rate_2: 30,
+    borrow_rate_3: 40,
+    utilization_rate_1: 10,
+    utilization_rate_2: 20,
+    management_fee_rate: 10,
+    management_fee_collect_threshold: 100,
+    collateral_utilization_threshold: 0,
+    max_collateral_weight_reduction: 0,
+    min_queued_withdrawal: 0,
+    deposit_cap: 0,
+    borrow_cap: 0,
+    flags: PoolFlags::ALLOW_LENDING.bits(),
+};
+
+async fn create_pool(ctx: &MarginTestContext) -> Result<MarginPoolAccounts, Error> {
+    let usdc = ctx.tokens.create_token(6, None, None).await?;
+    let usdc_fees = ctx
+        .tokens
+        .create_account(&usdc, &ctx.authority.pubkey())
+        .await?;
+    let usdc_oracle = ctx.tokens.create_oracle(&usdc).await?;
+
+    ctx.margin
+        .create_pool(&MarginPoolSetupInfo {
+            token: usdc,
+            fee_destination: usdc_fees,
+            token_kind: TokenKind::Collateral,
+            collateral_weight: 10_000,
+            config: DEFAULT_POOL_CONFIG,
+            oracle: usdc_oracle,
+        })
+        .await?;
+
+    Ok(MarginPoolAccounts::derive_from_token(usdc))
+}
+
+/// A runtime of its own for each test, since the governance takes over the
+/// root authority
+struct GovernanceTest {
+    ctx: MarginTestContext,
+    pool: MarginPoolAccounts,
+
+    /// The council members, with 2 of the 3 needed to approve a proposal
+    council: Vec<Keypair>,
+}
+
+/// Create a pool, and then the governance that takes over the root authority
+async fn setup() -> Result<GovernanceTest, Error> {
+    let ctx = MarginTestContext::new().await?;
+    let pool = create_pool(&ctx).await?;
+
+    let council = vec![Keypair::new(), Keypair::new(), Keypair::new()];
+    let keys = council.iter().map(|k| k.pubkey()).collect::<Vec<_>>();
+
+    ctx.margin.create_governance(&keys, 2, TIMELOCK).await?;
+
+    Ok(GovernanceTest { ctx, pool, council })
+}
+
+async fn get_pool(ctx: &MarginTestContext, address: &Pubkey) -> Result<MarginPool, Error> {
+    Ok(ctx
+        .margin
+        .find_pools()
+        .await?
+        .into_iter()
+        .find(|pool| pool.address == *address)
+        .unwrap())
+}
+
+/// A proposal needs enough council approvals, and then has to wait for the
+/// timelock before it can be executed
+#[tokio::test]
+async fn proposal_requires_approval_and_timelock() -> Result<(), anyhow::Error> {
+    let test = setup().await?;
+    let (ctx, pool, council) = (&test.ctx, &test.pool, &test.council);
+
+    let instruction = control::configure_token(
+        pool,
+        &get_governance_address(),
+        &TokenConfiguration {
+            pool_config: Some(MarginPoolConfig {
+                management_fee_rate: 25,
+                ..DEFAULT_POOL_CONFIG
+            }),
+            ..Default::default()
+        },
+    );
+
+    let proposal_id = ctx
+        .margin
+        .create_proposal(&council[0], &instruction)
+        .await?;
+
+    // The proposer's approval alone is not enough
+    let result = ctx.margin.execute_proposal(proposal_id).await;
+    assert_program_error_code!(ErrorCode::ProposalNotApproved.into(), result);
+
+    let result = ctx.margin.approve_proposal(&council[0], proposal_id).await;
+    assert_program_error_code!(ErrorCode::AlreadyApproved.into(), result);
+
+    let result = ctx
+        .margin
+        .approve_proposal(&Keypair::new(), proposal_id)
+        .await;
+    assert_program_error_code!(ErrorCode::Unauthorized.into(), result);
+
+    ctx.margin
+        .approve_proposal(&council[1], proposal_id)
+        .await?;
+
+    // Approved, but the timelock has not passed yet
+    let result = ctx.margin.execute_proposal(proposal_id).await;
+    assert_program_error_code!(ErrorCode::TimelockActive.into(), result);
+    assert_eq!(
+        10,
+        get_pool(ctx, &pool.address)
+            .await?
+            .config
+            .management_fee_rate
+    );
+
+    advance_clock(ctx, TIMELOCK);
+    ctx.margin.execute_proposal(proposal_id).await?;
+
+    assert_eq!(
+        25,
+        get_pool(ctx, &pool.address)
+            .await?
+            .config
+            .management_fee_rate
+    );
+
+    // Each proposal can only be executed once
+    let result = ctx.margin.execute_proposal(proposal_id).await;
+    assert_program_error_code!(ErrorCode::ProposalExecuted.into(), result);
+
+    Ok(())
+}
+
+/// Pausing a pool takes effect as soon as it is approved, while resuming it
+/// still has to wait for the timelock
+#[tokio::test]
+async fn emergency_pause_skips_timelock() -> Result<(), anyhow::Error> {
+    let test = setup().await?;
+    let (ctx, pool, council) = (&test.ctx, &test.pool, &test.council);
+
+    let pause = control::pause_pool(pool, &get_governance_address(), PoolFlags::PAUSE_ALL, true);
+    let proposal_id = ctx.margin.create_proposal(&council[1], &pause).await?;
+
+    ctx.margin
+        .approve_proposal(&council[2], proposal_id)
+        .await?;
+    ctx.margin.execute_proposal(proposal_id).await?;
+
+    let flags = PoolFlags::from_bits_truncate(get_pool(ctx, &pool.address).await?.config.flags);
+    assert!(flags.contains(PoolFlags::PAUSE_ALL));
+
+    let resume = control::pause_pool(pool, &get_governance_address(), PoolFlags::PAUSE_ALL, false);
+    let proposal_id = ctx.margin.create_proposal(&council[1], &resume).await?;
+
+    ctx.margin
+        .approve_proposal(&council[2], proposal_id)
+        .await?;
+    let result = ctx.margin.execute_proposal(proposal_id).await;
+    assert_program_error_code!(ErrorCode::TimelockActive.into(), result);
+
+    Ok(())
+}
+
+/// Creating the governance hands it the root authority, so privileged changes
+/// can only be made through its proposals, including handing the root to
+/// another key
+#[tokio::test]
+async fn governance_takes_over_root_authority() -> Result<(), anyhow::Error> {
+    let test = setup().await?;
+    let (ctx, pool, council) = (&test.ctx, &test.pool, &test.council);
+    let governance = get_governance_address();
+
+    let authority = ctx.margin.get_authority().await?;
+    assert_eq!(governance, authority.root);
+
+    // The previous root can no longer make changes directly
+    let result = ctx
+        .margin
+        .configure_token(
+            &pool.token_mint,
+            &TokenConfiguration {
+                pool_config: Some(DEFAULT_POOL_CONFIG),
+                ..Default::default()
+            },
+        )
+        .await;
+    assert_program_error_code!(ErrorCode::Unauthorized.into(), result);
+
+    let result = ctx
+        .margin
+        .propose_root_authority(&Keypair::new().pubkey())
+        .await;
+    assert_program_error_code!(ErrorCode::Unauthorized.into(), result);
+
+    // The governance can propose a new root, which then has to accept
+    let new_root = Keypair::new();
+    let propose = control::propose_root_authority(&governance, &new_root.pubkey());
+    let proposal_id = ctx.margin.create_proposal(&council[0], &propose).await?;
+
+    ctx.margin
+        .approve_proposal(&council[2], proposal_id)
+        .await?;
+    advance_clock(ctx, TIMELOCK);
+    ctx.margin.execute_proposal(proposal_id).await?;
+
+    // Only the proposed address can accept
+    let result = ctx.margin.accept_root_authority(&Keypair::new()).await;
+    assert_program_error_code!(ErrorCode::Unauthorized.into(), result);
+
+    ctx.margin.accept_root_authority(&new_root).await?;
+
+    let authority = ctx.margin.get_authority().await?;
+    assert_eq!(new_root.pubkey(), authority.root);
+    assert_eq!(Pubkey::default(), authority.pending_root);
+
+    Ok(())
+}
+
+/// Tokens can only be registered by the root authority, so once the governance
+/// holds it they're registered through a proposal, with the rent paid by the
+/// account executing it
+#[tokio::test]
+async fn governance_registers_tokens() -> Result<(), anyhow::Error> {
+    let test = setup().await?;
+    let (ctx, council) = (&test.ctx, &test.council);
+
+    let token = ctx.tokens.create_token(6, None, None).await?;
+    let result = ctx.margin.register_token(&token, DEFAULT_RISK_TIER).await;
+    assert_program_error_code!(ErrorCode::Unauthorized.into(), result);
+
+    let pool = MarginPoolAccounts::derive_from_token(token);
+    let register = control::register_token(
+        &pool,
+        &get_governance_address(),
+        &ctx.payer.pubkey(),
+        DEFAULT_RISK_TIER,
+    );
+    let proposal_id = ctx.margin.create_proposal(&council[0], &register).await?;
+
+    ctx.margin
+        .approve_proposal(&council[1], proposal_id)
+        .await?;
+    advance_clock(ctx, TIMELOCK);
+    ctx.margin.execute_proposal(proposal_id).await?;
+
+    assert_eq!(token, get_pool(ctx, &pool.address).await?.token_mint);
+
+    Ok(())
+}
+
+/// An authority account created before the root authority was stored in it
+/// only holds its seed, and is resized to hold the root
+#[tokio::test]
+async fn legacy_authority_is_migrated() -> Result<(), anyhow::Error> {
+    let runtime = Arc::new(jet_simulation::create_test_runtime![jet_control]);
+    let margin = MarginClient::new(runtime.clone());
+
+    let address = control::get_authority_address();
+    let (_, bump) = Pubkey::find_program_address(&[], &jet_control::ID);
+    let mut legacy_data = Authority::discriminator().to_vec();
+
+    legacy_data.push(bump);
+    assert_eq!(9, legacy_data.len());
+
+    runtime.create_account_rent_exempt(address, jet_control::ID, legacy_data);
+
+    margin.migrate_authority().await?;
+
+    let account = runtime.get_account(&address).await?.unwrap();
+    assert_eq!(Authority::SPACE, account.data.len());
+
+    let authority = margin.get_authority().await?;
+    assert_eq!([bump], authority.seed);
+    assert_eq!(runtime.payer().pubkey(), authority.root);
+    assert_eq!(Pubkey::default(), authority.pending_root);
+    assert_eq!(Pubkey::default(), authority.emergency_pauser);
+
+    // The account can only be migrated once
+    let result = margin.migrate_authority().await;
+    assert_program_error_code!(ErrorCode::AuthorityMigrated.into(), result);
+
+    // The migrated root can act as the root authority
+    margin
+        .propose_root_authority(&Keypair::new().pubkey())
+        .await?;
+
+    Ok(())
+}
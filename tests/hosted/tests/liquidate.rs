@@ -1,8 +1,8 @@
 use anyhow::{Error, Result};
 
-use jet_control::TokenMetadataParams;
+use jet_control::{LoanMetadataParams, TokenMetadataParams};
 use jet_margin::ErrorCode;
-use jet_margin_sdk::instructions::control::TokenConfiguration;
+use jet_margin_sdk::instructions::control::{TokenConfiguration, TokenPriceOracle};
 use jet_simulation::tokens::TokenPrice;
 use solana_sdk::native_token::LAMPORTS_PER_SOL;
 use solana_sdk::pubkey::Pubkey;
@@ -11,9 +11,9 @@ use solana_sdk::signature::Signer;
 use hosted_tests::context::{test_context, MarginTestContext};
 
 use jet_margin_pool::{Amount, MarginPoolConfig, PoolFlags};
-use jet_metadata::TokenKind;
+use jet_metadata::{LiquidationAdapterPolicy, TokenKind};
 use jet_simulation::margin::{MarginPoolSetupInfo, MarginUser};
-use jet_simulation::{assert_program_error, create_wallet};
+use jet_simulation::{assert_program_error, assert_program_error_code, create_wallet};
 
 const ONE_USDC: u64 = 1_000_000;
 const ONE_TSOL: u64 = LAMPORTS_PER_SOL;
@@ -27,6 +27,11 @@ const DEFAULT_POOL_CONFIG: MarginPoolConfig = MarginPoolConfig {
     utilization_rate_2: 20,
     management_fee_rate: 10,
     management_fee_collect_threshold: 100,
+    collateral_utilization_threshold: 0,
+    max_collateral_weight_reduction: 0,
+    min_queued_withdrawal: 0,
+    deposit_cap: 0,
+    borrow_cap: 0,
     flags: PoolFlags::ALLOW_LENDING.bits(),
 };
 
@@ -76,13 +81,16 @@ async fn setup_environment(ctx: &MarginTestContext) -> Result<TestEnv, Error> {
         .configure_token(
             &usdc,
             &TokenConfiguration {
-                pyth_price: Some(usdc_oracle.price),
-                pyth_product: Some(usdc_oracle.product),
+                oracle: Some(TokenPriceOracle::Pyth {
+                    product: usdc_oracle.product,
+                    price: usdc_oracle.price,
+                }),
                 pool_config: Some(DEFAULT_POOL_CONFIG),
                 metadata: Some(TokenMetadataParams {
                     token_kind: TokenKind::Collateral,
                     collateral_weight: 10_000,
                     collateral_max_staleness: 0,
+                    conservative_valuation: false,
                 }),
                 ..Default::default()
             },
@@ -93,13 +101,16 @@ async fn setup_environment(ctx: &MarginTestContext) -> Result<TestEnv, Error> {
         .configure_token(
             &tsol,
             &TokenConfiguration {
-                pyth_price: Some(tsol_oracle.price),
-                pyth_product: Some(tsol_oracle.product),
+                oracle: Some(TokenPriceOracle::Pyth {
+                    product: tsol_oracle.product,
+                    price: tsol_oracle.price,
+                }),
                 pool_config: Some(DEFAULT_POOL_CONFIG),
                 metadata: Some(TokenMetadataParams {
                     token_kind: TokenKind::Collateral,
                     collateral_weight: 9_500,
                     collateral_max_staleness: 0,
+                    conservative_valuation: false,
                 }),
                 ..Default::default()
             },
@@ -211,8 +222,12 @@ async fn scenario1() -> Result<Scenario1> {
     user_b.refresh_all_pool_positions().await?;
 
     // Have each user borrow the other's funds
-    user_a.borrow(&env.tsol, 8000 * ONE_TSOL).await?;
-    user_b.borrow(&env.usdc, 3_500_000 * ONE_USDC).await?;
+    user_a
+        .borrow(&env.tsol, Amount::tokens(8000 * ONE_TSOL))
+        .await?;
+    user_b
+        .borrow(&env.usdc, Amount::tokens(3_500_000 * ONE_USDC))
+        .await?;
 
     // User A deposited 5'000'000 USD worth, borrowed 800'000 USD worth
 
@@ -295,6 +310,58 @@ async fn cannot_transact_when_being_liquidated() -> Result<()> {
     Ok(())
 }
 
+/// Adding collateral can only make the account healthier, so it's allowed
+/// without the account having to be healthy
+#[tokio::test]
+async fn unhealthy_account_can_add_collateral() -> Result<()> {
+    let ctx = test_context().await;
+    let scen = scenario1().await?;
+
+    assert!(scen.user_b.verify_healthy().await.is_err());
+
+    let user_b_usdc_account = ctx
+        .tokens
+        .create_account_funded(&scen.usdc, scen.user_b.owner(), 1_000_000 * ONE_USDC)
+        .await?;
+    scen.user_b
+        .deposit(&scen.usdc, &user_b_usdc_account, 1_000_000 * ONE_USDC)
+        .await?;
+
+    // User B now has
+    // Collateral (800'000 * 0.95) + 4'500'000 = 5'260'000
+    // Claim 3'500'000
+    // C ratio = 150%
+    scen.user_b.refresh_all_pool_positions().await?;
+    scen.user_b.verify_healthy().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn can_add_collateral_when_being_liquidated() -> Result<()> {
+    let ctx = test_context().await;
+    let scen = scenario1().await?;
+
+    scen.user_b_liq.liquidate_begin().await?;
+
+    let user_b_usdc_account = ctx
+        .tokens
+        .create_account_funded(&scen.usdc, scen.user_b.owner(), 1_000_000 * ONE_USDC)
+        .await?;
+    scen.user_b
+        .deposit(&scen.usdc, &user_b_usdc_account, 1_000_000 * ONE_USDC)
+        .await?;
+
+    // Taking anything out is still blocked while the liquidation is in progress
+    let result = scen
+        .user_b
+        .withdraw(&scen.usdc, &user_b_usdc_account, Amount::tokens(ONE_USDC))
+        .await;
+    assert_program_error!(ErrorCode::Liquidating, result);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn liquidator_cannot_over_repay() -> Result<()> {
     let scen = scenario1().await?;
@@ -449,7 +516,7 @@ async fn cannot_borrow_too_much_during_liquidation() -> Result<()> {
 
     let result = scen
         .user_b_liq
-        .borrow(&scen.usdc, 5_000_000 * ONE_USDC)
+        .borrow(&scen.usdc, Amount::tokens(5_000_000 * ONE_USDC))
         .await;
     assert_program_error!(ErrorCode::LiquidationUnhealthy, result);
 
@@ -462,7 +529,7 @@ async fn can_borrow_some_during_liquidation() -> Result<()> {
 
     scen.user_b_liq.liquidate_begin().await?;
     scen.user_b_liq
-        .borrow(&scen.usdc, 500_000 * ONE_USDC)
+        .borrow(&scen.usdc, Amount::tokens(500_000 * ONE_USDC))
         .await?;
 
     Ok(())
@@ -484,6 +551,154 @@ async fn owner_cannot_end_liquidation_before_timeout() -> Result<()> {
     Ok(())
 }
 
+/// Revoking the liquidator stops the liquidation, so the owner can end it
+/// without waiting for the timeout
+#[tokio::test]
+async fn revoked_liquidator_cannot_continue_liquidation() -> Result<()> {
+    let ctx = test_context().await;
+    let scen = scenario1().await?;
+
+    scen.user_b_liq.liquidate_begin().await?;
+    ctx.margin.revoke_liquidator(&scen.liquidator).await?;
+
+    let result = scen
+        .user_b_liq
+        .repay(&scen.usdc, Amount::tokens(1_000_000 * ONE_USDC))
+        .await;
+    assert_program_error_code!(
+        anchor_lang::error::ErrorCode::ConstraintHasOne.into(),
+        result
+    );
+
+    scen.user_b.liquidate_end(Some(scen.liquidator)).await?;
+
+    Ok(())
+}
+
+/// A liquidator restricted to liquidation adapters can't repay through the
+/// margin pool, which is only registered as a margin adapter
+#[tokio::test]
+async fn liquidator_restricted_to_liquidation_adapters() -> Result<()> {
+    let ctx = test_context().await;
+    let scen = scenario1().await?;
+
+    ctx.margin
+        .register_liquidator(&scen.liquidator, LiquidationAdapterPolicy::LiquidationOnly)
+        .await?;
+    scen.user_b_liq.liquidate_begin().await?;
+
+    let result = scen
+        .user_b_liq
+        .repay(&scen.usdc, Amount::tokens(1_000_000 * ONE_USDC))
+        .await;
+    assert_program_error!(ErrorCode::UnauthorizedLiquidationAdapter, result);
+
+    // the policy can be relaxed while the liquidation is in progress
+    ctx.margin
+        .register_liquidator(&scen.liquidator, LiquidationAdapterPolicy::Any)
+        .await?;
+    scen.user_b_liq
+        .repay(&scen.usdc, Amount::tokens(1_000_000 * ONE_USDC))
+        .await?;
+    scen.user_b_liq.liquidate_end(None).await?;
+
+    Ok(())
+}
+
+/// A borrow weight configured for a pool's loans applies to existing positions
+/// once they have been refreshed
+#[tokio::test]
+async fn borrow_weight_applies_after_refresh() -> Result<()> {
+    let ctx = test_context().await;
+    let env = setup_environment(ctx).await?;
+
+    let wallet_a = create_wallet(&ctx.rpc, 10 * LAMPORTS_PER_SOL).await?;
+    let wallet_b = create_wallet(&ctx.rpc, 10 * LAMPORTS_PER_SOL).await?;
+    let wallet_liquidator = ctx.create_liquidator(100).await?;
+
+    let user_a = ctx.margin.user(&wallet_a).await?;
+    let user_b = ctx.margin.user(&wallet_b).await?;
+    let user_b_liq = ctx
+        .margin
+        .liquidator(&wallet_liquidator, &wallet_b.pubkey())
+        .await?;
+
+    user_a.create_account().await?;
+    user_b.create_account().await?;
+
+    let user_a_usdc_account = ctx
+        .tokens
+        .create_account_funded(&env.usdc, &wallet_a.pubkey(), 1_000_000 * ONE_USDC)
+        .await?;
+    let user_b_tsol_account = ctx
+        .tokens
+        .create_account_funded(&env.tsol, &wallet_b.pubkey(), 10_000 * ONE_TSOL)
+        .await?;
+
+    ctx.tokens
+        .set_price(
+            &env.usdc,
+            &TokenPrice {
+                exponent: -8,
+                price: 100_000_000,
+                confidence: 1_000_000,
+                twap: 100_000_000,
+            },
+        )
+        .await?;
+    ctx.tokens
+        .set_price(
+            &env.tsol,
+            &TokenPrice {
+                exponent: -8,
+                price: 10_000_000_000,
+                confidence: 100_000_000,
+                twap: 10_000_000_000,
+            },
+        )
+        .await?;
+
+    user_a
+        .deposit(&env.usdc, &user_a_usdc_account, 1_000_000 * ONE_USDC)
+        .await?;
+    user_b
+        .deposit(&env.tsol, &user_b_tsol_account, 10_000 * ONE_TSOL)
+        .await?;
+
+    user_a.refresh_all_pool_positions().await?;
+    user_b.refresh_all_pool_positions().await?;
+
+    // Total collateral = 500'000 + 1'000'000 * 95% = 1'450'000
+    // Total claims = 500'000
+    user_b
+        .borrow(&env.usdc, Amount::tokens(500_000 * ONE_USDC))
+        .await?;
+
+    // Weighting the USDC loans at 300% raises the claims to 1'500'000
+    ctx.margin
+        .configure_token(
+            &env.usdc,
+            &TokenConfiguration {
+                loan_metadata: Some(LoanMetadataParams {
+                    token_kind: TokenKind::Claim,
+                    borrow_weight: 30_000,
+                    max_staleness: 0,
+                }),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    // the existing loan position is unaffected until it's refreshed
+    let result = user_b_liq.liquidate_begin().await;
+    assert_program_error!(ErrorCode::Healthy, result);
+
+    user_b.refresh_all_pool_positions().await?;
+    user_b_liq.liquidate_begin().await?;
+
+    Ok(())
+}
+
 // todo enable this test when test runtime clock works
 // #[tokio::test]
 // fn owner_can_end_liquidation_after_timeout(ctx: SyncContext) {
@@ -0,0 +1,180 @@
+use anyhow::Error;
+
+use jet_control::TokenMetadataParams;
+use jet_margin_sdk::instructions::control::{TokenConfiguration, TokenPriceOracle};
+use jet_margin_sdk::instructions::native::get_wrapped_sol_address;
+use jet_margin_sdk::ix_builder::MarginPoolIxBuilder;
+use jet_simulation::swap::SwapPool;
+use jet_simulation::tokens::TokenPrice;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+use spl_token::native_mint;
+
+use hosted_tests::context::{test_context, MarginTestContext};
+
+use jet_margin_pool::{Amount, MarginPoolConfig, PoolFlags};
+use jet_metadata::TokenKind;
+use jet_simulation::create_wallet;
+use jet_simulation::margin::MarginPoolSetupInfo;
+
+const ONE_USDC: u64 = 1_000_000;
+const ONE_SOL: u64 = LAMPORTS_PER_SOL;
+
+const DEFAULT_POOL_CONFIG: MarginPoolConfig = MarginPoolConfig {
+    borrow_rate_0: 10,
+    borrow_rate_1: 20,
+    borrow_rate_2: 30,
+    borrow_rate_3: 40,
+    utilization_rate_1: 10,
+    utilization_rate_2: 20,
+    management_fee_rate: 10,
+    management_fee_collect_threshold: 100,
+    collateral_utilization_threshold: 0,
+    max_collateral_weight_reduction: 0,
+    min_queued_withdrawal: 0,
+    deposit_cap: 0,
+    borrow_cap: 0,
+    flags: PoolFlags::ALLOW_LENDING.bits(),
+};
+
+/// Create a pool for the token, priced at `price` USD
+async fn setup_pool(ctx: &MarginTestContext, token: Pubkey, price: i64) -> Result<(), Error> {
+    let fees = ctx
+        .tokens
+        .create_account(&token, &ctx.authority.pubkey())
+        .await?;
+    let oracle = ctx.tokens.create_oracle(&token).await?;
+
+    ctx.margin
+        .create_pool(&MarginPoolSetupInfo {
+            token,
+            fee_destination: fees,
+            token_kind: TokenKind::Collateral,
+            collateral_weight: 10_000,
+            config: DEFAULT_POOL_CONFIG,
+            oracle,
+        })
+        .await?;
+
+    ctx.margin
+        .configure_token(
+            &token,
+            &TokenConfiguration {
+                oracle: Some(TokenPriceOracle::Pyth {
+                    product: oracle.product,
+                    price: oracle.price,
+                }),
+                pool_config: Some(DEFAULT_POOL_CONFIG),
+                metadata: Some(TokenMetadataParams {
+                    token_kind: TokenKind::Collateral,
+                    collateral_weight: 10_000,
+                    collateral_max_staleness: 0,
+                    conservative_valuation: false,
+                }),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    ctx.tokens
+        .set_price(
+            &token,
+            &TokenPrice {
+                exponent: -8,
+                price: price * 100_000_000,
+                confidence: 1_000_000,
+                twap: price as u64 * 100_000_000,
+            },
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// The native mint can only have one pool, so all of the native SOL flows are
+/// exercised by this one test
+#[tokio::test]
+async fn native_sol_is_wrapped_and_unwrapped() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let sol = native_mint::ID;
+    let usdc = ctx.tokens.create_token(6, None, None).await?;
+
+    setup_pool(ctx, sol, 100).await?;
+    setup_pool(ctx, usdc, 1).await?;
+
+    let sol_vault = MarginPoolIxBuilder::new(sol).vault;
+
+    let wallet_a = create_wallet(&ctx.rpc, 100 * ONE_SOL).await?;
+    let wallet_b = create_wallet(&ctx.rpc, 100 * ONE_SOL).await?;
+    let user_a = ctx.margin.user(&wallet_a).await?;
+    let user_b = ctx.margin.user(&wallet_b).await?;
+
+    user_a.create_account().await?;
+    user_b.create_account().await?;
+
+    // User A deposits SOL straight from their wallet
+    user_a
+        .deposit(&sol, &wallet_a.pubkey(), 50 * ONE_SOL)
+        .await?;
+    assert_eq!(50 * ONE_SOL, ctx.tokens.get_balance(&sol_vault).await?);
+    assert!(ctx
+        .rpc
+        .get_account(&get_wrapped_sol_address(&wallet_a.pubkey()))
+        .await?
+        .is_none());
+
+    // User B borrows SOL against USDC, and takes some of it out to their wallet
+    let user_b_usdc = ctx
+        .tokens
+        .create_account_funded(&usdc, &wallet_b.pubkey(), 10_000 * ONE_USDC)
+        .await?;
+    user_b
+        .deposit(&usdc, &user_b_usdc, 10_000 * ONE_USDC)
+        .await?;
+    user_b.refresh_all_pool_positions().await?;
+    user_b.borrow(&sol, Amount::tokens(20 * ONE_SOL)).await?;
+    user_b
+        .withdraw(&sol, &wallet_b.pubkey(), Amount::tokens(10 * ONE_SOL))
+        .await?;
+    assert_eq!(40 * ONE_SOL, ctx.tokens.get_balance(&sol_vault).await?);
+
+    // The loan is paid back with SOL from the wallet, and from the margin account
+    user_b
+        .repay_from_wallet(&sol, &wallet_b.pubkey(), Amount::tokens(5 * ONE_SOL))
+        .await?;
+    assert_eq!(45 * ONE_SOL, ctx.tokens.get_balance(&sol_vault).await?);
+
+    user_b.repay(&sol, Amount::tokens(5 * ONE_SOL)).await?;
+    assert!(ctx
+        .rpc
+        .get_account(&get_wrapped_sol_address(&wallet_b.pubkey()))
+        .await?
+        .is_none());
+
+    // User A swaps some of their SOL deposit for USDC, through wrapped SOL
+    // transit accounts owned by the margin account
+    let swap_pool =
+        SwapPool::configure(&ctx.rpc, &sol, &usdc, 1_000 * ONE_SOL, 100_000 * ONE_USDC).await?;
+    let sol_transit = ctx.tokens.create_account(&sol, user_a.address()).await?;
+    let usdc_transit = ctx.tokens.create_account(&usdc, user_a.address()).await?;
+
+    user_a.refresh_all_pool_positions().await?;
+    user_a
+        .swap(
+            &sol,
+            &usdc,
+            &sol_transit,
+            &usdc_transit,
+            &swap_pool,
+            Amount::tokens(ONE_SOL),
+            Amount::tokens(90 * ONE_USDC),
+        )
+        .await?;
+    assert_eq!(
+        1_001 * ONE_SOL,
+        ctx.tokens.get_balance(&swap_pool.token_a).await?
+    );
+
+    Ok(())
+}
@@ -0,0 +1,206 @@
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anyhow::Error;
+
+use jet_margin_sdk::instructions::control::{
+    get_authority_address, TokenConfiguration, TokenPriceOracle,
+};
+use jet_simulation::tokens::TokenPrice;
+use solana_sdk::clock::Clock;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_program;
+
+use hosted_tests::context::{test_context, MarginTestContext};
+
+use jet_margin_pool::{ErrorCode, MAX_ORACLE_ROUND_AGE};
+use jet_simulation::margin::{MarginUser, DEFAULT_RISK_TIER};
+use jet_simulation::{assert_program_error_code, create_wallet};
+
+const PRICE: TokenPrice = TokenPrice {
+    exponent: -8,
+    price: 2_00000000,
+    confidence: 1_000_000,
+    twap: 2_00000000,
+};
+
+async fn register_token(ctx: &MarginTestContext) -> Result<(Pubkey, MarginUser), Error> {
+    let token = ctx.tokens.create_token(6, None, None).await?;
+    ctx.margin.register_token(&token, DEFAULT_RISK_TIER).await?;
+
+    let wallet = create_wallet(&ctx.rpc, 10 * LAMPORTS_PER_SOL).await?;
+    let user = ctx.margin.user(&wallet).await?;
+    user.create_account().await?;
+
+    Ok((token, user))
+}
+
+async fn configure_oracle(
+    ctx: &MarginTestContext,
+    token: &Pubkey,
+    oracle: TokenPriceOracle,
+) -> Result<(), Error> {
+    ctx.margin
+        .configure_token(
+            token,
+            &TokenConfiguration {
+                oracle: Some(oracle),
+                ..Default::default()
+            },
+        )
+        .await
+}
+
+/// Copy an account's data into an account owned by the metadata program
+async fn copy_account(ctx: &MarginTestContext, source: &Pubkey) -> Result<Pubkey, Error> {
+    let data = ctx.rpc.get_account(source).await?.unwrap().data;
+    let key = Keypair::new().pubkey();
+    let seed = "copy";
+    let (address, _) =
+        Pubkey::find_program_address(&[key.as_ref(), seed.as_bytes()], &jet_metadata::ID);
+
+    let mut instructions = vec![Instruction {
+        program_id: jet_metadata::ID,
+        accounts: jet_metadata::accounts::CreateEntry {
+            key_account: key,
+            metadata_account: address,
+            authority: get_authority_address(),
+            payer: ctx.rpc.payer().pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: jet_metadata::instruction::CreateEntry {
+            seed: seed.to_string(),
+            space: data.len() as u64,
+        }
+        .data(),
+    }];
+
+    for (i, chunk) in data.chunks(512).enumerate() {
+        instructions.push(Instruction {
+            program_id: jet_metadata::ID,
+            accounts: jet_metadata::accounts::SetEntry {
+                metadata_account: address,
+                authority: get_authority_address(),
+            }
+            .to_account_metas(None),
+            data: jet_metadata::instruction::SetEntry {
+                offset: (i * 512) as u64,
+                data: chunk.to_vec(),
+            }
+            .data(),
+        });
+    }
+
+    for ix in instructions {
+        let tx = ctx.rpc.create_transaction(&[], &[ix]).await?;
+        ctx.rpc.send_and_confirm_transaction(&tx).await?;
+    }
+
+    Ok(address)
+}
+
+/// Prices are read from a switchboard aggregator, as long as its latest
+/// round is recent
+#[tokio::test]
+async fn switchboard_prices_are_cached_until_stale() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let (token, user) = register_token(ctx).await?;
+    let aggregator = ctx.tokens.create_switchboard_oracle(&PRICE).await?;
+
+    configure_oracle(ctx, &token, TokenPriceOracle::Switchboard { aggregator }).await?;
+
+    user.refresh_pool_position(&token).await?;
+    let oracle = ctx.margin.get_pool_oracle(&token).await?;
+    assert_eq!(2_00000000, oracle.price().as_u64(-8));
+    assert_eq!(1_000_000, oracle.confidence().as_u64(-8));
+
+    ctx.tokens
+        .set_switchboard_price(
+            &aggregator,
+            &TokenPrice {
+                price: 2_10000000,
+                ..PRICE
+            },
+        )
+        .await?;
+
+    user.refresh_pool_position(&token).await?;
+    let oracle = ctx.margin.get_pool_oracle(&token).await?;
+    assert_eq!(2_10000000, oracle.price().as_u64(-8));
+
+    // the round is too old once the aggregator stops publishing
+    let mut clock: Clock = ctx.rpc.get_clock().unwrap();
+    clock.unix_timestamp += MAX_ORACLE_ROUND_AGE + 1;
+    ctx.rpc.set_clock(clock);
+
+    let result = user.refresh_pool_position(&token).await;
+    assert_program_error_code!(ErrorCode::StaleOracle.into(), result);
+
+    Ok(())
+}
+
+/// An account with the layout of an aggregator isn't accepted unless it's
+/// owned by the switchboard program
+#[tokio::test]
+async fn switchboard_aggregator_must_be_owned_by_switchboard() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let (token, _) = register_token(ctx).await?;
+    let aggregator = ctx.tokens.create_switchboard_oracle(&PRICE).await?;
+    let copied = copy_account(ctx, &aggregator).await?;
+
+    let result = configure_oracle(
+        ctx,
+        &token,
+        TokenPriceOracle::Switchboard { aggregator: copied },
+    )
+    .await;
+    assert_program_error_code!(ErrorCode::InvalidOracle.into(), result);
+
+    // a pyth price account isn't an aggregator either
+    let pyth_oracle = ctx.tokens.create_oracle(&token).await?;
+    let result = configure_oracle(
+        ctx,
+        &token,
+        TokenPriceOracle::Switchboard {
+            aggregator: pyth_oracle.price,
+        },
+    )
+    .await;
+    assert_program_error_code!(ErrorCode::InvalidOracle.into(), result);
+
+    Ok(())
+}
+
+/// A fixed price is used without any oracle account
+#[tokio::test]
+async fn fixed_price_is_cached() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let (token, user) = register_token(ctx).await?;
+
+    configure_oracle(
+        ctx,
+        &token,
+        TokenPriceOracle::Fixed {
+            price: 1_000,
+            exponent: -3,
+        },
+    )
+    .await?;
+
+    user.refresh_pool_position(&token).await?;
+    let oracle = ctx.margin.get_pool_oracle(&token).await?;
+    assert_eq!(1_000, oracle.price().as_u64(-3));
+    assert_eq!(0, oracle.confidence().as_u64(-3));
+    assert_eq!(-3, oracle.exponent);
+
+    // a fixed price still has to be usable for the token
+    for (price, exponent) in [(0, -3), (1, 2)] {
+        let result =
+            configure_oracle(ctx, &token, TokenPriceOracle::Fixed { price, exponent }).await;
+        assert_program_error_code!(ErrorCode::InvalidOracle.into(), result);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use std::sync::Arc;
+
+use anchor_lang::Discriminator;
+
+use jet_margin_pool::{MarginPool, PoolFlags};
+use jet_margin_sdk::ix_builder::MarginPoolIxBuilder;
+use jet_metadata::OracleSource;
+use jet_simulation::margin::MarginClient;
+use jet_simulation::tokens::TokenManager;
+use jet_simulation::TestRuntime;
+use jet_solana_rpc_api::SolanaRpcClient;
+use solana_sdk::pubkey::Pubkey;
+
+/// Pools created before the term loan and withdrawal queue fields were
+/// added still have the original layout, and can be migrated to the
+/// current one
+#[tokio::test]
+async fn baseline_pool_is_migrated() -> Result<(), anyhow::Error> {
+    let runtime = Arc::new(jet_simulation::create_test_runtime![jet_margin_pool]);
+    let tokens = TokenManager::new(runtime.clone());
+    let margin = MarginClient::new(runtime.clone());
+
+    let token = tokens.create_token(6, None, None).await?;
+    let pool = MarginPoolIxBuilder::new(token);
+    let vault = Pubkey::new_unique();
+    let fee_destination = Pubkey::new_unique();
+    let price_oracle = Pubkey::new_unique();
+    let flags = PoolFlags::ALLOW_LENDING.bits();
+    let (_, pool_bump) = Pubkey::find_program_address(&[token.as_ref()], &jet_margin_pool::ID);
+
+    // the layout of the pool before the term loan fields were appended
+    let mut legacy_data = MarginPool::discriminator().to_vec();
+
+    legacy_data.push(1);
+    legacy_data.push(pool_bump);
+    legacy_data.extend_from_slice(vault.as_ref());
+    legacy_data.extend_from_slice(fee_destination.as_ref());
+    legacy_data.extend_from_slice(pool.deposit_note_mint.as_ref());
+    legacy_data.extend_from_slice(pool.loan_note_mint.as_ref());
+    legacy_data.extend_from_slice(token.as_ref());
+    legacy_data.extend_from_slice(price_oracle.as_ref());
+    legacy_data.extend_from_slice(pool.address.as_ref());
+    legacy_data.extend_from_slice(&flags.to_le_bytes());
+    for value in [5_000u16, 8_000, 1_000, 2_000, 3_000, 4_000, 100] {
+        legacy_data.extend_from_slice(&value.to_le_bytes());
+    }
+    legacy_data.extend_from_slice(&1_000u64.to_le_bytes());
+    legacy_data.extend_from_slice(&[0; 48]);
+    for value in [500u64, 400, 0] {
+        legacy_data.extend_from_slice(&value.to_le_bytes());
+    }
+    legacy_data.extend_from_slice(&1_650_000_000i64.to_le_bytes());
+
+    // the account was allocated with the size of the original struct
+    legacy_data.resize(352, 0);
+    runtime.create_account_rent_exempt(pool.address, jet_margin_pool::ID, legacy_data.clone());
+
+    margin.migrate_pool(&token).await?;
+
+    let account = runtime.get_account(&pool.address).await?.unwrap();
+    let migrated = margin.get_pool(&token).await?;
+
+    assert_eq!(MarginPool::SPACE, account.data.len());
+    assert_eq!(vault, migrated.vault);
+    assert_eq!(fee_destination, migrated.fee_destination);
+    assert_eq!(price_oracle, migrated.token_price_oracle);
+    assert_eq!(pool.term_loan_note_mint, migrated.term_loan_note_mint);
+    assert_eq!(flags, migrated.config.flags);
+    assert_eq!(5_000, migrated.config.utilization_rate_1);
+    assert_eq!(4_000, migrated.config.borrow_rate_3);
+    assert_eq!(100, migrated.config.management_fee_rate);
+    assert_eq!(1_000, migrated.config.management_fee_collect_threshold);
+    assert_eq!(0, migrated.config.collateral_utilization_threshold);
+    assert_eq!(0, migrated.config.max_collateral_weight_reduction);
+    assert_eq!(0, migrated.config.min_queued_withdrawal);
+    assert_eq!(0, migrated.config.deposit_cap);
+    assert_eq!(0, migrated.config.borrow_cap);
+    assert_eq!(500, migrated.deposit_tokens);
+    assert_eq!(400, migrated.deposit_notes);
+    assert_eq!(1_650_000_000, migrated.accrued_until);
+    assert_eq!(0, migrated.term_loan_tokens);
+    assert_eq!(0, migrated.queued_withdrawal_notes);
+
+    assert!(runtime
+        .get_account(&pool.term_loan_note_mint)
+        .await?
+        .is_some());
+
+    let oracle = margin.get_pool_oracle(&token).await?;
+
+    assert_eq!(token, oracle.token_mint);
+    assert_eq!(OracleSource::Pyth, oracle.source);
+    assert_eq!(price_oracle, oracle.price_oracle);
+
+    // a pool can only be migrated once
+    assert!(margin.migrate_pool(&token).await.is_err());
+
+    Ok(())
+}
@@ -0,0 +1,374 @@
+use anyhow::Error;
+
+use jet_control::TokenMetadataParams;
+use jet_margin_sdk::accounts::MarginPoolAccounts;
+use jet_margin_sdk::instructions::control::{TokenConfiguration, TokenPriceOracle};
+use jet_margin_sdk::ix_builder::MarginIxBuilder;
+use jet_simulation::tokens::TokenPrice;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+use hosted_tests::context::{test_context, MarginTestContext};
+
+use jet_margin_pool::{Amount, MarginPoolConfig, PoolFlags};
+use jet_metadata::TokenKind;
+use jet_simulation::margin::{MarginPoolSetupInfo, DEFAULT_RISK_TIER};
+use jet_simulation::{assert_program_error_code, create_wallet};
+
+const ONE_USDC: u64 = 1_000_000;
+const ONE_TSOL: u64 = LAMPORTS_PER_SOL;
+
+const DEFAULT_POOL_CONFIG: MarginPoolConfig = MarginPoolConfig {
+    borrow_rate_0: 10,
+    borrow_rate_1: 20,
+    borrow_rate_2: 30,
+    borrow_rate_3: 40,
+    utilization_rate_1: 10,
+    utilization_rate_2: 20,
+    management_fee_rate: 10,
+    management_fee_collect_threshold: 100,
+    collateral_utilization_threshold: 0,
+    max_collateral_weight_reduction: 0,
+    min_queued_withdrawal: 0,
+    deposit_cap: 0,
+    borrow_cap: 0,
+    flags: PoolFlags::ALLOW_LENDING.bits(),
+};
+
+struct TestEnv {
+    usdc: Pubkey,
+    usdc_fees: Pubkey,
+    tsol: Pubkey,
+}
+
+async fn setup_environment(ctx: &MarginTestContext) -> Result<TestEnv, Error> {
+    let usdc = ctx.tokens.create_token(6, None, None).await?;
+    let usdc_fees = ctx
+        .tokens
+        .create_account(&usdc, &ctx.authority.pubkey())
+        .await?;
+    let usdc_oracle = ctx.tokens.create_oracle(&usdc).await?;
+    let tsol = ctx.tokens.create_token(9, None, None).await?;
+    let tsol_fees = ctx
+        .tokens
+        .create_account(&tsol, &ctx.authority.pubkey())
+        .await?;
+    let tsol_oracle = ctx.tokens.create_oracle(&tsol).await?;
+
+    let pools = [
+        MarginPoolSetupInfo {
+            token: usdc,
+            fee_destination: usdc_fees,
+            token_kind: TokenKind::Collateral,
+            collateral_weight: 10_000,
+            config: DEFAULT_POOL_CONFIG,
+            oracle: usdc_oracle,
+        },
+        MarginPoolSetupInfo {
+            token: tsol,
+            fee_destination: tsol_fees,
+            token_kind: TokenKind::Collateral,
+            collateral_weight: 9_500,
+            config: DEFAULT_POOL_CONFIG,
+            oracle: tsol_oracle,
+        },
+    ];
+
+    for pool_info in pools {
+        ctx.margin.create_pool(&pool_info).await?;
+    }
+
+    ctx.margin
+        .configure_token(
+            &usdc,
+            &TokenConfiguration {
+                oracle: Some(TokenPriceOracle::Pyth {
+                    product: usdc_oracle.product,
+                    price: usdc_oracle.price,
+                }),
+                pool_config: Some(DEFAULT_POOL_CONFIG),
+                metadata: Some(TokenMetadataParams {
+                    token_kind: TokenKind::Collateral,
+                    collateral_weight: 10_000,
+                    collateral_max_staleness: 0,
+                    conservative_valuation: false,
+                }),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    ctx.margin
+        .configure_token(
+            &tsol,
+            &TokenConfiguration {
+                oracle: Some(TokenPriceOracle::Pyth {
+                    product: tsol_oracle.product,
+                    price: tsol_oracle.price,
+                }),
+                pool_config: Some(DEFAULT_POOL_CONFIG),
+                metadata: Some(TokenMetadataParams {
+                    token_kind: TokenKind::Collateral,
+                    collateral_weight: 9_500,
+                    collateral_max_staleness: 0,
+                    conservative_valuation: false,
+                }),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    ctx.tokens
+        .set_price(
+            // Set price to 1 USD +- 0.01
+            &usdc,
+            &TokenPrice {
+                exponent: -8,
+                price: 100_000_000,
+                confidence: 1_000_000,
+                twap: 100_000_000,
+            },
+        )
+        .await?;
+    ctx.tokens
+        .set_price(
+            // Set price to 100 USD +- 1
+            &tsol,
+            &TokenPrice {
+                exponent: -8,
+                price: 10_000_000_000,
+                confidence: 100_000_000,
+                twap: 10_000_000_000,
+            },
+        )
+        .await?;
+
+    Ok(TestEnv {
+        usdc,
+        usdc_fees,
+        tsol,
+    })
+}
+
+/// Test retiring a pool for a token being delisted
+///
+/// The pool stops accepting deposits and borrows, settles the outstanding
+/// loan, lets everyone redeem, and is then closed.
+#[tokio::test]
+async fn retire_pool() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let env = setup_environment(ctx).await?;
+
+    let wallet_a = create_wallet(&ctx.rpc, 10 * LAMPORTS_PER_SOL).await?;
+    let wallet_b = create_wallet(&ctx.rpc, 10 * LAMPORTS_PER_SOL).await?;
+
+    let user_a = ctx.margin.user(&wallet_a).await?;
+    let user_b = ctx.margin.user(&wallet_b).await?;
+
+    user_a.create_account().await?;
+    user_b.create_account().await?;
+
+    let user_a_usdc_account = ctx
+        .tokens
+        .create_account_funded(&env.usdc, &wallet_a.pubkey(), 2_000_000 * ONE_USDC)
+        .await?;
+    let user_b_tsol_account = ctx
+        .tokens
+        .create_account_funded(&env.tsol, &wallet_b.pubkey(), 1_000 * ONE_TSOL)
+        .await?;
+
+    user_a
+        .deposit(&env.usdc, &user_a_usdc_account, 1_000_000 * ONE_USDC)
+        .await?;
+    user_b
+        .deposit(&env.tsol, &user_b_tsol_account, 1_000 * ONE_TSOL)
+        .await?;
+
+    user_a.refresh_all_pool_positions().await?;
+    user_b.refresh_all_pool_positions().await?;
+
+    user_b
+        .borrow(&env.usdc, Amount::tokens(1_000 * ONE_USDC))
+        .await?;
+
+    // Begin retiring the USDC pool, which stops new deposits and borrows
+    ctx.margin.begin_pool_retirement(&env.usdc).await?;
+
+    let deposit_result = user_a
+        .deposit(&env.usdc, &user_a_usdc_account, 1_000 * ONE_USDC)
+        .await;
+    assert_program_error_code!(jet_margin_pool::ErrorCode::Retiring.into(), deposit_result);
+
+    user_b.refresh_all_pool_positions().await?;
+    let borrow_result = user_b
+        .borrow(&env.usdc, Amount::tokens(1_000 * ONE_USDC))
+        .await;
+    assert_program_error_code!(jet_margin_pool::ErrorCode::Retiring.into(), borrow_result);
+
+    // The pool can't be closed until the loans have been settled
+    let close_result = ctx.margin.close_pool(&env.usdc, &env.usdc_fees).await;
+    assert_program_error_code!(jet_margin_pool::ErrorCode::NotRetiring.into(), close_result);
+
+    ctx.margin.settle_pool_retirement(&env.usdc).await?;
+
+    // Nor while there are still deposits and loans outstanding
+    let close_result = ctx.margin.close_pool(&env.usdc, &env.usdc_fees).await;
+    assert_program_error_code!(
+        jet_margin_pool::ErrorCode::PoolNotEmpty.into(),
+        close_result
+    );
+
+    // Repaying and withdrawing is still allowed in a retired pool
+    user_b
+        .repay(&env.usdc, Amount::tokens(1_000 * ONE_USDC))
+        .await?;
+    user_a
+        .withdraw(
+            &env.usdc,
+            &user_a_usdc_account,
+            Amount::tokens(1_000_000 * ONE_USDC),
+        )
+        .await?;
+
+    assert_eq!(
+        2_000_000 * ONE_USDC,
+        ctx.tokens.get_balance(&user_a_usdc_account).await?
+    );
+
+    // With everything redeemed, the pool can now be closed
+    ctx.margin.close_pool(&env.usdc, &env.usdc_fees).await?;
+
+    let pool = MarginPoolAccounts::derive_from_token(env.usdc);
+
+    for address in [pool.address, pool.vault, pool.oracle] {
+        let account = ctx.rpc.get_account(&address).await?;
+        assert!(account.map(|a| a.lamports == 0).unwrap_or(true));
+    }
+
+    Ok(())
+}
+
+/// Test retiring a pool with a loan that is never repaid
+///
+/// The unpaid loan is written off, the depositor takes the loss when redeeming,
+/// and the pool can be closed once the borrower burns the loan notes without
+/// paying. The token can't be listed again afterwards.
+#[tokio::test]
+async fn write_off_unpaid_loans() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let env = setup_environment(ctx).await?;
+
+    let wallet_a = create_wallet(&ctx.rpc, 10 * LAMPORTS_PER_SOL).await?;
+    let wallet_b = create_wallet(&ctx.rpc, 10 * LAMPORTS_PER_SOL).await?;
+
+    let user_a = ctx.margin.user(&wallet_a).await?;
+    let user_b = ctx.margin.user(&wallet_b).await?;
+
+    user_a.create_account().await?;
+    user_b.create_account().await?;
+
+    let user_a_usdc_account = ctx
+        .tokens
+        .create_account_funded(&env.usdc, &wallet_a.pubkey(), 2_000_000 * ONE_USDC)
+        .await?;
+    let user_b_usdc_account = ctx
+        .tokens
+        .create_account(&env.usdc, &wallet_b.pubkey())
+        .await?;
+    let user_b_tsol_account = ctx
+        .tokens
+        .create_account_funded(&env.tsol, &wallet_b.pubkey(), 1_000 * ONE_TSOL)
+        .await?;
+
+    user_a
+        .deposit(&env.usdc, &user_a_usdc_account, 1_000_000 * ONE_USDC)
+        .await?;
+    user_b
+        .deposit(&env.tsol, &user_b_tsol_account, 1_000 * ONE_TSOL)
+        .await?;
+
+    user_a.refresh_all_pool_positions().await?;
+    user_b.refresh_all_pool_positions().await?;
+
+    // User B borrows USDC and takes it out of their margin account
+    user_b
+        .borrow(&env.usdc, Amount::tokens(1_000 * ONE_USDC))
+        .await?;
+    user_b
+        .withdraw(
+            &env.usdc,
+            &user_b_usdc_account,
+            Amount::tokens(1_000 * ONE_USDC),
+        )
+        .await?;
+
+    ctx.margin.begin_pool_retirement(&env.usdc).await?;
+
+    // Loans can't be written off before they're settled
+    let write_off_result = ctx.margin.write_off_pool_loans(&env.usdc).await;
+    assert_program_error_code!(
+        jet_margin_pool::ErrorCode::NotRetiring.into(),
+        write_off_result
+    );
+
+    ctx.margin.settle_pool_retirement(&env.usdc).await?;
+    ctx.margin.write_off_pool_loans(&env.usdc).await?;
+
+    // The written off loan can no longer be repaid
+    user_b.refresh_all_pool_positions().await?;
+    let repay_result = user_b
+        .repay_from_wallet(
+            &env.usdc,
+            &user_b_usdc_account,
+            Amount::tokens(1_000 * ONE_USDC),
+        )
+        .await;
+    assert_program_error_code!(
+        jet_margin_pool::ErrorCode::LoansWrittenOff.into(),
+        repay_result
+    );
+
+    // User A redeems their deposit, less the loan that was written off
+    user_a.refresh_all_pool_positions().await?;
+    user_a
+        .withdraw(
+            &env.usdc,
+            &user_a_usdc_account,
+            Amount::notes(1_000_000 * ONE_USDC),
+        )
+        .await?;
+
+    assert_eq!(
+        1_999_000 * ONE_USDC,
+        ctx.tokens.get_balance(&user_a_usdc_account).await?
+    );
+
+    // The pool can't be closed while user B still holds the loan notes
+    let result = ctx.margin.close_pool(&env.usdc, &env.usdc_fees).await;
+    assert_program_error_code!(jet_margin_pool::ErrorCode::PoolNotEmpty.into(), result);
+
+    // User B burns the written off notes without paying anything back
+    user_b.burn_loan_notes(&env.usdc).await?;
+
+    let pool = MarginPoolAccounts::derive_from_token(env.usdc);
+    let (loan_position, _) =
+        MarginIxBuilder::new(wallet_b.pubkey(), 0).get_token_account_address(&pool.loan_note_mint);
+    assert_eq!(0, ctx.tokens.get_balance(&loan_position).await?);
+    assert_eq!(
+        1_000 * ONE_USDC,
+        ctx.tokens.get_balance(&user_b_usdc_account).await?
+    );
+
+    ctx.margin.close_pool(&env.usdc, &env.usdc_fees).await?;
+
+    // The note mints of the closed pool are still in place, so the token
+    // can't be given a new pool
+    let result = ctx
+        .margin
+        .register_token(&env.usdc, DEFAULT_RISK_TIER)
+        .await;
+    assert!(result.is_err());
+
+    Ok(())
+}
@@ -0,0 +1,353 @@
+use anchor_spl::token::spl_token;
+use anyhow::Error;
+
+use jet_margin_sdk::ix_builder::{MarginIxBuilder, MarginPoolIxBuilder};
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+
+use hosted_tests::context::{test_context, MarginTestContext};
+use hosted_tests::fixtures::{advance_clock, send, setup_usdc_pool};
+
+use jet_margin_pool::{Amount, ErrorCode, MarginPoolConfig, PoolFlags, RewardCampaignParams};
+use jet_simulation::margin::MarginUser;
+use jet_simulation::{assert_program_error_code, create_wallet};
+
+const ONE_USDC: u64 = 1_000_000;
+
+/// Reward tokens emitted per second, across all holders
+const EMISSION_RATE: u64 = 1_000;
+
+const POOL_CONFIG: MarginPoolConfig = MarginPoolConfig {
+    borrow_rate_0: 10,
+    borrow_rate_1: 20,
+    borrow_rate_2: 30,
+    borrow_rate_3: 40,
+    utilization_rate_1: 10,
+    utilization_rate_2: 20,
+    management_fee_rate: 0,
+    management_fee_collect_threshold: 100,
+    collateral_utilization_threshold: 0,
+    max_collateral_weight_reduction: 0,
+    min_queued_withdrawal: 0,
+    deposit_cap: 0,
+    borrow_cap: 0,
+    flags: PoolFlags::ALLOW_LENDING.bits(),
+};
+
+struct TestCampaign {
+    pool: MarginPoolIxBuilder,
+    campaign: Pubkey,
+    reward_mint: Pubkey,
+}
+
+/// Create a pool with a campaign rewarding its deposit notes, starting now
+async fn setup_campaign(ctx: &MarginTestContext) -> Result<TestCampaign, Error> {
+    let usdc = setup_usdc_pool(ctx, POOL_CONFIG).await?;
+
+    let pool = MarginPoolIxBuilder::new(usdc);
+    let reward_mint = ctx.tokens.create_token(6, None, None).await?;
+    let start = ctx.rpc.get_clock().unwrap().unix_timestamp;
+
+    ctx.margin
+        .create_reward_campaign(
+            &usdc,
+            &pool.deposit_note_mint,
+            &reward_mint,
+            RewardCampaignParams {
+                start,
+                end: start + 100_000,
+                emission_rate: EMISSION_RATE,
+            },
+        )
+        .await?;
+
+    let campaign = pool.reward_campaign(&pool.deposit_note_mint);
+    ctx.tokens
+        .mint(&reward_mint, &pool.reward_vault(&campaign), 1_000_000_000)
+        .await?;
+
+    Ok(TestCampaign {
+        pool,
+        campaign,
+        reward_mint,
+    })
+}
+
+/// A wallet holding deposit notes outside of a margin account
+struct Depositor {
+    wallet: Keypair,
+    notes: Pubkey,
+    rewards: Pubkey,
+}
+
+async fn deposit(
+    ctx: &MarginTestContext,
+    test: &TestCampaign,
+    amount: u64,
+) -> Result<Depositor, Error> {
+    let pool = &test.pool;
+    let wallet = create_wallet(&ctx.rpc, 10 * LAMPORTS_PER_SOL).await?;
+    let tokens = ctx
+        .tokens
+        .create_account_funded(&pool.token_mint, &wallet.pubkey(), amount)
+        .await?;
+    let notes = ctx
+        .tokens
+        .create_account(&pool.deposit_note_mint, &wallet.pubkey())
+        .await?;
+    let rewards = ctx
+        .tokens
+        .create_account(&test.reward_mint, &wallet.pubkey())
+        .await?;
+
+    send(
+        ctx,
+        &[&wallet],
+        pool.deposit(wallet.pubkey(), tokens, notes, amount),
+    )
+    .await?;
+    send(
+        ctx,
+        &[],
+        pool.create_reward_checkpoint(test.campaign, wallet.pubkey(), ctx.rpc.payer().pubkey()),
+    )
+    .await?;
+
+    Ok(Depositor {
+        wallet,
+        notes,
+        rewards,
+    })
+}
+
+async fn stake(
+    ctx: &MarginTestContext,
+    test: &TestCampaign,
+    depositor: &Depositor,
+    notes: u64,
+) -> Result<(), Error> {
+    send(
+        ctx,
+        &[&depositor.wallet],
+        test.pool.stake_rewards(
+            test.campaign,
+            depositor.wallet.pubkey(),
+            depositor.notes,
+            notes,
+        ),
+    )
+    .await
+}
+
+async fn unstake(
+    ctx: &MarginTestContext,
+    test: &TestCampaign,
+    depositor: &Depositor,
+    notes: u64,
+) -> Result<(), Error> {
+    send(
+        ctx,
+        &[&depositor.wallet],
+        test.pool.unstake_rewards(
+            test.campaign,
+            depositor.wallet.pubkey(),
+            depositor.notes,
+            notes,
+        ),
+    )
+    .await
+}
+
+/// Claim the rewards for a depositor, returning the total they've received
+async fn claim(
+    ctx: &MarginTestContext,
+    test: &TestCampaign,
+    depositor: &Depositor,
+) -> Result<u64, Error> {
+    send(
+        ctx,
+        &[&depositor.wallet],
+        test.pool
+            .claim_rewards(test.campaign, depositor.wallet.pubkey(), depositor.rewards),
+    )
+    .await?;
+
+    ctx.tokens.get_balance(&depositor.rewards).await
+}
+
+/// Staked notes earn their share of the emissions, and moving them between
+/// owners doesn't let them earn twice
+#[tokio::test]
+async fn staked_notes_are_rewarded_once() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let test = setup_campaign(ctx).await?;
+
+    let alice = deposit(ctx, &test, 100 * ONE_USDC).await?;
+    let bob = deposit(ctx, &test, 300 * ONE_USDC).await?;
+
+    stake(ctx, &test, &alice, 100 * ONE_USDC).await?;
+
+    let result = unstake(ctx, &test, &alice, 100 * ONE_USDC + 1).await;
+    assert_program_error_code!(ErrorCode::InvalidAmount.into(), result);
+
+    // alice holds a quarter of the notes
+    advance_clock(ctx, 1_000);
+    assert_eq!(250_000, claim(ctx, &test, &alice).await?);
+
+    // the notes are handed to bob, who only starts earning once they're staked again
+    unstake(ctx, &test, &alice, 100 * ONE_USDC).await?;
+    send(
+        ctx,
+        &[&alice.wallet],
+        spl_token::instruction::transfer(
+            &spl_token::ID,
+            &alice.notes,
+            &bob.notes,
+            &alice.wallet.pubkey(),
+            &[],
+            100 * ONE_USDC,
+        )?,
+    )
+    .await?;
+    stake(ctx, &test, &bob, 100 * ONE_USDC).await?;
+    assert_eq!(0, claim(ctx, &test, &bob).await?);
+
+    advance_clock(ctx, 1_000);
+    assert_eq!(250_000, claim(ctx, &test, &alice).await?);
+    assert_eq!(250_000, claim(ctx, &test, &bob).await?);
+
+    // notes that aren't staked don't earn anything
+    unstake(ctx, &test, &bob, 100 * ONE_USDC).await?;
+    advance_clock(ctx, 1_000);
+    assert_eq!(250_000, claim(ctx, &test, &bob).await?);
+
+    Ok(())
+}
+
+async fn margin_depositor(
+    ctx: &MarginTestContext,
+    test: &TestCampaign,
+    amount: u64,
+) -> Result<(MarginUser, Pubkey), Error> {
+    let pool = &test.pool;
+    let wallet = create_wallet(&ctx.rpc, 10 * LAMPORTS_PER_SOL).await?;
+    let user = ctx.margin.user(&wallet).await?;
+    let source = ctx
+        .tokens
+        .create_account_funded(&pool.token_mint, &wallet.pubkey(), amount)
+        .await?;
+
+    user.create_account().await?;
+    user.deposit(&pool.token_mint, &source, amount).await?;
+    user.refresh_pool_position(&pool.token_mint).await?;
+
+    let (position, _) =
+        MarginIxBuilder::new(wallet.pubkey(), 0).get_token_account_address(&pool.deposit_note_mint);
+    send(
+        ctx,
+        &[],
+        pool.create_margin_reward_checkpoint(
+            test.campaign,
+            *user.address(),
+            position,
+            ctx.rpc.payer().pubkey(),
+        ),
+    )
+    .await?;
+
+    Ok((user, source))
+}
+
+/// Notes in a margin account position earn rewards without being staked, with the
+/// rewards settled before the position is reduced
+#[tokio::test]
+async fn margin_positions_are_settled_when_reduced() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let test = setup_campaign(ctx).await?;
+    let usdc = test.pool.token_mint;
+
+    let (user, wallet_tokens) = margin_depositor(ctx, &test, 100 * ONE_USDC).await?;
+    let _other = deposit(ctx, &test, 300 * ONE_USDC).await?;
+
+    let rewards = ctx
+        .tokens
+        .create_account(&test.reward_mint, user.owner())
+        .await?;
+
+    // the rewards earned before a withdrawal are kept, but the withdrawn notes
+    // stop earning
+    advance_clock(ctx, 1_000);
+    user.refresh_pool_position(&usdc).await?;
+    user.withdraw(&usdc, &wallet_tokens, Amount::tokens(50 * ONE_USDC))
+        .await?;
+
+    // notes deposited back only earn once they've been held for a full period
+    advance_clock(ctx, 1_000);
+    user.deposit(&usdc, &wallet_tokens, 50 * ONE_USDC).await?;
+    user.claim_rewards(&usdc, &test.pool.deposit_note_mint, &rewards)
+        .await?;
+
+    let expected = 250_000 + 50 * ONE_USDC * EMISSION_RATE * 1_000 / (350 * ONE_USDC);
+    assert_eq!(expected, ctx.tokens.get_balance(&rewards).await?);
+
+    Ok(())
+}
+
+/// The rewards for a margin account can't be claimed by anyone else through
+/// accounting_invoke, whether into their own account or the owner's
+#[tokio::test]
+async fn third_party_cannot_claim_margin_rewards() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let test = setup_campaign(ctx).await?;
+    let usdc = test.pool.token_mint;
+
+    let (user, _) = margin_depositor(ctx, &test, 100 * ONE_USDC).await?;
+
+    let owner_rewards = ctx
+        .tokens
+        .create_account(&test.reward_mint, user.owner())
+        .await?;
+    let thief_rewards = ctx
+        .tokens
+        .create_account(&test.reward_mint, &ctx.rpc.payer().pubkey())
+        .await?;
+
+    advance_clock(ctx, 1_000);
+
+    let margin_ix = MarginIxBuilder::new(*user.owner(), 0);
+    let (position, _) = margin_ix.get_token_account_address(&test.pool.deposit_note_mint);
+
+    let claim_ix = |destination| {
+        margin_ix.accounting_invoke(test.pool.margin_claim_rewards(
+            *user.address(),
+            test.campaign,
+            position,
+            destination,
+        ))
+    };
+
+    let result = send(ctx, &[], claim_ix(thief_rewards)).await;
+    assert_program_error_code!(ErrorCode::InvalidRewardDestination.into(), result);
+
+    let result = send(ctx, &[], claim_ix(owner_rewards)).await;
+    assert_program_error_code!(jet_margin::ErrorCode::UnauthorizedInvocation.into(), result);
+
+    assert_eq!(0, ctx.tokens.get_balance(&thief_rewards).await?);
+    assert_eq!(0, ctx.tokens.get_balance(&owner_rewards).await?);
+
+    // the owner can't send the rewards to someone else either
+    let result = user
+        .claim_rewards(&usdc, &test.pool.deposit_note_mint, &thief_rewards)
+        .await;
+    assert_program_error_code!(ErrorCode::InvalidRewardDestination.into(), result);
+
+    user.claim_rewards(&usdc, &test.pool.deposit_note_mint, &owner_rewards)
+        .await?;
+    assert_eq!(
+        EMISSION_RATE * 1_000,
+        ctx.tokens.get_balance(&owner_rewards).await?
+    );
+
+    Ok(())
+}
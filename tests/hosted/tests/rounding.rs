@@ -1,7 +1,7 @@
 use anyhow::{Error, Result};
 
 use jet_control::TokenMetadataParams;
-use jet_margin_sdk::instructions::control::TokenConfiguration;
+use jet_margin_sdk::instructions::control::{TokenConfiguration, TokenPriceOracle};
 use jet_simulation::tokens::TokenPrice;
 use solana_sdk::clock::Clock;
 use solana_sdk::native_token::LAMPORTS_PER_SOL;
@@ -10,7 +10,7 @@ use solana_sdk::signature::Signer;
 
 use hosted_tests::context::{test_context, MarginTestContext};
 
-use jet_margin_pool::{Amount, MarginPoolConfig, PoolFlags};
+use jet_margin_pool::{Amount, MarginPool, MarginPoolConfig, PoolFlags};
 use jet_metadata::TokenKind;
 use jet_simulation::margin::MarginPoolSetupInfo;
 use jet_simulation::{assert_program_error_code, create_wallet};
@@ -27,6 +27,11 @@ const DEFAULT_POOL_CONFIG: MarginPoolConfig = MarginPoolConfig {
     utilization_rate_2: 20,
     management_fee_rate: 10,
     management_fee_collect_threshold: 100,
+    collateral_utilization_threshold: 0,
+    max_collateral_weight_reduction: 0,
+    min_queued_withdrawal: 0,
+    deposit_cap: 0,
+    borrow_cap: 0,
     flags: PoolFlags::ALLOW_LENDING.bits(),
 };
 
@@ -76,13 +81,16 @@ async fn setup_environment(ctx: &MarginTestContext) -> Result<TestEnv, Error> {
         .configure_token(
             &usdc,
             &TokenConfiguration {
-                pyth_price: Some(usdc_oracle.price),
-                pyth_product: Some(usdc_oracle.product),
+                oracle: Some(TokenPriceOracle::Pyth {
+                    product: usdc_oracle.product,
+                    price: usdc_oracle.price,
+                }),
                 pool_config: Some(DEFAULT_POOL_CONFIG),
                 metadata: Some(TokenMetadataParams {
                     token_kind: TokenKind::Collateral,
                     collateral_weight: 10_000,
                     collateral_max_staleness: 0,
+                    conservative_valuation: false,
                 }),
                 ..Default::default()
             },
@@ -93,13 +101,16 @@ async fn setup_environment(ctx: &MarginTestContext) -> Result<TestEnv, Error> {
         .configure_token(
             &tsol,
             &TokenConfiguration {
-                pyth_price: Some(tsol_oracle.price),
-                pyth_product: Some(tsol_oracle.product),
+                oracle: Some(TokenPriceOracle::Pyth {
+                    product: tsol_oracle.product,
+                    price: tsol_oracle.price,
+                }),
                 pool_config: Some(DEFAULT_POOL_CONFIG),
                 metadata: Some(TokenMetadataParams {
                     token_kind: TokenKind::Collateral,
                     collateral_weight: 9_500,
                     collateral_max_staleness: 0,
+                    conservative_valuation: false,
                 }),
                 ..Default::default()
             },
@@ -175,7 +186,9 @@ async fn rounding_poc() -> Result<()> {
     user_a.refresh_all_pool_positions().await?;
     user_b.refresh_all_pool_positions().await?;
 
-    user_b.borrow(&env.usdc, 50000000000).await?;
+    user_b
+        .borrow(&env.usdc, Amount::tokens(50000000000))
+        .await?;
 
     let mut clk: Clock = match ctx.rpc.get_clock() {
         Some(c) => c,
@@ -203,3 +216,104 @@ async fn rounding_poc() -> Result<()> {
 
     Ok(())
 }
+
+async fn get_pool(ctx: &MarginTestContext, token: &Pubkey) -> Result<MarginPool> {
+    ctx.margin
+        .find_pools()
+        .await?
+        .into_iter()
+        .find(|pool| pool.token_mint == *token)
+        .ok_or_else(|| anyhow::anyhow!("no pool for token {}", token))
+}
+
+#[tokio::test]
+async fn margin_borrow_notes_rounding() -> Result<()> {
+    let ctx = test_context().await;
+    let env = setup_environment(ctx).await?;
+
+    let wallet_a = create_wallet(&ctx.rpc, 10 * LAMPORTS_PER_SOL).await?;
+    let wallet_b = create_wallet(&ctx.rpc, 10 * LAMPORTS_PER_SOL).await?;
+
+    let user_a = ctx.margin.user(&wallet_a).await?;
+    let user_b = ctx.margin.user(&wallet_b).await?;
+
+    user_a.create_account().await?;
+    user_b.create_account().await?;
+
+    let user_a_usdc_account = ctx
+        .tokens
+        .create_account_funded(&env.usdc, &wallet_a.pubkey(), 10_000_000 * ONE_USDC)
+        .await?;
+    let user_b_tsol_account = ctx
+        .tokens
+        .create_account_funded(&env.tsol, &wallet_b.pubkey(), 10_000 * ONE_TSOL)
+        .await?;
+
+    ctx.tokens
+        .set_price(
+            // Set price to 1 USD +- 0.01
+            &env.usdc,
+            &TokenPrice {
+                exponent: -8,
+                price: 100_000_000,
+                confidence: 1_000_000,
+                twap: 100_000_000,
+            },
+        )
+        .await?;
+    ctx.tokens
+        .set_price(
+            // Set price to 100 USD +- 1
+            &env.tsol,
+            &TokenPrice {
+                exponent: -8,
+                price: 10_000_000_000,
+                confidence: 100_000_000,
+                twap: 10_000_000_000,
+            },
+        )
+        .await?;
+
+    user_a
+        .deposit(&env.usdc, &user_a_usdc_account, 5_000_000 * ONE_USDC)
+        .await?;
+    user_b
+        .deposit(&env.tsol, &user_b_tsol_account, 10_000 * ONE_TSOL)
+        .await?;
+
+    user_a.refresh_all_pool_positions().await?;
+    user_b.refresh_all_pool_positions().await?;
+
+    // Borrowing notes before any interest accrues is exchanged 1:1
+    user_b
+        .borrow(&env.usdc, Amount::notes(50_000 * ONE_USDC))
+        .await?;
+
+    let pool = get_pool(ctx, &env.usdc).await?;
+    assert_eq!(50_000 * ONE_USDC, pool.loan_notes);
+    assert_eq!(5_050_000 * ONE_USDC, pool.deposit_notes);
+    assert_eq!(5_000_000 * ONE_USDC, pool.deposit_tokens);
+
+    let mut clk: Clock = match ctx.rpc.get_clock() {
+        Some(c) => c,
+        None => panic!("bad"),
+    };
+
+    // 1 second later...
+    clk.unix_timestamp += 1;
+    ctx.rpc.set_clock(clk);
+
+    user_b.refresh_all_pool_positions().await?;
+
+    // With interest accrued, a single loan note is worth slightly more than one
+    // token. The borrowed tokens round down to 1, and depositing that single token
+    // back into the pool rounds down to 0 notes, so the user gains no claim on the
+    // pool while still owing the full note.
+    user_b.borrow(&env.usdc, Amount::notes(1)).await?;
+
+    let pool = get_pool(ctx, &env.usdc).await?;
+    assert_eq!(50_000 * ONE_USDC + 1, pool.loan_notes);
+    assert_eq!(5_050_000 * ONE_USDC, pool.deposit_notes);
+
+    Ok(())
+}
@@ -2,7 +2,8 @@ use anyhow::Error;
 
 use jet_control::TokenMetadataParams;
 use jet_margin::PositionKind;
-use jet_margin_sdk::instructions::control::TokenConfiguration;
+use jet_margin_sdk::instructions::control::{TokenConfiguration, TokenPriceOracle};
+use jet_margin_sdk::ix_builder::{MarginIxBuilder, MarginPoolIxBuilder};
 use jet_simulation::tokens::TokenPrice;
 use solana_sdk::native_token::LAMPORTS_PER_SOL;
 use solana_sdk::pubkey::Pubkey;
@@ -27,6 +28,11 @@ const DEFAULT_POOL_CONFIG: MarginPoolConfig = MarginPoolConfig {
     utilization_rate_2: 20,
     management_fee_rate: 10,
     management_fee_collect_threshold: 100,
+    collateral_utilization_threshold: 0,
+    max_collateral_weight_reduction: 0,
+    min_queued_withdrawal: 0,
+    deposit_cap: 0,
+    borrow_cap: 0,
     flags: PoolFlags::ALLOW_LENDING.bits(),
 };
 
@@ -76,13 +82,16 @@ async fn setup_environment(ctx: &MarginTestContext) -> Result<TestEnv, Error> {
         .configure_token(
             &usdc,
             &TokenConfiguration {
-                pyth_price: Some(usdc_oracle.price),
-                pyth_product: Some(usdc_oracle.product),
+                oracle: Some(TokenPriceOracle::Pyth {
+                    product: usdc_oracle.product,
+                    price: usdc_oracle.price,
+                }),
                 pool_config: Some(DEFAULT_POOL_CONFIG),
                 metadata: Some(TokenMetadataParams {
                     token_kind: TokenKind::Collateral,
                     collateral_weight: 10_000,
                     collateral_max_staleness: 0,
+                    conservative_valuation: false,
                 }),
                 ..Default::default()
             },
@@ -93,13 +102,16 @@ async fn setup_environment(ctx: &MarginTestContext) -> Result<TestEnv, Error> {
         .configure_token(
             &tsol,
             &TokenConfiguration {
-                pyth_price: Some(tsol_oracle.price),
-                pyth_product: Some(tsol_oracle.product),
+                oracle: Some(TokenPriceOracle::Pyth {
+                    product: tsol_oracle.product,
+                    price: tsol_oracle.price,
+                }),
                 pool_config: Some(DEFAULT_POOL_CONFIG),
                 metadata: Some(TokenMetadataParams {
                     token_kind: TokenKind::Collateral,
                     collateral_weight: 9_500,
                     collateral_max_staleness: 0,
+                    conservative_valuation: false,
                 }),
                 ..Default::default()
             },
@@ -187,11 +199,17 @@ async fn sanity_test() -> Result<(), anyhow::Error> {
     user_b.refresh_all_pool_positions().await?;
 
     // Have each user borrow the other's funds
-    user_a.borrow(&env.tsol, 10 * ONE_TSOL).await?;
-    user_b.borrow(&env.usdc, 1_000 * ONE_USDC).await?;
+    user_a
+        .borrow(&env.tsol, Amount::tokens(10 * ONE_TSOL))
+        .await?;
+    user_b
+        .borrow(&env.usdc, Amount::tokens(1_000 * ONE_USDC))
+        .await?;
 
     // User should not be able to borrow more than what's in the pool
-    let excess_borrow_result = user_a.borrow(&env.tsol, 5_000 * ONE_TSOL).await;
+    let excess_borrow_result = user_a
+        .borrow(&env.tsol, Amount::tokens(5_000 * ONE_TSOL))
+        .await;
 
     assert_program_error_code!(
         jet_margin_pool::ErrorCode::InsufficientLiquidity.into(),
@@ -269,3 +287,230 @@ async fn sanity_test() -> Result<(), anyhow::Error> {
 
     Ok(())
 }
+
+/// Loans can be repaid with tokens from a wallet, either by the owner of the
+/// margin account or by anyone else on their behalf.
+#[tokio::test]
+async fn repay_from_wallet_test() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let env = setup_environment(ctx).await?;
+
+    let wallet_a = create_wallet(&ctx.rpc, 10 * LAMPORTS_PER_SOL).await?;
+    let wallet_b = create_wallet(&ctx.rpc, 10 * LAMPORTS_PER_SOL).await?;
+    let wallet_c = create_wallet(&ctx.rpc, 10 * LAMPORTS_PER_SOL).await?;
+
+    let user_a = ctx.margin.user(&wallet_a).await?;
+    let user_b = ctx.margin.user(&wallet_b).await?;
+
+    // User C has no margin account, and only acts on User B's account
+    let user_c = ctx
+        .margin
+        .third_party(&wallet_c, &wallet_b.pubkey())
+        .await?;
+
+    user_a.create_account().await?;
+    user_b.create_account().await?;
+
+    let user_a_usdc_account = ctx
+        .tokens
+        .create_account_funded(&env.usdc, &wallet_a.pubkey(), 1_000_000 * ONE_USDC)
+        .await?;
+    let user_b_tsol_account = ctx
+        .tokens
+        .create_account_funded(&env.tsol, &wallet_b.pubkey(), 1_000 * ONE_TSOL)
+        .await?;
+    let user_b_usdc_account = ctx
+        .tokens
+        .create_account_funded(&env.usdc, &wallet_b.pubkey(), 400 * ONE_USDC)
+        .await?;
+    let user_c_usdc_account = ctx
+        .tokens
+        .create_account_funded(&env.usdc, &wallet_c.pubkey(), 600 * ONE_USDC)
+        .await?;
+
+    ctx.tokens
+        .set_price(
+            // Set price to 1 USD +- 0.01
+            &env.usdc,
+            &TokenPrice {
+                exponent: -8,
+                price: 100_000_000,
+                confidence: 1_000_000,
+                twap: 100_000_000,
+            },
+        )
+        .await?;
+    ctx.tokens
+        .set_price(
+            // Set price to 100 USD +- 1
+            &env.tsol,
+            &TokenPrice {
+                exponent: -8,
+                price: 10_000_000_000,
+                confidence: 100_000_000,
+                twap: 10_000_000_000,
+            },
+        )
+        .await?;
+
+    user_a
+        .deposit(&env.usdc, &user_a_usdc_account, 1_000_000 * ONE_USDC)
+        .await?;
+    user_b
+        .deposit(&env.tsol, &user_b_tsol_account, 1_000 * ONE_TSOL)
+        .await?;
+
+    user_a.refresh_all_pool_positions().await?;
+    user_b.refresh_all_pool_positions().await?;
+
+    user_b
+        .borrow(&env.usdc, Amount::tokens(1_000 * ONE_USDC))
+        .await?;
+
+    // User B repays part of the loan from their own wallet
+    user_b
+        .repay_from_wallet(
+            &env.usdc,
+            &user_b_usdc_account,
+            Amount::tokens(400 * ONE_USDC),
+        )
+        .await?;
+    assert_eq!(0, ctx.tokens.get_balance(&user_b_usdc_account).await?);
+
+    // User C repays the rest of the loan for User B
+    user_c
+        .repay_from_wallet(
+            &env.usdc,
+            &user_c_usdc_account,
+            Amount::tokens(600 * ONE_USDC),
+        )
+        .await?;
+    assert_eq!(0, ctx.tokens.get_balance(&user_c_usdc_account).await?);
+
+    // With the loan repaid, User B can withdraw all the borrowed tokens
+    user_b
+        .withdraw(
+            &env.usdc,
+            &user_b_usdc_account,
+            Amount::tokens(1_000 * ONE_USDC),
+        )
+        .await?;
+    assert_eq!(
+        1_000 * ONE_USDC,
+        ctx.tokens.get_balance(&user_b_usdc_account).await?
+    );
+
+    // The loan position has no balance left, so it can be closed
+    user_b.close_token_positions(&env.usdc).await?;
+
+    Ok(())
+}
+
+/// Anyone can call accounting_invoke on a margin account, but only to repay its
+/// loans, so withdrawing or borrowing through it has to fail.
+#[tokio::test]
+async fn third_party_cannot_withdraw_or_borrow() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let env = setup_environment(ctx).await?;
+
+    let wallet_a = create_wallet(&ctx.rpc, 10 * LAMPORTS_PER_SOL).await?;
+    let wallet_b = create_wallet(&ctx.rpc, 10 * LAMPORTS_PER_SOL).await?;
+
+    let user_a = ctx.margin.user(&wallet_a).await?;
+    let user_b = ctx.margin.user(&wallet_b).await?;
+
+    user_a.create_account().await?;
+    user_b.create_account().await?;
+
+    let user_a_usdc_account = ctx
+        .tokens
+        .create_account_funded(&env.usdc, &wallet_a.pubkey(), 1_000_000 * ONE_USDC)
+        .await?;
+    let user_b_tsol_account = ctx
+        .tokens
+        .create_account_funded(&env.tsol, &wallet_b.pubkey(), 1_000 * ONE_TSOL)
+        .await?;
+
+    ctx.tokens
+        .set_price(
+            // Set price to 1 USD +- 0.01
+            &env.usdc,
+            &TokenPrice {
+                exponent: -8,
+                price: 100_000_000,
+                confidence: 1_000_000,
+                twap: 100_000_000,
+            },
+        )
+        .await?;
+    ctx.tokens
+        .set_price(
+            // Set price to 100 USD +- 1
+            &env.tsol,
+            &TokenPrice {
+                exponent: -8,
+                price: 10_000_000_000,
+                confidence: 100_000_000,
+                twap: 10_000_000_000,
+            },
+        )
+        .await?;
+
+    user_a
+        .deposit(&env.usdc, &user_a_usdc_account, 1_000_000 * ONE_USDC)
+        .await?;
+    user_b
+        .deposit(&env.tsol, &user_b_tsol_account, 1_000 * ONE_TSOL)
+        .await?;
+
+    user_a.refresh_all_pool_positions().await?;
+    user_b.refresh_all_pool_positions().await?;
+
+    user_b
+        .borrow(&env.usdc, Amount::tokens(1_000 * ONE_USDC))
+        .await?;
+
+    // The payer for the test has no authority over User B's account
+    let thief_tsol_account = ctx
+        .tokens
+        .create_account(&env.tsol, &ctx.rpc.payer().pubkey())
+        .await?;
+
+    let user_b_ix = MarginIxBuilder::new(wallet_b.pubkey(), 0);
+    let tsol_pool = MarginPoolIxBuilder::new(env.tsol);
+    let usdc_pool = MarginPoolIxBuilder::new(env.usdc);
+    let (tsol_deposit, _) = user_b_ix.get_token_account_address(&tsol_pool.deposit_note_mint);
+    let (usdc_deposit, _) = user_b_ix.get_token_account_address(&usdc_pool.deposit_note_mint);
+    let (usdc_loan, _) = user_b_ix.get_token_account_address(&usdc_pool.loan_note_mint);
+
+    let withdraw_ix = user_b_ix.accounting_invoke(tsol_pool.margin_withdraw(
+        user_b_ix.address,
+        tsol_deposit,
+        thief_tsol_account,
+        Amount::tokens(ONE_TSOL),
+    ));
+    let tx = ctx.rpc.create_transaction(&[], &[withdraw_ix]).await?;
+    let withdraw_result = ctx.rpc.send_and_confirm_transaction(&tx).await;
+    assert_program_error_code!(
+        jet_margin::ErrorCode::UnauthorizedInvocation.into(),
+        withdraw_result
+    );
+
+    let borrow_ix = user_b_ix.accounting_invoke(usdc_pool.margin_borrow(
+        user_b_ix.address,
+        usdc_deposit,
+        usdc_loan,
+        Amount::tokens(ONE_USDC),
+    ));
+    let tx = ctx.rpc.create_transaction(&[], &[borrow_ix]).await?;
+    let borrow_result = ctx.rpc.send_and_confirm_transaction(&tx).await;
+    assert_program_error_code!(
+        jet_margin::ErrorCode::UnauthorizedInvocation.into(),
+        borrow_result
+    );
+
+    assert_eq!(0, ctx.tokens.get_balance(&thief_tsol_account).await?);
+    assert_eq!(1_000 * ONE_USDC, ctx.tokens.get_balance(&usdc_loan).await?);
+
+    Ok(())
+}
@@ -1,7 +1,7 @@
 use anyhow::Error;
 
 use jet_control::TokenMetadataParams;
-use jet_margin_sdk::instructions::control::TokenConfiguration;
+use jet_margin_sdk::instructions::control::{TokenConfiguration, TokenPriceOracle};
 use jet_simulation::swap::SwapPool;
 use jet_simulation::tokens::TokenPrice;
 use solana_sdk::native_token::LAMPORTS_PER_SOL;
@@ -27,6 +27,11 @@ const DEFAULT_POOL_CONFIG: MarginPoolConfig = MarginPoolConfig {
     utilization_rate_2: 20,
     management_fee_rate: 10,
     management_fee_collect_threshold: 100,
+    collateral_utilization_threshold: 0,
+    max_collateral_weight_reduction: 0,
+    min_queued_withdrawal: 0,
+    deposit_cap: 0,
+    borrow_cap: 0,
     flags: PoolFlags::ALLOW_LENDING.bits(),
 };
 
@@ -76,13 +81,16 @@ async fn setup_environment(ctx: &MarginTestContext) -> Result<TestEnv, Error> {
         .configure_token(
             &usdc,
             &TokenConfiguration {
-                pyth_price: Some(usdc_oracle.price),
-                pyth_product: Some(usdc_oracle.product),
+                oracle: Some(TokenPriceOracle::Pyth {
+                    product: usdc_oracle.product,
+                    price: usdc_oracle.price,
+                }),
                 pool_config: Some(DEFAULT_POOL_CONFIG),
                 metadata: Some(TokenMetadataParams {
                     token_kind: TokenKind::Collateral,
                     collateral_weight: 10_000,
                     collateral_max_staleness: 0,
+                    conservative_valuation: false,
                 }),
                 ..Default::default()
             },
@@ -93,13 +101,16 @@ async fn setup_environment(ctx: &MarginTestContext) -> Result<TestEnv, Error> {
         .configure_token(
             &tsol,
             &TokenConfiguration {
-                pyth_price: Some(tsol_oracle.price),
-                pyth_product: Some(tsol_oracle.product),
+                oracle: Some(TokenPriceOracle::Pyth {
+                    product: tsol_oracle.product,
+                    price: tsol_oracle.price,
+                }),
                 pool_config: Some(DEFAULT_POOL_CONFIG),
                 metadata: Some(TokenMetadataParams {
                     token_kind: TokenKind::Collateral,
                     collateral_weight: 9_500,
                     collateral_max_staleness: 0,
+                    conservative_valuation: false,
                 }),
                 ..Default::default()
             },
@@ -0,0 +1,198 @@
+use anchor_lang::AccountDeserialize;
+use anyhow::Error;
+
+use jet_margin_sdk::ix_builder::MarginPoolIxBuilder;
+use jet_simulation::tokens::TokenPrice;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+use hosted_tests::context::{test_context, MarginTestContext};
+use hosted_tests::fixtures::{advance_clock, setup_usdc_pool};
+
+use jet_margin::MarginAccount;
+use jet_margin_pool::{MarginPoolConfig, PoolFlags, TermLoan, TERM_LOAN_LATE_PENALTY};
+use jet_simulation::create_wallet;
+use jet_simulation::margin::MarginUser;
+
+const ONE_USDC: u64 = 1_000_000;
+
+const POOL_CONFIG: MarginPoolConfig = MarginPoolConfig {
+    borrow_rate_0: 1_000,
+    borrow_rate_1: 2_000,
+    borrow_rate_2: 3_000,
+    borrow_rate_3: 4_000,
+    utilization_rate_1: 5_000,
+    utilization_rate_2: 8_000,
+    management_fee_rate: 0,
+    management_fee_collect_threshold: 100,
+    collateral_utilization_threshold: 0,
+    max_collateral_weight_reduction: 0,
+    min_queued_withdrawal: 0,
+    deposit_cap: 0,
+    borrow_cap: 0,
+    flags: PoolFlags::ALLOW_LENDING.bits(),
+};
+
+const PRICE: TokenPrice = TokenPrice {
+    exponent: -8,
+    price: 1_00000000,
+    confidence: 0,
+    twap: 1_00000000,
+};
+
+async fn setup_pool(ctx: &MarginTestContext) -> Result<Pubkey, Error> {
+    let usdc = setup_usdc_pool(ctx, POOL_CONFIG).await?;
+
+    ctx.tokens.set_price(&usdc, &PRICE).await?;
+
+    Ok(usdc)
+}
+
+async fn deposit_user(
+    ctx: &MarginTestContext,
+    token: &Pubkey,
+    amount: u64,
+) -> Result<MarginUser, Error> {
+    let wallet = create_wallet(&ctx.rpc, 10 * LAMPORTS_PER_SOL).await?;
+    let user = ctx.margin.user(&wallet).await?;
+    let source = ctx
+        .tokens
+        .create_account_funded(token, &wallet.pubkey(), amount)
+        .await?;
+
+    user.create_account().await?;
+    user.deposit(token, &source, amount).await?;
+    user.refresh_pool_position(token).await?;
+
+    Ok(user)
+}
+
+async fn get_term_loan(
+    ctx: &MarginTestContext,
+    token: &Pubkey,
+    user: &MarginUser,
+) -> Result<TermLoan, Error> {
+    let address = MarginPoolIxBuilder::new(*token).term_loan(user.address());
+    let account = ctx.rpc.get_account(&address).await?.unwrap();
+
+    Ok(TermLoan::try_deserialize(&mut &account.data[..])?)
+}
+
+/// The price recorded in a margin account for one of its positions
+async fn get_position_price(
+    ctx: &MarginTestContext,
+    user: &MarginUser,
+    position_mint: &Pubkey,
+) -> Result<i64, Error> {
+    let account = ctx.rpc.get_account(user.address()).await?.unwrap();
+    let margin_account = MarginAccount::try_deserialize(&mut &account.data[..])?;
+
+    Ok(margin_account
+        .get_position(position_mint)
+        .unwrap()
+        .price
+        .value)
+}
+
+/// A term loan is priced at the pool's rate once the loan is included in
+/// its utilization, and the interest is earned by depositors over the term
+#[tokio::test]
+async fn term_loan_is_priced_with_the_loan_included() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let usdc = setup_pool(ctx).await?;
+    let pool = MarginPoolIxBuilder::new(usdc);
+
+    let lender = deposit_user(ctx, &usdc, 1_000_000 * ONE_USDC).await?;
+    let borrower = deposit_user(ctx, &usdc, 1_000_000 * ONE_USDC).await?;
+
+    // with nothing borrowed the pool charges the lowest rate, but the loan brings
+    // the utilization to 1/6 once the borrowed tokens are deposited back
+    let term = 1_000_000;
+    borrower
+        .term_borrow(&usdc, 400_000 * ONE_USDC, term)
+        .await?;
+
+    let term_loan = get_term_loan(ctx, &usdc, &borrower).await?;
+    assert_eq!(1_333, term_loan.interest_rate);
+    assert_eq!(400_000 * ONE_USDC, term_loan.principal);
+    assert!(term_loan.notes > term_loan.principal);
+
+    let interest = term_loan.notes - term_loan.principal;
+    let pool_state = ctx.margin.get_pool(&usdc).await?;
+    assert_eq!(400_000 * ONE_USDC, pool_state.term_loan_tokens);
+    assert_eq!(interest, pool_state.term_loan_interest_owed);
+
+    // the loan notes are worth a token each until maturity
+    borrower.refresh_pool_position(&usdc).await?;
+    let note_price = get_position_price(ctx, &borrower, &pool.term_loan_note_mint).await?;
+    assert_eq!(PRICE.price, note_price);
+
+    // half way through the term, depositors have earned half the interest once
+    // the pool accrues it
+    advance_clock(ctx, term / 2);
+    lender.refresh_pool_position(&usdc).await?;
+    let deposit_price = get_position_price(ctx, &lender, &pool.deposit_note_mint).await?;
+    assert_eq!(PRICE.price, deposit_price);
+
+    let top_up = ctx
+        .tokens
+        .create_account_funded(&usdc, lender.owner(), ONE_USDC)
+        .await?;
+    lender.deposit(&usdc, &top_up, ONE_USDC).await?;
+    lender.refresh_pool_position(&usdc).await?;
+
+    let expected = PRICE.price as u128 * (2_400_000 * ONE_USDC + interest / 2) as u128
+        / (2_400_000 * ONE_USDC) as u128;
+    let deposit_price = get_position_price(ctx, &lender, &pool.deposit_note_mint).await?;
+    assert!((deposit_price as u128).abs_diff(expected) <= 1);
+
+    // after maturity the notes are worth more until repaid
+    advance_clock(ctx, term / 2);
+    borrower.refresh_pool_position(&usdc).await?;
+    let note_price = get_position_price(ctx, &borrower, &pool.term_loan_note_mint).await?;
+    assert_eq!(
+        PRICE.price * (10_000 + TERM_LOAN_LATE_PENALTY as i64) / 10_000,
+        note_price
+    );
+
+    Ok(())
+}
+
+/// A margin account only has one term loan from a pool at a time, and can
+/// take another once the first is repaid
+#[tokio::test]
+async fn one_term_loan_per_margin_account() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let usdc = setup_pool(ctx).await?;
+
+    let _lender = deposit_user(ctx, &usdc, 1_000_000 * ONE_USDC).await?;
+    let borrower = deposit_user(ctx, &usdc, 1_000_000 * ONE_USDC).await?;
+
+    let term = 1_000_000;
+    borrower
+        .term_borrow(&usdc, 100_000 * ONE_USDC, term)
+        .await?;
+
+    // the loan's address is already in use while it's outstanding
+    let result = borrower.term_borrow(&usdc, 100_000 * ONE_USDC, term).await;
+    assert!(result.is_err());
+
+    let term_loan = get_term_loan(ctx, &usdc, &borrower).await?;
+    assert_eq!(100_000 * ONE_USDC, term_loan.principal);
+
+    // repaying the loan in full closes it, so another can be taken
+    borrower.term_repay(&usdc, term_loan.notes).await?;
+
+    let address = MarginPoolIxBuilder::new(usdc).term_loan(borrower.address());
+    assert!(ctx.rpc.get_account(&address).await?.is_none());
+
+    borrower
+        .term_borrow(&usdc, 200_000 * ONE_USDC, term)
+        .await?;
+
+    let term_loan = get_term_loan(ctx, &usdc, &borrower).await?;
+    assert_eq!(200_000 * ONE_USDC, term_loan.principal);
+
+    Ok(())
+}
@@ -0,0 +1,296 @@
+use anyhow::Error;
+
+use jet_control::TokenMetadataParams;
+use jet_margin_sdk::instructions::control::{TokenConfiguration, TokenPriceOracle};
+use jet_margin_sdk::ix_builder::{MarginIxBuilder, MarginPoolIxBuilder};
+use jet_simulation::swap::SwapPool;
+use jet_simulation::tokens::{MintExtensions, TokenOracle, TokenPrice};
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+use hosted_tests::context::{test_context, MarginTestContext};
+
+use jet_margin_pool::{Amount, MarginPoolConfig, PoolFlags};
+use jet_metadata::TokenKind;
+use jet_simulation::margin::{MarginPoolSetupInfo, MarginUser};
+use jet_simulation::{assert_program_error_code, create_wallet};
+
+const ONE: u64 = 1_000_000;
+
+/// The fee charged on transfers of the fee token, in basis points
+const TRANSFER_FEE_BPS: u16 = 100;
+
+const DEFAULT_POOL_CONFIG: MarginPoolConfig = MarginPoolConfig {
+    borrow_rate_0: 10,
+    borrow_rate_1: 20,
+    borrow_rate_2: 30,
+    borrow_rate_3: 40,
+    utilization_rate_1: 10,
+    utilization_rate_2: 20,
+    management_fee_rate: 10,
+    management_fee_collect_threshold: 100,
+    collateral_utilization_threshold: 0,
+    max_collateral_weight_reduction: 0,
+    min_queued_withdrawal: 0,
+    deposit_cap: 0,
+    borrow_cap: 0,
+    flags: PoolFlags::ALLOW_LENDING.bits(),
+};
+
+async fn create_pool(ctx: &MarginTestContext, token: Pubkey) -> Result<TokenOracle, Error> {
+    let fees = ctx
+        .tokens
+        .create_account(&token, &ctx.authority.pubkey())
+        .await?;
+    let oracle = ctx.tokens.create_oracle(&token).await?;
+
+    ctx.margin
+        .create_pool(&MarginPoolSetupInfo {
+            token,
+            fee_destination: fees,
+            token_kind: TokenKind::Collateral,
+            collateral_weight: 10_000,
+            config: DEFAULT_POOL_CONFIG,
+            oracle,
+        })
+        .await?;
+
+    Ok(oracle)
+}
+
+/// Create a pool for the token, with a price of 1 USD
+async fn setup_pool(ctx: &MarginTestContext, token: Pubkey) -> Result<(), Error> {
+    let oracle = create_pool(ctx, token).await?;
+
+    ctx.margin
+        .configure_token(
+            &token,
+            &TokenConfiguration {
+                oracle: Some(TokenPriceOracle::Pyth {
+                    product: oracle.product,
+                    price: oracle.price,
+                }),
+                pool_config: Some(DEFAULT_POOL_CONFIG),
+                metadata: Some(TokenMetadataParams {
+                    token_kind: TokenKind::Collateral,
+                    collateral_weight: 10_000,
+                    collateral_max_staleness: 0,
+                    conservative_valuation: false,
+                }),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    ctx.tokens
+        .set_price(
+            &token,
+            &TokenPrice {
+                exponent: -8,
+                price: 100_000_000,
+                confidence: 1_000_000,
+                twap: 100_000_000,
+            },
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn create_user(ctx: &MarginTestContext) -> Result<MarginUser, Error> {
+    let wallet = create_wallet(&ctx.rpc, 10 * LAMPORTS_PER_SOL).await?;
+    let user = ctx.margin.user(&wallet).await?;
+
+    user.create_account().await?;
+
+    Ok(user)
+}
+
+/// Get the balance of notes in a margin account's position
+async fn position_balance(
+    ctx: &MarginTestContext,
+    user: &MarginUser,
+    note_mint: &Pubkey,
+) -> Result<u64, Error> {
+    let (position, _) = MarginIxBuilder::new(*user.owner(), 0).get_token_account_address(note_mint);
+
+    ctx.tokens.get_balance(&position).await
+}
+
+#[tokio::test]
+async fn token_2022_deposit_and_withdraw() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let token = ctx
+        .tokens
+        .create_token_2022(6, &MintExtensions::default())
+        .await?;
+
+    setup_pool(ctx, token).await?;
+
+    let vault = MarginPoolIxBuilder::new(token).vault;
+    let user = create_user(ctx).await?;
+    let wallet_tokens = ctx
+        .tokens
+        .create_account_funded(&token, user.owner(), 1_000 * ONE)
+        .await?;
+
+    user.deposit(&token, &wallet_tokens, 1_000 * ONE).await?;
+    assert_eq!(1_000 * ONE, ctx.tokens.get_balance(&vault).await?);
+
+    user.refresh_all_pool_positions().await?;
+    user.withdraw(&token, &wallet_tokens, Amount::tokens(400 * ONE))
+        .await?;
+
+    assert_eq!(600 * ONE, ctx.tokens.get_balance(&vault).await?);
+    assert_eq!(400 * ONE, ctx.tokens.get_balance(&wallet_tokens).await?);
+
+    Ok(())
+}
+
+/// Only the tokens actually received by the pool are credited when the token
+/// charges a fee on transfers
+#[tokio::test]
+async fn transfer_fees_are_excluded_from_deposits_and_repayments() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let token = ctx
+        .tokens
+        .create_token_2022(
+            6,
+            &MintExtensions {
+                transfer_fee: Some((TRANSFER_FEE_BPS, 1_000_000 * ONE)),
+                ..Default::default()
+            },
+        )
+        .await?;
+    let usdc = ctx.tokens.create_token(6, None, None).await?;
+
+    setup_pool(ctx, token).await?;
+    setup_pool(ctx, usdc).await?;
+
+    let pool = MarginPoolIxBuilder::new(token);
+
+    // 1% of the deposit is kept as a fee, so the pool only receives 990
+    let lender = create_user(ctx).await?;
+    let lender_tokens = ctx
+        .tokens
+        .create_account_funded(&token, lender.owner(), 1_000 * ONE)
+        .await?;
+
+    lender.deposit(&token, &lender_tokens, 1_000 * ONE).await?;
+    assert_eq!(990 * ONE, ctx.tokens.get_balance(&pool.vault).await?);
+    assert_eq!(
+        990 * ONE,
+        position_balance(ctx, &lender, &pool.deposit_note_mint).await?
+    );
+
+    // The pool sends the full amount withdrawn, and the fee is taken from what
+    // the lender receives
+    lender.refresh_all_pool_positions().await?;
+    lender
+        .withdraw(&token, &lender_tokens, Amount::tokens(100 * ONE))
+        .await?;
+    assert_eq!(890 * ONE, ctx.tokens.get_balance(&pool.vault).await?);
+    assert_eq!(99 * ONE, ctx.tokens.get_balance(&lender_tokens).await?);
+
+    // A repayment only pays off the tokens the pool received
+    let borrower = create_user(ctx).await?;
+    let borrower_usdc = ctx
+        .tokens
+        .create_account_funded(&usdc, borrower.owner(), 10_000 * ONE)
+        .await?;
+    let borrower_tokens = ctx
+        .tokens
+        .create_account_funded(&token, borrower.owner(), 200 * ONE)
+        .await?;
+
+    borrower
+        .deposit(&usdc, &borrower_usdc, 10_000 * ONE)
+        .await?;
+    borrower.refresh_all_pool_positions().await?;
+    borrower.borrow(&token, Amount::tokens(500 * ONE)).await?;
+    assert_eq!(
+        500 * ONE,
+        position_balance(ctx, &borrower, &pool.loan_note_mint).await?
+    );
+
+    borrower
+        .repay_from_wallet(&token, &borrower_tokens, Amount::tokens(100 * ONE))
+        .await?;
+    assert_eq!(
+        401 * ONE,
+        position_balance(ctx, &borrower, &pool.loan_note_mint).await?
+    );
+    assert_eq!(989 * ONE, ctx.tokens.get_balance(&pool.vault).await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn unsupported_mint_extensions_are_rejected() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let token = ctx
+        .tokens
+        .create_token_2022(
+            6,
+            &MintExtensions {
+                close_authority: true,
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    let result = create_pool(ctx, token).await;
+    assert_program_error_code!(
+        jet_margin_pool::ErrorCode::UnsupportedTokenExtension.into(),
+        result
+    );
+
+    Ok(())
+}
+
+/// The swap program only supports the original token program, so swaps out of
+/// a token-2022 pool are rejected before any tokens are moved
+#[tokio::test]
+async fn swap_rejects_token_2022_pools() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let token = ctx
+        .tokens
+        .create_token_2022(6, &MintExtensions::default())
+        .await?;
+    let usdc = ctx.tokens.create_token(6, None, None).await?;
+    let other = ctx.tokens.create_token(6, None, None).await?;
+
+    setup_pool(ctx, token).await?;
+    setup_pool(ctx, usdc).await?;
+
+    let user = create_user(ctx).await?;
+    let wallet_tokens = ctx
+        .tokens
+        .create_account_funded(&token, user.owner(), 100 * ONE)
+        .await?;
+    user.deposit(&token, &wallet_tokens, 100 * ONE).await?;
+
+    let token_transit = ctx.tokens.create_account(&token, user.address()).await?;
+    let usdc_transit = ctx.tokens.create_account(&usdc, user.address()).await?;
+    let swap_pool = SwapPool::configure(&ctx.rpc, &usdc, &other, 1_000 * ONE, 1_000 * ONE).await?;
+
+    user.refresh_all_pool_positions().await?;
+    let result = user
+        .swap(
+            &token,
+            &usdc,
+            &token_transit,
+            &usdc_transit,
+            &swap_pool,
+            Amount::tokens(10 * ONE),
+            Amount::tokens(9 * ONE),
+        )
+        .await;
+    assert_program_error_code!(
+        jet_margin_swap::ErrorCode::UnsupportedTokenProgram.into(),
+        result
+    );
+
+    Ok(())
+}
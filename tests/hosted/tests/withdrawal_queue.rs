@@ -0,0 +1,248 @@
+use anchor_spl::token::spl_token;
+use anyhow::Error;
+
+use jet_margin_sdk::ix_builder::MarginPoolIxBuilder;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+
+use hosted_tests::context::{test_context, MarginTestContext};
+use hosted_tests::fixtures::{send, setup_usdc_pool};
+
+use jet_margin_pool::{Amount, ErrorCode, MarginPoolConfig, PoolFlags};
+use jet_simulation::{assert_program_error_code, create_wallet};
+
+const ONE_USDC: u64 = 1_000_000;
+
+const POOL_CONFIG: MarginPoolConfig = MarginPoolConfig {
+    borrow_rate_0: 10,
+    borrow_rate_1: 20,
+    borrow_rate_2: 30,
+    borrow_rate_3: 40,
+    utilization_rate_1: 10,
+    utilization_rate_2: 20,
+    management_fee_rate: 10,
+    management_fee_collect_threshold: 100,
+    collateral_utilization_threshold: 0,
+    max_collateral_weight_reduction: 0,
+    min_queued_withdrawal: 10 * ONE_USDC,
+    deposit_cap: 0,
+    borrow_cap: 0,
+    flags: PoolFlags::ALLOW_LENDING.bits(),
+};
+
+async fn setup_pool(ctx: &MarginTestContext) -> Result<MarginPoolIxBuilder, Error> {
+    let usdc = setup_usdc_pool(ctx, POOL_CONFIG).await?;
+
+    let pool = MarginPoolIxBuilder::new(usdc);
+    send(
+        ctx,
+        &[],
+        pool.create_withdrawal_queue(ctx.rpc.payer().pubkey()),
+    )
+    .await?;
+
+    Ok(pool)
+}
+
+/// A wallet with deposit notes for the pool, held outside of a margin account
+struct Depositor {
+    wallet: Keypair,
+    tokens: Pubkey,
+    notes: Pubkey,
+}
+
+async fn deposit(
+    ctx: &MarginTestContext,
+    pool: &MarginPoolIxBuilder,
+    amount: u64,
+) -> Result<Depositor, Error> {
+    let wallet = create_wallet(&ctx.rpc, 10 * LAMPORTS_PER_SOL).await?;
+    let tokens = ctx
+        .tokens
+        .create_account_funded(&pool.token_mint, &wallet.pubkey(), amount)
+        .await?;
+    let notes = ctx
+        .tokens
+        .create_account(&pool.deposit_note_mint, &wallet.pubkey())
+        .await?;
+
+    send(
+        ctx,
+        &[&wallet],
+        pool.deposit(wallet.pubkey(), tokens, notes, amount),
+    )
+    .await?;
+
+    Ok(Depositor {
+        wallet,
+        tokens,
+        notes,
+    })
+}
+
+async fn queue_withdrawal(
+    ctx: &MarginTestContext,
+    pool: &MarginPoolIxBuilder,
+    depositor: &Depositor,
+    destination: Pubkey,
+    notes: u64,
+) -> Result<(), Error> {
+    send(
+        ctx,
+        &[&depositor.wallet],
+        pool.queue_withdrawal(
+            depositor.wallet.pubkey(),
+            depositor.notes,
+            destination,
+            notes,
+        ),
+    )
+    .await
+}
+
+/// Requests have to be large enough, and for a destination that can receive the
+/// pool's token
+#[tokio::test]
+async fn queued_withdrawals_are_validated() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let pool = setup_pool(ctx).await?;
+    let depositor = deposit(ctx, &pool, 100 * ONE_USDC).await?;
+
+    let result = queue_withdrawal(ctx, &pool, &depositor, depositor.tokens, ONE_USDC).await;
+    assert_program_error_code!(ErrorCode::WithdrawalRequestTooSmall.into(), result);
+
+    let result = queue_withdrawal(ctx, &pool, &depositor, depositor.notes, 50 * ONE_USDC).await;
+    assert_program_error_code!(ErrorCode::InvalidWithdrawalDestination.into(), result);
+
+    let result = queue_withdrawal(
+        ctx,
+        &pool,
+        &depositor,
+        depositor.wallet.pubkey(),
+        50 * ONE_USDC,
+    )
+    .await;
+    assert_program_error_code!(ErrorCode::InvalidWithdrawalDestination.into(), result);
+
+    queue_withdrawal(ctx, &pool, &depositor, depositor.tokens, 50 * ONE_USDC).await?;
+    assert_eq!(
+        50 * ONE_USDC,
+        ctx.margin
+            .get_pool(&pool.token_mint)
+            .await?
+            .queued_withdrawal_notes
+    );
+
+    Ok(())
+}
+
+/// Tokens waiting to be withdrawn through the queue can't be taken by other
+/// withdrawals
+#[tokio::test]
+async fn queued_withdrawals_reserve_liquidity() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let pool = setup_pool(ctx).await?;
+    let queued = deposit(ctx, &pool, 100 * ONE_USDC).await?;
+    let other = deposit(ctx, &pool, 100 * ONE_USDC).await?;
+
+    queue_withdrawal(ctx, &pool, &queued, queued.tokens, 100 * ONE_USDC).await?;
+
+    let result = send(
+        ctx,
+        &[&other.wallet],
+        pool.withdraw(
+            other.wallet.pubkey(),
+            other.notes,
+            other.tokens,
+            Amount::tokens(100 * ONE_USDC + 1),
+        ),
+    )
+    .await;
+    assert_program_error_code!(ErrorCode::InsufficientLiquidity.into(), result);
+
+    send(
+        ctx,
+        &[&other.wallet],
+        pool.withdraw(
+            other.wallet.pubkey(),
+            other.notes,
+            other.tokens,
+            Amount::tokens(100 * ONE_USDC),
+        ),
+    )
+    .await?;
+
+    // the queued request is still filled in full
+    send(ctx, &[], pool.fill_withdrawals(&[queued.tokens])).await?;
+    assert_eq!(
+        100 * ONE_USDC,
+        ctx.tokens.get_balance(&queued.tokens).await?
+    );
+    assert_eq!(
+        0,
+        ctx.margin
+            .get_pool(&pool.token_mint)
+            .await?
+            .queued_withdrawal_notes
+    );
+
+    Ok(())
+}
+
+/// A destination closed after queueing doesn't hold up the rest of the queue,
+/// and the skipped request can still be cancelled
+#[tokio::test]
+async fn closed_destinations_are_skipped() -> Result<(), anyhow::Error> {
+    let ctx = test_context().await;
+    let pool = setup_pool(ctx).await?;
+    let skipped = deposit(ctx, &pool, 100 * ONE_USDC).await?;
+    let filled = deposit(ctx, &pool, 100 * ONE_USDC).await?;
+
+    let closed = ctx
+        .tokens
+        .create_account(&pool.token_mint, &skipped.wallet.pubkey())
+        .await?;
+
+    queue_withdrawal(ctx, &pool, &skipped, closed, 100 * ONE_USDC).await?;
+    queue_withdrawal(ctx, &pool, &filled, filled.tokens, 100 * ONE_USDC).await?;
+
+    send(
+        ctx,
+        &[&skipped.wallet],
+        spl_token::instruction::close_account(
+            &spl_token::ID,
+            &closed,
+            &skipped.wallet.pubkey(),
+            &skipped.wallet.pubkey(),
+            &[],
+        )?,
+    )
+    .await?;
+
+    send(ctx, &[], pool.fill_withdrawals(&[closed, filled.tokens])).await?;
+    assert_eq!(
+        100 * ONE_USDC,
+        ctx.tokens.get_balance(&filled.tokens).await?
+    );
+    assert_eq!(
+        0,
+        ctx.margin
+            .get_pool(&pool.token_mint)
+            .await?
+            .queued_withdrawal_notes
+    );
+
+    send(
+        ctx,
+        &[&skipped.wallet],
+        pool.cancel_withdrawal(skipped.wallet.pubkey(), skipped.notes, 0),
+    )
+    .await?;
+    assert_eq!(
+        100 * ONE_USDC,
+        ctx.tokens.get_balance(&skipped.notes).await?
+    );
+
+    Ok(())
+}